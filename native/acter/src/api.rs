@@ -32,9 +32,13 @@ mod common;
 mod convo;
 mod deep_linking;
 mod device;
+mod history;
+mod ical;
 mod invitations;
 mod news;
+mod outbox;
 mod pins;
+mod polls;
 mod profile;
 mod push;
 mod reactions;
@@ -46,6 +50,7 @@ mod spaces;
 mod stories;
 mod super_invites;
 mod tasks;
+mod templates;
 mod timeline;
 mod typing;
 mod utils;
@@ -56,10 +61,11 @@ pub mod read_receipts;
 pub use account::{Account, ExternalId, ThreePidEmailTokenResponse};
 pub use acter_core::{
     events::{
-        calendar::EventLocationInfo, news::NewsContent, stories::StoryContent, Category,
-        CategoryBuilder, Colorize, ColorizeBuilder, Display, DisplayBuilder, ObjRefBuilder,
-        UtcDateTime,
+        calendar::EventLocationInfo, news::NewsContent, stories::StoryContent,
+        tasks::WorkflowState, Category, CategoryBuilder, Colorize, ColorizeBuilder, Display,
+        DisplayBuilder, ObjRefBuilder, UtcDateTime,
     },
+    history::FieldChange,
     models::{
         status::{
             MembershipContent, PolicyRuleRoomContent, PolicyRuleServerContent,
@@ -88,7 +94,9 @@ pub use backup::BackupManager;
 pub use bookmarks::Bookmarks;
 pub use calendar_events::{CalendarEvent, CalendarEventDraft, CalendarEventUpdateBuilder};
 pub use categories::{Categories, CategoriesBuilder};
-pub use client::{Client, ClientStateBuilder, HistoryLoadState, LocalUrlPreview, SyncState};
+pub use client::{
+    Client, ClientStateBuilder, HistoryLoadState, LocalUrlPreview, ModelIdDiff, SyncState,
+};
 pub use comments::{Comment, CommentDraft, CommentUpdateBuilder, CommentsManager};
 pub use common::{
     duration_from_secs, new_colorize_builder, new_display_builder, new_obj_ref_builder,
@@ -101,9 +109,13 @@ pub use convo::{
 pub use core::time::Duration as EfkDuration;
 pub use deep_linking::{new_link_ref_details, ObjRef, RefDetails};
 pub use device::DeviceEvent;
+pub use history::{EditHistory, HistoryEntry};
+pub use ical::{IcalImportItem, IcalImportReport};
 pub use invitations::{InvitationsManager, ObjectInvitationsManager, RoomInvitation};
 pub use news::{NewsEntry, NewsEntryDraft, NewsEntryUpdateBuilder, NewsSlide, NewsSlideDraft};
+pub use outbox::OutboxEntry;
 pub use pins::{Pin as ActerPin, PinDraft, PinUpdateBuilder};
+pub use polls::{Poll as ActerPoll, PollAnswer, PollDraft, PollUpdateBuilder, PollVotesManager};
 pub use profile::UserProfile;
 pub use push::{
     NotificationItem, NotificationRoom, NotificationSender, NotificationSettings, Pusher,
@@ -116,16 +128,20 @@ pub use room::{
     RoomPreview, SpaceHierarchyRoomInfo, SpaceRelation, SpaceRelations, UserRoomSettings,
 };
 pub use rsvp::{Rsvp, RsvpDraft, RsvpManager, RsvpStatus};
-pub use search::{PublicSearchResult, PublicSearchResultItem};
+pub use search::{
+    new_object_search_filters, ObjectSearchFilters, ObjectSearchResult, PublicSearchResult,
+    PublicSearchResultItem,
+};
 pub use settings::{
     ActerAppSettings, ActerAppSettingsBuilder, ActerUserAppSettings, ActerUserAppSettingsBuilder,
-    EventsSettings, NewsSettings, PinsSettings, RoomPowerLevels, SimpleOnOffSetting,
+    EventsSettings, NewsSettings, PinsSettings, PollsSettings, RoomPowerLevels, SimpleOnOffSetting,
     SimpleOnOffSettingBuilder, SimpleSettingWithTurnOff, SimpleSettingWithTurnOffBuilder,
     StoriesSettings, TasksSettings,
 };
 pub use spaces::{
     new_app_permissions_builder, new_space_settings_builder, AppPermissionsBuilder,
-    CreateSpaceSettings, CreateSpaceSettingsBuilder, RelationTargetType, Space, SpaceDiff,
+    CreateSpaceSettings, CreateSpaceSettingsBuilder, HistoryProgress, RelationTargetType, Space,
+    SpaceDiff,
 };
 pub use stories::{Story, StoryDraft, StorySlide, StorySlideDraft, StoryUpdateBuilder};
 pub use super_invites::{
@@ -134,6 +150,7 @@ pub use super_invites::{
 pub use tasks::{
     Task, TaskDraft, TaskList, TaskListDraft, TaskListUpdateBuilder, TaskUpdateBuilder,
 };
+pub use templates::{TemplateApplication, TemplateInput};
 pub use timeline::{
     EventSendState, MsgContent, MsgDraft, TimelineEventItem, TimelineItem, TimelineItemDiff,
     TimelineStream, TimelineVirtualItem,
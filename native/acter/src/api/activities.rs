@@ -15,7 +15,7 @@ use acter_core::{
         },
         ActerModel,
     },
-    referencing::IndexKey,
+    referencing::{IndexKey, SpecialListsIndex},
 };
 use futures::{FutureExt, Stream, StreamExt};
 use matrix_sdk::ruma::{EventId, OwnedEventId, OwnedRoomId, RoomId};
@@ -73,6 +73,13 @@ impl Activity {
         }
     }
 
+    pub fn reply_to_msg_content(&self) -> Option<MsgContent> {
+        self.inner
+            .in_reply_to()
+            .and_then(|r| r.content.as_ref())
+            .map(MsgContent::from)
+    }
+
     pub fn membership_content(&self) -> Option<MembershipContent> {
         self.inner.membership_content()
     }
@@ -153,7 +160,15 @@ impl Activity {
         let Ok(user_id) = self.client.user_id() else {
             return false;
         };
-        self.inner.whom().contains(&user_id.to_string())
+        self.inner.whom().contains(&user_id.to_string()) || self.inner.mentions_user(&user_id)
+    }
+
+    pub fn mentioned_users(&self) -> Vec<String> {
+        self.inner
+            .mentioned_users()
+            .iter()
+            .map(ToString::to_string)
+            .collect()
     }
 }
 
@@ -241,4 +256,11 @@ impl Client {
             client: self.clone(),
         })
     }
+
+    pub fn mentions_activities(&self) -> anyhow::Result<Activities> {
+        Ok(Activities {
+            index: IndexKey::Special(SpecialListsIndex::MyMentions),
+            client: self.clone(),
+        })
+    }
 }
@@ -3,11 +3,13 @@ use acter_core::{
     events::{
         calendar::{
             self as calendar_events, CalendarEventBuilder, EventLocation, EventLocationInfo,
+            RecurrenceOverride, RecurrenceRule,
         },
         rsvp::RsvpStatus,
-        CalendarEventRefPreview, RefDetails as CoreRefDetails, UtcDateTime,
+        CalendarEventRefPreview, EditRule, RefDetails as CoreRefDetails, UtcDateTime,
     },
     models::{self, can_redact, ActerModel, AnyActerModel},
+    outbox,
     referencing::{IndexKey, SectionIndex},
 };
 use anyhow::{bail, Result};
@@ -19,20 +21,22 @@ use matrix_sdk::ruma::EventId;
 use matrix_sdk_base::{
     ruma::{
         events::{room::message::TextMessageEventContent, MessageLikeEventType},
-        OwnedEventId, OwnedRoomId, OwnedUserId,
+        OwnedEventId, OwnedRoomId, OwnedUserId, UserId,
     },
     RoomState,
 };
 use std::{
     collections::{hash_map::Entry, HashMap},
     ops::Deref,
+    str::FromStr,
 };
 use tokio::sync::broadcast::Receiver;
 use tokio_stream::{wrappers::BroadcastStream, Stream};
 use tracing::warn;
 
 use super::{
-    client::Client, common::OptionRsvpStatus, deep_linking::RefDetails, spaces::Space, RUNTIME,
+    client::Client, common::OptionRsvpStatus, deep_linking::RefDetails, history::EditHistory,
+    spaces::Space, RUNTIME,
 };
 
 impl Client {
@@ -91,11 +95,14 @@ pub struct CalendarEvent {
     client: Client,
     room: Room,
     inner: models::CalendarEvent,
+    /// the specific occurrence of a recurring event this refers to
+    occurrence: Option<models::CalendarEventOccurrence>,
 }
 
 impl PartialEq for CalendarEvent {
     fn eq(&self, other: &Self) -> bool {
         self.inner.event_id() == other.inner.event_id()
+            && self.occurrence_id() == other.occurrence_id()
     }
 }
 
@@ -109,7 +116,7 @@ impl PartialOrd for CalendarEvent {
 
 impl Ord for CalendarEvent {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.inner.utc_start.cmp(&other.inner.utc_start)
+        self.utc_start().cmp(&other.utc_start())
     }
 }
 
@@ -126,6 +133,11 @@ impl CalendarEvent {
         self.inner.event_id().to_owned()
     }
 
+    /// whether this wasn’t sent yet but is shown from the outbox
+    pub fn is_local_echo(&self) -> bool {
+        outbox::is_local_echo(self.inner.event_id())
+    }
+
     pub fn room_id_str(&self) -> String {
         self.inner.room_id().to_string()
     }
@@ -133,6 +145,37 @@ impl CalendarEvent {
     pub fn sender(&self) -> OwnedUserId {
         self.inner.sender().to_owned()
     }
+
+    pub fn title(&self) -> String {
+        self.occurrence
+            .as_ref()
+            .and_then(|o| o.title.clone())
+            .unwrap_or_else(|| self.inner.title())
+    }
+
+    pub fn utc_start(&self) -> UtcDateTime {
+        self.occurrence
+            .as_ref()
+            .map(|o| o.utc_start)
+            .unwrap_or_else(|| self.inner.utc_start())
+    }
+
+    pub fn utc_end(&self) -> UtcDateTime {
+        self.occurrence
+            .as_ref()
+            .map(|o| o.utc_end)
+            .unwrap_or_else(|| self.inner.utc_end())
+    }
+
+    /// the recurrence rule in RFC 5545 RRULE format
+    pub fn recurrence_rule(&self) -> Option<String> {
+        self.inner.recurrence_rule.as_ref().map(ToString::to_string)
+    }
+
+    /// the original start of the occurrence this refers to, if recurring
+    pub fn occurrence_id(&self) -> Option<UtcDateTime> {
+        self.occurrence.as_ref().map(|o| o.recurrence_id)
+    }
 }
 
 /// Custom functions
@@ -142,24 +185,88 @@ impl CalendarEvent {
             client,
             room,
             inner,
+            occurrence: None,
+        }
+    }
+
+    /// refer to the specific occurrence, ignored for non-recurring events
+    pub(crate) fn with_occurrence(
+        client: Client,
+        room: Room,
+        inner: models::CalendarEvent,
+        occurrence: models::CalendarEventOccurrence,
+    ) -> Self {
+        let occurrence = inner.is_recurring().then_some(occurrence);
+        CalendarEvent {
+            client,
+            room,
+            inner,
+            occurrence,
         }
     }
 
+    /// the next `count` occurrences from now on
+    pub fn next_occurrences(&self, count: u32) -> Vec<CalendarEvent> {
+        let now = chrono::Utc::now();
+        self.inner
+            .occurrences_after(&now, count as usize)
+            .into_iter()
+            .map(|o| {
+                CalendarEvent::with_occurrence(
+                    self.client.clone(),
+                    self.room.clone(),
+                    self.inner.clone(),
+                    o,
+                )
+            })
+            .collect()
+    }
+
     pub async fn refresh(&self) -> Result<CalendarEvent> {
         let key = self.inner.event_id().to_owned();
         let client = self.client.clone();
         let room = self.room.clone();
+        let occurrence_id = self.occurrence_id();
 
         RUNTIME
             .spawn(async move {
                 let AnyActerModel::CalendarEvent(inner) = client.store().get(&key).await? else {
                     bail!("Refreshing failed. {key} not a calendar_event")
                 };
-                Ok(CalendarEvent::new(client, room, inner))
+                let occurrence = occurrence_id.and_then(|id| inner.occurrence(&id));
+                Ok(CalendarEvent {
+                    client,
+                    room,
+                    inner,
+                    occurrence,
+                })
             })
             .await?
     }
 
+    /// the creation and all updates of this calendar event, oldest first
+    pub async fn edit_history(&self) -> Result<EditHistory> {
+        self.client
+            .edit_history_of(self.inner.event_id().to_owned())
+            .await
+    }
+
+    /// this calendar event as it was right after the given update
+    pub async fn state_as_of(&self, event_id: String) -> Result<CalendarEvent> {
+        let key = self.inner.event_id().to_owned();
+        let event_id = EventId::parse(event_id)?;
+        let AnyActerModel::CalendarEvent(inner) = self.client.state_as_of(key, event_id).await?
+        else {
+            bail!("Not a calendar event");
+        };
+        Ok(CalendarEvent {
+            client: self.client.clone(),
+            room: self.room.clone(),
+            inner,
+            occurrence: None,
+        })
+    }
+
     pub async fn can_redact(&self) -> Result<bool> {
         let sender = self.inner.sender().to_owned();
         let room = self.room.clone();
@@ -173,6 +280,14 @@ impl CalendarEvent {
         matches!(self.room.state(), RoomState::Joined)
     }
 
+    /// whether the current user may update this calendar event, room moderators always may
+    pub async fn can_edit(&self) -> Result<bool> {
+        if !self.is_joined() {
+            return Ok(false);
+        }
+        self.client.may_edit(self.inner.event_id().to_owned()).await
+    }
+
     pub fn update_builder(&self) -> Result<CalendarEventUpdateBuilder> {
         if !self.is_joined() {
             bail!("Can only update calendar_events in joined rooms");
@@ -181,6 +296,7 @@ impl CalendarEvent {
             client: self.client.clone(),
             room: self.room.clone(),
             inner: self.inner.updater(),
+            recurrence_overrides: self.inner.recurrence_overrides.clone(),
         })
     }
 
@@ -207,11 +323,12 @@ impl CalendarEvent {
         crate::AttachmentsManager::new(client, room, event_id).await
     }
 
+    /// the RSVP manager, for recurring events scoped to this occurrence
     pub async fn rsvps(&self) -> Result<crate::RsvpManager> {
         let client = self.client.clone();
         let room = self.room.clone();
         let event_id = self.inner.event_id().to_owned();
-        crate::RsvpManager::new(client, room, event_id).await
+        crate::RsvpManager::new(client, room, event_id, self.occurrence_id()).await
     }
 
     pub async fn reactions(&self) -> Result<crate::ReactionManager> {
@@ -274,7 +391,7 @@ impl CalendarEvent {
     }
 
     pub fn ical_for_sharing(&self, file_name: String) -> Result<bool> {
-        let ical_data: String = (&iCalendar::from([self.inner.as_ical_event()])).try_into()?;
+        let ical_data: String = (&iCalendar::from_iter(self.inner.as_ical_events())).try_into()?;
         std::fs::write(file_name, ical_data)?;
         Ok(true)
     }
@@ -284,8 +401,8 @@ impl CalendarEvent {
         let client = self.client.deref().clone();
         let target_id = self.inner.event_id().to_owned();
         let room_id = self.room.room_id().to_owned();
-        let title = self.inner.title.clone();
-        let start_at_utc = self.inner.utc_start;
+        let title = self.title();
+        let start_at_utc = self.utc_start();
         let participants = self.participants().await?.len() as u32;
 
         RUNTIME
@@ -378,6 +495,25 @@ impl CalendarEventDraft {
         Ok(())
     }
 
+    /// set the recurrence rule in RFC 5545 RRULE format, e.g. `FREQ=WEEKLY;BYDAY=MO`
+    pub fn recurrence_rule(&mut self, rrule: String) -> Result<()> {
+        let rule = RecurrenceRule::from_str(&rrule)?;
+        self.inner.recurrence_rule(Some(rule));
+        Ok(())
+    }
+
+    pub fn unset_recurrence_rule(&mut self) -> &mut Self {
+        self.inner.recurrence_rule(None);
+        self
+    }
+
+    /// skip the occurrence originally starting at the given rfc3339 time
+    pub fn exclude_occurrence_from_rfc3339(&mut self, recurrence_id: String) -> Result<()> {
+        let dt: UtcDateTime = DateTime::parse_from_rfc3339(&recurrence_id)?.into();
+        self.inner.exclude_occurrence(dt);
+        Ok(())
+    }
+
     #[allow(clippy::too_many_arguments)]
     pub fn add_physical_location(
         &mut self,
@@ -431,6 +567,27 @@ impl CalendarEventDraft {
         self
     }
 
+    /// only allow the author and the given moderators to update this calendar event
+    #[allow(clippy::ptr_arg)]
+    pub fn restrict_edits(&mut self, moderators: &mut Vec<String>) -> Result<()> {
+        let moderators = moderators
+            .iter()
+            .map(UserId::parse)
+            .collect::<Result<Vec<_>, _>>()?;
+        self.inner
+            .edit_rule(EditRule::author_and_moderators(moderators));
+        Ok(())
+    }
+
+    /// Queue the event to be sent once online, returning the id it is
+    /// shown under until then
+    pub async fn queue(&self) -> Result<String> {
+        let inner = self.inner.build()?;
+        self.client
+            .queue_acter_event(self.room.clone(), inner)
+            .await
+    }
+
     pub async fn send(&self) -> Result<OwnedEventId> {
         let room = self.room.clone();
         let my_id = self.client.user_id()?;
@@ -456,6 +613,8 @@ pub struct CalendarEventUpdateBuilder {
     client: Client,
     room: Room,
     inner: calendar_events::CalendarEventUpdateBuilder,
+    /// the current overrides, we can only replace them as a whole
+    recurrence_overrides: Vec<RecurrenceOverride>,
 }
 
 impl CalendarEventUpdateBuilder {
@@ -538,6 +697,66 @@ impl CalendarEventUpdateBuilder {
         self
     }
 
+    /// set the recurrence rule in RFC 5545 RRULE format, e.g. `FREQ=WEEKLY;BYDAY=MO`
+    pub fn recurrence_rule(&mut self, rrule: String) -> Result<()> {
+        let rule = RecurrenceRule::from_str(&rrule)?;
+        self.inner.recurrence_rule(Some(Some(rule)));
+        Ok(())
+    }
+
+    /// stop this event from recurring
+    pub fn unset_recurrence_rule(&mut self) -> &mut Self {
+        self.inner.recurrence_rule(Some(None));
+        self
+    }
+
+    pub fn unset_recurrence_rule_update(&mut self) -> &mut Self {
+        self.inner.recurrence_rule(None);
+        self
+    }
+
+    /// skip the occurrence originally starting at the given rfc3339 time
+    pub fn exclude_occurrence_from_rfc3339(&mut self, recurrence_id: String) -> Result<()> {
+        let dt: UtcDateTime = DateTime::parse_from_rfc3339(&recurrence_id)?.into();
+        self.recurrence_overrides.retain(|o| o.recurrence_id != dt);
+        self.recurrence_overrides
+            .push(RecurrenceOverride::excluded(dt));
+        self.inner
+            .recurrence_overrides(Some(self.recurrence_overrides.clone()));
+        Ok(())
+    }
+
+    /// move the occurrence originally starting at `recurrence_id` to a new time, all in rfc3339
+    pub fn move_occurrence_from_rfc3339(
+        &mut self,
+        recurrence_id: String,
+        utc_start: String,
+        utc_end: String,
+    ) -> Result<()> {
+        let recurrence_id: UtcDateTime = DateTime::parse_from_rfc3339(&recurrence_id)?.into();
+        let utc_start: UtcDateTime = DateTime::parse_from_rfc3339(&utc_start)?.into();
+        let utc_end: UtcDateTime = DateTime::parse_from_rfc3339(&utc_end)?.into();
+        self.recurrence_overrides
+            .retain(|o| o.recurrence_id != recurrence_id);
+        self.recurrence_overrides.push(RecurrenceOverride {
+            recurrence_id,
+            excluded: false,
+            utc_start: Some(utc_start),
+            utc_end: Some(utc_end),
+            title: None,
+        });
+        self.inner
+            .recurrence_overrides(Some(self.recurrence_overrides.clone()));
+        Ok(())
+    }
+
+    /// drop all exclusions and changes to single occurrences
+    pub fn unset_recurrence_overrides(&mut self) -> &mut Self {
+        self.recurrence_overrides.clear();
+        self.inner.recurrence_overrides(Some(vec![]));
+        self
+    }
+
     #[allow(clippy::too_many_arguments)]
     pub fn add_physical_location(
         &mut self,
@@ -601,6 +820,14 @@ impl CalendarEventUpdateBuilder {
         self
     }
 
+    /// Queue the update to be sent once online, returning its transaction id
+    pub async fn queue(&self) -> Result<String> {
+        let inner = self.inner.build()?;
+        self.client
+            .queue_acter_event(self.room.clone(), inner)
+            .await
+    }
+
     pub async fn send(&self) -> Result<OwnedEventId> {
         let room = self.room.clone();
         let my_id = self.client.user_id()?;
@@ -1,11 +1,11 @@
 use acter_core::{
     client::CoreClient,
     executor::Executor,
-    models::AnyActerModel,
+    models::{may_edit, AnyActerModel},
     referencing::{
         ExecuteReference, IndexKey, ModelParam, ObjectListIndex, RoomParam, SectionIndex,
     },
-    store::Store,
+    store::{SqliteModelStore, Store, DATA_PATH_KEY},
     templates::Engine,
     CustomAuthSession, RestoreToken,
 };
@@ -22,9 +22,9 @@ use matrix_sdk::{room::Room as SdkRoom, Client as SdkClient};
 use matrix_sdk_base::{
     media::{MediaRequestParameters, UniqueKey},
     ruma::{
-        device_id, events::room::MediaSource, OwnedDeviceId, OwnedMxcUri, OwnedRoomAliasId,
-        OwnedRoomId, OwnedRoomOrAliasId, OwnedServerName, OwnedUserId, RoomAliasId, RoomId,
-        RoomOrAliasId, UserId,
+        device_id, events::room::MediaSource, OwnedDeviceId, OwnedEventId, OwnedMxcUri,
+        OwnedRoomAliasId, OwnedRoomId, OwnedRoomOrAliasId, OwnedServerName, OwnedUserId,
+        RoomAliasId, RoomId, RoomOrAliasId, UserId,
     },
     RoomStateFilter,
 };
@@ -39,17 +39,23 @@ use tracing::{error, trace};
 
 use crate::{Account, Convo, OptionString, Room, Space, ThumbnailSize, RUNTIME};
 
+use super::utils::{remap_for_diff, ApiVectorDiff};
+
 use super::{
     api::FfiBuffer, device::DeviceController, typing::TypingController,
     verification::VerificationController, VecStringBuilder,
 };
 
+mod backfill;
 mod models;
+mod sliding_sync;
 mod sync;
 mod url_preview;
 pub use sync::{HistoryLoadState, SyncState};
 pub use url_preview::LocalUrlPreview;
 
+use backfill::BackfillController;
+
 #[derive(Default, Builder, Debug)]
 pub struct ClientState {
     #[builder(default)]
@@ -75,6 +81,7 @@ pub struct Client {
     pub(crate) verification_controller: VerificationController,
     pub(crate) device_controller: DeviceController,
     pub(crate) typing_controller: TypingController,
+    pub(crate) backfill: BackfillController,
     pub spaces: Arc<RwLock<ObservableVector<Space>>>,
     pub convos: Arc<RwLock<ObservableVector<Convo>>>,
 }
@@ -176,10 +183,24 @@ impl Client {
     }
 }
 
+/// Changes to a list of objects, by their event id
+pub type ModelIdDiff = ApiVectorDiff<String>;
+
 // external API
 impl Client {
     pub async fn new(client: SdkClient, state: ClientState) -> Result<Self> {
-        let core = CoreClient::new(client.clone()).await?;
+        let db_path = client
+            .state_store()
+            .get_custom_value(DATA_PATH_KEY.as_bytes())
+            .await?;
+        let core = match db_path {
+            Some(path) => {
+                let path = PathBuf::from(String::from_utf8(path)?);
+                let db = SqliteModelStore::open(&path, state.db_passphrase.as_deref())?;
+                CoreClient::new_with_model_db(client.clone(), db).await?
+            }
+            None => CoreClient::new(client.clone()).await?,
+        };
         let mut cl = Client {
             core: core.clone(),
             state: Arc::new(RwLock::new(state)),
@@ -188,6 +209,7 @@ impl Client {
             verification_controller: VerificationController::new(),
             device_controller: DeviceController::new(client),
             typing_controller: TypingController::new(),
+            backfill: BackfillController::new(),
         };
         cl.load_from_cache().await;
         cl.setup_handlers();
@@ -246,6 +268,22 @@ impl Client {
         self.core.executor()
     }
 
+    /// Whether the current user may update the object under its edit rule,
+    /// room moderators always may
+    pub(crate) async fn may_edit(&self, object_id: OwnedEventId) -> Result<bool> {
+        let me = self.clone();
+        let user_id = self.user_id()?;
+        RUNTIME
+            .spawn(async move { Ok(may_edit(me.store(), &object_id, &user_id).await?) })
+            .await?
+    }
+
+    pub fn set_pending_update_expiry(&self, secs: u64) {
+        self.core
+            .executor()
+            .set_pending_expiry(Duration::from_secs(secs));
+    }
+
     pub async fn template_engine(&self, template: &str) -> Result<Engine> {
         let engine = self.core.template_engine(template).await?;
         Ok(engine)
@@ -398,6 +436,57 @@ impl Client {
         Ok(BroadcastStream::new(self.subscribe(index)).map(|_| true))
     }
 
+    /// The event ids listed at `index` now, followed by the changes to them
+    pub fn subscribe_index_diff(&self, index: IndexKey) -> Result<impl Stream<Item = ModelIdDiff>> {
+        let store = self.store().clone();
+        Ok(async_stream::stream! {
+            // the index might have to be loaded from the database first
+            let subscription = RUNTIME
+                .spawn(async move { anyhow::Ok(store.subscribe_index_diff(&index).await?) })
+                .await;
+            let (current, mut stream) = match subscription.map_err(anyhow::Error::from) {
+                Ok(Ok(subscription)) => subscription,
+                Ok(Err(error)) | Err(error) => {
+                    error!(?error, "subscribing to index failed");
+                    return;
+                }
+            };
+            yield ModelIdDiff::current_items(current.iter().map(ToString::to_string).collect());
+            while let Some(diff) = stream.next().await {
+                yield remap_for_diff(diff, |id| id.to_string());
+            }
+        })
+    }
+
+    pub fn subscribe_section_diff_stream(
+        &self,
+        section: String,
+    ) -> Result<impl Stream<Item = ModelIdDiff>> {
+        self.subscribe_index_diff(IndexKey::Section(SectionIndex::from_str(&section)?))
+    }
+
+    pub fn subscribe_room_section_diff_stream(
+        &self,
+        key: String,
+        section: String,
+    ) -> Result<impl Stream<Item = ModelIdDiff>> {
+        self.subscribe_index_diff(IndexKey::RoomSection(
+            RoomId::parse(key)?,
+            SectionIndex::from_str(&section)?,
+        ))
+    }
+
+    pub fn subscribe_model_objects_diff_stream(
+        &self,
+        key: String,
+        sublist: String,
+    ) -> Result<impl Stream<Item = ModelIdDiff>> {
+        self.subscribe_index_diff(IndexKey::ObjectList(
+            EventId::parse(key)?,
+            ObjectListIndex::from_str(&sublist)?,
+        ))
+    }
+
     pub fn subscribe_model_stream(&self, key: String) -> Result<impl Stream<Item = bool>> {
         let model_id = EventId::parse(key)?;
         Ok(BroadcastStream::new(self.subscribe(model_id)).map(|_| true))
@@ -0,0 +1,77 @@
+use futures_signals::signal::{Mutable, SignalExt};
+use std::time::Duration;
+use tracing::trace;
+
+use super::Client;
+
+/// How many spaces to load the history of at the same time, unless configured
+const DEFAULT_CONCURRENCY: usize = 4;
+
+/// Settings and pause switch of the history backfill of spaces
+#[derive(Clone, Debug)]
+pub(crate) struct BackfillController {
+    paused: Mutable<bool>,
+    concurrency: Mutable<usize>,
+    /// load only that many days first, and older history after all spaces caught up
+    recent_days: Mutable<Option<u32>>,
+}
+
+impl BackfillController {
+    pub fn new() -> Self {
+        BackfillController {
+            paused: Mutable::new(false),
+            concurrency: Mutable::new(DEFAULT_CONCURRENCY),
+            recent_days: Mutable::new(None),
+        }
+    }
+
+    pub fn concurrency(&self) -> usize {
+        self.concurrency.get()
+    }
+
+    /// How far back the history of a space is loaded at first
+    pub fn recent_window(&self) -> Option<Duration> {
+        self.recent_days
+            .get()
+            .map(|days| Duration::from_secs(u64::from(days) * 24 * 60 * 60))
+    }
+
+    /// Resolves right away, or once the backfill is resumed
+    pub async fn wait_if_paused(&self) {
+        if self.paused.get() {
+            trace!("history backfill paused");
+            self.paused.signal().wait_for(false).await;
+            trace!("history backfill resumed");
+        }
+    }
+}
+
+// external API
+impl Client {
+    /// Hold loading the history of spaces after the current page of events
+    pub fn pause_history_backfill(&self) {
+        self.backfill.paused.set(true);
+    }
+
+    /// Continue loading the history of spaces where it was paused
+    pub fn resume_history_backfill(&self) {
+        self.backfill.paused.set(false);
+    }
+
+    pub fn is_history_backfill_paused(&self) -> bool {
+        self.backfill.paused.get()
+    }
+
+    /// How many spaces to load the history of at the same time
+    ///
+    /// Applies to the spaces loaded after this change.
+    pub fn set_history_backfill_concurrency(&self, concurrency: u32) {
+        self.backfill.concurrency.set((concurrency as usize).max(1));
+    }
+
+    /// Load only the last `days` of spaces never loaded before at first, and
+    /// their older history once all spaces caught up; `0` loads all at once
+    pub fn set_history_backfill_recent_days(&self, days: u32) {
+        self.backfill.recent_days.set((days > 0).then_some(days));
+    }
+}
@@ -0,0 +1,259 @@
+use acter_core::referencing::ExecuteReference;
+use futures::stream::{self, StreamExt};
+use matrix_sdk::{
+    event_handler::EventHandlerHandle,
+    ruma::{
+        events::{AnyGlobalAccountDataEvent, StateEventType},
+        serde::Raw,
+        uint, OwnedRoomId, RoomId,
+    },
+    sliding_sync::http::request::RoomSubscription,
+    sync::RoomUpdates,
+    RumaApiError, SessionChange, SlidingSync,
+};
+use matrix_sdk_ui::{
+    room_list_service::State as RoomListState,
+    sync_service::{State, SyncService},
+};
+use std::{borrow::Cow, collections::BTreeSet, time::Duration};
+use tokio_stream::wrappers::{errors::BroadcastStreamRecvError, BroadcastStream};
+use tracing::{error, info, trace, warn};
+
+use crate::RUNTIME;
+
+use super::{
+    sync::{SyncError, SyncState},
+    Client,
+};
+
+static SPACES_SYNC_ID: &str = "acter-spaces";
+
+/// What the sliding sync loop reacts upon
+enum SyncUpdate {
+    Rooms(Result<RoomUpdates, BroadcastStreamRecvError>),
+    Service(State),
+    RoomList(RoomListState),
+    Session(Result<SessionChange, BroadcastStreamRecvError>),
+    Spaces(matrix_sdk::Result<()>),
+}
+
+/// All the state of a space and its latest events
+///
+/// The room list only asks for the few state events needed to render the
+/// list, not enough to tell acter spaces apart or to show their settings.
+fn space_subscription() -> RoomSubscription {
+    let mut subscription = RoomSubscription::default();
+    subscription.required_state = vec![(StateEventType::from("*"), "*".to_owned())];
+    subscription.timeline_limit = uint!(20);
+    subscription
+}
+
+// internal API
+impl Client {
+    async fn spaces_sliding_sync(&self) -> matrix_sdk::Result<SlidingSync> {
+        self.core
+            .client()
+            .sliding_sync(SPACES_SYNC_ID)?
+            .poll_timeout(Duration::from_secs(25))
+            .build()
+            .await
+    }
+
+    /// Subscribe to the full state of the spaces we haven’t subscribed to yet
+    fn subscribe_to_new_spaces(
+        &self,
+        spaces_sync: &SlidingSync,
+        subscribed: &mut BTreeSet<OwnedRoomId>,
+    ) {
+        let new_spaces = self
+            .core
+            .client()
+            .joined_rooms()
+            .into_iter()
+            .filter(|room| room.is_space() && !subscribed.contains(room.room_id()))
+            .map(|room| room.room_id().to_owned())
+            .collect::<Vec<_>>();
+        if new_spaces.is_empty() {
+            return;
+        }
+        trace!(?new_spaces, "prioritizing spaces");
+        let room_ids = new_spaces
+            .iter()
+            .map(AsRef::as_ref)
+            .collect::<Vec<&RoomId>>();
+        spaces_sync.subscribe_to_rooms(&room_ids, Some(space_subscription()), true);
+        subscribed.extend(new_spaces);
+    }
+
+    /// The room list service only hands out room updates, global account data
+    /// is picked up by the client and passed on to the listeners from here
+    fn notify_global_account_data(&self) -> EventHandlerHandle {
+        let me = self.clone();
+        self.core
+            .client()
+            .add_event_handler(move |raw: Raw<AnyGlobalAccountDataEvent>| {
+                let me = me.clone();
+                async move {
+                    if let Ok(Some(key)) = raw.get_field::<String>("type") {
+                        trace!(key, "global account data changed");
+                        me.executor()
+                            .notify(vec![ExecuteReference::AccountData(Cow::Owned(key))]);
+                    }
+                }
+            })
+    }
+}
+
+// external API
+impl Client {
+    /// Start syncing through sliding sync rather than the `/sync` loop
+    ///
+    /// Rooms come in through the room list service, while spaces get their
+    /// own subscription with all their state, so the space list is usable
+    /// before the rest of the rooms arrived.
+    pub fn start_sliding_sync(&mut self) -> SyncState {
+        info!("starting sliding sync");
+        let state = self.state.clone();
+        let me = self.clone();
+        let client = self.core.client().clone();
+
+        let (sync_state, first_synced_arc, sync_error_arc) = self.prepare_sync();
+        let history_loading = sync_state.history_loading.clone();
+        let first_sync_task = sync_state.first_sync_task.clone();
+
+        let handle = RUNTIME.spawn(async move {
+            info!("spawning sliding sync");
+            // subscribe before syncing to not miss the first updates
+            let room_updates = BroadcastStream::new(client.subscribe_to_all_room_updates());
+
+            let sync_service = match SyncService::builder(client.clone()).build().await {
+                Ok(sync_service) => sync_service,
+                Err(error) => {
+                    error!(?error, "Setting up the sync service failed");
+                    sync_error_arc.send(SyncError::Other {
+                        msg: Some(error.to_string()),
+                    });
+                    return;
+                }
+            };
+            let spaces_sync = match me.spaces_sliding_sync().await {
+                Ok(spaces_sync) => spaces_sync,
+                Err(error) => {
+                    error!(?error, "Setting up the spaces sync failed");
+                    sync_error_arc.send(SyncError::Other {
+                        msg: Some(error.to_string()),
+                    });
+                    return;
+                }
+            };
+
+            // the spaces we already know from previous runs go first
+            let mut subscribed = BTreeSet::new();
+            me.subscribe_to_new_spaces(&spaces_sync, &mut subscribed);
+
+            let account_data_handler = me.notify_global_account_data();
+            sync_service.start().await;
+
+            let mut updates = stream::select_all([
+                room_updates.map(SyncUpdate::Rooms).boxed(),
+                sync_service.state().map(SyncUpdate::Service).boxed(),
+                sync_service
+                    .room_list_service()
+                    .state()
+                    .map(SyncUpdate::RoomList)
+                    .boxed(),
+                BroadcastStream::new(client.subscribe_to_session_changes())
+                    .map(SyncUpdate::Session)
+                    .boxed(),
+                spaces_sync
+                    .sync()
+                    .map(|r| SyncUpdate::Spaces(r.map(|_| ())))
+                    .boxed(),
+            ]);
+
+            // the sync service doesn’t tell why it failed, the same problem most
+            // likely hit the spaces sync as well
+            let mut last_error = None;
+            let mut is_first = true;
+            while let Some(update) = updates.next().await {
+                let rooms = match update {
+                    SyncUpdate::Rooms(Ok(rooms)) => rooms,
+                    SyncUpdate::Rooms(Err(BroadcastStreamRecvError::Lagged(skipped))) => {
+                        warn!(skipped, "Missed room updates");
+                        continue;
+                    }
+                    SyncUpdate::Service(State::Error) => {
+                        error!("Sync service failed");
+                        sync_error_arc.send(last_error.take().unwrap_or(SyncError::Other {
+                            msg: Some("Sync service failed".to_owned()),
+                        }));
+                        break;
+                    }
+                    SyncUpdate::Service(service_state) => {
+                        trace!(?service_state, "sync service state changed");
+                        continue;
+                    }
+                    SyncUpdate::RoomList(RoomListState::Running) if is_first => {
+                        // the full room list arrived, not just the first rooms of it
+                        is_first = false;
+                        let sync_keys = client
+                            .joined_rooms()
+                            .iter()
+                            .map(|room| room.room_id().to_owned())
+                            .collect();
+                        me.handle_first_sync(
+                            sync_keys,
+                            &first_synced_arc,
+                            first_sync_task.clone(),
+                            history_loading.clone(),
+                        );
+                        continue;
+                    }
+                    SyncUpdate::RoomList(room_list_state) => {
+                        trace!(?room_list_state, "room list state changed");
+                        continue;
+                    }
+                    SyncUpdate::Session(Ok(SessionChange::UnknownToken { soft_logout })) => {
+                        error!(soft_logout, "Session no longer valid");
+                        sync_error_arc.send(SyncError::Unauthorized { soft_logout });
+                        break;
+                    }
+                    SyncUpdate::Session(_) => continue,
+                    SyncUpdate::Spaces(Err(error)) => {
+                        if let Some(RumaApiError::ClientApi(e)) = error.as_ruma_api_error() {
+                            last_error = Some(SyncError::from(e));
+                        }
+                        // the room list keeps syncing, spaces just lose their priority
+                        warn!(?error, "Spaces sync failed");
+                        continue;
+                    }
+                    SyncUpdate::Spaces(Ok(())) => continue,
+                };
+
+                // rooms just listed may be spaces to prioritize
+                me.subscribe_to_new_spaces(&spaces_sync, &mut subscribed);
+
+                if !is_first {
+                    // those known by then are caught up on with the first sync
+                    me.catch_up_on_new_spaces(&rooms, history_loading.clone())
+                        .await;
+                }
+                me.notify_room_updates(&rooms).await;
+
+                if !me.keep_syncing() {
+                    break;
+                }
+            }
+            drop(updates);
+            client.remove_event_handler(account_data_handler);
+            sync_service.stop().await;
+            trace!("sliding sync stopped");
+
+            if let Ok(mut w) = state.try_write() {
+                w.is_syncing = false;
+            };
+        });
+        sync_state.handle.set(Some(handle));
+        sync_state
+    }
+}
@@ -5,14 +5,13 @@ use acter_core::{
 use anyhow::Result;
 use core::time::Duration;
 use futures::{
-    future::join_all,
     pin_mut,
-    stream::{Stream, StreamExt},
+    stream::{self, Stream, StreamExt},
 };
 use futures_signals::signal::{Mutable, MutableSignalCloned, SignalExt, SignalStream};
 use matrix_sdk::{
     config::SyncSettings, deserialized_responses::TimelineEventKind, event_handler::Ctx,
-    room::Room as SdkRoom, RumaApiError,
+    room::Room as SdkRoom, sync::RoomUpdates, RumaApiError,
 };
 use matrix_sdk_base::{
     ruma::{
@@ -21,7 +20,7 @@ use matrix_sdk_base::{
             Error,
         },
         events::room::redaction::{RoomRedactionEvent, SyncRoomRedactionEvent},
-        OwnedRoomId,
+        OwnedRoomId, RoomId,
     },
     RoomState,
 };
@@ -38,7 +37,7 @@ use std::{
 };
 use tokio::{
     sync::{
-        broadcast::{channel, Receiver},
+        broadcast::{channel, Receiver, Sender},
         RwLockWriteGuard,
     },
     task::JoinHandle,
@@ -46,7 +45,7 @@ use tokio::{
 use tokio_stream::wrappers::BroadcastStream;
 use tracing::{error, info, trace, warn};
 
-use crate::{Convo, Room, Space, RUNTIME};
+use crate::{Convo, HistoryProgress, Room, Space, RUNTIME};
 
 use super::Client;
 
@@ -55,6 +54,7 @@ pub struct HistoryLoadState {
     pub has_started: bool,
     loading_spaces: BTreeMap<OwnedRoomId, bool>,
     done_spaces: Vec<OwnedRoomId>,
+    progress: BTreeMap<OwnedRoomId, HistoryProgress>,
 }
 
 // internal API
@@ -71,6 +71,11 @@ impl HistoryLoadState {
     fn forget_room(&mut self, room_id: &OwnedRoomId) {
         self.loading_spaces.remove(room_id);
         self.done_spaces.retain(|v| v != room_id);
+        self.progress.remove(room_id);
+    }
+
+    pub(crate) fn update_progress(&mut self, room_id: OwnedRoomId, progress: HistoryProgress) {
+        self.progress.insert(room_id, progress);
     }
 
     fn knows_room(&self, room_id: &OwnedRoomId) -> bool {
@@ -103,6 +108,19 @@ impl HistoryLoadState {
     pub fn total_spaces(&self) -> usize {
         self.loading_spaces.len() + self.done_spaces.len()
     }
+
+    /// How far loading the history of the given space got
+    pub fn space_progress(&self, room_id: &RoomId) -> Option<&HistoryProgress> {
+        self.progress.get(room_id)
+    }
+
+    /// The sum of events processed over all spaces
+    pub fn events_processed(&self) -> u64 {
+        self.progress
+            .values()
+            .map(HistoryProgress::events_processed)
+            .sum()
+    }
 }
 
 #[derive(Clone)]
@@ -148,11 +166,12 @@ impl SyncError {
 
 #[derive(Clone)]
 pub struct SyncState {
-    handle: Mutable<Option<JoinHandle<()>>>,
-    first_sync_task: Mutable<Option<JoinHandle<Result<()>>>>,
+    pub(super) handle: Mutable<Option<JoinHandle<()>>>,
+    pub(super) first_sync_task: Mutable<Option<JoinHandle<Result<()>>>>,
     first_synced_rx: Arc<Receiver<bool>>,
     sync_error: Arc<Receiver<SyncError>>,
-    history_loading: Mutable<HistoryLoadState>,
+    pub(super) history_loading: Mutable<HistoryLoadState>,
+    outbox_worker: Mutable<Option<JoinHandle<()>>>,
 }
 
 impl SyncState {
@@ -163,6 +182,7 @@ impl SyncState {
             history_loading: Default::default(),
             first_sync_task: Default::default(),
             handle: Default::default(),
+            outbox_worker: Default::default(),
         }
     }
 
@@ -212,6 +232,9 @@ impl SyncState {
         if let Some(handle) = self.handle.replace(None) {
             handle.abort();
         }
+        if let Some(handle) = self.outbox_worker.replace(None) {
+            handle.abort();
+        }
     }
 }
 
@@ -254,6 +277,163 @@ impl Client {
         );
     }
 
+    /// Register the handlers and channels both sync modes need
+    pub(super) fn prepare_sync(
+        &mut self,
+    ) -> (SyncState, Arc<Sender<bool>>, Arc<Sender<SyncError>>) {
+        let client = self.core.client().clone();
+        self.typing_controller.add_event_handler(&client);
+
+        self.verification_controller
+            .add_to_device_event_handler(&client);
+        // sync event is the event that my device was off so it may be timed out possibly
+        // in fact, when user opens app, he sees old verification popup sometimes
+        // in order to avoid this issue, comment out sync event
+        self.verification_controller.add_sync_event_handler(&client);
+
+        let (first_synced_tx, first_synced_rx) = channel(1);
+        let (sync_error_tx, sync_error_rx) = channel(1);
+        let sync_state = SyncState::new(first_synced_rx, sync_error_rx);
+        // send what was queued while offline as soon as we are syncing
+        sync_state
+            .outbox_worker
+            .set(Some(self.spawn_outbox_worker()));
+        (
+            sync_state,
+            Arc::new(first_synced_tx),
+            Arc::new(sync_error_tx),
+        )
+    }
+
+    /// Inform about the first sync and start loading the history of the spaces found
+    pub(super) fn handle_first_sync(
+        &self,
+        sync_keys: Vec<OwnedRoomId>,
+        first_synced: &Sender<bool>,
+        first_sync_task: Mutable<Option<JoinHandle<Result<()>>>>,
+        history: Mutable<HistoryLoadState>,
+    ) {
+        info!("received first sync");
+        trace!(user_id=?self.user_id_ref(), "initial synced");
+
+        info!("issuing first sync update");
+        first_synced.send(true);
+        if let Ok(mut w) = self.state.try_write() {
+            w.has_first_synced = true;
+        };
+        // background and keep the handle around.
+        self.refresh_history_on_start(sync_keys, first_sync_task, history);
+    }
+
+    /// Load the history of acter spaces we haven’t seen before
+    pub(super) async fn catch_up_on_new_spaces(
+        &self,
+        rooms: &RoomUpdates,
+        history_loading: Mutable<HistoryLoadState>,
+    ) {
+        let mut new_spaces = Vec::new();
+        for room_id in rooms.joined.keys() {
+            if history_loading.lock_mut().knows_room(room_id) {
+                continue;
+            }
+            let Some(full_room) = self.get_room(room_id) else {
+                error!("room not found. how can that be?");
+                continue;
+            };
+            if is_acter_space(&full_room).await {
+                new_spaces.push(full_room);
+            }
+        }
+
+        if !new_spaces.is_empty() {
+            // in the background, so pausing the backfill doesn’t hold up the sync
+            self.refresh_history_on_way(history_loading, new_spaces);
+        }
+    }
+
+    /// Refresh the changed rooms and notify the listeners of what changed
+    pub(super) async fn notify_room_updates(&self, rooms: &RoomUpdates) {
+        let changed_rooms = rooms
+            .joined
+            .keys()
+            .chain(rooms.left.keys())
+            .chain(rooms.invited.keys())
+            .collect::<Vec<&OwnedRoomId>>();
+
+        if changed_rooms.is_empty() {
+            return;
+        }
+        // changes observed, calculate which keys need to be updated
+        trace!(?changed_rooms, "changed rooms");
+        // by first refreshing rooms where necessary
+        let mut updated_room_ids = self.refresh_rooms(changed_rooms).await;
+
+        let mut keys = Vec::new();
+
+        // and then checking if any updates in the joined rooms warrant us notifying
+        for (room_id, updates) in rooms.joined.iter() {
+            if let Some(idx) = updated_room_ids.iter().position(|id| id == room_id) {
+                // we generally notify about this room as it was found above
+                updated_room_ids.remove(idx); // remove the instance to not inform about them twice
+                keys.push(ExecuteReference::Room(room_id.clone()));
+            } else {
+                // only notifiy if any update warrant us notifying
+                if !updates.state.is_empty()
+                    || updates.timeline.events.iter().any(|t| {
+                        let TimelineEventKind::PlainText { event } = &t.kind else {
+                            return false;
+                        };
+                        // check if any event received is a state event
+                        matches!(
+                            event.get_field::<String>("state_key"),
+                            Ok(Some(state_event))
+                        )
+                    })
+                {
+                    // state or at least one item in  the timeline is a state event, we need to notify
+                    trace!(?room_id, "room state changed");
+                    keys.push(ExecuteReference::Room(room_id.clone()));
+                }
+            }
+            // finally, let's see if there is any room account data to inform about
+            keys.extend(updates.account_data.iter().filter_map(|raw| {
+                raw.get_field::<String>("type")
+                    .ok()
+                    .flatten()
+                    .map(|s| ExecuteReference::RoomAccountData(room_id.clone(), Cow::Owned(s)))
+            }));
+        }
+
+        // if there are other room_ids left after clearing the joined, we also want to notify about them
+        keys.extend(
+            updated_room_ids
+                .iter()
+                .map(|id| ExecuteReference::Room(id.clone())),
+        );
+
+        if !keys.is_empty() {
+            info!(?keys, "update notify keys");
+            self.executor().notify(keys);
+        }
+    }
+
+    /// Whether to go on syncing, unless the user asked us to stop
+    pub(super) fn keep_syncing(&self) -> bool {
+        if let Ok(mut w) = self.state.try_write() {
+            if w.should_stop_syncing {
+                w.is_syncing = false;
+                trace!("Stopping syncing upon user request");
+                return false;
+            }
+        }
+        if let Ok(mut w) = self.state.try_write() {
+            if !w.is_syncing {
+                w.is_syncing = true;
+            }
+        }
+        true
+    }
+
     fn refresh_history_on_start(
         &self,
         sync_keys: Vec<OwnedRoomId>,
@@ -270,7 +450,7 @@ impl Client {
 
         *first_sync_inner = Some(tokio::spawn(async move {
             trace!(user_id=?me.user_id_ref(), "refreshing history");
-            let mut spaces = me.spaces().await?;
+            let spaces = me.spaces().await?;
             let initial_space_setup = spaces
                 .iter()
                 .map(|r| r.room_id().to_owned())
@@ -278,70 +458,88 @@ impl Client {
                 .collect();
             history.lock_mut().initialize(initial_space_setup);
 
-            futures::future::join_all(spaces.iter_mut().map(|space| async {
-                let room_id = space.room_id();
-                let is_acter_space = match space.is_acter_space().await {
-                    Ok(b) => b,
-                    Err(error) => {
-                        error!(
-                            ?room_id,
-                            ?error,
-                            "checking for is-acter-space status failed"
-                        );
-                        false
+            let concurrency = me.backfill.concurrency();
+            stream::iter(spaces.iter())
+                .for_each_concurrent(concurrency, |space| async {
+                    let room_id = space.room_id();
+                    let is_acter_space = match space.is_acter_space().await {
+                        Ok(b) => b,
+                        Err(error) => {
+                            error!(
+                                ?room_id,
+                                ?error,
+                                "checking for is-acter-space status failed"
+                            );
+                            false
+                        }
+                    };
+                    if !is_acter_space {
+                        trace!(?room_id, "not an acter space");
+                        history.lock_mut().forget_room(&room_id.to_owned());
+                        return;
                     }
-                };
-                if !is_acter_space {
-                    trace!(?room_id, "not an acter space");
-                    history.lock_mut().forget_room(&room_id.to_owned());
-                    return;
-                }
 
-                if let Err(err) = space.refresh_history().await {
-                    error!(?err, ?room_id, "Loading space history failed");
-                };
+                    if let Err(err) = space.refresh_history(&history).await {
+                        error!(?err, ?room_id, "Loading space history failed");
+                    };
+
+                    history.lock_mut().done_loading(room_id.to_owned());
+                })
+                .await;
 
-                history.lock_mut().done_loading(room_id.to_owned());
-            }))
-            .await;
+            // with all spaces caught up, load what is older than their recent history
+            stream::iter(spaces.iter())
+                .for_each_concurrent(concurrency, |space| async {
+                    if let Err(err) = space.refresh_older_history(&history).await {
+                        let room_id = space.room_id();
+                        error!(?err, ?room_id, "Loading older space history failed");
+                    }
+                })
+                .await;
             // once done, let’s reset the first_sync_task to clear it from memory
             first_sync_task_inner.set(None);
             Ok(())
         }));
     }
 
-    async fn refresh_history_on_way(
-        &self,
-        history: Mutable<HistoryLoadState>,
-        new_spaces: Vec<SdkRoom>,
-    ) -> Result<()> {
+    fn refresh_history_on_way(&self, history: Mutable<HistoryLoadState>, new_spaces: Vec<SdkRoom>) {
         trace!(user_id=?self.user_id_ref(), count=?new_spaces.len(), "found new spaces");
-
-        futures::future::join_all(
-            new_spaces
+        let me = self.clone();
+        tokio::spawn(async move {
+            let concurrency = me.backfill.concurrency();
+            let spaces = new_spaces
                 .into_iter()
-                .map(|room| Space::new(self.clone(), Room::new(self.core.clone(), room)))
-                .map(|mut space| {
-                    let history = history.clone();
-                    async move {
-                        let room_id = space.room_id().to_owned();
-                        {
-                            let mut history = history.lock_mut();
-                            if !history.start_loading(room_id.clone()) {
-                                trace!(?room_id, "Already loading room.");
-                                return;
-                            }
-                        }
-
-                        if let Err(err) = space.refresh_history().await {
-                            error!(?err, ?room_id, "refreshing history failed");
-                        }
-                        history.lock_mut().done_loading(room_id.clone());
+                .map(|room| Space::new(me.clone(), Room::new(me.core.clone(), room)))
+                .filter(|space| {
+                    let room_id = space.room_id().to_owned();
+                    if !history.lock_mut().start_loading(room_id.clone()) {
+                        trace!(?room_id, "Already loading room.");
+                        return false;
                     }
-                }),
-        )
-        .await;
-        Ok(())
+                    true
+                })
+                .collect::<Vec<_>>();
+
+            stream::iter(spaces.iter())
+                .for_each_concurrent(concurrency, |space| async {
+                    let room_id = space.room_id();
+                    if let Err(err) = space.refresh_history(&history).await {
+                        error!(?err, ?room_id, "refreshing history failed");
+                    }
+                    history.lock_mut().done_loading(room_id.to_owned());
+                })
+                .await;
+
+            // the older history only once the recent one of all new spaces is in
+            stream::iter(spaces.iter())
+                .for_each_concurrent(concurrency, |space| async {
+                    if let Err(err) = space.refresh_older_history(&history).await {
+                        let room_id = space.room_id();
+                        error!(?err, ?room_id, "refreshing older history failed");
+                    }
+                })
+                .await;
+        });
     }
 
     async fn refresh_rooms(&self, changed_rooms: Vec<&OwnedRoomId>) -> Vec<OwnedRoomId> {
@@ -461,28 +659,10 @@ impl Client {
         info!("starting sync");
         let state = self.state.clone();
         let me = self.clone();
-        let executor = self.executor().clone();
         let client = self.core.client().clone();
 
-        self.typing_controller.add_event_handler(&client);
-
-        self.verification_controller
-            .add_to_device_event_handler(&client);
-        // sync event is the event that my device was off so it may be timed out possibly
-        // in fact, when user opens app, he sees old verification popup sometimes
-        // in order to avoid this issue, comment out sync event
-        self.verification_controller.add_sync_event_handler(&client);
-
-        let mut device_controller = self.device_controller.clone();
-
-        let (first_synced_tx, first_synced_rx) = channel(1);
-        let first_synced_arc = Arc::new(first_synced_tx);
-
-        let (sync_error_tx, sync_error_rx) = channel(1);
-        let sync_error_arc = Arc::new(sync_error_tx);
-
+        let (sync_state, first_synced_arc, sync_error_arc) = self.prepare_sync();
         let initial = Arc::new(AtomicBool::from(true));
-        let sync_state = SyncState::new(first_synced_rx, sync_error_rx);
         let history_loading = sync_state.history_loading.clone();
         let first_sync_task = sync_state.first_sync_task.clone();
 
@@ -532,106 +712,19 @@ impl Client {
                 if initial.compare_exchange(true, false, Ordering::Relaxed, Ordering::Relaxed)
                     == Ok(true)
                 {
-                    info!("received first sync");
-                    trace!(user_id=?client.user_id(), "initial synced");
-
                     initial.store(false, Ordering::SeqCst);
-
-                    info!("issuing first sync update");
-                    first_synced_arc.send(true);
-                    if let Ok(mut w) = state.try_write() {
-                        w.has_first_synced = true;
-                    };
                     let sync_keys = response.rooms.joined.keys().cloned().collect();
-                    // background and keep the handle around.
-                    me.refresh_history_on_start(
+                    me.handle_first_sync(
                         sync_keys,
+                        &first_synced_arc,
                         first_sync_task.clone(),
                         history_loading.clone(),
                     );
                 } else {
-                    // see if we have new spaces to catch up upon
-                    let mut new_spaces = Vec::new();
-                    for (room_id, joined_state) in response.rooms.joined.iter() {
-                        if history_loading.lock_mut().knows_room(room_id) {
-                            continue;
-                        }
-                        let Some(full_room) = me.get_room(room_id) else {
-                            error!("room not found. how can that be?");
-                            continue;
-                        };
-                        if is_acter_space(&full_room).await {
-                            new_spaces.push(full_room);
-                        }
-                    }
-
-                    if !new_spaces.is_empty() {
-                        me.refresh_history_on_way(history_loading.clone(), new_spaces)
-                            .await;
-                    }
-                }
-
-                let changed_rooms = response
-                    .rooms
-                    .joined
-                    .keys()
-                    .chain(response.rooms.left.keys())
-                    .chain(response.rooms.invited.keys())
-                    .collect::<Vec<&OwnedRoomId>>();
-
-                if !changed_rooms.is_empty() {
-                    // changes observed, calculate which keys need to be updated
-                    trace!(?changed_rooms, "changed rooms");
-                    // by first refreshing rooms where necessary
-                    let mut updated_room_ids = me.refresh_rooms(changed_rooms).await;
-
-                    let mut keys = Vec::new();
-
-                    // and then checking if any updates in the joined rooms warrant us notifying
-                    for (room_id, updates) in response.rooms.joined.iter() {
-                        if let Some(idx) = updated_room_ids.iter().position(|id| id == room_id) {
-                            // we generally notify about this room as it was found above
-                            updated_room_ids.remove(idx); // remove the instance to not inform about them twice
-                            keys.push(ExecuteReference::Room(room_id.clone()));
-                        } else {
-                            // only notifiy if any update warrant us notifying
-                            if !updates.state.is_empty()
-                                || updates.timeline.events.iter().any(|t| {
-                                    let TimelineEventKind::PlainText { event } = &t.kind else {
-                                        return false;
-                                    };
-                                    // check if any event received is a state event
-                                    matches!(
-                                        event.get_field::<String>("state_key"),
-                                        Ok(Some(state_event))
-                                    )
-                                })
-                            {
-                                // state or at least one item in  the timeline is a state event, we need to notify
-                                trace!(?room_id, "room state changed");
-                                keys.push(ExecuteReference::Room(room_id.clone()));
-                            }
-                        }
-                        // finally, let's see if there is any room account data to inform about
-                        keys.extend(updates.account_data.iter().filter_map(|raw| {
-                            raw.get_field::<String>("type").ok().flatten().map(|s| {
-                                ExecuteReference::RoomAccountData(room_id.clone(), Cow::Owned(s))
-                            })
-                        }));
-                    }
-
-                    // if there are other room_ids left after clearing the joined, we also want to notify about them
-                    keys.extend(
-                        updated_room_ids
-                            .iter()
-                            .map(|id| ExecuteReference::Room(id.clone())),
-                    );
-
-                    if !keys.is_empty() {
-                        info!(?keys, "update notify keys");
-                        me.executor().notify(keys);
-                    }
+                    me.catch_up_on_new_spaces(&response.rooms, history_loading.clone())
+                        .await;
                 }
+                me.notify_room_updates(&response.rooms).await;
 
                 if !response.account_data.is_empty() {
                     info!("account data found!");
@@ -652,17 +745,8 @@ impl Client {
                     }
                 }
 
-                if let Ok(mut w) = state.try_write() {
-                    if w.should_stop_syncing {
-                        w.is_syncing = false;
-                        trace!("Stopping syncing upon user request");
-                        return;
-                    }
-                }
-                if let Ok(mut w) = state.try_write() {
-                    if !w.is_syncing {
-                        w.is_syncing = true;
-                    }
+                if !me.keep_syncing() {
+                    return;
                 }
 
                 trace!(token = response.next_batch, "storing sync token");
@@ -1,5 +1,8 @@
 use acter_core::{
-    events::comments::{self, CommentBuilder},
+    events::{
+        comments::{self, CommentBuilder},
+        MentionsBuilder,
+    },
     models::{self, can_redact, ActerModel, AnyActerModel},
 };
 use anyhow::{bail, Result};
@@ -8,7 +11,7 @@ use matrix_sdk::room::Room;
 use matrix_sdk_base::{
     ruma::{
         events::{room::message::TextMessageEventContent, MessageLikeEventType},
-        OwnedEventId, OwnedUserId,
+        OwnedEventId, OwnedUserId, UserId,
     },
     RoomState,
 };
@@ -114,6 +117,22 @@ impl Comment {
         (&self.inner.content).into()
     }
 
+    pub fn event_id_str(&self) -> String {
+        self.inner.event_id().to_string()
+    }
+
+    pub fn reply_to_id_str(&self) -> Option<String> {
+        self.inner.in_reply_to().map(|e| e.to_string())
+    }
+
+    /// The replies to this comment, with their own stats
+    pub async fn replies(&self) -> Result<CommentsManager> {
+        let client = self.client.clone();
+        let room = self.room.clone();
+        let event_id = self.inner.event_id().to_owned();
+        CommentsManager::new(client, room, event_id).await
+    }
+
     pub fn update_builder(&self) -> Result<CommentUpdateBuilder> {
         if !self.is_joined() {
             bail!("Can only update comments in joined rooms");
@@ -158,6 +177,16 @@ impl CommentDraft {
         self
     }
 
+    pub fn add_mention(&mut self, user_id: String) -> Result<()> {
+        self.inner.add_mention(UserId::parse(user_id)?);
+        Ok(())
+    }
+
+    pub fn add_room_mention(&mut self, mention: bool) -> &mut Self {
+        self.inner.room_mention(mention);
+        self
+    }
+
     pub async fn send(&self) -> Result<OwnedEventId> {
         let room = self.room.clone();
         let my_id = self.client.user_id()?;
@@ -286,6 +315,28 @@ impl CommentsManager {
             .await?
     }
 
+    pub async fn top_level_comments(&self) -> Result<Vec<Comment>> {
+        let manager = self.inner.clone();
+        let client = self.client.clone();
+        let room = self.room.clone();
+
+        RUNTIME
+            .spawn(async move {
+                let res = manager
+                    .top_level_comments()
+                    .await?
+                    .into_iter()
+                    .map(|comment| Comment {
+                        client: client.clone(),
+                        room: room.clone(),
+                        inner: comment,
+                    })
+                    .collect();
+                Ok(res)
+            })
+            .await?
+    }
+
     fn is_joined(&self) -> bool {
         matches!(self.room.state(), RoomState::Joined)
     }
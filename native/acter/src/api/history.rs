@@ -0,0 +1,85 @@
+use acter_core::{
+    history::{self, FieldChange},
+    models::AnyActerModel,
+};
+use anyhow::Result;
+use matrix_sdk_base::ruma::{OwnedEventId, OwnedUserId};
+
+use super::{client::Client, RUNTIME};
+
+impl Client {
+    pub(crate) async fn edit_history_of(&self, object_id: OwnedEventId) -> Result<EditHistory> {
+        let client = self.clone();
+        RUNTIME
+            .spawn(async move {
+                let inner = client.core.edit_history(&object_id).await?;
+                Ok(EditHistory { inner })
+            })
+            .await?
+    }
+
+    /// the state of the object right after the given creation or update
+    pub(crate) async fn state_as_of(
+        &self,
+        object_id: OwnedEventId,
+        event_id: OwnedEventId,
+    ) -> Result<AnyActerModel> {
+        let history = self.edit_history_of(object_id.clone()).await?;
+        history
+            .inner
+            .state_as_of(&event_id)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("{event_id} isn’t an edit of {object_id}"))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct EditHistory {
+    inner: history::EditHistory,
+}
+
+impl EditHistory {
+    pub fn object_id_str(&self) -> String {
+        self.inner.object_id().to_string()
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.inner.is_complete()
+    }
+
+    pub fn entries(&self) -> Vec<HistoryEntry> {
+        self.inner
+            .entries()
+            .iter()
+            .cloned()
+            .map(|inner| HistoryEntry { inner })
+            .collect()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct HistoryEntry {
+    inner: history::HistoryEntry,
+}
+
+impl HistoryEntry {
+    pub fn event_id_str(&self) -> String {
+        self.inner.event_id().to_string()
+    }
+
+    pub fn sender(&self) -> OwnedUserId {
+        self.inner.sender()
+    }
+
+    pub fn origin_server_ts(&self) -> u64 {
+        self.inner.origin_server_ts().get().into()
+    }
+
+    pub fn is_creation(&self) -> bool {
+        self.inner.is_creation()
+    }
+
+    pub fn changes(&self) -> Vec<FieldChange> {
+        self.inner.changes().to_vec()
+    }
+}
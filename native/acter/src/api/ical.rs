@@ -0,0 +1,243 @@
+use acter_core::{
+    events::tasks::TaskListBuilder,
+    ical::{add_attendee, ical_feed, parse_ical, IcalContent},
+    models,
+};
+use anyhow::Result;
+use icalendar::CalendarComponent;
+use matrix_sdk_base::ruma::OwnedEventId;
+use std::collections::HashMap;
+
+use super::{client::Client, spaces::Space, RUNTIME};
+
+/// name of the task list imported todos are put in, if the calendar has no name
+const DEFAULT_TASK_LIST_NAME: &str = "Imported Tasks";
+
+/// name of the feed across all spaces
+const CLIENT_FEED_NAME: &str = "Acter";
+
+/// all events with their RSVPs and all tasks with due date of the space
+async fn ical_components(space: &Space) -> Result<Vec<CalendarComponent>> {
+    let store = space.client.store().clone();
+    let mut components = vec![];
+    for cal_event in space.calendar_events().await? {
+        let mut ical_events = cal_event.as_ical_events().into_iter();
+        let Some(mut main) = ical_events.next() else {
+            continue;
+        };
+        // responses to single occurrences aren’t representable here
+        let entries = models::RsvpManager::from_store_and_event_id(&store, &cal_event.event_id())
+            .await
+            .rsvp_entries_for(None)
+            .await?;
+        for (user_id, entry) in entries {
+            add_attendee(&mut main, &user_id, &entry.status);
+        }
+        components.push(main.into());
+        components.extend(ical_events.map(Into::into));
+    }
+    for task_list in space.task_lists().await? {
+        for task in task_list.tasks().await? {
+            if let Some(todo) = task.as_ical_todo() {
+                components.push(todo.into());
+            }
+        }
+    }
+    Ok(components)
+}
+
+#[derive(Clone, Debug)]
+enum IcalImportStatus {
+    Created(OwnedEventId),
+    /// this `UID` has been imported before as this object
+    Skipped(OwnedEventId),
+    Failed(String),
+}
+
+/// The outcome of importing a single `VEVENT` or `VTODO`
+#[derive(Clone, Debug)]
+pub struct IcalImportItem {
+    uid: Option<String>,
+    summary: Option<String>,
+    status: IcalImportStatus,
+}
+
+impl IcalImportItem {
+    pub fn uid(&self) -> Option<String> {
+        self.uid.clone()
+    }
+
+    pub fn summary(&self) -> Option<String> {
+        self.summary.clone()
+    }
+
+    pub fn is_created(&self) -> bool {
+        matches!(self.status, IcalImportStatus::Created(_))
+    }
+
+    pub fn is_skipped(&self) -> bool {
+        matches!(self.status, IcalImportStatus::Skipped(_))
+    }
+
+    /// the id of the created or already existing object
+    pub fn event_id_str(&self) -> Option<String> {
+        match &self.status {
+            IcalImportStatus::Created(event_id) | IcalImportStatus::Skipped(event_id) => {
+                Some(event_id.to_string())
+            }
+            IcalImportStatus::Failed(_) => None,
+        }
+    }
+
+    pub fn error(&self) -> Option<String> {
+        match &self.status {
+            IcalImportStatus::Failed(error) => Some(error.clone()),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct IcalImportReport {
+    items: Vec<IcalImportItem>,
+}
+
+impl IcalImportReport {
+    pub fn items(&self) -> Vec<IcalImportItem> {
+        self.items.clone()
+    }
+
+    pub fn created_count(&self) -> u32 {
+        self.items.iter().filter(|i| i.is_created()).count() as u32
+    }
+
+    pub fn skipped_count(&self) -> u32 {
+        self.items.iter().filter(|i| i.is_skipped()).count() as u32
+    }
+
+    pub fn failed_count(&self) -> u32 {
+        self.items.iter().filter(|i| i.error().is_some()).count() as u32
+    }
+}
+
+impl Space {
+    /// Write all calendar events and tasks with due date of this space as one iCalendar file
+    pub async fn ical_feed(&self, file_name: String) -> Result<bool> {
+        let me = self.clone();
+        RUNTIME
+            .spawn(async move {
+                let name = me.room.cached_display_name().map(|n| n.to_string());
+                let components = ical_components(&me).await?;
+                let feed = ical_feed(name.as_deref().unwrap_or(CLIENT_FEED_NAME), components);
+                tokio::fs::write(file_name, feed.to_string()).await?;
+                Ok(true)
+            })
+            .await?
+    }
+
+    /// Import the events and todos of the iCalendar file at `path`
+    ///
+    /// Items whose `UID` was imported (or exported from here) before are skipped.
+    /// Todos go into the task list named like the calendar, created if needed.
+    pub async fn import_ical(&self, path: String) -> Result<IcalImportReport> {
+        let me = self.clone();
+        RUNTIME
+            .spawn(async move {
+                let data = parse_ical(&tokio::fs::read_to_string(path).await?)?;
+
+                let mut known = HashMap::new();
+                for cal_event in me.calendar_events().await? {
+                    known.insert(cal_event.ical_uid().to_owned(), cal_event.event_id());
+                }
+                let task_list_name = data
+                    .name
+                    .clone()
+                    .unwrap_or_else(|| DEFAULT_TASK_LIST_NAME.to_owned());
+                let mut task_list_id = None;
+                for task_list in me.task_lists().await? {
+                    for task in task_list.tasks().await? {
+                        known.insert(task.ical_uid().to_owned(), task.event_id().to_owned());
+                    }
+                    if task_list.name() == task_list_name {
+                        task_list_id = Some(task_list.event_id().to_owned());
+                    }
+                }
+
+                let mut items = vec![];
+                for item in data.items {
+                    let existing = item.uid.as_ref().and_then(|uid| known.get(uid)).cloned();
+                    let status = match (existing, item.content) {
+                        (Some(event_id), _) => IcalImportStatus::Skipped(event_id),
+                        (None, Err(error)) => IcalImportStatus::Failed(error.to_string()),
+                        (None, Ok(content)) => {
+                            // failures are reported per item, the others still get imported
+                            let res: Result<OwnedEventId> = async {
+                                match content {
+                                    IcalContent::CalendarEvent(builder) => {
+                                        me.calendar_event_draft_with_builder(builder)?.send().await
+                                    }
+                                    IcalContent::Task(mut builder) => {
+                                        let task_list_id = match &task_list_id {
+                                            Some(id) => id.clone(),
+                                            None => {
+                                                let mut content = TaskListBuilder::default();
+                                                content.name(task_list_name.clone());
+                                                let id = me
+                                                    .task_list_draft_with_builder(content)?
+                                                    .send()
+                                                    .await?;
+                                                task_list_id = Some(id.clone());
+                                                id
+                                            }
+                                        };
+                                        builder.task_list_id(task_list_id);
+                                        me.task_draft_with_builder(builder)?.send().await
+                                    }
+                                }
+                            }
+                            .await;
+                            match res {
+                                Ok(event_id) => {
+                                    if let Some(uid) = &item.uid {
+                                        // the same UID might show up again in this file
+                                        known.insert(uid.clone(), event_id.clone());
+                                    }
+                                    IcalImportStatus::Created(event_id)
+                                }
+                                Err(error) => IcalImportStatus::Failed(error.to_string()),
+                            }
+                        }
+                    };
+                    items.push(IcalImportItem {
+                        uid: item.uid,
+                        summary: item.summary,
+                        status,
+                    });
+                }
+                Ok(IcalImportReport { items })
+            })
+            .await?
+    }
+}
+
+impl Client {
+    /// Write all calendar events and tasks with due date of all spaces the user
+    /// enabled calendar sync for as one iCalendar file
+    pub async fn ical_feed(&self, file_name: String) -> Result<bool> {
+        let me = self.clone();
+        RUNTIME
+            .spawn(async move {
+                let mut components = vec![];
+                for space in me.spaces().await? {
+                    if !space.user_settings().await?.include_cal_sync() {
+                        continue;
+                    }
+                    components.extend(ical_components(&space).await?);
+                }
+                let feed = ical_feed(CLIENT_FEED_NAME, components);
+                tokio::fs::write(file_name, feed.to_string()).await?;
+                Ok(true)
+            })
+            .await?
+    }
+}
@@ -1,7 +1,7 @@
 use acter_core::{
     events::{
         news::{self, FallbackNewsContent, NewsContent, NewsEntryBuilder, NewsSlideBuilder},
-        Colorize, ColorizeBuilder, ObjRef as CoreObjRef, ObjRefBuilder,
+        Colorize, ColorizeBuilder, EditRule, MentionsBuilder, ObjRef as CoreObjRef, ObjRefBuilder,
         RefDetails as CoreRefDetails, RefPreview,
     },
     models::{self, can_redact, ActerModel, AnyActerModel, ReactionManager},
@@ -13,7 +13,7 @@ use matrix_sdk::room::Room;
 use matrix_sdk_base::{
     ruma::{
         events::{room::message::MessageType, MessageLikeEventType},
-        OwnedEventId, OwnedRoomId, OwnedUserId,
+        OwnedEventId, OwnedRoomId, OwnedUserId, UserId,
     },
     RoomState,
 };
@@ -32,6 +32,7 @@ use super::{
     client::Client,
     common::ThumbnailSize,
     deep_linking::{ObjRef, RefDetails},
+    outbox,
     spaces::Space,
     timeline::MsgContent,
     RUNTIME,
@@ -403,6 +404,16 @@ impl NewsEntry {
         matches!(self.room.state(), RoomState::Joined)
     }
 
+    /// whether the current user may update this news entry, room moderators always may
+    pub async fn can_edit(&self) -> Result<bool> {
+        if !self.is_joined() {
+            return Ok(false);
+        }
+        self.client
+            .may_edit(self.content.event_id().to_owned())
+            .await
+    }
+
     pub fn update_builder(&self) -> Result<NewsEntryUpdateBuilder> {
         if !self.is_joined() {
             bail!("Can only update news in joined rooms");
@@ -442,6 +453,11 @@ impl NewsEntry {
         self.content.event_id().to_owned()
     }
 
+    /// whether this wasn’t sent yet but is shown from the outbox
+    pub fn is_local_echo(&self) -> bool {
+        outbox::is_local_echo(self.content.event_id())
+    }
+
     pub fn origin_server_ts(&self) -> u64 {
         self.content.meta.origin_server_ts.get().into()
     }
@@ -507,6 +523,50 @@ impl NewsEntryDraft {
         self
     }
 
+    pub fn add_mention(&mut self, user_id: String) -> Result<()> {
+        self.content.add_mention(UserId::parse(user_id)?);
+        Ok(())
+    }
+
+    pub fn add_room_mention(&mut self, mention: bool) -> &mut Self {
+        self.content.room_mention(mention);
+        self
+    }
+
+    /// only allow the author and the given moderators to update this news entry
+    #[allow(clippy::ptr_arg)]
+    pub fn restrict_edits(&mut self, moderators: &mut Vec<String>) -> Result<()> {
+        let moderators = moderators
+            .iter()
+            .map(UserId::parse)
+            .collect::<Result<Vec<_>, _>>()?;
+        self.content
+            .edit_rule(EditRule::author_and_moderators(moderators));
+        Ok(())
+    }
+
+    /// Queue the news entry to be sent once online, returning the id it is
+    /// shown under until then. The slides’ media is uploaded right away.
+    pub async fn queue(&self) -> Result<String> {
+        let client = self.client.clone();
+        let room = self.room.clone();
+        let slides_drafts = self.slides.clone();
+        let mut builder = self.content.clone();
+
+        RUNTIME
+            .spawn(async move {
+                let mut slides = vec![];
+                for slide in &slides_drafts {
+                    let saved_slide = slide.clone().build(&client, &room).await?;
+                    slides.push(saved_slide);
+                }
+                builder.slides(slides);
+                let content = builder.build()?;
+                client.queue_acter_event(room, content).await
+            })
+            .await?
+    }
+
     pub async fn send(&mut self) -> Result<OwnedEventId> {
         trace!("starting send");
         let client = self.client.clone();
@@ -588,6 +648,14 @@ impl NewsEntryUpdateBuilder {
         self
     }
 
+    /// Queue the update to be sent once online, returning its transaction id
+    pub async fn queue(&self) -> Result<String> {
+        let content = self.content.build()?;
+        self.client
+            .queue_acter_event(self.room.clone(), content)
+            .await
+    }
+
     pub async fn send(&self) -> Result<OwnedEventId> {
         let room = self.room.clone();
         let my_id = self.client.user_id()?;
@@ -0,0 +1,198 @@
+use acter_core::outbox;
+use anyhow::{bail, Result};
+use futures::future::select;
+use matrix_sdk::room::Room;
+use matrix_sdk_base::ruma::{
+    events::{MessageLikeEventContent, MessageLikeEventType},
+    OwnedTransactionId,
+};
+use serde::Serialize;
+use tokio::{task::JoinHandle, time::sleep};
+use tracing::{error, trace, warn};
+
+use super::{client::Client, RUNTIME};
+
+// internal API
+impl Client {
+    /// Queue the acter event for sending once we are online
+    ///
+    /// Returns the id the event is shown under until it was sent, or
+    /// the transaction id if it has no local echo.
+    pub(crate) async fn queue_acter_event<C>(&self, room: Room, content: C) -> Result<String>
+    where
+        C: MessageLikeEventContent + Serialize + Send + 'static,
+    {
+        let my_id = self.user_id()?;
+        let outbox = self.core.outbox().clone();
+        RUNTIME
+            .spawn(async move {
+                let permitted = room
+                    .can_user_send_message(&my_id, MessageLikeEventType::RoomMessage)
+                    .await?;
+                if !permitted {
+                    bail!("No permissions to send message in this room");
+                }
+                let event_type = content.event_type().to_string();
+                let entry = outbox
+                    .queue(
+                        room.room_id().to_owned(),
+                        event_type,
+                        serde_json::to_value(&content)?,
+                    )
+                    .await?;
+                Ok(entry
+                    .local_echo_id
+                    .map(|id| id.to_string())
+                    .unwrap_or_else(|| entry.transaction_id.to_string()))
+            })
+            .await?
+    }
+
+    /// Send the queued acter events one after the other, retrying with a
+    /// backoff when sending fails and dropping the ones the server refused
+    pub(crate) fn spawn_outbox_worker(&self) -> JoinHandle<()> {
+        let outbox = self.core.outbox().clone();
+        let client = self.core.client().clone();
+        RUNTIME.spawn(async move {
+            loop {
+                let entry = match outbox.next().await {
+                    Ok(Some(entry)) => entry,
+                    Ok(None) => {
+                        outbox.changed().await;
+                        continue;
+                    }
+                    Err(error) => {
+                        error!(?error, "Reading the outbox failed");
+                        return;
+                    }
+                };
+                let wait = entry.wait_time();
+                if !wait.is_zero() {
+                    // a retry may be requested before the backoff is over
+                    let _ = select(Box::pin(sleep(wait)), Box::pin(outbox.changed())).await;
+                    continue;
+                }
+                let txn_id = entry.transaction_id.clone();
+                let Some(room) = client.get_room(&entry.room_id) else {
+                    warn!(?txn_id, room_id = ?entry.room_id, "Dropping event for unknown room");
+                    if let Err(error) = outbox.discard(&txn_id).await {
+                        error!(?error, "Discarding outbox entry failed");
+                        return;
+                    }
+                    continue;
+                };
+                let result = room
+                    .send_raw(&entry.event_type, entry.content)
+                    .with_transaction_id(txn_id.clone())
+                    .await;
+                let recorded = match result {
+                    Ok(response) => outbox.mark_sent(&txn_id, response.event_id).await,
+                    Err(error) if is_refused(&error) => {
+                        // retrying won’t help and would hold up all entries after it
+                        warn!(?txn_id, ?error, "Server refused queued event, dropping it");
+                        outbox.discard(&txn_id).await.map(|_| ())
+                    }
+                    Err(error) => {
+                        trace!(?txn_id, ?error, "Sending queued event failed");
+                        outbox
+                            .mark_failed(&txn_id, error.to_string())
+                            .await
+                            .map(|_| ())
+                    }
+                };
+                if let Err(error) = recorded {
+                    error!(?error, "Updating the outbox failed");
+                    return;
+                }
+            }
+        })
+    }
+}
+
+/// Whether the server refused the event itself, rather than failing to take
+/// it right now, e.g. for being rate limited, offline or logged out
+fn is_refused(error: &matrix_sdk::Error) -> bool {
+    let Some(api_error) = error.as_client_api_error() else {
+        return false;
+    };
+    let status = api_error.status_code.as_u16();
+    (400..500).contains(&status) && ![401, 408, 429].contains(&status)
+}
+
+// external API
+impl Client {
+    /// The acter events waiting to be sent, in the order they are sent in
+    pub async fn outbox_entries(&self) -> Result<Vec<OutboxEntry>> {
+        let outbox = self.core.outbox().clone();
+        RUNTIME
+            .spawn(async move {
+                Ok(outbox
+                    .entries()
+                    .await?
+                    .into_iter()
+                    .map(|inner| OutboxEntry { inner })
+                    .collect())
+            })
+            .await?
+    }
+
+    /// Give up on sending the queued event, removing its local echo
+    pub async fn discard_outbox_entry(&self, transaction_id: String) -> Result<bool> {
+        let outbox = self.core.outbox().clone();
+        RUNTIME
+            .spawn(async move {
+                let txn_id = OwnedTransactionId::from(transaction_id);
+                Ok(outbox.discard(&txn_id).await?)
+            })
+            .await?
+    }
+
+    /// Retry sending the queued events right away rather than after backoff
+    pub async fn retry_outbox_now(&self) -> Result<bool> {
+        let outbox = self.core.outbox().clone();
+        RUNTIME
+            .spawn(async move {
+                outbox.retry_now().await?;
+                Ok(true)
+            })
+            .await?
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct OutboxEntry {
+    inner: outbox::OutboxEntry,
+}
+
+impl OutboxEntry {
+    pub fn transaction_id(&self) -> String {
+        self.inner.transaction_id.to_string()
+    }
+
+    pub fn room_id_str(&self) -> String {
+        self.inner.room_id.to_string()
+    }
+
+    pub fn event_type(&self) -> String {
+        self.inner.event_type.clone()
+    }
+
+    /// the id the event is shown under until sent
+    pub fn local_echo_id(&self) -> Option<String> {
+        self.inner.local_echo_id.as_ref().map(ToString::to_string)
+    }
+
+    /// how many times sending failed so far
+    pub fn attempts(&self) -> u32 {
+        self.inner.attempts
+    }
+
+    pub fn last_error(&self) -> Option<String> {
+        self.inner.last_error.clone()
+    }
+
+    /// when sending is retried next, in milliseconds since the epoch
+    pub fn next_attempt_at(&self) -> Option<u64> {
+        self.inner.next_attempt_at.map(|ts| ts.get().into())
+    }
+}
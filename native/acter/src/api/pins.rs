@@ -1,9 +1,10 @@
 use acter_core::{
     events::{
         pins::{self, PinBuilder},
-        Display, RefDetails as CoreRefDetails, RefPreview,
+        Display, EditRule, MentionsBuilder, RefDetails as CoreRefDetails, RefPreview,
     },
     models::{self, can_redact, ActerModel, AnyActerModel},
+    outbox,
     referencing::{IndexKey, SectionIndex},
 };
 use anyhow::{bail, Result};
@@ -13,7 +14,7 @@ use matrix_sdk::ruma::EventId;
 use matrix_sdk_base::{
     ruma::{
         events::{room::message::TextMessageEventContent, MessageLikeEventType},
-        OwnedEventId, OwnedRoomId, OwnedUserId,
+        OwnedEventId, OwnedRoomId, OwnedUserId, UserId,
     },
     RoomState,
 };
@@ -27,7 +28,9 @@ use tracing::warn;
 
 use crate::MsgContent;
 
-use super::{client::Client, deep_linking::RefDetails, spaces::Space, RUNTIME};
+use super::{
+    client::Client, deep_linking::RefDetails, history::EditHistory, spaces::Space, RUNTIME,
+};
 
 impl Client {
     pub async fn wait_for_pin(&self, key: String, timeout: Option<u8>) -> Result<Pin> {
@@ -167,6 +170,11 @@ impl Pin {
         self.content.event_id().to_string()
     }
 
+    /// whether this wasn’t sent yet but is shown from the outbox
+    pub fn is_local_echo(&self) -> bool {
+        outbox::is_local_echo(self.content.event_id())
+    }
+
     pub fn room_id_str(&self) -> String {
         self.content.room_id().to_string()
     }
@@ -197,6 +205,27 @@ impl Pin {
             .await?
     }
 
+    /// the creation and all updates of this pin, oldest first
+    pub async fn edit_history(&self) -> Result<EditHistory> {
+        self.client
+            .edit_history_of(self.content.event_id().to_owned())
+            .await
+    }
+
+    /// this pin as it was right after the given update
+    pub async fn state_as_of(&self, event_id: String) -> Result<Pin> {
+        let key = self.content.event_id().to_owned();
+        let event_id = EventId::parse(event_id)?;
+        let AnyActerModel::Pin(content) = self.client.state_as_of(key, event_id).await? else {
+            bail!("Not a pin");
+        };
+        Ok(Pin {
+            client: self.client.clone(),
+            room: self.room.clone(),
+            content,
+        })
+    }
+
     pub async fn can_redact(&self) -> Result<bool> {
         let sender = self.content.sender().to_owned();
         let room = self.room.clone();
@@ -210,6 +239,16 @@ impl Pin {
         matches!(self.room.state(), RoomState::Joined)
     }
 
+    /// whether the current user may update this pin, room moderators always may
+    pub async fn can_edit(&self) -> Result<bool> {
+        if !self.is_joined() {
+            return Ok(false);
+        }
+        self.client
+            .may_edit(self.content.event_id().to_owned())
+            .await
+    }
+
     pub fn update_builder(&self) -> Result<PinUpdateBuilder> {
         if !self.is_joined() {
             bail!("Can only update pins in joined rooms");
@@ -296,11 +335,42 @@ impl PinDraft {
         self
     }
 
+    /// only allow the author and the given moderators to update this pin
+    #[allow(clippy::ptr_arg)]
+    pub fn restrict_edits(&mut self, moderators: &mut Vec<String>) -> Result<()> {
+        let moderators = moderators
+            .iter()
+            .map(UserId::parse)
+            .collect::<Result<Vec<_>, _>>()?;
+        self.content
+            .edit_rule(EditRule::author_and_moderators(moderators));
+        Ok(())
+    }
+
     pub fn unset_display(&mut self) -> &mut Self {
         self.content.display(None);
         self
     }
 
+    pub fn add_mention(&mut self, user_id: String) -> Result<()> {
+        self.content.add_mention(UserId::parse(user_id)?);
+        Ok(())
+    }
+
+    pub fn add_room_mention(&mut self, mention: bool) -> &mut Self {
+        self.content.room_mention(mention);
+        self
+    }
+
+    /// Queue the pin to be sent once online, returning the id it is
+    /// shown under until then
+    pub async fn queue(&self) -> Result<String> {
+        let content = self.content.build()?;
+        self.client
+            .queue_acter_event(self.room.clone(), content)
+            .await
+    }
+
     pub async fn send(&self) -> Result<OwnedEventId> {
         let room = self.room.clone();
         let my_id = self.client.user_id()?;
@@ -398,13 +468,38 @@ impl PinUpdateBuilder {
         self
     }
 
+    pub fn add_mention(&mut self, user_id: String) -> Result<()> {
+        self.content.add_mention(UserId::parse(user_id)?);
+        Ok(())
+    }
+
+    pub fn add_room_mention(&mut self, mention: bool) -> &mut Self {
+        self.content.room_mention(mention);
+        self
+    }
+
+    /// Queue the update to be sent once online, returning its transaction id
+    pub async fn queue(&self) -> Result<String> {
+        let content = self.content.build()?;
+        if !self.client.may_edit(content.pin.event_id.clone()).await? {
+            bail!("Not allowed to edit this pin");
+        }
+        self.client
+            .queue_acter_event(self.room.clone(), content)
+            .await
+    }
+
     pub async fn send(&self) -> Result<OwnedEventId> {
+        let client = self.client.clone();
         let room = self.room.clone();
         let my_id = self.client.user_id()?;
         let content = self.content.build()?;
 
         RUNTIME
             .spawn(async move {
+                if !client.may_edit(content.pin.event_id.clone()).await? {
+                    bail!("Not allowed to edit this pin");
+                }
                 let permitted = room
                     .can_user_send_message(&my_id, MessageLikeEventType::RoomMessage)
                     .await?;
@@ -0,0 +1,586 @@
+pub use acter_core::events::polls::PollAnswer;
+use acter_core::{
+    events::{
+        polls::{self, PollBuilder, PollKind, PollVoting},
+        Display, EditRule, UtcDateTime,
+    },
+    models::{self, can_redact, ActerModel, AnyActerModel},
+    referencing::{IndexKey, SectionIndex},
+};
+use anyhow::{bail, Result};
+use chrono::{DateTime, Utc};
+use futures::stream::StreamExt;
+use matrix_sdk::room::Room;
+use matrix_sdk::ruma::EventId;
+use matrix_sdk_base::{
+    ruma::{
+        events::{room::message::TextMessageEventContent, MessageLikeEventType},
+        OwnedEventId, OwnedUserId, UserId,
+    },
+    RoomState,
+};
+use std::{ops::Deref, str::FromStr};
+use tokio::sync::broadcast::Receiver;
+use tokio_stream::{wrappers::BroadcastStream, Stream};
+
+use crate::MsgContent;
+
+use super::{client::Client, spaces::Space, RUNTIME};
+
+impl Client {
+    pub async fn wait_for_poll(&self, key: String, timeout: Option<u8>) -> Result<Poll> {
+        let me = self.clone();
+        RUNTIME
+            .spawn(async move {
+                let AnyActerModel::Poll(content) = me.wait_for(key.clone(), timeout).await? else {
+                    bail!("{key} is not a poll");
+                };
+                let room = me.room_by_id_typed(content.room_id())?;
+                Ok(Poll {
+                    client: me.clone(),
+                    room,
+                    content,
+                })
+            })
+            .await?
+    }
+
+    pub async fn polls(&self) -> Result<Vec<Poll>> {
+        Ok(self
+            .models_of_list_with_room(IndexKey::Section(SectionIndex::Polls))
+            .await?
+            .map(|(inner, room)| Poll {
+                client: self.clone(),
+                room,
+                content: inner,
+            })
+            .collect())
+    }
+
+    pub async fn poll(&self, poll_id: String) -> Result<Poll> {
+        self.model_with_room(EventId::parse(poll_id)?)
+            .await
+            .map(|(inner, room)| Poll {
+                client: self.clone(),
+                room,
+                content: inner,
+            })
+    }
+}
+
+impl Space {
+    pub async fn polls(&self) -> Result<Vec<Poll>> {
+        let room = self.room.clone();
+        Ok(self
+            .client
+            .models_of_list_with_room_under_check(
+                IndexKey::RoomSection(room.room_id().to_owned(), SectionIndex::Polls),
+                move |_r| Ok(room.clone()),
+            )
+            .await?
+            .map(|(inner, room)| Poll {
+                client: self.client.clone(),
+                room,
+                content: inner,
+            })
+            .collect())
+    }
+
+    pub fn poll_draft(&self) -> Result<PollDraft> {
+        if !self.is_joined() {
+            bail!("Unable to create polls for spaces we are not part on");
+        }
+        Ok(PollDraft {
+            client: self.client.clone(),
+            room: self.inner.room.clone(),
+            content: Default::default(),
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Poll {
+    client: Client,
+    room: Room,
+    content: models::Poll,
+}
+
+impl Deref for Poll {
+    type Target = models::Poll;
+    fn deref(&self) -> &Self::Target {
+        &self.content
+    }
+}
+
+/// helpers for content
+impl Poll {
+    pub fn title(&self) -> String {
+        self.content.title.clone()
+    }
+
+    pub fn content(&self) -> Option<MsgContent> {
+        self.content.content.as_ref().map(MsgContent::from)
+    }
+
+    pub fn answers(&self) -> Vec<PollAnswer> {
+        self.content.answers.clone()
+    }
+
+    pub fn kind_str(&self) -> String {
+        self.content.kind.to_string()
+    }
+
+    pub fn voting_str(&self) -> String {
+        self.content.voting.to_string()
+    }
+
+    pub fn is_multiple_choice(&self) -> bool {
+        self.content.is_multiple_choice()
+    }
+
+    pub fn is_anonymous(&self) -> bool {
+        self.content.is_anonymous()
+    }
+
+    pub fn utc_closes_at(&self) -> Option<UtcDateTime> {
+        self.content.utc_closes_at
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.content.is_closed_at(&Utc::now())
+    }
+
+    pub fn display(&self) -> Option<Display> {
+        self.content.display.clone()
+    }
+
+    pub fn event_id_str(&self) -> String {
+        self.content.event_id().to_string()
+    }
+
+    pub fn room_id_str(&self) -> String {
+        self.content.room_id().to_string()
+    }
+
+    pub fn sender(&self) -> OwnedUserId {
+        self.content.sender().to_owned()
+    }
+}
+
+/// Custom functions
+impl Poll {
+    pub async fn refresh(&self) -> Result<Poll> {
+        let key = self.content.event_id().to_owned();
+        let client = self.client.clone();
+        let room = self.room.clone();
+
+        RUNTIME
+            .spawn(async move {
+                let AnyActerModel::Poll(content) = client.store().get(&key).await? else {
+                    bail!("Refreshing failed. {key} not a poll")
+                };
+                Ok(Poll {
+                    client,
+                    room,
+                    content,
+                })
+            })
+            .await?
+    }
+
+    pub async fn can_redact(&self) -> Result<bool> {
+        let sender = self.content.sender().to_owned();
+        let room = self.room.clone();
+
+        RUNTIME
+            .spawn(async move { Ok(can_redact(&room, &sender).await?) })
+            .await?
+    }
+
+    fn is_joined(&self) -> bool {
+        matches!(self.room.state(), RoomState::Joined)
+    }
+
+    /// whether the current user may update this poll, room moderators always may
+    pub async fn can_edit(&self) -> Result<bool> {
+        if !self.is_joined() {
+            return Ok(false);
+        }
+        self.client
+            .may_edit(self.content.event_id().to_owned())
+            .await
+    }
+
+    pub fn update_builder(&self) -> Result<PollUpdateBuilder> {
+        if !self.is_joined() {
+            bail!("Can only update polls in joined rooms");
+        }
+        Ok(PollUpdateBuilder {
+            client: self.client.clone(),
+            room: self.room.clone(),
+            content: self.content.updater(),
+        })
+    }
+
+    pub fn subscribe_stream(&self) -> impl Stream<Item = bool> {
+        BroadcastStream::new(self.subscribe()).map(|_| true)
+    }
+
+    pub fn subscribe(&self) -> Receiver<()> {
+        let key = self.content.event_id().to_owned();
+        self.client.subscribe(key)
+    }
+
+    pub async fn votes(&self) -> Result<PollVotesManager> {
+        PollVotesManager::new(self.client.clone(), self.room.clone(), self.content.clone()).await
+    }
+
+    pub async fn comments(&self) -> Result<crate::CommentsManager> {
+        let client = self.client.clone();
+        let room = self.room.clone();
+        let event_id = self.content.event_id().to_owned();
+        crate::CommentsManager::new(client, room, event_id).await
+    }
+
+    pub async fn attachments(&self) -> Result<crate::AttachmentsManager> {
+        let client = self.client.clone();
+        let room = self.room.clone();
+        let event_id = self.content.event_id().to_owned();
+        crate::AttachmentsManager::new(client, room, event_id).await
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct PollVotesManager {
+    client: Client,
+    room: Room,
+    poll: models::Poll,
+    inner: models::PollVotesManager,
+}
+
+impl Deref for PollVotesManager {
+    type Target = models::PollVotesManager;
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl PollVotesManager {
+    pub(crate) async fn new(
+        client: Client,
+        room: Room,
+        poll: models::Poll,
+    ) -> Result<PollVotesManager> {
+        RUNTIME
+            .spawn(async move {
+                let inner = models::PollVotesManager::from_store_and_event_id(
+                    client.store(),
+                    poll.event_id(),
+                )
+                .await;
+                Ok(PollVotesManager {
+                    client,
+                    room,
+                    poll,
+                    inner,
+                })
+            })
+            .await?
+    }
+
+    pub fn total_voters(&self) -> u32 {
+        *self.inner.total_voters()
+    }
+
+    pub fn votes_for(&self, answer_id: String) -> u32 {
+        self.inner.votes_for(&answer_id)
+    }
+
+    /// The ids of the answers the user picked, empty if they haven’t voted
+    pub async fn my_answers(&self) -> Result<Vec<String>> {
+        let manager = self.inner.clone();
+        let poll = self.poll.clone();
+        let my_id = self.client.user_id()?;
+        RUNTIME
+            .spawn(async move {
+                let mut votes = manager.votes(&poll).await?;
+                Ok(votes
+                    .remove(&my_id)
+                    .map(|(_, answers)| answers)
+                    .unwrap_or_default())
+            })
+            .await?
+    }
+
+    /// The users that picked the given answer, not available for anonymous polls
+    pub async fn voters_for(&self, answer_id: String) -> Result<Vec<OwnedUserId>> {
+        if self.poll.is_anonymous() {
+            bail!("Voters of anonymous polls aren’t shown");
+        }
+        let manager = self.inner.clone();
+        let poll = self.poll.clone();
+        RUNTIME
+            .spawn(async move {
+                let voters = manager
+                    .votes(&poll)
+                    .await?
+                    .into_iter()
+                    .filter(|(_, (_, answers))| answers.contains(&answer_id))
+                    .map(|(user_id, _)| user_id)
+                    .collect();
+                Ok(voters)
+            })
+            .await?
+    }
+
+    fn is_joined(&self) -> bool {
+        matches!(self.room.state(), RoomState::Joined)
+    }
+
+    /// Vote for the given answers, replacing any previous vote.
+    /// Sending no answers retracts the vote.
+    pub async fn vote(&self, answers: Vec<String>) -> Result<OwnedEventId> {
+        if !self.is_joined() {
+            bail!("Can vote in only joined rooms");
+        }
+        if self.poll.is_closed_at(&Utc::now()) {
+            bail!("This poll is closed");
+        }
+        if answers.len() > 1 && !self.poll.is_multiple_choice() {
+            bail!("Only one answer can be picked in this poll");
+        }
+        if let Some(unknown) = answers.iter().find(|a| self.poll.answer(a).is_none()) {
+            bail!("{unknown} is not an answer of this poll");
+        }
+        let room = self.room.clone();
+        let my_id = self.client.user_id()?;
+        let content = self.inner.draft_builder().answers(answers).build()?;
+
+        RUNTIME
+            .spawn(async move {
+                let permitted = room
+                    .can_user_send_message(&my_id, MessageLikeEventType::RoomMessage)
+                    .await?;
+                if !permitted {
+                    bail!("No permissions to send message in this room");
+                }
+                let response = room.send(content).await?;
+                Ok(response.event_id)
+            })
+            .await?
+    }
+
+    pub fn subscribe_stream(&self) -> impl Stream<Item = bool> {
+        BroadcastStream::new(self.subscribe()).map(|_| true)
+    }
+
+    pub fn subscribe(&self) -> Receiver<()> {
+        self.client.subscribe(self.inner.update_key())
+    }
+}
+
+#[derive(Clone)]
+pub struct PollDraft {
+    client: Client,
+    room: Room,
+    content: PollBuilder,
+}
+
+impl PollDraft {
+    pub fn title(&mut self, title: String) -> &mut Self {
+        self.content.title(title);
+        self
+    }
+
+    pub fn content_text(&mut self, body: String) -> &mut Self {
+        self.content
+            .content(Some(TextMessageEventContent::plain(body)));
+        self
+    }
+
+    pub fn content_markdown(&mut self, body: String) -> &mut Self {
+        self.content
+            .content(Some(TextMessageEventContent::markdown(body)));
+        self
+    }
+
+    pub fn unset_content(&mut self) -> &mut Self {
+        self.content.content(None);
+        self
+    }
+
+    pub fn add_answer(&mut self, title: String) -> &mut Self {
+        self.content.add_answer(title);
+        self
+    }
+
+    pub fn unset_answers(&mut self) -> &mut Self {
+        self.content.answers(vec![]);
+        self
+    }
+
+    /// `single-choice` or `multiple-choice`
+    pub fn kind(&mut self, kind: String) -> Result<()> {
+        self.content.kind(PollKind::from_str(&kind)?);
+        Ok(())
+    }
+
+    /// `public` or `anonymous`
+    pub fn voting(&mut self, voting: String) -> Result<()> {
+        self.content.voting(PollVoting::from_str(&voting)?);
+        Ok(())
+    }
+
+    pub fn utc_closes_at_from_rfc3339(&mut self, utc_closes_at: String) -> Result<()> {
+        let dt: UtcDateTime = DateTime::parse_from_rfc3339(&utc_closes_at)?.into();
+        self.content.utc_closes_at(Some(dt));
+        Ok(())
+    }
+
+    pub fn unset_utc_closes_at(&mut self) -> &mut Self {
+        self.content.utc_closes_at(None);
+        self
+    }
+
+    pub fn display(&mut self, display: Box<Display>) -> &mut Self {
+        self.content.display(Some(*display));
+        self
+    }
+
+    pub fn unset_display(&mut self) -> &mut Self {
+        self.content.display(None);
+        self
+    }
+
+    /// only allow the author and the given moderators to update this poll
+    #[allow(clippy::ptr_arg)]
+    pub fn restrict_edits(&mut self, moderators: &mut Vec<String>) -> Result<()> {
+        let moderators = moderators
+            .iter()
+            .map(UserId::parse)
+            .collect::<Result<Vec<_>, _>>()?;
+        self.content
+            .edit_rule(EditRule::author_and_moderators(moderators));
+        Ok(())
+    }
+
+    pub async fn send(&self) -> Result<OwnedEventId> {
+        let room = self.room.clone();
+        let my_id = self.client.user_id()?;
+        let content = self.content.build()?;
+        if content.answers.len() < 2 {
+            bail!("A poll needs at least two answers");
+        }
+
+        RUNTIME
+            .spawn(async move {
+                let permitted = room
+                    .can_user_send_message(&my_id, MessageLikeEventType::RoomMessage)
+                    .await?;
+                if !permitted {
+                    bail!("No permissions to send message in this room");
+                }
+                let response = room.send(content).await?;
+                Ok(response.event_id)
+            })
+            .await?
+    }
+}
+
+#[derive(Clone)]
+pub struct PollUpdateBuilder {
+    client: Client,
+    room: Room,
+    content: polls::PollUpdateBuilder,
+}
+
+impl PollUpdateBuilder {
+    pub fn title(&mut self, title: String) -> &mut Self {
+        self.content.title(Some(title));
+        self
+    }
+
+    pub fn unset_title_update(&mut self) -> &mut Self {
+        self.content.title(None);
+        self
+    }
+
+    pub fn content_text(&mut self, body: String) -> &mut Self {
+        self.content
+            .content(Some(Some(TextMessageEventContent::plain(body))));
+        self
+    }
+
+    pub fn content_markdown(&mut self, body: String) -> &mut Self {
+        self.content
+            .content(Some(Some(TextMessageEventContent::markdown(body))));
+        self
+    }
+
+    pub fn unset_content(&mut self) -> &mut Self {
+        self.content.content(Some(None));
+        self
+    }
+
+    pub fn unset_content_update(&mut self) -> &mut Self {
+        self.content
+            .content(None::<Option<TextMessageEventContent>>);
+        self
+    }
+
+    pub fn utc_closes_at_from_rfc3339(&mut self, utc_closes_at: String) -> Result<()> {
+        let dt: UtcDateTime = DateTime::parse_from_rfc3339(&utc_closes_at)?.into();
+        self.content.utc_closes_at(Some(Some(dt)));
+        Ok(())
+    }
+
+    /// close the poll right away
+    pub fn close_now(&mut self) -> &mut Self {
+        self.content.utc_closes_at(Some(Some(Utc::now())));
+        self
+    }
+
+    pub fn unset_utc_closes_at(&mut self) -> &mut Self {
+        self.content.utc_closes_at(Some(None));
+        self
+    }
+
+    pub fn unset_utc_closes_at_update(&mut self) -> &mut Self {
+        self.content.utc_closes_at(None::<Option<UtcDateTime>>);
+        self
+    }
+
+    pub fn display(&mut self, display: Box<Display>) -> &mut Self {
+        self.content.display(Some(Some(*display)));
+        self
+    }
+
+    pub fn unset_display(&mut self) -> &mut Self {
+        self.content.display(Some(None));
+        self
+    }
+
+    pub fn unset_display_update(&mut self) -> &mut Self {
+        self.content.display(None::<Option<Display>>);
+        self
+    }
+
+    pub async fn send(&self) -> Result<OwnedEventId> {
+        let room = self.room.clone();
+        let my_id = self.client.user_id()?;
+        let content = self.content.build()?;
+
+        RUNTIME
+            .spawn(async move {
+                let permitted = room
+                    .can_user_send_message(&my_id, MessageLikeEventType::RoomMessage)
+                    .await?;
+                if !permitted {
+                    bail!("No permissions to send message in this room");
+                }
+                let response = room.send(content).await?;
+                Ok(response.event_id)
+            })
+            .await?
+    }
+}
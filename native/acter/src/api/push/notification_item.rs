@@ -341,6 +341,27 @@ impl NotificationItem {
         a.whom()
     }
 
+    pub fn new_status(&self) -> Option<String> {
+        let NotificationItemInner::Activity(a) = &self.inner else {
+            return None;
+        };
+        a.new_status()
+    }
+
+    pub fn new_status_name(&self) -> Option<String> {
+        let NotificationItemInner::Activity(a) = &self.inner else {
+            return None;
+        };
+        a.new_status_name()
+    }
+
+    pub fn reply_to_comment_id_str(&self) -> Option<String> {
+        let NotificationItemInner::Activity(a) = &self.inner else {
+            return None;
+        };
+        a.reply_to_comment_id_str()
+    }
+
     pub async fn image(&self) -> Result<FfiBuffer<u8>> {
         #[allow(clippy::diverging_sub_expression)]
         let Some(source) = self.msg_content.as_ref().and_then(|a| a.source()) else {
@@ -562,6 +583,20 @@ impl NotificationItemBuilder {
                 builder.title("removed due date".to_owned())
             }
             ActivityContent::TaskAdd { task_title, .. } => builder.title(task_title.clone()),
+            ActivityContent::Comment {
+                in_reply_to: Some(parent),
+                ..
+            } => builder.mentions_you(parent.sender.as_ref() == Some(&user_id)),
+            ActivityContent::TaskStatusChange {
+                new_status,
+                new_status_name,
+                ..
+            } => builder.title(
+                new_status_name
+                    .clone()
+                    .or_else(|| new_status.clone())
+                    .unwrap_or("removed status".to_owned()),
+            ),
             ActivityContent::DescriptionChange {
                 object,
                 content: Some(content),
@@ -569,9 +604,18 @@ impl NotificationItemBuilder {
             ActivityContent::ObjectInvitation { object, invitees } => builder
                 .title(object.title().unwrap_or("Object".to_owned()))
                 .mentions_you(invitees.contains(&user_id)),
+            ActivityContent::TaskAssign { object, assignee }
+            | ActivityContent::TaskUnassign { object, assignee } => builder
+                .title(object.title().unwrap_or("Task".to_owned()))
+                .mentions_you(assignee == &user_id),
             _ => &mut builder,
         };
 
+        // mentioned in the content of the object or update
+        if activity.mentions_user(&user_id) {
+            builder.mentions_you(true);
+        }
+
         Ok(builder
             .inner(NotificationItemInner::Activity(activity))
             .build()?)
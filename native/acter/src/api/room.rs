@@ -13,6 +13,7 @@ use acter_core::{
         calendar::CalendarEventEventContent,
         news::NewsEntryEventContent,
         pins::PinEventContent,
+        polls::PollEventContent,
         settings::ActerAppSettingsContent,
         stories::StoryEventContent,
         tasks::{TaskEventContent, TaskListEventContent},
@@ -106,6 +107,7 @@ pub enum MemberPermission {
     CanPostNews,
     CanPostStories,
     CanPostPin,
+    CanPostPoll,
     CanPostEvent,
     CanPostTaskList,
     CanPostTask,
@@ -261,6 +263,21 @@ impl Member {
                     return false;
                 }
             }
+            MemberPermission::CanPostPoll => {
+                if self
+                    .acter_app_settings
+                    .as_ref()
+                    .map(|s| s.polls().active())
+                    .unwrap_or_default()
+                {
+                    PermissionTest::Message(MessageLikeEventType::from(
+                        <PollEventContent as StaticEventContent>::TYPE,
+                    ))
+                } else {
+                    // Not an acter space or Polls are not activated..
+                    return false;
+                }
+            }
             MemberPermission::CanPostEvent => {
                 if self
                     .acter_app_settings
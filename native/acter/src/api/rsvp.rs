@@ -1,6 +1,6 @@
 pub use acter_core::events::rsvp::RsvpStatus;
 use acter_core::{
-    events::rsvp::RsvpBuilder,
+    events::{rsvp::RsvpBuilder, UtcDateTime},
     models::{self, ActerModel, AnyActerModel},
     referencing::{IndexKey, SectionIndex},
 };
@@ -19,6 +19,9 @@ use tracing::{error, warn};
 
 use super::{calendar_events::CalendarEvent, client::Client, common::OptionRsvpStatus, RUNTIME};
 
+/// How many occurrences of a single recurring event we list at most
+const MAX_OCCURRENCES_PER_EVENT: usize = 50;
+
 impl Client {
     pub async fn wait_for_rsvp(&self, key: String, timeout: Option<u8>) -> Result<Rsvp> {
         let me = self.clone();
@@ -51,21 +54,15 @@ impl Client {
                     .await?
                 {
                     if let AnyActerModel::CalendarEvent(inner) = mdl {
-                        let now = chrono::Utc::now();
-                        let start_time = inner.utc_start();
-                        if now > start_time {
-                            // skip past events
-                            continue;
-                        }
-                        if let Some(secs) = secs_from_now {
-                            if start_time > now + chrono::Duration::seconds(secs as i64) {
-                                // skip too far events
-                                continue;
-                            }
-                        }
                         let room = me.room_by_id_typed(inner.room_id())?;
-                        let cal_event = CalendarEvent::new(me.clone(), room, inner);
-                        cal_events.push(cal_event);
+                        for occurrence in upcoming_occurrences(&inner, secs_from_now) {
+                            cal_events.push(CalendarEvent::with_occurrence(
+                                me.clone(),
+                                room.clone(),
+                                inner.clone(),
+                                occurrence,
+                            ));
+                        }
                     } else {
                         warn!(
                             "Non calendar_event model found in `calendar_events` index: {:?}",
@@ -93,28 +90,23 @@ impl Client {
                     .await?
                 {
                     if let AnyActerModel::CalendarEvent(inner) = mdl {
-                        let now = chrono::Utc::now();
-                        let start_time = inner.utc_start();
-                        if now > start_time {
-                            // skip past events
-                            continue;
-                        }
-                        if let Some(secs) = secs_from_now {
-                            if start_time > now + chrono::Duration::seconds(secs as i64) {
-                                // skip too far events
-                                continue;
-                            }
-                        }
                         let room = me.room_by_id_typed(inner.room_id())?;
-                        let cal_event = CalendarEvent::new(me.clone(), room, inner);
-                        // fliter only events that i sent rsvp
-                        let rsvp_manager = cal_event.rsvps().await?;
-                        let status = rsvp_manager.responded_by_me().await?;
-                        match status.status() {
-                            Some(RsvpStatus::Yes) | Some(RsvpStatus::Maybe) => {
-                                cal_events.push(cal_event);
+                        for occurrence in upcoming_occurrences(&inner, secs_from_now) {
+                            let cal_event = CalendarEvent::with_occurrence(
+                                me.clone(),
+                                room.clone(),
+                                inner.clone(),
+                                occurrence,
+                            );
+                            // fliter only events that i sent rsvp
+                            let rsvp_manager = cal_event.rsvps().await?;
+                            let status = rsvp_manager.responded_by_me().await?;
+                            match status.status() {
+                                Some(RsvpStatus::Yes) | Some(RsvpStatus::Maybe) => {
+                                    cal_events.push(cal_event);
+                                }
+                                _ => {}
                             }
-                            _ => {}
                         }
                     } else {
                         warn!(
@@ -123,6 +115,7 @@ impl Client {
                         );
                     }
                 }
+                cal_events.sort();
                 Ok(cal_events)
             })
             .await?
@@ -139,28 +132,23 @@ impl Client {
                     .await?
                 {
                     if let AnyActerModel::CalendarEvent(inner) = mdl {
-                        let now = chrono::Utc::now();
-                        let start_time = inner.utc_start();
-                        if start_time > now {
-                            // skip upcoming events
-                            continue;
-                        }
-                        if let Some(secs) = secs_from_now {
-                            if start_time < now - chrono::Duration::seconds(secs as i64) {
-                                // skip too far events
-                                continue;
-                            }
-                        }
                         let room = me.room_by_id_typed(inner.room_id())?;
-                        let cal_event = CalendarEvent::new(me.clone(), room, inner);
-                        // fliter only events that i sent rsvp
-                        let rsvp_manager = cal_event.rsvps().await?;
-                        let status = rsvp_manager.responded_by_me().await?;
-                        match status.status() {
-                            Some(RsvpStatus::Yes) | Some(RsvpStatus::Maybe) => {
-                                cal_events.push(cal_event);
+                        for occurrence in past_occurrences(&inner, secs_from_now) {
+                            let cal_event = CalendarEvent::with_occurrence(
+                                me.clone(),
+                                room.clone(),
+                                inner.clone(),
+                                occurrence,
+                            );
+                            // fliter only events that i sent rsvp
+                            let rsvp_manager = cal_event.rsvps().await?;
+                            let status = rsvp_manager.responded_by_me().await?;
+                            match status.status() {
+                                Some(RsvpStatus::Yes) | Some(RsvpStatus::Maybe) => {
+                                    cal_events.push(cal_event);
+                                }
+                                _ => {}
                             }
-                            _ => {}
                         }
                     } else {
                         warn!(
@@ -169,12 +157,48 @@ impl Client {
                         );
                     }
                 }
+                // latest first
+                cal_events.sort_by(|a, b| b.cmp(a));
                 Ok(cal_events)
             })
             .await?
     }
 }
 
+/// occurrences starting from now on, up to `secs_from_now` if given
+fn upcoming_occurrences(
+    inner: &models::CalendarEvent,
+    secs_from_now: Option<u32>,
+) -> Vec<models::CalendarEventOccurrence> {
+    let now = chrono::Utc::now();
+    match secs_from_now {
+        Some(secs) => {
+            inner.occurrences_between(&now, &(now + chrono::Duration::seconds(secs as i64)))
+        }
+        None => inner.occurrences_after(&now, MAX_OCCURRENCES_PER_EVENT),
+    }
+    .into_iter()
+    .filter(|o| o.utc_start >= now) // skip running events
+    .collect()
+}
+
+/// occurrences that started already, back to `secs_from_now` if given
+fn past_occurrences(
+    inner: &models::CalendarEvent,
+    secs_from_now: Option<u32>,
+) -> Vec<models::CalendarEventOccurrence> {
+    let now = chrono::Utc::now();
+    inner
+        .occurrences_before(&now, MAX_OCCURRENCES_PER_EVENT)
+        .into_iter()
+        .filter(|o| match secs_from_now {
+            // skip too far events
+            Some(secs) => o.utc_start >= now - chrono::Duration::seconds(secs as i64),
+            None => true,
+        })
+        .collect()
+}
+
 #[derive(Clone, Debug)]
 pub struct Rsvp {
     client: Client,
@@ -244,6 +268,8 @@ pub struct RsvpManager {
     client: Client,
     room: Room,
     inner: models::RsvpManager,
+    /// the occurrence of a recurring event the responses are for
+    occurrence: Option<UtcDateTime>,
 }
 
 impl Deref for RsvpManager {
@@ -258,6 +284,7 @@ impl RsvpManager {
         client: Client,
         room: Room,
         event_id: OwnedEventId,
+        occurrence: Option<UtcDateTime>,
     ) -> Result<RsvpManager> {
         RUNTIME
             .spawn(async move {
@@ -267,6 +294,7 @@ impl RsvpManager {
                     client,
                     room,
                     inner,
+                    occurrence,
                 })
             })
             .await?
@@ -286,13 +314,14 @@ impl RsvpManager {
 
     pub async fn rsvp_entries(&self) -> Result<Vec<Rsvp>> {
         let manager = self.inner.clone();
+        let occurrence = self.occurrence;
         let client = self.client.clone();
         let room = self.room.clone();
 
         RUNTIME
             .spawn(async move {
                 let res = manager
-                    .rsvp_entries()
+                    .rsvp_entries_for(occurrence.as_ref())
                     .await?
                     .into_iter()
                     .map(|(user_id, inner)| Rsvp {
@@ -308,10 +337,11 @@ impl RsvpManager {
 
     pub async fn responded_by_me(&self) -> Result<OptionRsvpStatus> {
         let manager = self.inner.clone();
+        let occurrence = self.occurrence;
         let my_id = self.client.user_id()?;
         RUNTIME
             .spawn(async move {
-                let entries = manager.rsvp_entries().await?;
+                let entries = manager.rsvp_entries_for(occurrence.as_ref()).await?;
                 let status = entries.get(&my_id).map(|x| x.status.clone());
                 Ok(OptionRsvpStatus::new(status))
             })
@@ -320,10 +350,11 @@ impl RsvpManager {
 
     pub async fn count_at_status(&self, status: String) -> Result<u32> {
         let manager = self.inner.clone();
+        let occurrence = self.occurrence;
         RUNTIME
             .spawn(async move {
                 let mut count = 0;
-                let entries = manager.rsvp_entries().await?;
+                let entries = manager.rsvp_entries_for(occurrence.as_ref()).await?;
                 for (user_id, entry) in entries {
                     if entry.status.to_string() == status {
                         count += 1;
@@ -345,10 +376,11 @@ impl RsvpManager {
         status: RsvpStatus,
     ) -> Result<Vec<OwnedUserId>> {
         let manager = self.inner.clone();
+        let occurrence = self.occurrence;
         RUNTIME
             .spawn(async move {
                 let mut senders = vec![];
-                let entries = manager.rsvp_entries().await?;
+                let entries = manager.rsvp_entries_for(occurrence.as_ref()).await?;
                 for (user_id, entry) in entries {
                     if entry.status == status {
                         senders.push(user_id);
@@ -367,10 +399,12 @@ impl RsvpManager {
         if !self.is_joined() {
             bail!("Can do RSVP in only joined rooms");
         }
+        let mut inner = self.inner.draft_builder();
+        inner.occurrence(self.occurrence);
         Ok(RsvpDraft {
             client: self.client.clone(),
             room: self.room.clone(),
-            inner: self.inner.draft_builder(),
+            inner,
         })
     }
 
@@ -1,5 +1,6 @@
 use std::collections::BTreeMap;
 
+use acter_core::store::{SearchFilters, SearchHit};
 use anyhow::Result;
 use matrix_sdk::{room::RoomMember, RoomMemberships};
 use matrix_sdk_base::{
@@ -149,8 +150,90 @@ impl PublicSearchResult {
     }
 }
 
+/// Which local objects to search through, all if nothing is set
+#[derive(Clone, Debug, Default)]
+pub struct ObjectSearchFilters {
+    inner: SearchFilters,
+}
+
+impl ObjectSearchFilters {
+    /// only search in this space, can be given several times
+    pub fn space(&mut self, space_id: String) -> Result<()> {
+        self.inner.room_ids.push(RoomId::parse(space_id)?);
+        Ok(())
+    }
+
+    /// only search objects of this type, can be given several times
+    pub fn object_type(&mut self, object_type: String) -> &mut Self {
+        self.inner.object_types.push(object_type);
+        self
+    }
+
+    pub fn limit(&mut self, limit: u32) -> &mut Self {
+        self.inner.limit = Some(limit as usize);
+        self
+    }
+}
+
+pub fn new_object_search_filters() -> ObjectSearchFilters {
+    ObjectSearchFilters::default()
+}
+
+pub struct ObjectSearchResult {
+    hit: SearchHit,
+}
+
+impl ObjectSearchResult {
+    pub fn event_id_str(&self) -> String {
+        self.hit.document.event_id.to_string()
+    }
+
+    pub fn room_id_str(&self) -> String {
+        self.hit.document.room_id.to_string()
+    }
+
+    pub fn object_type_str(&self) -> String {
+        self.hit.document.object_type.clone()
+    }
+
+    /// the object this belongs to, e.g. the object a comment was made on
+    pub fn parent_id_str(&self) -> Option<String> {
+        self.hit
+            .document
+            .parent_id
+            .as_ref()
+            .map(ToString::to_string)
+    }
+
+    pub fn title(&self) -> String {
+        self.hit.document.title.clone()
+    }
+
+    pub fn origin_server_ts(&self) -> u64 {
+        self.hit.document.origin_server_ts.get().into()
+    }
+}
+
 // public API
 impl Client {
+    /// Full-text search over the objects of all spaces on this device, best match first
+    pub async fn search_objects(
+        &self,
+        query: String,
+        filters: Box<ObjectSearchFilters>,
+    ) -> Result<Vec<ObjectSearchResult>> {
+        let me = self.clone();
+        RUNTIME
+            .spawn(async move {
+                let hits = me.store().search(&query, &filters.inner)?;
+                Ok(hits
+                    .into_iter()
+                    .map(|hit| ObjectSearchResult { hit })
+                    .collect())
+            })
+            .await?
+    }
+
     pub async fn search_public_room(
         &self,
         search_term: Option<String>,
@@ -3,8 +3,9 @@ mod user;
 
 pub use space::{
     ActerAppSettings, ActerAppSettingsBuilder, ActerAppSettingsContent, EventsSettings,
-    NewsSettings, PinsSettings, RoomPowerLevels, SimpleOnOffSetting, SimpleOnOffSettingBuilder,
-    SimpleSettingWithTurnOff, SimpleSettingWithTurnOffBuilder, StoriesSettings, TasksSettings,
+    NewsSettings, PinsSettings, PollsSettings, RoomPowerLevels, SimpleOnOffSetting,
+    SimpleOnOffSettingBuilder, SimpleSettingWithTurnOff, SimpleSettingWithTurnOffBuilder,
+    StoriesSettings, TasksSettings,
 };
 
 pub use user::{ActerUserAppSettings, ActerUserAppSettingsBuilder};
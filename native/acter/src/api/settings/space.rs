@@ -1,7 +1,7 @@
 pub use acter_core::events::settings::{
-    ActerAppSettingsContent, EventsSettings, NewsSettings, PinsSettings, SimpleOnOffSetting,
-    SimpleOnOffSettingBuilder, SimpleSettingWithTurnOff, SimpleSettingWithTurnOffBuilder,
-    StoriesSettings, TasksSettings,
+    ActerAppSettingsContent, EventsSettings, NewsSettings, PinsSettings, PollsSettings,
+    SimpleOnOffSetting, SimpleOnOffSettingBuilder, SimpleSettingWithTurnOff,
+    SimpleSettingWithTurnOffBuilder, StoriesSettings, TasksSettings,
 };
 use acter_core::events::{
     attachments::AttachmentEventContent,
@@ -9,10 +9,13 @@ use acter_core::events::{
     comments::CommentEventContent,
     news::NewsEntryEventContent,
     pins::PinEventContent,
+    polls::{PollEventContent, PollVoteEventContent},
     rsvp::RsvpEventContent,
     settings::ActerAppSettingsContentBuilder,
     stories::StoryEventContent,
-    tasks::{TaskEventContent, TaskListEventContent},
+    tasks::{
+        TaskAssignEventContent, TaskEventContent, TaskListEventContent, TaskUnassignEventContent,
+    },
 };
 use anyhow::{bail, Context, Result};
 use matrix_sdk_base::{
@@ -57,6 +60,9 @@ impl ActerAppSettingsBuilder {
     pub fn tasks(&mut self, value: Option<Box<TasksSettings>>) {
         self.inner.tasks(value.map(|i| *i));
     }
+    pub fn polls(&mut self, value: Option<Box<PollsSettings>>) {
+        self.inner.polls(value.map(|i| *i));
+    }
 }
 
 pub struct RoomPowerLevels {
@@ -97,12 +103,36 @@ impl RoomPowerLevels {
     pub fn tasks_key(&self) -> String {
         <TaskEventContent as StaticEventContent>::TYPE.into()
     }
+    pub fn task_assign(&self) -> Option<i64> {
+        self.get_for_key(<TaskAssignEventContent as StaticEventContent>::TYPE.into())
+    }
+    pub fn task_assign_key(&self) -> String {
+        <TaskAssignEventContent as StaticEventContent>::TYPE.into()
+    }
+    pub fn task_unassign(&self) -> Option<i64> {
+        self.get_for_key(<TaskUnassignEventContent as StaticEventContent>::TYPE.into())
+    }
+    pub fn task_unassign_key(&self) -> String {
+        <TaskUnassignEventContent as StaticEventContent>::TYPE.into()
+    }
     pub fn pins(&self) -> Option<i64> {
         self.get_for_key(<PinEventContent as StaticEventContent>::TYPE.into())
     }
     pub fn pins_key(&self) -> String {
         <PinEventContent as StaticEventContent>::TYPE.into()
     }
+    pub fn polls(&self) -> Option<i64> {
+        self.get_for_key(<PollEventContent as StaticEventContent>::TYPE.into())
+    }
+    pub fn polls_key(&self) -> String {
+        <PollEventContent as StaticEventContent>::TYPE.into()
+    }
+    pub fn poll_votes(&self) -> Option<i64> {
+        self.get_for_key(<PollVoteEventContent as StaticEventContent>::TYPE.into())
+    }
+    pub fn poll_votes_key(&self) -> String {
+        <PollVoteEventContent as StaticEventContent>::TYPE.into()
+    }
     pub fn comments(&self) -> Option<i64> {
         self.get_for_key(<CommentEventContent as StaticEventContent>::TYPE.into())
     }
@@ -11,28 +11,37 @@ use acter_core::{
 };
 use anyhow::{bail, Context, Result};
 use futures::stream::StreamExt;
+use futures_signals::signal::Mutable;
 use matrix_sdk::room::{Messages, MessagesOptions};
 use matrix_sdk_base::{
     deserialized_responses::SyncOrStrippedState,
     ruma::{
-        api::client::state::send_state_event,
+        api::{
+            client::{room::get_event_by_timestamp, state::send_state_event},
+            Direction,
+        },
         assign,
         events::{
             space::child::SpaceChildEventContent, AnyStateEventContent, MessageLikeEventType,
             StateEventType,
         },
         serde::Raw,
-        OwnedRoomAliasId, OwnedRoomId, RoomAliasId, RoomId, RoomOrAliasId, ServerName,
+        uint, MilliSecondsSinceUnixEpoch, OwnedRoomAliasId, OwnedRoomId, RoomAliasId, RoomId,
+        RoomOrAliasId, ServerName,
     },
 };
 use matrix_sdk_ui::timeline::RoomExt;
 use serde::{Deserialize, Serialize};
-use std::{ops::Deref, sync::Arc};
+use std::{
+    ops::Deref,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
 use tokio::sync::broadcast::Receiver;
 use tokio_stream::{wrappers::BroadcastStream, Stream};
 use tracing::{error, info, trace, warn};
 
-use crate::{Client, Room, TimelineStream, RUNTIME};
+use crate::{Client, HistoryLoadState, Room, TimelineStream, RUNTIME};
 
 use super::utils::{remap_for_diff, ApiVectorDiff};
 
@@ -62,16 +71,25 @@ impl Ord for Space {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub(crate) struct HistoryState {
     /// The last `end` send from the server
-    seen: String,
+    seen: Option<String>,
+    /// How many events of the history we went through so far
+    #[serde(default)]
+    events_processed: u64,
+    /// The oldest event we went through so far
+    #[serde(default)]
+    oldest_ts: Option<MilliSecondsSinceUnixEpoch>,
+    /// When only the recent history was loaded, the time it starts at
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    older_until: Option<MilliSecondsSinceUnixEpoch>,
+    /// The last `end` of the older history, loaded up to `older_until`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    older_seen: Option<String>,
 }
 
 impl HistoryState {
-    pub fn new(last_seen: String) -> HistoryState {
-        HistoryState { seen: last_seen }
-    }
     pub(crate) fn storage_key(room_id: &RoomId) -> String {
         format!("{room_id}::history")
     }
@@ -80,15 +98,57 @@ impl HistoryState {
         let history = store
             .get_raw::<HistoryState>(&HistoryState::storage_key(room_id))
             .await?;
-        trace!(?room_id, seen = history.seen, "Loading history key");
+        trace!(?room_id, seen = ?history.seen, "Loading history key");
         Ok(history)
     }
-    pub(crate) async fn store(store: &Store, room_id: &RoomId, seen: String) -> Result<()> {
-        trace!(?room_id, ?seen, "Storing history key");
+
+    pub(crate) async fn store(&self, store: &Store, room_id: &RoomId) -> Result<()> {
+        trace!(?room_id, seen = ?self.seen, "Storing history key");
         Ok(store
-            .set_raw(&HistoryState::storage_key(room_id), &HistoryState { seen })
+            .set_raw(&HistoryState::storage_key(room_id), self)
             .await?)
     }
+
+    fn processed(&mut self, origin_server_ts: Option<MilliSecondsSinceUnixEpoch>) {
+        self.events_processed += 1;
+        if let Some(ts) = origin_server_ts {
+            if self.oldest_ts.map_or(true, |oldest| ts < oldest) {
+                self.oldest_ts = Some(ts);
+            }
+        }
+    }
+
+    pub(crate) fn progress(&self) -> HistoryProgress {
+        HistoryProgress {
+            events_processed: self.events_processed,
+            oldest_ts: self.oldest_ts,
+            has_older_history: self.older_until.is_some(),
+        }
+    }
+}
+
+/// How far loading the history of a space got
+#[derive(Debug, Clone, Default)]
+pub struct HistoryProgress {
+    events_processed: u64,
+    oldest_ts: Option<MilliSecondsSinceUnixEpoch>,
+    has_older_history: bool,
+}
+
+impl HistoryProgress {
+    pub fn events_processed(&self) -> u64 {
+        self.events_processed
+    }
+
+    /// The time of the oldest event loaded so far
+    pub fn oldest_ts(&self) -> Option<u64> {
+        self.oldest_ts.map(|ts| ts.get().into())
+    }
+
+    /// Whether history older than the recent days is yet to be loaded
+    pub fn has_older_history(&self) -> bool {
+        self.has_older_history
+    }
 }
 
 // internal API
@@ -105,18 +165,113 @@ impl Space {
         }
     }
 
-    pub(crate) async fn refresh_history(&self) -> Result<()> {
+    /// Load the history of this space we haven’t seen yet
+    ///
+    /// For spaces never loaded before, only the recent days are loaded if so
+    /// configured, see [`Self::refresh_older_history`] for the rest.
+    pub(crate) async fn refresh_history(&self, history: &Mutable<HistoryLoadState>) -> Result<()> {
         let name = self.room.name();
         let room_id = self.room.room_id();
         trace!(name, ?room_id, "refreshing history");
-        let client = self.room.client();
         // self.room.sync_members().await.context("Unable to sync members of room")?;
 
-        let mut from = if let Ok(h) = HistoryState::load(self.client.store(), room_id).await {
-            trace!(name, state=?h.seen, "found history state");
-            Some(h.seen)
+        let mut state = match HistoryState::load(self.client.store(), room_id).await {
+            Ok(state) => {
+                trace!(name, ?state, "found history state");
+                state
+            }
+            Err(_) => self.fresh_history_state().await,
+        };
+
+        self.load_messages(&mut state, false, history).await?;
+        trace!(name, "history loaded");
+        Ok(())
+    }
+
+    /// Load the history older than the recent days loaded at first, if any
+    pub(crate) async fn refresh_older_history(
+        &self,
+        history: &Mutable<HistoryLoadState>,
+    ) -> Result<()> {
+        let room_id = self.room.room_id();
+        let Ok(mut state) = HistoryState::load(self.client.store(), room_id).await else {
+            return Ok(());
+        };
+        if state.older_until.is_none() {
+            return Ok(());
+        }
+        trace!(?room_id, until = ?state.older_until, "loading older history");
+        self.load_messages(&mut state, true, history).await?;
+        trace!(?room_id, "older history loaded");
+        Ok(())
+    }
+
+    /// Where to start loading the history of a space we have never loaded
+    async fn fresh_history_state(&self) -> HistoryState {
+        let Some(window) = self.client.backfill.recent_window() else {
+            return HistoryState::default();
+        };
+        match self.recent_history_start(window).await {
+            Ok(Some((seen, since))) => HistoryState {
+                seen: Some(seen),
+                older_until: Some(since),
+                ..Default::default()
+            },
+            Ok(None) => HistoryState::default(),
+            Err(error) => {
+                let room_id = self.room.room_id();
+                warn!(
+                    ?error,
+                    ?room_id,
+                    "Finding recent history failed, loading all"
+                );
+                HistoryState::default()
+            }
+        }
+    }
+
+    /// The token to load forward from to get the history of the last `window`,
+    /// and the time of its first event
+    async fn recent_history_start(
+        &self,
+        window: Duration,
+    ) -> Result<Option<(String, MilliSecondsSinceUnixEpoch)>> {
+        let Some(since) = SystemTime::now()
+            .checked_sub(window)
+            .and_then(MilliSecondsSinceUnixEpoch::from_system_time)
+        else {
+            return Ok(None);
+        };
+        let request = get_event_by_timestamp::v1::Request::new(
+            self.room.room_id().to_owned(),
+            since,
+            Direction::Forward,
+        );
+        let response = self.room.client().send(request).await?;
+        let context = self
+            .room
+            .event_with_context(&response.event_id, false, uint!(0), None)
+            .await?;
+        Ok(context
+            .prev_batch_token
+            .map(|token| (token, response.origin_server_ts)))
+    }
+
+    /// Go through the messages from where `state` left off, persisting the
+    /// progress after every page
+    async fn load_messages(
+        &self,
+        state: &mut HistoryState,
+        older: bool,
+        history: &Mutable<HistoryLoadState>,
+    ) -> Result<()> {
+        let name = self.room.name();
+        let room_id = self.room.room_id();
+        let until = if older { state.older_until } else { None };
+        let from = if older {
+            state.older_seen.clone()
         } else {
-            None
+            state.seen.clone()
         };
 
         let mut msg_options = MessagesOptions::forward().from(from.as_deref());
@@ -128,16 +283,34 @@ impl Space {
         let executor = self.client.executor();
 
         loop {
+            self.client.backfill.wait_if_paused().await;
+
             trace!(?room_id, name, ?msg_options, "fetching messages");
             let Messages {
-                end, chunk, state, ..
+                end,
+                chunk,
+                state: room_state,
+                ..
             } = self.room.messages(msg_options).await?;
             trace!(?room_id, name, ?chunk, end, "messages received");
 
             let has_chunks = !chunk.is_empty();
+            let mut reached_until = false;
 
             for msg in chunk {
-                let event = match msg.kind.raw().deserialize_as::<AnyActerEvent>() {
+                let raw = msg.kind.raw();
+                let origin_server_ts = raw
+                    .get_field::<MilliSecondsSinceUnixEpoch>("origin_server_ts")
+                    .ok()
+                    .flatten();
+                if until.is_some_and(|until| origin_server_ts.is_some_and(|ts| ts >= until)) {
+                    // the recent history was loaded before
+                    reached_until = true;
+                    break;
+                }
+                state.processed(origin_server_ts);
+
+                let event = match raw.deserialize_as::<AnyActerEvent>() {
                     Ok(AnyActerEvent::RegularTimelineEvent(event)) => {
                         info!(?event, "Received regular event. Ignoring for now");
                         continue;
@@ -154,22 +327,36 @@ impl Space {
 
             // Todo: Do we want to do something with the states, too?
 
-            if let Some(seen) = end {
-                from = Some(seen.clone());
-                msg_options = MessagesOptions::forward().from(from.as_deref());
-                HistoryState::store(self.client.store(), room_id, seen).await?;
-            } else {
-                // how do we want to understand this case?
-                trace!(room_id = ?self.room.room_id(), "Done loading");
-                break;
+            let done = match end {
+                Some(seen) if !reached_until => {
+                    msg_options = MessagesOptions::forward().from(Some(seen.as_str()));
+                    if older {
+                        state.older_seen = Some(seen);
+                    } else {
+                        state.seen = Some(seen);
+                    }
+                    // nothing new to process, we are done catching up
+                    !has_chunks && room_state.is_empty()
+                }
+                _ => {
+                    // how do we want to understand this case?
+                    trace!(?room_id, "Done loading");
+                    true
+                }
+            };
+            if done && older {
+                state.older_until = None;
+                state.older_seen = None;
             }
+            state.store(self.client.store(), room_id).await?;
+            history
+                .lock_mut()
+                .update_progress(room_id.to_owned(), state.progress());
 
-            if !has_chunks && state.is_empty() {
-                // nothing new to process, we are done catching up
+            if done {
                 break;
             }
         }
-        trace!(name, "history loaded");
         Ok(())
     }
 }
@@ -190,6 +377,20 @@ impl Space {
         TimelineStream::new(room, timeline)
     }
 
+    /// How far loading the history of this space got
+    pub async fn history_progress(&self) -> Result<HistoryProgress> {
+        let client = self.client.clone();
+        let room_id = self.room_id().to_owned();
+        RUNTIME
+            .spawn(async move {
+                Ok(HistoryState::load(client.store(), &room_id)
+                    .await
+                    .map(|state| state.progress())
+                    .unwrap_or_default())
+            })
+            .await?
+    }
+
     pub async fn create_onboarding_data(&self) -> Result<bool> {
         let mut engine = Engine::with_template(std::include_str!("../templates/onboarding.toml"))?;
         let core = self.client.core.clone();
@@ -208,6 +409,15 @@ impl Space {
             .await?
     }
 
+    /// The acter objects of this space as a template to recreate them elsewhere
+    pub async fn export_template(&self) -> Result<String> {
+        let core = self.client.core.clone();
+        let room_id = self.room.room_id().to_owned();
+        RUNTIME
+            .spawn(async move { Ok(core.export_template(&room_id).await?) })
+            .await?
+    }
+
     pub fn subscribe_stream(&self) -> impl Stream<Item = bool> {
         BroadcastStream::new(self.subscribe()).map(|_| true)
     }
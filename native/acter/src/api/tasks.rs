@@ -1,19 +1,26 @@
 use acter_core::{
     events::{
-        tasks::{self, Priority, TaskBuilder, TaskListBuilder},
-        Display, RefDetails as CoreRefDetails, RefPreview,
+        calendar::RecurrenceRule,
+        tasks::{self, Priority, TaskBuilder, TaskListBuilder, WorkflowState},
+        Display, EditRule, MentionsBuilder, RefDetails as CoreRefDetails, RefPreview,
     },
-    models::{self, can_redact, ActerModel, AnyActerModel, TaskStats},
+    models::{
+        self, can_redact, ensure_no_task_cycle, next_task_instance, ActerModel, AnyActerModel,
+        TaskStats,
+    },
+    outbox,
     referencing::{IndexKey, SectionIndex, SpecialListsIndex},
 };
 use anyhow::{bail, Context, Result};
-use chrono::DateTime;
+use chrono::{DateTime, Utc};
 use futures::stream::StreamExt;
 use matrix_sdk::room::Room;
 use matrix_sdk_base::{
     ruma::{
-        events::{room::message::TextMessageEventContent, MessageLikeEventType},
-        EventId, OwnedEventId, OwnedRoomId, OwnedUserId,
+        events::{
+            room::message::TextMessageEventContent, MessageLikeEventType, StaticEventContent,
+        },
+        EventId, OwnedEventId, OwnedRoomId, OwnedUserId, UserId,
     },
     RoomState,
 };
@@ -21,6 +28,7 @@ use scc::ebr::Owned;
 use std::{
     collections::{hash_map::Entry, HashMap},
     ops::Deref,
+    str::FromStr,
 };
 use tokio::sync::broadcast::Receiver;
 use tokio_stream::{wrappers::BroadcastStream, Stream};
@@ -28,7 +36,141 @@ use tracing::warn;
 
 use crate::MsgContent;
 
-use super::{client::Client, deep_linking::RefDetails, spaces::Space, RUNTIME};
+use super::{
+    client::Client, deep_linking::RefDetails, history::EditHistory, spaces::Space, RUNTIME,
+};
+
+/// Check the subtask and blocking relations of a task before sending them
+///
+/// `task_id` is `None` for tasks not yet created, they can’t be part of a cycle.
+async fn check_task_relations(
+    client: &Client,
+    task_id: Option<&EventId>,
+    task_list_id: &EventId,
+    parent: Option<&OwnedEventId>,
+    blocked_by: &[OwnedEventId],
+) -> Result<()> {
+    let store = client.store();
+    if let Some(parent) = parent {
+        let AnyActerModel::Task(parent_task) = store.get(parent).await? else {
+            bail!("Parent {parent} is not a task");
+        };
+        if *parent_task.task_list_id.event_id != *task_list_id {
+            bail!("Parent task must be in the same task list");
+        }
+    }
+    for blocker in blocked_by {
+        let AnyActerModel::Task(_) = store.get(blocker).await? else {
+            bail!("Blocker {blocker} is not a task");
+        };
+    }
+    if let Some(task_id) = task_id {
+        ensure_no_task_cycle(store, task_id, parent, blocked_by).await?;
+    }
+    Ok(())
+}
+
+/// The workflow state `status` refers to in the given task list
+async fn workflow_state(
+    client: &Client,
+    task_list_id: &OwnedEventId,
+    status: &str,
+) -> Result<WorkflowState> {
+    let AnyActerModel::TaskList(task_list) = client.store().get(task_list_id).await? else {
+        bail!("{task_list_id} is not a task list");
+    };
+    task_list
+        .workflow_state(status)
+        .cloned()
+        .with_context(|| format!("Task list has no workflow state {status}"))
+}
+
+/// Check the new task and complete it if its status is a done one
+async fn prepare_task(
+    client: &Client,
+    mut content: tasks::TaskEventContent,
+) -> Result<tasks::TaskEventContent> {
+    if !client
+        .may_edit(content.task_list_id.event_id.clone())
+        .await?
+    {
+        bail!("Not allowed to add tasks to this task list");
+    }
+    if let Some(status) = &content.status {
+        let state = workflow_state(client, &content.task_list_id.event_id, status).await?;
+        if state.is_done && content.progress_percent.is_none() {
+            content.progress_percent = Some(100);
+        }
+    }
+    let parent = content.parent_task.as_ref().map(|p| &p.event_id);
+    check_task_relations(
+        client,
+        None,
+        &content.task_list_id.event_id,
+        parent,
+        &content.blocked_by,
+    )
+    .await?;
+    Ok(content)
+}
+
+/// Check the task update and complete or reopen the task along with its
+/// status, returning the next instance of a recurring task completed by it
+async fn prepare_task_update(
+    client: &Client,
+    mut content: tasks::TaskUpdateEventContent,
+) -> Result<(tasks::TaskUpdateEventContent, Option<TaskBuilder>)> {
+    let task_id = content.task.event_id.clone();
+    let AnyActerModel::Task(task) = client.store().get(&task_id).await? else {
+        bail!("{task_id} is not a task");
+    };
+    if !client.may_edit(task_id.clone()).await? {
+        bail!("Not allowed to edit this task");
+    }
+    // moving into or out of a done state completes or reopens the task
+    if let Some(Some(status)) = &content.status {
+        let state = workflow_state(client, &task.task_list_id.event_id, status).await?;
+        if content.progress_percent.is_none() {
+            if state.is_done {
+                content.progress_percent = Some(Some(100));
+            } else if task.is_done() {
+                content.progress_percent = Some(None);
+            }
+        }
+    }
+    let parent = match &content.parent_task {
+        Some(parent) => parent.as_ref().map(|p| p.event_id.clone()),
+        None => task.parent_task_id(),
+    };
+    let blocked_by = content
+        .blocked_by
+        .clone()
+        .unwrap_or_else(|| task.blocked_by.clone());
+    if content.parent_task.is_some() || content.blocked_by.is_some() {
+        check_task_relations(
+            client,
+            Some(&task_id),
+            &task.task_list_id.event_id,
+            parent.as_ref(),
+            &blocked_by,
+        )
+        .await?;
+    }
+    if matches!(content.progress_percent, Some(Some(p)) if p >= 100) {
+        let blockers = client.store().get_many(blocked_by).await;
+        let open_blocker = blockers.into_iter().any(|b| match b {
+            Some(AnyActerModel::Task(blocker)) => !blocker.is_done(),
+            _ => false,
+        });
+        if open_blocker {
+            bail!("Can’t complete a task that is blocked by unfinished tasks");
+        }
+    }
+    // completing a recurring task brings up its next instance
+    let next_instance =
+        next_task_instance(client.store(), &task, &content, Utc::now().date_naive()).await?;
+    Ok((content, next_instance))
+}
 
 impl Client {
     pub async fn task_list(&self, key: String, timeout: Option<u8>) -> Result<TaskList> {
@@ -144,6 +286,7 @@ pub struct TaskListDraft {
     client: Client,
     room: Room,
     content: TaskListBuilder,
+    workflow: Vec<WorkflowState>,
 }
 
 impl TaskListDraft {
@@ -207,15 +350,53 @@ impl TaskListDraft {
         self
     }
 
+    /// only allow the author and the given moderators to update this task list
+    #[allow(clippy::ptr_arg)]
+    pub fn restrict_edits(&mut self, moderators: &mut Vec<String>) -> Result<()> {
+        let moderators = moderators
+            .iter()
+            .map(UserId::parse)
+            .collect::<Result<Vec<_>, _>>()?;
+        self.content
+            .edit_rule(EditRule::author_and_moderators(moderators));
+        Ok(())
+    }
+
     pub fn unset_display(&mut self) -> &mut Self {
         self.content.display(None);
         self
     }
 
+    /// append a state to the workflow of this task list
+    pub fn add_workflow_state(&mut self, key: String, name: String, is_done: bool) -> &mut Self {
+        self.workflow.push(WorkflowState::new(key, name, is_done));
+        self
+    }
+
+    pub fn unset_workflow(&mut self) -> &mut Self {
+        self.workflow.clear();
+        self
+    }
+
+    /// Queue the task list to be sent once online, returning the id it is
+    /// shown under until then
+    pub async fn queue(&self) -> Result<String> {
+        let mut content = self.content.build()?;
+        if !self.workflow.is_empty() {
+            content.workflow.clone_from(&self.workflow);
+        }
+        self.client
+            .queue_acter_event(self.room.clone(), content)
+            .await
+    }
+
     pub async fn send(&self) -> Result<OwnedEventId> {
         let room = self.room.clone();
         let my_id = self.client.user_id()?;
-        let content = self.content.build()?;
+        let mut content = self.content.build()?;
+        if !self.workflow.is_empty() {
+            content.workflow.clone_from(&self.workflow);
+        }
 
         RUNTIME
             .spawn(async move {
@@ -275,6 +456,11 @@ impl TaskList {
         self.content.event_id().to_string()
     }
 
+    /// whether this wasn’t sent yet but is shown from the outbox
+    pub fn is_local_echo(&self) -> bool {
+        outbox::is_local_echo(self.content.event_id())
+    }
+
     pub fn time_zone(&self) -> Option<String> {
         self.content.time_zone.as_ref().map(ToString::to_string)
     }
@@ -385,6 +571,16 @@ impl TaskList {
         matches!(self.room.state(), RoomState::Joined)
     }
 
+    /// whether the current user may update this task list, room moderators always may
+    pub async fn can_edit(&self) -> Result<bool> {
+        if !self.is_joined() {
+            return Ok(false);
+        }
+        self.client
+            .may_edit(self.content.event_id().to_owned())
+            .await
+    }
+
     pub fn task_builder(&self) -> Result<TaskDraft> {
         if !self.is_joined() {
             bail!("Can only create tasks in joined rooms");
@@ -406,6 +602,7 @@ impl TaskList {
             client: self.client.clone(),
             room: self.room.clone(),
             content: self.content.updater(),
+            workflow: None,
         })
     }
 
@@ -417,6 +614,43 @@ impl TaskList {
         self.tasks_with_filter(|_| true).await
     }
 
+    /// The states tasks of this list move through, in order
+    pub fn workflow_states(&self) -> Vec<WorkflowState> {
+        self.content.workflow.clone()
+    }
+
+    /// The tasks currently in the given workflow state
+    pub async fn tasks_with_status(&self, status: String) -> Result<Vec<Task>> {
+        let key = self.content.status_key(status);
+        let client = self.client.clone();
+        let room = self.room.clone();
+        RUNTIME
+            .spawn(async move {
+                let res = client
+                    .store()
+                    .get_list(&key)
+                    .await?
+                    .filter_map(|e| {
+                        let AnyActerModel::Task(content) = e else {
+                            return None;
+                        };
+                        Some(Task {
+                            client: client.clone(),
+                            room: room.clone(),
+                            content,
+                        })
+                    })
+                    .collect();
+                Ok(res)
+            })
+            .await?
+    }
+
+    pub fn subscribe_status_stream(&self, status: String) -> impl Stream<Item = bool> {
+        let key = self.content.status_key(status);
+        BroadcastStream::new(self.client.subscribe(key)).map(|_| true)
+    }
+
     pub async fn task(&self, task_id: String) -> Result<Task> {
         let event_id = EventId::parse(task_id)?;
         self.tasks_with_filter(move |t| t.event_id() == event_id)
@@ -498,6 +732,11 @@ impl Task {
         self.content.event_id().to_string()
     }
 
+    /// whether this wasn’t sent yet but is shown from the outbox
+    pub fn is_local_echo(&self) -> bool {
+        outbox::is_local_echo(self.content.event_id())
+    }
+
     pub fn display(&self) -> Option<Display> {
         self.content.display.clone()
     }
@@ -553,8 +792,45 @@ impl Task {
         self.content.is_done()
     }
 
+    /// derived from the subtasks if there are any
     pub fn progress_percent(&self) -> Option<u8> {
-        self.content.progress_percent
+        self.content.percent()
+    }
+
+    pub fn parent_task_id_str(&self) -> Option<String> {
+        self.content.parent_task_id().map(|p| p.to_string())
+    }
+
+    pub fn blocked_by_str(&self) -> Vec<String> {
+        self.content
+            .blocked_by
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<String>>()
+    }
+
+    pub fn has_subtasks(&self) -> bool {
+        self.content.has_subtasks()
+    }
+
+    /// key of the workflow state of the task list this task is in
+    pub fn status(&self) -> Option<String> {
+        self.content.status.clone()
+    }
+
+    pub fn recurrence_rule(&self) -> Option<String> {
+        self.content
+            .recurrence_rule
+            .as_ref()
+            .map(ToString::to_string)
+    }
+
+    /// the done instance of the recurring task this one was created from
+    pub fn previous_instance_id_str(&self) -> Option<String> {
+        self.content
+            .previous_instance
+            .as_ref()
+            .map(ToString::to_string)
     }
 
     pub fn keywords(&self) -> Vec<String> {
@@ -601,6 +877,27 @@ impl Task {
             .await?
     }
 
+    /// the creation and all updates of this task, oldest first
+    pub async fn edit_history(&self) -> Result<EditHistory> {
+        self.client
+            .edit_history_of(self.content.event_id().to_owned())
+            .await
+    }
+
+    /// this task as it was right after the given update
+    pub async fn state_as_of(&self, event_id: String) -> Result<Task> {
+        let key = self.content.event_id().to_owned();
+        let event_id = EventId::parse(event_id)?;
+        let AnyActerModel::Task(content) = self.client.state_as_of(key, event_id).await? else {
+            bail!("Not a task");
+        };
+        Ok(Task {
+            client: self.client.clone(),
+            room: self.room.clone(),
+            content,
+        })
+    }
+
     pub async fn can_redact(&self) -> Result<bool> {
         let sender = self.content.sender().to_owned();
         let room = self.room.clone();
@@ -614,6 +911,17 @@ impl Task {
         matches!(self.room.state(), RoomState::Joined)
     }
 
+    /// whether the current user may update this task as its task list allows,
+    /// room moderators always may
+    pub async fn can_edit(&self) -> Result<bool> {
+        if !self.is_joined() {
+            return Ok(false);
+        }
+        self.client
+            .may_edit(self.content.event_id().to_owned())
+            .await
+    }
+
     pub fn is_assigned_to_me(&self) -> bool {
         let assignees = self.content.assignees();
         if assignees.is_empty() {
@@ -629,6 +937,9 @@ impl Task {
         if !self.is_joined() {
             bail!("Can only update tasks in joined rooms");
         }
+        if !self.can_edit().await? {
+            bail!("Not allowed to assign yourself to this task");
+        }
         let room = self.room.clone();
         let my_id = self.client.user_id()?;
         let content = self.content.self_assign_event_content();
@@ -669,6 +980,66 @@ impl Task {
             .await?
     }
 
+    pub async fn assign_user(&self, user_id: String) -> Result<OwnedEventId> {
+        if !self.is_joined() {
+            bail!("Can only update tasks in joined rooms");
+        }
+        if !self.can_edit().await? {
+            bail!("Not allowed to assign others to this task");
+        }
+        let room = self.room.clone();
+        let my_id = self.client.user_id()?;
+        let content = self.content.assign_event_content(UserId::parse(user_id)?);
+
+        RUNTIME
+            .spawn(async move {
+                let permitted = room
+                    .can_user_send_message(
+                        &my_id,
+                        MessageLikeEventType::from(
+                            <tasks::TaskAssignEventContent as StaticEventContent>::TYPE,
+                        ),
+                    )
+                    .await?;
+                if !permitted {
+                    bail!("No permissions to assign others to tasks in this room");
+                }
+                let response = room.send(content).await?;
+                Ok(response.event_id)
+            })
+            .await?
+    }
+
+    pub async fn unassign_user(&self, user_id: String) -> Result<OwnedEventId> {
+        if !self.is_joined() {
+            bail!("Can only update tasks in joined rooms");
+        }
+        if !self.can_edit().await? {
+            bail!("Not allowed to unassign others from this task");
+        }
+        let room = self.room.clone();
+        let my_id = self.client.user_id()?;
+        let content = self.content.unassign_event_content(UserId::parse(user_id)?);
+
+        RUNTIME
+            .spawn(async move {
+                let permitted = room
+                    .can_user_send_message(
+                        &my_id,
+                        MessageLikeEventType::from(
+                            <tasks::TaskUnassignEventContent as StaticEventContent>::TYPE,
+                        ),
+                    )
+                    .await?;
+                if !permitted {
+                    bail!("No permissions to unassign others from tasks in this room");
+                }
+                let response = room.send(content).await?;
+                Ok(response.event_id)
+            })
+            .await?
+    }
+
     pub fn update_builder(&self) -> Result<TaskUpdateBuilder> {
         if !self.is_joined() {
             bail!("Can only update tasks in joined rooms");
@@ -703,6 +1074,96 @@ impl Task {
         crate::AttachmentsManager::new(client, room, event_id).await
     }
 
+    pub fn subtask_builder(&self) -> Result<TaskDraft> {
+        if !self.is_joined() {
+            bail!("Can only create tasks in joined rooms");
+        }
+        let mut content = TaskBuilder::default();
+        content
+            .task_list_id(self.content.task_list_id.event_id.clone())
+            .parent_task(Some(self.content.event_id().to_owned().into()));
+        Ok(TaskDraft {
+            client: self.client.clone(),
+            room: self.room.clone(),
+            content,
+        })
+    }
+
+    pub async fn subtasks(&self) -> Result<Vec<Task>> {
+        self.tasks_of_list(self.content.subtasks_key()).await
+    }
+
+    pub fn subscribe_subtasks_stream(&self) -> impl Stream<Item = bool> {
+        BroadcastStream::new(self.client.subscribe(self.content.subtasks_key())).map(|_| true)
+    }
+
+    /// The tasks blocking this one, changes come with `subscribe_stream`
+    pub async fn blockers(&self) -> Result<Vec<Task>> {
+        let blocked_by = self.content.blocked_by.clone();
+        let client = self.client.clone();
+        let room = self.room.clone();
+        RUNTIME
+            .spawn(async move {
+                let res = client
+                    .store()
+                    .get_many(blocked_by)
+                    .await
+                    .into_iter()
+                    .filter_map(|e| {
+                        let Some(AnyActerModel::Task(content)) = e else {
+                            return None;
+                        };
+                        Some(Task {
+                            client: client.clone(),
+                            room: room.clone(),
+                            content,
+                        })
+                    })
+                    .collect();
+                Ok(res)
+            })
+            .await?
+    }
+
+    /// Whether any of the tasks blocking this one isn’t done yet
+    pub async fn is_blocked(&self) -> Result<bool> {
+        Ok(self.blockers().await?.iter().any(|t| !t.is_done()))
+    }
+
+    /// The tasks this one is blocking
+    pub async fn blocking(&self) -> Result<Vec<Task>> {
+        self.tasks_of_list(self.content.blocking_key()).await
+    }
+
+    pub fn subscribe_blocking_stream(&self) -> impl Stream<Item = bool> {
+        BroadcastStream::new(self.client.subscribe(self.content.blocking_key())).map(|_| true)
+    }
+
+    async fn tasks_of_list(&self, key: IndexKey) -> Result<Vec<Task>> {
+        let client = self.client.clone();
+        let room = self.room.clone();
+        RUNTIME
+            .spawn(async move {
+                let res = client
+                    .store()
+                    .get_list(&key)
+                    .await?
+                    .filter_map(|e| {
+                        let AnyActerModel::Task(content) = e else {
+                            return None;
+                        };
+                        Some(Task {
+                            client: client.clone(),
+                            room: room.clone(),
+                            content,
+                        })
+                    })
+                    .collect();
+                Ok(res)
+            })
+            .await?
+    }
+
     pub async fn invitations(&self) -> Result<crate::ObjectInvitationsManager> {
         let client = self.client.clone();
         let room = self.room.clone();
@@ -835,13 +1296,89 @@ impl TaskDraft {
         self
     }
 
+    pub fn parent_task(&mut self, task_id: String) -> Result<()> {
+        let task_id = EventId::parse(task_id)?;
+        self.content.parent_task(Some(task_id.into()));
+        Ok(())
+    }
+
+    pub fn unset_parent_task(&mut self) -> &mut Self {
+        self.content.parent_task(None);
+        self
+    }
+
+    #[allow(clippy::ptr_arg)]
+    pub fn blocked_by(&mut self, task_ids: &mut Vec<String>) -> Result<()> {
+        let task_ids = task_ids
+            .iter()
+            .map(EventId::parse)
+            .collect::<Result<Vec<_>, _>>()?;
+        self.content.blocked_by(task_ids);
+        Ok(())
+    }
+
+    pub fn unset_blocked_by(&mut self) -> &mut Self {
+        self.content.blocked_by(vec![]);
+        self
+    }
+
+    /// repeat this task as given by the RRULE-string, needs a due date
+    pub fn recurrence_rule(&mut self, rrule: String) -> Result<()> {
+        let rule = RecurrenceRule::from_str(&rrule)?;
+        self.content.recurrence_rule(Some(rule));
+        Ok(())
+    }
+
+    pub fn unset_recurrence_rule(&mut self) -> &mut Self {
+        self.content.recurrence_rule(None);
+        self
+    }
+
+    /// key of the workflow state of the task list
+    pub fn status(&mut self, status: String) -> &mut Self {
+        self.content.status(Some(status));
+        self
+    }
+
+    pub fn unset_status(&mut self) -> &mut Self {
+        self.content.status(None);
+        self
+    }
+
+    pub fn add_mention(&mut self, user_id: String) -> Result<()> {
+        self.content.add_mention(UserId::parse(user_id)?);
+        Ok(())
+    }
+
+    pub fn add_room_mention(&mut self, mention: bool) -> &mut Self {
+        self.content.room_mention(mention);
+        self
+    }
+
+    /// Queue the task to be sent once online, returning the id it is
+    /// shown under until then
+    pub async fn queue(&self) -> Result<String> {
+        let client = self.client.clone();
+        let room = self.room.clone();
+        let content = self.content.build()?;
+
+        RUNTIME
+            .spawn(async move {
+                let content = prepare_task(&client, content).await?;
+                client.queue_acter_event(room, content).await
+            })
+            .await?
+    }
+
     pub async fn send(&self) -> Result<OwnedEventId> {
+        let client = self.client.clone();
         let room = self.room.clone();
         let my_id = self.client.user_id()?;
         let content = self.content.build()?;
 
         RUNTIME
             .spawn(async move {
+                let content = prepare_task(&client, content).await?;
                 let permitted = room
                     .can_user_send_message(&my_id, MessageLikeEventType::RoomMessage)
                     .await?;
@@ -952,6 +1489,74 @@ impl TaskUpdateBuilder {
         self.content.categories(None);
         self
     }
+    pub fn parent_task(&mut self, task_id: String) -> Result<()> {
+        let task_id = EventId::parse(task_id)?;
+        self.content.parent_task(Some(Some(task_id.into())));
+        Ok(())
+    }
+
+    pub fn unset_parent_task(&mut self) -> &mut Self {
+        self.content.parent_task(Some(None));
+        self
+    }
+
+    pub fn unset_parent_task_update(&mut self) -> &mut Self {
+        self.content.parent_task(None);
+        self
+    }
+
+    #[allow(clippy::ptr_arg)]
+    pub fn blocked_by(&mut self, task_ids: &mut Vec<String>) -> Result<()> {
+        let task_ids = task_ids
+            .iter()
+            .map(EventId::parse)
+            .collect::<Result<Vec<_>, _>>()?;
+        self.content.blocked_by(Some(task_ids));
+        Ok(())
+    }
+
+    pub fn unset_blocked_by(&mut self) -> &mut Self {
+        self.content.blocked_by(Some(vec![]));
+        self
+    }
+
+    pub fn unset_blocked_by_update(&mut self) -> &mut Self {
+        self.content.blocked_by(None);
+        self
+    }
+
+    pub fn recurrence_rule(&mut self, rrule: String) -> Result<()> {
+        let rule = RecurrenceRule::from_str(&rrule)?;
+        self.content.recurrence_rule(Some(Some(rule)));
+        Ok(())
+    }
+
+    pub fn unset_recurrence_rule(&mut self) -> &mut Self {
+        self.content.recurrence_rule(Some(None));
+        self
+    }
+
+    pub fn unset_recurrence_rule_update(&mut self) -> &mut Self {
+        self.content.recurrence_rule(None);
+        self
+    }
+
+    /// move the task to this workflow state of its task list
+    pub fn status(&mut self, status: String) -> &mut Self {
+        self.content.status(Some(Some(status)));
+        self
+    }
+
+    pub fn unset_status(&mut self) -> &mut Self {
+        self.content.status(Some(None));
+        self
+    }
+
+    pub fn unset_status_update(&mut self) -> &mut Self {
+        self.content.status(None);
+        self
+    }
+
     pub fn mark_done(&mut self) -> &mut Self {
         self.content.progress_percent(Some(Some(100)));
         self
@@ -1038,13 +1643,49 @@ impl TaskUpdateBuilder {
         self
     }
 
+    pub fn add_mention(&mut self, user_id: String) -> Result<()> {
+        self.content.add_mention(UserId::parse(user_id)?);
+        Ok(())
+    }
+
+    pub fn add_room_mention(&mut self, mention: bool) -> &mut Self {
+        self.content.room_mention(mention);
+        self
+    }
+
+    /// Queue the update to be sent once online, returning the id it is
+    /// shown under until then
+    pub async fn queue(&self) -> Result<String> {
+        let client = self.client.clone();
+        let room = self.room.clone();
+        let content = self.content.build()?;
+
+        RUNTIME
+            .spawn(async move {
+                let (content, next_instance) = prepare_task_update(&client, content).await?;
+                let queued = client.queue_acter_event(room.clone(), content).await?;
+                if let Some(next_instance) = next_instance {
+                    let draft = TaskDraft {
+                        client,
+                        room,
+                        content: next_instance,
+                    };
+                    draft.queue().await?;
+                }
+                Ok(queued)
+            })
+            .await?
+    }
+
     pub async fn send(&self) -> Result<OwnedEventId> {
+        let client = self.client.clone();
         let room = self.room.clone();
         let my_id = self.client.user_id()?;
         let content = self.content.build()?;
 
         RUNTIME
             .spawn(async move {
+                let (content, next_instance) = prepare_task_update(&client, content).await?;
                 let permitted = room
                     .can_user_send_message(&my_id, MessageLikeEventType::RoomMessage)
                     .await?;
@@ -1052,6 +1693,14 @@ impl TaskUpdateBuilder {
                     bail!("No permissions to send message in this room");
                 }
                 let response = room.send(content).await?;
+                if let Some(next_instance) = next_instance {
+                    let draft = TaskDraft {
+                        client,
+                        room,
+                        content: next_instance,
+                    };
+                    draft.send().await?;
+                }
                 Ok(response.event_id)
             })
             .await?
@@ -1063,6 +1712,7 @@ pub struct TaskListUpdateBuilder {
     client: Client,
     room: Room,
     content: tasks::TaskListUpdateBuilder,
+    workflow: Option<Vec<WorkflowState>>,
 }
 
 impl TaskListUpdateBuilder {
@@ -1151,13 +1801,52 @@ impl TaskListUpdateBuilder {
         self
     }
 
+    /// append a state to the new workflow, replacing the current one on send
+    pub fn add_workflow_state(&mut self, key: String, name: String, is_done: bool) -> &mut Self {
+        self.workflow
+            .get_or_insert_with(Vec::new)
+            .push(WorkflowState::new(key, name, is_done));
+        self
+    }
+
+    pub fn unset_workflow(&mut self) -> &mut Self {
+        self.workflow = Some(vec![]);
+        self
+    }
+
+    pub fn unset_workflow_update(&mut self) -> &mut Self {
+        self.workflow = None;
+        self
+    }
+
+    /// Queue the update to be sent once online, returning its transaction id
+    pub async fn queue(&self) -> Result<String> {
+        let mut content = self.content.build()?;
+        content.workflow.clone_from(&self.workflow);
+        if !self
+            .client
+            .may_edit(content.task_list.event_id.clone())
+            .await?
+        {
+            bail!("Not allowed to edit this task list");
+        }
+        self.client
+            .queue_acter_event(self.room.clone(), content)
+            .await
+    }
+
     pub async fn send(&self) -> Result<OwnedEventId> {
+        let client = self.client.clone();
         let room = self.room.clone();
         let my_id = self.client.user_id()?;
-        let content = self.content.build()?;
+        let mut content = self.content.build()?;
+        content.workflow.clone_from(&self.workflow);
 
         RUNTIME
             .spawn(async move {
+                if !client.may_edit(content.task_list.event_id.clone()).await? {
+                    bail!("Not allowed to edit this task list");
+                }
                 let permitted = room
                     .can_user_send_message(&my_id, MessageLikeEventType::RoomMessage)
                     .await?;
@@ -1179,6 +1868,7 @@ impl Space {
         Ok(TaskListDraft {
             client: self.client.clone(),
             room: self.inner.room.clone(),
+            workflow: vec![],
             content: Default::default(),
         })
     }
@@ -1188,6 +1878,19 @@ impl Space {
             bail!("Unable to create tasks for spaces we are not part on");
         }
         Ok(TaskListDraft {
+            client: self.client.clone(),
+            room: self.inner.room.clone(),
+            workflow: vec![],
+            content,
+        })
+    }
+
+    /// `content` must already refer to the task list of this space
+    pub fn task_draft_with_builder(&self, content: TaskBuilder) -> Result<TaskDraft> {
+        if !self.inner.is_joined() {
+            bail!("Unable to create tasks for spaces we are not part on");
+        }
+        Ok(TaskDraft {
             client: self.client.clone(),
             room: self.inner.room.clone(),
             content,
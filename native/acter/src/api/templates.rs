@@ -0,0 +1,169 @@
+use acter_core::templates::{manifest::Manifest, Engine, Value};
+use anyhow::{bail, Context, Result};
+use futures::stream::StreamExt;
+
+use super::{client::Client, spaces::Space, RUNTIME};
+
+/// An input a template asks for
+#[derive(Clone, Debug)]
+pub struct TemplateInput {
+    name: String,
+    type_name: String,
+    required: bool,
+    description: Option<String>,
+    default_value: Option<String>,
+    options: Vec<String>,
+}
+
+impl TemplateInput {
+    pub fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    /// `text`, `user`, `space`, `date`, `date-time`, `number`, `boolean`,
+    /// `choice` or `list`
+    pub fn type_name(&self) -> String {
+        self.type_name.clone()
+    }
+
+    pub fn is_required(&self) -> bool {
+        self.required
+    }
+
+    pub fn description(&self) -> Option<String> {
+        self.description.clone()
+    }
+
+    pub fn default_value(&self) -> Option<String> {
+        self.default_value.clone()
+    }
+
+    /// the values to pick from for `choice` inputs
+    pub fn options(&self) -> Vec<String> {
+        self.options.clone()
+    }
+}
+
+/// How far applying a template got
+#[derive(Clone, Debug)]
+pub struct TemplateApplication {
+    manifest: String,
+    error: Option<String>,
+}
+
+impl TemplateApplication {
+    /// the manifest json of the objects created so far, to resume or roll back
+    pub fn manifest(&self) -> String {
+        self.manifest.clone()
+    }
+
+    /// why applying stopped partway, if it did
+    pub fn error(&self) -> Option<String> {
+        self.error.clone()
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+impl Client {
+    /// The inputs the given template asks for
+    pub fn template_inputs(&self, template: String) -> Result<Vec<TemplateInput>> {
+        let engine = Engine::with_template(&template)?;
+        Ok(engine
+            .requested_inputs()
+            .iter()
+            .map(|(name, input)| TemplateInput {
+                name: name.clone(),
+                type_name: input.type_name().to_owned(),
+                required: input.is_required(),
+                description: input.description().map(ToOwned::to_owned),
+                default_value: input.default_text(),
+                options: input.options().to_vec(),
+            })
+            .collect())
+    }
+
+    /// Redact what executing a template created, as recorded in the manifest
+    ///
+    /// Returns the manifest of what is left, the created spaces.
+    pub async fn rollback_template(
+        &self,
+        manifest: String,
+        reason: Option<String>,
+    ) -> Result<String> {
+        let core = self.core.clone();
+        RUNTIME
+            .spawn(async move {
+                let mut manifest = Manifest::from_json(&manifest)?;
+                core.rollback_template(&mut manifest, reason.as_deref())
+                    .await?;
+                Ok(manifest.to_json()?)
+            })
+            .await?
+    }
+}
+
+impl Space {
+    /// Create the objects of the template in this space
+    ///
+    /// Input values come as `name=value`. Space inputs not given are this
+    /// space, the user inputs this client. Given the manifest of an earlier
+    /// run, the objects it created are skipped. Failing partway still
+    /// returns the manifest, so the run can be resumed or rolled back.
+    pub async fn apply_template(
+        &self,
+        template: String,
+        values: Vec<String>,
+        manifest: Option<String>,
+    ) -> Result<TemplateApplication> {
+        let mut engine = Engine::with_template(&template)?;
+        let core = self.client.core.clone();
+        let room_id = self.room_id().to_string();
+        RUNTIME
+            .spawn(async move {
+                let inputs = engine
+                    .requested_inputs()
+                    .iter()
+                    .map(|(name, input)| (name.clone(), input.type_name()))
+                    .collect::<Vec<_>>();
+                for (name, type_name) in inputs {
+                    let value = values
+                        .iter()
+                        .filter_map(|v| v.split_once('='))
+                        .find(|(key, _)| *key == name)
+                        .map(|(_, value)| value.to_owned());
+                    match (type_name, value) {
+                        ("user", None) => engine.add_user(name, core.clone()).await?,
+                        ("user", Some(_)) => bail!("{name}: only the current user is supported"),
+                        ("space", value) => engine.add_ref(
+                            name,
+                            "space".to_owned(),
+                            value.unwrap_or_else(|| room_id.clone()),
+                        )?,
+                        (_, Some(value)) => engine.add_context(name, Value::from(value))?,
+                        (_, None) => {}
+                    }
+                }
+
+                if let Some(manifest) = manifest {
+                    engine.resume_from(Manifest::from_json(&manifest)?)?;
+                }
+
+                let mut error = None;
+                let mut executer = engine.execute()?;
+                while let Some(i) = executer.next().await {
+                    if let Err(e) = i.context("Creating template object failed") {
+                        error = Some(format!("{e:#}"));
+                        break;
+                    }
+                }
+                Ok(TemplateApplication {
+                    manifest: engine.manifest()?.to_json()?,
+                    error,
+                })
+            })
+            .await?
+    }
+}
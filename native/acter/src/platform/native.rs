@@ -1,9 +1,13 @@
+use acter_core::store::DATA_PATH_KEY;
 use anyhow::{bail, Result};
 use chrono::Local;
 use lazy_static::lazy_static;
 use log::{log_enabled, Level, LevelFilter, Log, Metadata, Record};
 use matrix_sdk::{Client, ClientBuilder, SqliteEventCacheStore};
-use matrix_sdk_base::{event_cache::store::EventCacheStoreError, store::StoreConfig};
+use matrix_sdk_base::{
+    event_cache::store::EventCacheStoreError,
+    store::{StateStore, StoreConfig},
+};
 use matrix_sdk_sqlite::{OpenStoreError, SqliteCryptoStore, SqliteStateStore};
 use parse_env_filter::eager::{filters, Filter};
 use std::{
@@ -317,6 +321,16 @@ async fn make_store_config(
         .crypto_store(SqliteCryptoStore::open(path, passphrase).await?);
 
     let sql_state_store = SqliteStateStore::open(path, passphrase).await?;
+    // the acter models database lives right next to the matrix stores
+    if let Err(e) = sql_state_store
+        .set_custom_value_no_read(
+            DATA_PATH_KEY.as_bytes(),
+            path.to_string_lossy().as_bytes().to_vec(),
+        )
+        .await
+    {
+        tracing::warn!("Failed to store models database path: {e}");
+    }
     let event_cache_store = SqliteEventCacheStore::open(path, passphrase).await?;
     let Some(passphrase) = passphrase else {
         return Ok(config
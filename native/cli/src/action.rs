@@ -2,11 +2,13 @@ use anyhow::Result;
 use clap::Subcommand;
 
 mod execute;
+mod export;
 mod history;
 mod list;
 mod manage;
 
 pub use execute::ExecuteOpts;
+pub use export::ExportOpts;
 pub use history::HistoryOpts;
 pub use list::List;
 pub use manage::Manage;
@@ -21,6 +23,8 @@ pub enum Action {
     History(HistoryOpts),
     /// Template Execution
     Execute(ExecuteOpts),
+    /// Export the objects of a space as a template
+    Export(ExportOpts),
 }
 
 impl Action {
@@ -30,6 +34,7 @@ impl Action {
             Action::List(config) => config.run().await?,
             Action::History(config) => config.run().await?,
             Action::Execute(config) => config.run().await?,
+            Action::Export(config) => config.run().await?,
         };
         Ok(())
     }
@@ -1,3 +1,5 @@
+use acter::api::Client;
+use acter_core::templates::{manifest::Manifest, Value};
 use anyhow::{bail, Result};
 use clap::Parser;
 use futures::{pin_mut, stream::StreamExt};
@@ -17,12 +19,28 @@ pub struct ExecuteOpts {
     #[clap(long)]
     pub ignore_sync: bool,
 
+    /// Only render and check the objects, without creating any
+    #[clap(long)]
+    pub dry_run: bool,
+
+    /// Record the created objects in this file and skip the ones already
+    /// recorded in it, to resume a failed run
+    #[clap(long)]
+    pub manifest: Option<PathBuf>,
+
+    /// Redact the objects recorded in the manifest instead of executing
+    #[clap(long, requires = "manifest")]
+    pub rollback: bool,
+
     #[clap()]
     pub templates: Vec<PathBuf>,
 }
 
 impl ExecuteOpts {
     pub async fn run(&self) -> Result<()> {
+        if self.manifest.is_some() && self.templates.len() > 1 {
+            bail!("A manifest can only be kept for a single template");
+        }
         let mapped_inputs = self
             .inputs
             .iter()
@@ -30,13 +48,18 @@ impl ExecuteOpts {
             .collect::<HashMap<&str, &str>>();
         let mut user = self.login.client().await?;
 
-        let sync_state = user.start_sync();
+        // a dry run doesn’t need anything from the homeserver
+        let sync_state = (!self.dry_run).then(|| user.start_sync());
 
-        if !self.ignore_sync {
+        if let Some(sync_state) = sync_state.as_ref().filter(|_| !self.ignore_sync) {
             let mut is_synced = sync_state.first_synced_rx();
             while is_synced.next().await != Some(true) {} // let’s wait for it to have synced
         }
 
+        if self.rollback {
+            return self.rollback(&user).await;
+        }
+
         for tmpl_path in self.templates.iter() {
             let template = std::fs::read_to_string(tmpl_path)?;
 
@@ -45,16 +68,20 @@ impl ExecuteOpts {
                 tmpl_engine
                     .requested_inputs()
                     .iter()
-                    .map(|(key, input)| (key.clone(), (input.is_required(), input.is_space())))
+                    .map(|(key, input)| (key.clone(), (input.is_required(), input.type_name())))
                     .collect::<Vec<_>>()
             };
-            for (key, (is_required, is_space)) in input_values {
+            for (key, (is_required, type_name)) in input_values {
                 if let Some(res) = mapped_inputs.get(key.as_str()).cloned() {
-                    if !is_space {
-                        bail!("{key} : non-space input values not yet supported");
+                    match type_name {
+                        "space" => {
+                            tmpl_engine.add_ref(key.clone(), "space".to_owned(), res.to_owned())?
+                        }
+                        "user" => bail!("{key} : user input values not yet supported"),
+                        // parsed into the type of the input
+                        _ => tmpl_engine.add_context(key.clone(), Value::from(res))?,
                     }
-                    tmpl_engine.add_ref(key.clone(), "space".to_owned(), res.to_owned())?;
-                } else if is_required {
+                } else if is_required && !self.dry_run {
                     if key != "main" {
                         bail!("Missing required input value {key} for {tmpl_path:?}");
                     }
@@ -64,12 +91,75 @@ impl ExecuteOpts {
                 }
             }
 
+            if self.dry_run {
+                let report = tmpl_engine.validate();
+                for key in &report.missing_inputs {
+                    println!("missing input: {key}");
+                }
+                for error in &report.errors {
+                    println!("error: {error}");
+                }
+                for object in &report.objects {
+                    let obj_type = object.obj_type.unwrap_or("?");
+                    match &object.error {
+                        None => println!("{} ({obj_type}): ok", object.key),
+                        Some(error) => println!("{} ({obj_type}): {error}", object.key),
+                    }
+                    if let Some(rendered) = &object.rendered {
+                        println!("    {rendered}");
+                    }
+                }
+                if !report.is_valid() {
+                    bail!("{tmpl_path:?} is not valid");
+                }
+                continue;
+            }
+
+            if let Some(manifest) = self.load_manifest()? {
+                info!(
+                    "Resuming, skipping {} created objects",
+                    manifest.objects().len()
+                );
+                tmpl_engine.resume_from(manifest)?;
+            }
+
             let exec_stream = tmpl_engine.execute()?;
             pin_mut!(exec_stream);
             while let Some(i) = exec_stream.next().await {
+                // saved before bailing, so a failed run can be resumed
+                self.save_manifest(&tmpl_engine.manifest()?)?;
                 i?
             }
         }
         Ok(())
     }
+
+    fn load_manifest(&self) -> Result<Option<Manifest>> {
+        match &self.manifest {
+            Some(path) if path.exists() => {
+                Ok(Some(Manifest::from_json(&std::fs::read_to_string(path)?)?))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn save_manifest(&self, manifest: &Manifest) -> Result<()> {
+        if let Some(path) = &self.manifest {
+            std::fs::write(path, manifest.to_json()?)?;
+        }
+        Ok(())
+    }
+
+    async fn rollback(&self, user: &Client) -> Result<()> {
+        let Some(manifest) = self.load_manifest()? else {
+            bail!("No manifest found at {:?}", self.manifest);
+        };
+        let remaining = user.rollback_template(manifest.to_json()?, None).await?;
+        let remaining = Manifest::from_json(&remaining)?;
+        self.save_manifest(&remaining)?;
+        for (key, created) in remaining.objects() {
+            info!("{key} ({}) kept: {}", created.obj_type, created.id);
+        }
+        Ok(())
+    }
 }
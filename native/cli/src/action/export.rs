@@ -0,0 +1,43 @@
+use anyhow::Result;
+use clap::Parser;
+use futures::stream::StreamExt;
+use matrix_sdk_base::ruma::OwnedRoomId;
+use std::path::PathBuf;
+use tracing::info;
+
+use crate::config::{LoginConfig, ENV_ROOM};
+
+#[derive(Parser, Debug)]
+pub struct ExportOpts {
+    #[clap(flatten)]
+    pub login: LoginConfig,
+
+    /// The space to export the objects of
+    #[clap(env = ENV_ROOM)]
+    pub room: OwnedRoomId,
+
+    /// Write the template to this file rather than stdout
+    #[clap(short, long)]
+    pub output: Option<PathBuf>,
+}
+
+impl ExportOpts {
+    pub async fn run(&self) -> Result<()> {
+        let mut client = self.login.client().await?;
+
+        info!(" - Syncing -");
+        let sync_state = client.start_sync();
+
+        let mut is_synced = sync_state.first_synced_rx();
+        while is_synced.next().await != Some(true) {} // let’s wait for it to have synced
+        info!(" - First Sync finished - ");
+
+        let space = client.space(self.room.to_string()).await?;
+        let template = space.export_template().await?;
+        match &self.output {
+            Some(path) => std::fs::write(path, template)?,
+            None => println!("{template}"),
+        }
+        Ok(())
+    }
+}
@@ -3,6 +3,7 @@ use anyhow::Result;
 use clap::{Parser, Subcommand};
 use futures::stream::StreamExt;
 use matrix_sdk_base::ruma::{OwnedRoomId, RoomId};
+use std::path::{Path, PathBuf};
 use tracing::{info, warn};
 
 use crate::config::LoginConfig;
@@ -13,6 +14,8 @@ pub enum Action {
     CreateOnboardingSpace,
     /// Mark the space as an acter space
     MarkAsActerSpace { room_id: OwnedRoomId },
+    /// Import the events and todos of an iCalendar (.ics) file into the space
+    ImportIcal { room_id: OwnedRoomId, path: PathBuf },
 }
 
 /// Posting a news item to a given room
@@ -30,9 +33,49 @@ impl Manage {
         match self.action {
             Action::CreateOnboardingSpace => self.run_create_onboarding_space().await,
             Action::MarkAsActerSpace { ref room_id } => self.run_marking_space(room_id).await,
+            Action::ImportIcal {
+                ref room_id,
+                ref path,
+            } => self.run_import_ical(room_id, path).await,
         }
     }
 
+    async fn run_import_ical(&self, room_id: &RoomId, path: &Path) -> Result<()> {
+        let mut client = self.login.client().await?;
+        info!(" - Syncing -");
+        let sync_state = client.start_sync();
+
+        let mut is_synced = sync_state.first_synced_rx();
+        while is_synced.next().await != Some(true) {} // let’s wait for it to have synced
+        info!(" - First Sync finished - ");
+
+        let space = client.space(room_id.to_string()).await?;
+        let report = space
+            .import_ical(path.to_string_lossy().into_owned())
+            .await?;
+
+        for item in report.items() {
+            let name = item
+                .summary()
+                .or_else(|| item.uid())
+                .unwrap_or_else(|| "<unnamed>".to_owned());
+            if let Some(error) = item.error() {
+                println!(" - failed:   {name}: {error}");
+            } else if item.is_skipped() {
+                println!(" - skipped:  {name} (already imported)");
+            } else {
+                println!(" - imported: {name}");
+            }
+        }
+        println!(
+            "{} imported, {} skipped, {} failed",
+            report.created_count(),
+            report.skipped_count(),
+            report.failed_count()
+        );
+        Ok(())
+    }
+
     async fn run_marking_space(&self, room_id: &RoomId) -> Result<()> {
         let mut client = self.login.client().await?;
         info!(" - Syncing -");
@@ -1,13 +1,16 @@
 use chrono::{NaiveDate, NaiveTime, Utc};
-use matrix_sdk::ruma::{events::room::message::TextMessageEventContent, OwnedEventId, OwnedUserId};
+use matrix_sdk::ruma::{
+    events::{room::message::TextMessageEventContent, Mentions},
+    OwnedEventId, OwnedUserId, UserId,
+};
 use object::ActivityObject;
 use urlencoding::encode;
 
 use crate::{
     client::CoreClient,
     events::{
-        attachments::AttachmentContent, news::NewsContent, rsvp::RsvpStatus, RefDetails,
-        UtcDateTime,
+        attachments::AttachmentContent, mentions_user, news::NewsContent, rsvp::RsvpStatus,
+        RefDetails, UtcDateTime,
     },
     models::{
         status::{
@@ -26,6 +29,15 @@ use crate::{
 pub mod object;
 pub mod status;
 
+/// The comment a comment is a reply to
+#[derive(Clone, Debug)]
+pub struct ReplyToComment {
+    pub event_id: OwnedEventId,
+    /// `None` if the comment isn’t known locally
+    pub sender: Option<OwnedUserId>,
+    pub content: Option<TextMessageEventContent>,
+}
+
 #[derive(Clone, Debug)]
 pub enum ActivityContent {
     MembershipChange(MembershipContent),
@@ -61,6 +73,7 @@ pub enum ActivityContent {
     Comment {
         object: ActivityObject,
         content: TextMessageEventContent,
+        in_reply_to: Option<ReplyToComment>,
     },
     Reaction {
         object: ActivityObject,
@@ -100,12 +113,26 @@ pub enum ActivityContent {
         object: ActivityObject,
         new_due_date: Option<NaiveDate>,
     },
+    TaskStatusChange {
+        object: ActivityObject,
+        new_status: Option<String>,
+        /// the name of the workflow state, if it was found
+        new_status_name: Option<String>,
+    },
     TaskAccept {
         object: ActivityObject,
     },
     TaskDecline {
         object: ActivityObject,
     },
+    TaskAssign {
+        object: ActivityObject,
+        assignee: OwnedUserId,
+    },
+    TaskUnassign {
+        object: ActivityObject,
+        assignee: OwnedUserId,
+    },
     ObjectInvitation {
         object: ActivityObject,
         invitees: Vec<OwnedUserId>,
@@ -119,11 +146,17 @@ pub enum ActivityContent {
 pub struct Activity {
     inner: ActivityContent,
     meta: EventMeta,
+    /// the users and rooms mentioned by the underlying event
+    mentions: Option<Mentions>,
 }
 
 impl Activity {
     fn new(meta: EventMeta, inner: ActivityContent) -> Self {
-        Self { meta, inner }
+        Self {
+            meta,
+            inner,
+            mentions: None,
+        }
     }
     pub fn content(&self) -> &ActivityContent {
         &self.inner
@@ -184,8 +217,11 @@ impl Activity {
                 }
             }
             ActivityContent::TaskDueDateChange { .. } => "taskDueDateChange",
+            ActivityContent::TaskStatusChange { .. } => "taskStatusChange",
             ActivityContent::TaskAccept { .. } => "taskAccept",
             ActivityContent::TaskDecline { .. } => "taskDecline",
+            ActivityContent::TaskAssign { .. } => "taskAssign",
+            ActivityContent::TaskUnassign { .. } => "taskUnassign",
             ActivityContent::Boost { .. } => "news",
             ActivityContent::Creation { .. } => "creation",
             ActivityContent::TitleChange { .. } => "titleChange",
@@ -426,8 +462,11 @@ impl Activity {
             | ActivityContent::TaskAdd { object, .. }
             | ActivityContent::TaskProgress { object, .. }
             | ActivityContent::TaskDueDateChange { object, .. }
+            | ActivityContent::TaskStatusChange { object, .. }
             | ActivityContent::TaskAccept { object }
             | ActivityContent::TaskDecline { object }
+            | ActivityContent::TaskAssign { object, .. }
+            | ActivityContent::TaskUnassign { object, .. }
             | ActivityContent::ObjectInvitation { object, .. } => Some(object.clone()),
         }
     }
@@ -456,6 +495,42 @@ impl Activity {
         }
     }
 
+    pub fn new_status(&self) -> Option<String> {
+        if let ActivityContent::TaskStatusChange { new_status, .. } = &self.inner {
+            new_status.clone()
+        } else {
+            None
+        }
+    }
+
+    pub fn new_status_name(&self) -> Option<String> {
+        if let ActivityContent::TaskStatusChange {
+            new_status_name, ..
+        } = &self.inner
+        {
+            new_status_name.clone()
+        } else {
+            None
+        }
+    }
+
+    /// For a reply, the comment it replied to
+    pub fn in_reply_to(&self) -> Option<&ReplyToComment> {
+        if let ActivityContent::Comment { in_reply_to, .. } = &self.inner {
+            in_reply_to.as_ref()
+        } else {
+            None
+        }
+    }
+
+    pub fn reply_to_comment_id_str(&self) -> Option<String> {
+        self.in_reply_to().map(|r| r.event_id.to_string())
+    }
+
+    pub fn reply_to_sender(&self) -> Option<OwnedUserId> {
+        self.in_reply_to().and_then(|r| r.sender.clone())
+    }
+
     pub fn ref_details(&self) -> Option<RefDetails> {
         if let ActivityContent::Reference { details, .. } = &self.inner {
             Some(details.clone())
@@ -473,8 +548,11 @@ impl Activity {
             | ActivityContent::Rsvp { object, .. }
             | ActivityContent::TaskProgress { object, .. }
             | ActivityContent::TaskDueDateChange { object, .. }
+            | ActivityContent::TaskStatusChange { object, .. }
             | ActivityContent::TaskAccept { object, .. }
             | ActivityContent::TaskDecline { object, .. }
+            | ActivityContent::TaskAssign { object, .. }
+            | ActivityContent::TaskUnassign { object, .. }
             | ActivityContent::OtherChanges { object }
             | ActivityContent::Creation { object, .. }
             | ActivityContent::ObjectInvitation { object, .. } => object.target_url(),
@@ -526,13 +604,33 @@ impl Activity {
     }
 
     pub fn whom(&self) -> Vec<String> {
-        let ActivityContent::ObjectInvitation { ref invitees, .. } = self.content() else {
-            return vec![];
-        };
-        invitees
-            .iter()
-            .map(ToString::to_string)
-            .collect::<Vec<String>>()
+        match self.content() {
+            ActivityContent::ObjectInvitation { invitees, .. } => invitees
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<String>>(),
+            ActivityContent::TaskAssign { assignee, .. }
+            | ActivityContent::TaskUnassign { assignee, .. } => vec![assignee.to_string()],
+            _ => vec![],
+        }
+    }
+
+    /// The users explicitly mentioned by this activity
+    pub fn mentioned_users(&self) -> Vec<OwnedUserId> {
+        self.mentions
+            .as_ref()
+            .map(|m| m.user_ids.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Whether everyone in the room was mentioned
+    pub fn mentions_room(&self) -> bool {
+        self.mentions.as_ref().is_some_and(|m| m.room)
+    }
+
+    /// Whether the user was mentioned, directly or by mentioning the room
+    pub fn mentions_user(&self, user_id: &UserId) -> bool {
+        mentions_user(self.mentions.as_ref(), &self.meta.sender, user_id)
     }
 
     pub fn task_list_id_str(&self) -> Option<String> {
@@ -540,15 +638,40 @@ impl Activity {
             ActivityContent::TaskAccept { object }
             | ActivityContent::TaskAdd { object, .. }
             | ActivityContent::TaskDecline { object }
+            | ActivityContent::TaskAssign { object, .. }
+            | ActivityContent::TaskUnassign { object, .. }
             | ActivityContent::TaskDueDateChange { object, .. }
+            | ActivityContent::TaskStatusChange { object, .. }
             | ActivityContent::TaskProgress { object, .. } => object.task_list_id_str(),
             _ => None,
         }
     }
 }
 
+/// The name of the workflow state of the tasklist the task is in
+async fn workflow_state_name(store: &Store, task_id: &OwnedEventId, key: &str) -> Option<String> {
+    let Ok(AnyActerModel::Task(task)) = store.get(task_id).await else {
+        return None;
+    };
+    let Ok(AnyActerModel::TaskList(task_list)) = store.get(&task.task_list_id.event_id).await
+    else {
+        return None;
+    };
+    task_list.workflow_state(key).map(|s| s.name.clone())
+}
+
 impl Activity {
     pub async fn for_acter_model(store: &Store, mdl: AnyActerModel) -> Result<Self, crate::Error> {
+        let mentions = mdl.mentions().cloned();
+        let mut activity = Self::content_for_acter_model(store, mdl).await?;
+        activity.mentions = mentions;
+        Ok(activity)
+    }
+
+    async fn content_for_acter_model(
+        store: &Store,
+        mdl: AnyActerModel,
+    ) -> Result<Self, crate::Error> {
         let meta = mdl.event_meta().clone();
         match mdl {
             AnyActerModel::RoomStatus(s) => match s.inner {
@@ -627,11 +750,27 @@ impl Activity {
                     .unwrap_or_else(|| ActivityObject::Unknown {
                         object_id: e.inner.on.event_id.clone(),
                     });
+                let in_reply_to = match e.in_reply_to() {
+                    Some(event_id) => Some(match store.get(&event_id).await {
+                        Ok(AnyActerModel::Comment(c)) => ReplyToComment {
+                            event_id,
+                            sender: Some(c.meta.sender.clone()),
+                            content: Some(c.content.clone()),
+                        },
+                        _ => ReplyToComment {
+                            event_id,
+                            sender: None,
+                            content: None,
+                        },
+                    }),
+                    None => None,
+                };
                 Ok(Self::new(
                     meta,
                     ActivityContent::Comment {
                         object,
                         content: e.content.clone(),
+                        in_reply_to,
                     },
                 ))
             }
@@ -747,6 +886,47 @@ impl Activity {
                 }
             }
 
+            // -- Poll
+            AnyActerModel::Poll(e) => {
+                let object = ActivityObject::Poll {
+                    object_id: e.event_id().to_owned(),
+                    title: e.title(),
+                };
+                Ok(Self::new(meta, ActivityContent::Creation { object }))
+            }
+
+            AnyActerModel::PollUpdate(e) => {
+                let object = store
+                    .get(&e.inner.poll.event_id)
+                    .await
+                    .map_err(|error| {
+                        tracing::error!(?error, "Error loading parent of poll update");
+                    })
+                    .ok()
+                    .and_then(|o| ActivityObject::try_from(&o).ok())
+                    .unwrap_or_else(|| ActivityObject::Unknown {
+                        object_id: e.inner.poll.event_id.clone(),
+                    });
+
+                if let Some(new_title) = e.inner.title {
+                    Ok(Self::new(
+                        meta,
+                        ActivityContent::TitleChange { object, new_title },
+                    ))
+                } else if let Some(Some(new_content)) = e.inner.content {
+                    Ok(Self::new(
+                        meta,
+                        ActivityContent::DescriptionChange {
+                            object,
+                            content: Some(new_content),
+                        },
+                    ))
+                } else {
+                    // fallback: other changes
+                    Ok(Self::new(meta, ActivityContent::OtherChanges { object }))
+                }
+            }
+
             // ---- Event
             AnyActerModel::CalendarEvent(e) => {
                 let object = ActivityObject::CalendarEvent {
@@ -896,7 +1076,20 @@ impl Activity {
                         object_id: e.inner.task.event_id.clone(),
                     });
 
-                if let Some(new_percent) = e.inner.progress_percent {
+                if let Some(new_status) = e.inner.status {
+                    let new_status_name = match &new_status {
+                        Some(key) => workflow_state_name(store, &e.inner.task.event_id, key).await,
+                        None => None,
+                    };
+                    Ok(Self::new(
+                        meta,
+                        ActivityContent::TaskStatusChange {
+                            object,
+                            new_status,
+                            new_status_name,
+                        },
+                    ))
+                } else if let Some(new_percent) = e.inner.progress_percent {
                     Ok(Self::new(
                         meta,
                         ActivityContent::TaskProgress {
@@ -963,12 +1156,57 @@ impl Activity {
 
                 Ok(Self::new(meta, ActivityContent::TaskDecline { object }))
             }
+
+            AnyActerModel::TaskAssign(e) => {
+                let object = store
+                    .get(&e.inner.task.event_id)
+                    .await
+                    .map_err(|error| {
+                        tracing::error!(?error, "Error loading task of assignment");
+                    })
+                    .ok()
+                    .and_then(|o| ActivityObject::try_from(&o).ok())
+                    .unwrap_or_else(|| ActivityObject::Unknown {
+                        object_id: e.inner.task.event_id.clone(),
+                    });
+
+                Ok(Self::new(
+                    meta,
+                    ActivityContent::TaskAssign {
+                        object,
+                        assignee: e.inner.assignee.clone(),
+                    },
+                ))
+            }
+
+            AnyActerModel::TaskUnassign(e) => {
+                let object = store
+                    .get(&e.inner.task.event_id)
+                    .await
+                    .map_err(|error| {
+                        tracing::error!(?error, "Error loading task of assignment");
+                    })
+                    .ok()
+                    .and_then(|o| ActivityObject::try_from(&o).ok())
+                    .unwrap_or_else(|| ActivityObject::Unknown {
+                        object_id: e.inner.task.event_id.clone(),
+                    });
+
+                Ok(Self::new(
+                    meta,
+                    ActivityContent::TaskUnassign {
+                        object,
+                        assignee: e.inner.assignee.clone(),
+                    },
+                ))
+            }
             AnyActerModel::RedactedActerModel(_)
             | AnyActerModel::NewsEntryUpdate(_)
             | AnyActerModel::Story(_)
             | AnyActerModel::StoryUpdate(_)
             | AnyActerModel::CommentUpdate(_)
             | AnyActerModel::AttachmentUpdate(_)
+            | AnyActerModel::PollVote(_)
             | AnyActerModel::ReadReceipt(_) => Err(crate::Error::Custom(
                 "Converting model into activity not yet supported".to_owned(),
             )),
@@ -15,6 +15,10 @@ pub enum ActivityObject {
         object_id: OwnedEventId,
         title: String,
     },
+    Poll {
+        object_id: OwnedEventId,
+        title: String,
+    },
     CalendarEvent {
         object_id: OwnedEventId,
         title: String,
@@ -38,6 +42,7 @@ impl ActivityObject {
         match self {
             ActivityObject::News { .. } => "news",
             ActivityObject::Pin { .. } => "pin",
+            ActivityObject::Poll { .. } => "poll",
             ActivityObject::CalendarEvent { .. } => "event",
             ActivityObject::TaskList { .. } => "task-list",
             ActivityObject::Task { .. } => "task",
@@ -50,6 +55,7 @@ impl ActivityObject {
         match self {
             ActivityObject::News { object_id }
             | ActivityObject::Pin { object_id, .. }
+            | ActivityObject::Poll { object_id, .. }
             | ActivityObject::TaskList { object_id, .. }
             | ActivityObject::Task { object_id, .. }
             | ActivityObject::Unknown { object_id, .. }
@@ -63,6 +69,7 @@ impl ActivityObject {
             | ActivityObject::Unknown { .. }
             | ActivityObject::Story { .. } => None,
             ActivityObject::Pin { title, .. }
+            | ActivityObject::Poll { title, .. }
             | ActivityObject::TaskList { title, .. }
             | ActivityObject::Task { title, .. }
             | ActivityObject::CalendarEvent { title, .. } => Some(title.clone()),
@@ -74,6 +81,7 @@ impl ActivityObject {
             ActivityObject::News { object_id } => format!("/updates/{}", object_id),
             ActivityObject::Story { object_id } => format!("/updates/{}", object_id),
             ActivityObject::Pin { object_id, .. } => format!("/pins/{}", object_id),
+            ActivityObject::Poll { object_id, .. } => format!("/polls/{}", object_id),
             ActivityObject::TaskList { object_id, .. } => format!("/tasks/{}", object_id),
             ActivityObject::Task {
                 object_id, tl_id, ..
@@ -98,6 +106,7 @@ impl ActivityObject {
         match self {
             ActivityObject::News { .. } => "🚀",          // boost rocket
             ActivityObject::Pin { .. } => "📌",           // pin
+            ActivityObject::Poll { .. } => "🗳️",          // poll -> ballot box
             ActivityObject::TaskList { .. } => "📋",      // tasklist-> clipboard
             ActivityObject::CalendarEvent { .. } => "🗓️", // calendar
             ActivityObject::Task { .. } => "☑️",          // task -> checkoff
@@ -127,6 +136,10 @@ impl TryFrom<&AnyActerModel> for ActivityObject {
                 object_id: e.event_id().to_owned(),
                 title: e.title(),
             }),
+            AnyActerModel::Poll(e) => Ok(ActivityObject::Poll {
+                object_id: e.event_id().to_owned(),
+                title: e.title(),
+            }),
             AnyActerModel::TaskList(e) => Ok(ActivityObject::TaskList {
                 object_id: e.event_id().to_owned(),
                 title: e.name().clone(),
@@ -143,7 +156,11 @@ impl TryFrom<&AnyActerModel> for ActivityObject {
             | AnyActerModel::TaskUpdate(_)
             | AnyActerModel::TaskSelfAssign(_)
             | AnyActerModel::TaskSelfUnassign(_)
+            | AnyActerModel::TaskAssign(_)
+            | AnyActerModel::TaskUnassign(_)
             | AnyActerModel::PinUpdate(_)
+            | AnyActerModel::PollUpdate(_)
+            | AnyActerModel::PollVote(_)
             | AnyActerModel::NewsEntryUpdate(_)
             | AnyActerModel::StoryUpdate(_)
             | AnyActerModel::Comment(_)
@@ -1,7 +1,13 @@
 use derive_getters::Getters;
 use matrix_sdk::Client;
 
-use crate::{error::Error, executor::Executor, referencing::ExecuteReference, store::Store};
+use crate::{
+    error::Error,
+    executor::Executor,
+    outbox::Outbox,
+    referencing::ExecuteReference,
+    store::{SqliteModelStore, Store},
+};
 use tokio::sync::broadcast::Receiver;
 
 /// Core Client wrapper
@@ -10,6 +16,7 @@ pub struct CoreClient {
     pub(crate) client: Client,
     pub(crate) store: Store,
     pub(crate) executor: Executor,
+    pub(crate) outbox: Outbox,
 }
 
 impl CoreClient {
@@ -17,10 +24,27 @@ impl CoreClient {
         let store = Store::new(client.clone()).await?;
         let executor = Executor::new(store.clone()).await?;
         client.add_event_handler_context(executor.clone());
+        let outbox = Outbox::new(executor.clone());
 
         Ok(CoreClient {
             store,
             executor,
+            outbox,
+            client,
+        })
+    }
+
+    /// Keep the acter models in the given database
+    pub async fn new_with_model_db(client: Client, db: SqliteModelStore) -> Result<Self, Error> {
+        let store = Store::new_with_db(client.clone(), db).await?;
+        let executor = Executor::new(store.clone()).await?;
+        client.add_event_handler_context(executor.clone());
+        let outbox = Outbox::new(executor.clone());
+
+        Ok(CoreClient {
+            store,
+            executor,
+            outbox,
             client,
         })
     }
@@ -30,6 +30,12 @@ pub enum Error {
     #[error("IO Error: {0}")]
     IoError(#[from] std::io::Error),
 
+    #[error("Error in the model database: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+
+    #[error("Error with the store cipher: {0}")]
+    StoreCipher(#[from] matrix_sdk_store_encryption::Error),
+
     #[error("Store Dirty Lock Poisoned Error.")]
     StoreDirtyPoisoned,
 
@@ -6,6 +6,7 @@ mod common;
 pub mod explicit_invites;
 pub mod news;
 pub mod pins;
+pub mod polls;
 pub mod read_receipt;
 pub mod room;
 pub mod rsvp;
@@ -33,6 +34,10 @@ pub enum AnyActerEvent {
     Pin(pins::PinEvent),
     PinUpdate(pins::PinUpdateEvent),
 
+    Poll(polls::PollEvent),
+    PollUpdate(polls::PollUpdateEvent),
+    PollVote(polls::PollVoteEvent),
+
     NewsEntry(news::NewsEntryEvent),
     NewsEntryUpdate(news::NewsEntryUpdateEvent),
 
@@ -46,6 +51,8 @@ pub enum AnyActerEvent {
     TaskUpdate(tasks::TaskUpdateEvent),
     TaskSelfAssign(tasks::TaskSelfAssignEvent),
     TaskSelfUnassign(tasks::TaskSelfUnassignEvent),
+    TaskAssign(tasks::TaskAssignEvent),
+    TaskUnassign(tasks::TaskUnassignEvent),
 
     // Generic Relative Features
     Comment(comments::CommentEvent),
@@ -70,6 +77,9 @@ impl AnyActerEvent {
             AnyActerEvent::CalendarEventUpdate(e) => e.room_id(),
             AnyActerEvent::Pin(e) => e.room_id(),
             AnyActerEvent::PinUpdate(e) => e.room_id(),
+            AnyActerEvent::Poll(e) => e.room_id(),
+            AnyActerEvent::PollUpdate(e) => e.room_id(),
+            AnyActerEvent::PollVote(e) => e.room_id(),
             AnyActerEvent::NewsEntry(e) => e.room_id(),
             AnyActerEvent::NewsEntryUpdate(e) => e.room_id(),
             AnyActerEvent::Story(e) => e.room_id(),
@@ -80,6 +90,8 @@ impl AnyActerEvent {
             AnyActerEvent::TaskUpdate(e) => e.room_id(),
             AnyActerEvent::TaskSelfAssign(e) => e.room_id(),
             AnyActerEvent::TaskSelfUnassign(e) => e.room_id(),
+            AnyActerEvent::TaskAssign(e) => e.room_id(),
+            AnyActerEvent::TaskUnassign(e) => e.room_id(),
             AnyActerEvent::Comment(e) => e.room_id(),
             AnyActerEvent::CommentUpdate(e) => e.room_id(),
             AnyActerEvent::Attachment(e) => e.room_id(),
@@ -125,6 +137,22 @@ impl<'de> serde::Deserialize<'de> for AnyActerEvent {
                 Ok(Self::PinUpdate(event))
             }
 
+            polls::PollEventContent::TYPE => {
+                let event = smart_serde_json::from_str::<polls::PollEvent>(json.get())
+                    .map_err(D::Error::custom)?;
+                Ok(Self::Poll(event))
+            }
+            polls::PollUpdateEventContent::TYPE => {
+                let event = smart_serde_json::from_str::<polls::PollUpdateEvent>(json.get())
+                    .map_err(D::Error::custom)?;
+                Ok(Self::PollUpdate(event))
+            }
+            polls::PollVoteEventContent::TYPE => {
+                let event = smart_serde_json::from_str::<polls::PollVoteEvent>(json.get())
+                    .map_err(D::Error::custom)?;
+                Ok(Self::PollVote(event))
+            }
+
             news::NewsEntryEventContent::TYPE => {
                 let event = smart_serde_json::from_str::<news::NewsEntryEvent>(json.get())
                     .map_err(D::Error::custom)?;
@@ -179,6 +207,16 @@ impl<'de> serde::Deserialize<'de> for AnyActerEvent {
                     .map_err(D::Error::custom)?;
                 Ok(Self::TaskSelfUnassign(event))
             }
+            tasks::TaskAssignEventContent::TYPE => {
+                let event = smart_serde_json::from_str::<tasks::TaskAssignEvent>(json.get())
+                    .map_err(D::Error::custom)?;
+                Ok(Self::TaskAssign(event))
+            }
+            tasks::TaskUnassignEventContent::TYPE => {
+                let event = smart_serde_json::from_str::<tasks::TaskUnassignEvent>(json.get())
+                    .map_err(D::Error::custom)?;
+                Ok(Self::TaskUnassign(event))
+            }
 
             comments::CommentEventContent::TYPE => {
                 let event = smart_serde_json::from_str::<comments::CommentEvent>(json.get())
@@ -246,6 +284,9 @@ impl<'de> serde::Deserialize<'de> for AnyActerEvent {
                             calendar::CalendarEventUpdateEventContent::TYPE,
                             pins::PinEventContent::TYPE,
                             pins::PinUpdateEventContent::TYPE,
+                            polls::PollEventContent::TYPE,
+                            polls::PollUpdateEventContent::TYPE,
+                            polls::PollVoteEventContent::TYPE,
                             news::NewsEntryEventContent::TYPE,
                             news::NewsEntryUpdateEventContent::TYPE,
                             stories::StoryEventContent::TYPE,
@@ -256,6 +297,8 @@ impl<'de> serde::Deserialize<'de> for AnyActerEvent {
                             tasks::TaskUpdateEventContent::TYPE,
                             tasks::TaskSelfAssignEventContent::TYPE,
                             tasks::TaskSelfUnassignEventContent::TYPE,
+                            tasks::TaskAssignEventContent::TYPE,
+                            tasks::TaskUnassignEventContent::TYPE,
                             comments::CommentEventContent::TYPE,
                             comments::CommentUpdateEventContent::TYPE,
                             attachments::AttachmentEventContent::TYPE,
@@ -279,6 +322,10 @@ pub enum AnySyncActerEvent {
     Pin(pins::SyncPinEvent),
     PinUpdate(pins::SyncPinUpdateEvent),
 
+    Poll(polls::SyncPollEvent),
+    PollUpdate(polls::SyncPollUpdateEvent),
+    PollVote(polls::SyncPollVoteEvent),
+
     NewsEntry(news::SyncNewsEntryEvent),
     NewsEntryUpdate(news::SyncNewsEntryUpdateEvent),
 
@@ -293,6 +340,8 @@ pub enum AnySyncActerEvent {
 
     TaskSelfAssign(tasks::SyncTaskSelfAssignEvent),
     TaskSelfUnassign(tasks::SyncTaskSelfUnassignEvent),
+    TaskAssign(tasks::SyncTaskAssignEvent),
+    TaskUnassign(tasks::SyncTaskUnassignEvent),
 
     // Generic Relative Features
     Comment(comments::SyncCommentEvent),
@@ -319,6 +368,9 @@ impl AnySyncActerEvent {
             }
             Self::Pin(e) => AnyActerEvent::Pin(e.into_full_event(room_id)),
             Self::PinUpdate(e) => AnyActerEvent::PinUpdate(e.into_full_event(room_id)),
+            Self::Poll(e) => AnyActerEvent::Poll(e.into_full_event(room_id)),
+            Self::PollUpdate(e) => AnyActerEvent::PollUpdate(e.into_full_event(room_id)),
+            Self::PollVote(e) => AnyActerEvent::PollVote(e.into_full_event(room_id)),
             Self::NewsEntry(e) => AnyActerEvent::NewsEntry(e.into_full_event(room_id)),
             Self::NewsEntryUpdate(e) => AnyActerEvent::NewsEntryUpdate(e.into_full_event(room_id)),
             Self::Story(e) => AnyActerEvent::Story(e.into_full_event(room_id)),
@@ -331,6 +383,8 @@ impl AnySyncActerEvent {
             Self::TaskSelfUnassign(e) => {
                 AnyActerEvent::TaskSelfUnassign(e.into_full_event(room_id))
             }
+            Self::TaskAssign(e) => AnyActerEvent::TaskAssign(e.into_full_event(room_id)),
+            Self::TaskUnassign(e) => AnyActerEvent::TaskUnassign(e.into_full_event(room_id)),
             Self::Comment(e) => AnyActerEvent::Comment(e.into_full_event(room_id)),
             Self::CommentUpdate(e) => AnyActerEvent::CommentUpdate(e.into_full_event(room_id)),
             Self::Attachment(e) => AnyActerEvent::Attachment(e.into_full_event(room_id)),
@@ -382,6 +436,22 @@ impl<'de> serde::Deserialize<'de> for AnySyncActerEvent {
                 Ok(Self::PinUpdate(event))
             }
 
+            polls::PollEventContent::TYPE => {
+                let event = smart_serde_json::from_str::<polls::SyncPollEvent>(json.get())
+                    .map_err(D::Error::custom)?;
+                Ok(Self::Poll(event))
+            }
+            polls::PollUpdateEventContent::TYPE => {
+                let event = smart_serde_json::from_str::<polls::SyncPollUpdateEvent>(json.get())
+                    .map_err(D::Error::custom)?;
+                Ok(Self::PollUpdate(event))
+            }
+            polls::PollVoteEventContent::TYPE => {
+                let event = smart_serde_json::from_str::<polls::SyncPollVoteEvent>(json.get())
+                    .map_err(D::Error::custom)?;
+                Ok(Self::PollVote(event))
+            }
+
             news::NewsEntryEventContent::TYPE => {
                 let event = smart_serde_json::from_str::<news::SyncNewsEntryEvent>(json.get())
                     .map_err(D::Error::custom)?;
@@ -440,6 +510,16 @@ impl<'de> serde::Deserialize<'de> for AnySyncActerEvent {
                         .map_err(D::Error::custom)?;
                 Ok(Self::TaskSelfUnassign(event))
             }
+            tasks::TaskAssignEventContent::TYPE => {
+                let event = smart_serde_json::from_str::<tasks::SyncTaskAssignEvent>(json.get())
+                    .map_err(D::Error::custom)?;
+                Ok(Self::TaskAssign(event))
+            }
+            tasks::TaskUnassignEventContent::TYPE => {
+                let event = smart_serde_json::from_str::<tasks::SyncTaskUnassignEvent>(json.get())
+                    .map_err(D::Error::custom)?;
+                Ok(Self::TaskUnassign(event))
+            }
 
             comments::CommentEventContent::TYPE => {
                 let event = smart_serde_json::from_str::<comments::SyncCommentEvent>(json.get())
@@ -512,6 +592,9 @@ impl<'de> serde::Deserialize<'de> for AnySyncActerEvent {
                             calendar::CalendarEventUpdateEventContent::TYPE,
                             pins::PinEventContent::TYPE,
                             pins::PinUpdateEventContent::TYPE,
+                            polls::PollEventContent::TYPE,
+                            polls::PollUpdateEventContent::TYPE,
+                            polls::PollVoteEventContent::TYPE,
                             news::NewsEntryEventContent::TYPE,
                             news::NewsEntryUpdateEventContent::TYPE,
                             stories::StoryEventContent::TYPE,
@@ -522,6 +605,8 @@ impl<'de> serde::Deserialize<'de> for AnySyncActerEvent {
                             tasks::TaskUpdateEventContent::TYPE,
                             tasks::TaskSelfAssignEventContent::TYPE,
                             tasks::TaskSelfUnassignEventContent::TYPE,
+                            tasks::TaskAssignEventContent::TYPE,
+                            tasks::TaskUnassignEventContent::TYPE,
                             comments::CommentEventContent::TYPE,
                             comments::CommentUpdateEventContent::TYPE,
                             attachments::AttachmentEventContent::TYPE,
@@ -1,16 +1,22 @@
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
 use derive_builder::Builder;
 use derive_getters::Getters;
 use matrix_sdk_base::ruma::events::{macros::EventContent, room::message::TextMessageEventContent};
 use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
 use tracing::trace;
 
-use crate::{models::TextMessageContent, util::deserialize_some, Result};
+use crate::{
+    models::TextMessageContent,
+    util::{deserialize_some, is_false},
+    Error, Result,
+};
 
 /// Calendar Events
 /// modeled after [JMAP Calendar Events](https://jmap.io/spec-calendars.html#calendar-events), extensions to
 /// [ietf rfc8984](https://www.rfc-editor.org/rfc/rfc8984.html#name-event).
 ///
-use super::{Display, Icon, Update, UtcDateTime};
+use super::{Display, EditRule, Icon, Update, UtcDateTime};
 
 /// Event Location
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -173,6 +179,352 @@ impl EventLocationInfo {
     }
 }
 
+/// How often a recurring event repeats
+///
+/// see [RFC 8984 frequency](https://www.rfc-editor.org/rfc/rfc8984.html#section-4.3.3)
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Frequency {
+    Yearly,
+    Monthly,
+    Weekly,
+    Daily,
+    Hourly,
+}
+
+impl Frequency {
+    fn as_rrule_str(&self) -> &'static str {
+        match self {
+            Frequency::Yearly => "YEARLY",
+            Frequency::Monthly => "MONTHLY",
+            Frequency::Weekly => "WEEKLY",
+            Frequency::Daily => "DAILY",
+            Frequency::Hourly => "HOURLY",
+        }
+    }
+}
+
+/// Day of the week as used in recurrence rules
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, Eq, PartialEq)]
+pub enum WeekDay {
+    #[serde(rename = "mo")]
+    Monday,
+    #[serde(rename = "tu")]
+    Tuesday,
+    #[serde(rename = "we")]
+    Wednesday,
+    #[serde(rename = "th")]
+    Thursday,
+    #[serde(rename = "fr")]
+    Friday,
+    #[serde(rename = "sa")]
+    Saturday,
+    #[serde(rename = "su")]
+    Sunday,
+}
+
+impl WeekDay {
+    fn as_rrule_str(&self) -> &'static str {
+        match self {
+            WeekDay::Monday => "MO",
+            WeekDay::Tuesday => "TU",
+            WeekDay::Wednesday => "WE",
+            WeekDay::Thursday => "TH",
+            WeekDay::Friday => "FR",
+            WeekDay::Saturday => "SA",
+            WeekDay::Sunday => "SU",
+        }
+    }
+
+    fn from_rrule_str(value: &str) -> Option<Self> {
+        Some(match value {
+            "MO" => WeekDay::Monday,
+            "TU" => WeekDay::Tuesday,
+            "WE" => WeekDay::Wednesday,
+            "TH" => WeekDay::Thursday,
+            "FR" => WeekDay::Friday,
+            "SA" => WeekDay::Saturday,
+            "SU" => WeekDay::Sunday,
+            _ => return None,
+        })
+    }
+}
+
+impl From<WeekDay> for chrono::Weekday {
+    fn from(value: WeekDay) -> Self {
+        match value {
+            WeekDay::Monday => chrono::Weekday::Mon,
+            WeekDay::Tuesday => chrono::Weekday::Tue,
+            WeekDay::Wednesday => chrono::Weekday::Wed,
+            WeekDay::Thursday => chrono::Weekday::Thu,
+            WeekDay::Friday => chrono::Weekday::Fri,
+            WeekDay::Saturday => chrono::Weekday::Sat,
+            WeekDay::Sunday => chrono::Weekday::Sun,
+        }
+    }
+}
+
+/// A day of the week, optionally limited to its n-th appearance in the period
+///
+/// see [RFC 8984 NDay](https://www.rfc-editor.org/rfc/rfc8984.html#section-4.3.3)
+#[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
+pub struct NDay {
+    pub day: WeekDay,
+
+    /// `1` for the first, `-1` for the last of this day in the month (or year)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nth_of_period: Option<i32>,
+}
+
+/// Recurrence Rule of a calendar event
+///
+/// modeled after the [RFC 8984 RecurrenceRule](https://www.rfc-editor.org/rfc/rfc8984.html#section-4.3.3),
+/// a subset of [RFC 5545 RRULE](https://www.rfc-editor.org/rfc/rfc5545#section-3.3.10). Use `to_string`
+/// and `from_str` to convert from and into the RRULE-string representation.
+#[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
+pub struct RecurrenceRule {
+    pub frequency: Frequency,
+
+    /// Repeat every n-th period, defaults to 1
+    #[serde(default = "RecurrenceRule::default_interval")]
+    pub interval: u32,
+
+    /// Stop after this many occurrences
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub count: Option<u32>,
+
+    /// Stop after this point in time (inclusive)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub until: Option<UtcDateTime>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub by_day: Vec<NDay>,
+
+    /// Days of the month, negative values count from the end of the month
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub by_month_day: Vec<i32>,
+
+    /// Months of the year, 1 to 12
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub by_month: Vec<u32>,
+}
+
+impl RecurrenceRule {
+    fn default_interval() -> u32 {
+        1
+    }
+
+    pub fn new(frequency: Frequency) -> Self {
+        RecurrenceRule {
+            frequency,
+            interval: 1,
+            count: None,
+            until: None,
+            by_day: vec![],
+            by_month_day: vec![],
+            by_month: vec![],
+        }
+    }
+}
+
+fn rrule_error(msg: String) -> Error {
+    Error::FailedToParse {
+        model_type: "RRULE".to_owned(),
+        msg,
+    }
+}
+
+/// Parse a RFC 5545 DATE or DATE-TIME value as found in `UNTIL`, `EXDATE` and the like
+///
+/// Floating times are treated as UTC, dates are resolved to the end of that day.
+pub fn parse_ical_date_time(value: &str) -> Result<UtcDateTime> {
+    let value = value.trim();
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y%m%d") {
+        let end_of_day = NaiveTime::from_hms_opt(23, 59, 59).expect("end of day exists");
+        return Ok(date.and_time(end_of_day).and_utc());
+    }
+    NaiveDateTime::parse_from_str(value.trim_end_matches('Z'), "%Y%m%dT%H%M%S")
+        .map(|dt| dt.and_utc())
+        .map_err(|e| rrule_error(format!("invalid date-time `{value}`: {e}")))
+}
+
+/// Format a timestamp as RFC 5545 UTC DATE-TIME value
+pub fn format_ical_date_time(value: &UtcDateTime) -> String {
+    value.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+impl FromStr for RecurrenceRule {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        let s = s.strip_prefix("RRULE:").unwrap_or(s);
+        let mut frequency = None;
+        let mut rule = RecurrenceRule::new(Frequency::Daily);
+        for part in s.split(';').filter(|p| !p.is_empty()) {
+            let Some((key, value)) = part.split_once('=') else {
+                return Err(rrule_error(format!("`{part}` is not a key=value pair")));
+            };
+            match key.to_uppercase().as_str() {
+                "FREQ" => {
+                    frequency = Some(match value.to_uppercase().as_str() {
+                        "YEARLY" => Frequency::Yearly,
+                        "MONTHLY" => Frequency::Monthly,
+                        "WEEKLY" => Frequency::Weekly,
+                        "DAILY" => Frequency::Daily,
+                        "HOURLY" => Frequency::Hourly,
+                        other => return Err(rrule_error(format!("unsupported FREQ `{other}`"))),
+                    })
+                }
+                "INTERVAL" => {
+                    rule.interval = value
+                        .parse()
+                        .map_err(|e| rrule_error(format!("invalid INTERVAL: {e}")))?;
+                    if rule.interval == 0 {
+                        return Err(rrule_error("INTERVAL must be positive".to_owned()));
+                    }
+                }
+                "COUNT" => {
+                    rule.count = Some(
+                        value
+                            .parse()
+                            .map_err(|e| rrule_error(format!("invalid COUNT: {e}")))?,
+                    )
+                }
+                "UNTIL" => rule.until = Some(parse_ical_date_time(value)?),
+                "BYDAY" => {
+                    for day in value.split(',') {
+                        let day = day.trim().to_uppercase();
+                        if day.len() < 2 || !day.is_ascii() {
+                            return Err(rrule_error(format!("invalid BYDAY `{day}`")));
+                        }
+                        let (nth, code) = day.split_at(day.len() - 2);
+                        let day = WeekDay::from_rrule_str(code)
+                            .ok_or_else(|| rrule_error(format!("invalid BYDAY `{day}`")))?;
+                        let nth_of_period = if nth.is_empty() {
+                            None
+                        } else {
+                            Some(
+                                nth.trim_start_matches('+')
+                                    .parse()
+                                    .map_err(|e| rrule_error(format!("invalid BYDAY: {e}")))?,
+                            )
+                        };
+                        rule.by_day.push(NDay { day, nth_of_period });
+                    }
+                }
+                "BYMONTHDAY" => {
+                    for day in value.split(',') {
+                        rule.by_month_day.push(
+                            day.trim()
+                                .trim_start_matches('+')
+                                .parse()
+                                .map_err(|e| rrule_error(format!("invalid BYMONTHDAY: {e}")))?,
+                        );
+                    }
+                }
+                "BYMONTH" => {
+                    for month in value.split(',') {
+                        let month: u32 = month
+                            .trim()
+                            .parse()
+                            .map_err(|e| rrule_error(format!("invalid BYMONTH: {e}")))?;
+                        if !(1..=12).contains(&month) {
+                            return Err(rrule_error(format!("invalid BYMONTH `{month}`")));
+                        }
+                        rule.by_month.push(month);
+                    }
+                }
+                // we always start the week on monday
+                "WKST" => {}
+                other => return Err(rrule_error(format!("unsupported rule part `{other}`"))),
+            }
+        }
+        rule.frequency = frequency.ok_or_else(|| rrule_error("FREQ is missing".to_owned()))?;
+        Ok(rule)
+    }
+}
+
+impl fmt::Display for RecurrenceRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "FREQ={}", self.frequency.as_rrule_str())?;
+        if self.interval > 1 {
+            write!(f, ";INTERVAL={}", self.interval)?;
+        }
+        if let Some(count) = self.count {
+            write!(f, ";COUNT={count}")?;
+        }
+        if let Some(until) = &self.until {
+            write!(f, ";UNTIL={}", format_ical_date_time(until))?;
+        }
+        if !self.by_day.is_empty() {
+            let days = self
+                .by_day
+                .iter()
+                .map(|d| match d.nth_of_period {
+                    Some(nth) => format!("{nth}{}", d.day.as_rrule_str()),
+                    None => d.day.as_rrule_str().to_owned(),
+                })
+                .collect::<Vec<_>>();
+            write!(f, ";BYDAY={}", days.join(","))?;
+        }
+        if !self.by_month_day.is_empty() {
+            let days = self
+                .by_month_day
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>();
+            write!(f, ";BYMONTHDAY={}", days.join(","))?;
+        }
+        if !self.by_month.is_empty() {
+            let months = self
+                .by_month
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>();
+            write!(f, ";BYMONTH={}", months.join(","))?;
+        }
+        Ok(())
+    }
+}
+
+/// Changes to a single occurrence of a recurring event
+///
+/// modeled after [RFC 8984 recurrenceOverrides](https://www.rfc-editor.org/rfc/rfc8984.html#section-4.3.5)
+#[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
+pub struct RecurrenceOverride {
+    /// The start of the occurrence as computed from the rule, identifying it within the series
+    pub recurrence_id: UtcDateTime,
+
+    /// This occurrence has been removed from the series (like an `EXDATE`)
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub excluded: bool,
+
+    /// The occurrence has been moved to start at this point instead
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub utc_start: Option<UtcDateTime>,
+
+    /// The occurrence has been moved to end at this point instead
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub utc_end: Option<UtcDateTime>,
+
+    /// A different title just for this occurrence
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+}
+
+impl RecurrenceOverride {
+    pub fn excluded(recurrence_id: UtcDateTime) -> Self {
+        RecurrenceOverride {
+            recurrence_id,
+            excluded: true,
+            utc_start: None,
+            utc_end: None,
+            title: None,
+        }
+    }
+}
+
 /// The Calendar Event
 ///
 /// modeled after [JMAP Calendar Events](https://jmap.io/spec-calendars.html#calendar-events)
@@ -221,9 +573,43 @@ pub struct CalendarEventEventContent {
     #[builder(setter(into), default)]
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub categories: Vec<String>,
+
+    /// How does this event repeat?
+    #[builder(setter(into), default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recurrence_rule: Option<RecurrenceRule>,
+
+    /// Exceptions to single occurrences of the recurring event
+    #[builder(default)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub recurrence_overrides: Vec<RecurrenceOverride>,
+
+    /// The `UID` of the iCalendar event this was imported from
+    #[builder(setter(into), default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ical_uid: Option<String>,
+
+    /// Who may update this calendar event
+    #[builder(setter(into), default)]
+    #[serde(default, skip_serializing_if = "EditRule::is_anyone")]
+    pub edit_rule: EditRule,
 }
 
 impl CalendarEventBuilder {
+    /// remove the occurrence starting at `recurrence_id` from the series
+    pub fn exclude_occurrence(&mut self, recurrence_id: UtcDateTime) -> &mut Self {
+        self.override_occurrence(RecurrenceOverride::excluded(recurrence_id))
+    }
+
+    /// set the override for a single occurrence, replacing any previous one
+    pub fn override_occurrence(&mut self, overridden: RecurrenceOverride) -> &mut Self {
+        let mut overrides = self.recurrence_overrides.clone().unwrap_or_default();
+        overrides.retain(|o| o.recurrence_id != overridden.recurrence_id);
+        overrides.push(overridden);
+        self.recurrence_overrides = Some(overrides);
+        self
+    }
+
     pub fn add_physical_location(
         &mut self,
         name: Option<String>,
@@ -356,6 +742,24 @@ pub struct CalendarEventUpdateEventContent {
         deserialize_with = "deserialize_some"
     )]
     pub display: Option<Option<Display>>,
+
+    /// How does this event repeat?
+    #[builder(setter(into), default)]
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_some"
+    )]
+    pub recurrence_rule: Option<Option<RecurrenceRule>>,
+
+    /// Exceptions to single occurrences, replaces the existing list
+    #[builder(default)]
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_some"
+    )]
+    pub recurrence_overrides: Option<Vec<RecurrenceOverride>>,
 }
 
 impl CalendarEventUpdateEventContent {
@@ -406,6 +810,18 @@ impl CalendarEventUpdateEventContent {
             updated = true;
         }
 
+        if let Some(recurrence_rule) = &self.recurrence_rule {
+            calendar_event.recurrence_rule.clone_from(recurrence_rule);
+            updated = true;
+        }
+
+        if let Some(recurrence_overrides) = &self.recurrence_overrides {
+            calendar_event
+                .recurrence_overrides
+                .clone_from(recurrence_overrides);
+            updated = true;
+        }
+
         trace!(update = ?self, ?updated, ?calendar_event, "CalendarEvent updated");
 
         Ok(updated)
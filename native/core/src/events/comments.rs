@@ -1,9 +1,11 @@
 use derive_builder::Builder;
 use derive_getters::Getters;
-use matrix_sdk_base::ruma::events::{macros::EventContent, room::message::TextMessageEventContent};
+use matrix_sdk_base::ruma::events::{
+    macros::EventContent, room::message::TextMessageEventContent, Mentions,
+};
 use serde::{Deserialize, Serialize};
 
-use super::{BelongsTo, References, Update};
+use super::{BelongsTo, MentionsBuilder, References, Update};
 use crate::Result;
 
 /// Comment Event
@@ -20,6 +22,23 @@ pub struct CommentEventContent {
     pub reply_to: Option<References>,
 
     pub content: TextMessageEventContent,
+
+    /// The users and rooms mentioned in the comment
+    #[builder(setter(into), default)]
+    #[serde(
+        rename = "m.mentions",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub mentions: Option<Mentions>,
+}
+
+impl MentionsBuilder for CommentBuilder {
+    fn mentions_mut(&mut self) -> &mut Mentions {
+        self.mentions
+            .get_or_insert_with(Default::default)
+            .get_or_insert_with(Mentions::new)
+    }
 }
 
 /// The Comment Update Event
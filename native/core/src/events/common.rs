@@ -5,7 +5,9 @@ use serde::{Deserialize, Serialize};
 mod categories;
 mod color;
 mod display;
+mod edit_rule;
 mod labels;
+mod mentions;
 mod object_reference;
 mod rendering;
 
@@ -13,7 +15,9 @@ pub use categories::{
     CategoriesStateEvent, CategoriesStateEventContent, Category, CategoryBuilder,
 };
 pub use color::Color;
+pub use edit_rule::{EditRule, MODERATOR_POWER_LEVEL};
 pub use labels::Labels;
+pub use mentions::{mentions_user, MentionsBuilder};
 pub use object_reference::{
     CalendarEventAction, CalendarEventRefPreview, ObjRef, ObjRefBuilder, RefDetails, RefPreview,
     TaskAction, TaskListAction,
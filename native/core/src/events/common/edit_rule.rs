@@ -0,0 +1,75 @@
+use matrix_sdk_base::ruma::{OwnedUserId, UserId};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+/// The room power level from which on members count as moderators
+pub const MODERATOR_POWER_LEVEL: i64 = 50;
+
+/// Who may update an object, set when the object is created
+///
+/// Room permissions only decide who may send updates of a given type at all,
+/// this narrows it down per object. Updates by anyone else are ignored.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(tag = "rule", rename_all = "snake_case")]
+pub enum EditRule {
+    /// anyone allowed to send updates in the room
+    #[default]
+    Anyone,
+    /// only the author of the object and the room moderators, plus the
+    /// listed users on top of those
+    AuthorAndModerators {
+        #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+        moderators: BTreeSet<OwnedUserId>,
+    },
+}
+
+impl EditRule {
+    pub fn author_and_moderators(moderators: impl IntoIterator<Item = OwnedUserId>) -> Self {
+        EditRule::AuthorAndModerators {
+            moderators: moderators.into_iter().collect(),
+        }
+    }
+
+    pub fn is_anyone(&self) -> bool {
+        matches!(self, EditRule::Anyone)
+    }
+
+    /// Whether `editor` may update an object created by `author`, given
+    /// whether they are a moderator of the room by power level
+    pub fn allows(&self, author: &UserId, editor: &UserId, is_room_moderator: bool) -> bool {
+        match self {
+            EditRule::Anyone => true,
+            EditRule::AuthorAndModerators { moderators } => {
+                is_room_moderator || author == editor || moderators.contains(editor)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use matrix_sdk_base::ruma::user_id;
+
+    #[test]
+    fn author_and_moderators_only() {
+        let rule = EditRule::author_and_moderators([user_id!("@mod:example.org").to_owned()]);
+        let author = user_id!("@author:example.org");
+        assert!(rule.allows(author, author, false));
+        assert!(rule.allows(author, user_id!("@mod:example.org"), false));
+        assert!(!rule.allows(author, user_id!("@other:example.org"), false));
+        assert!(rule.allows(author, user_id!("@other:example.org"), true));
+        assert!(EditRule::Anyone.allows(author, user_id!("@other:example.org"), false));
+    }
+
+    #[test]
+    fn serialized_with_rule_tag() {
+        let rule = EditRule::author_and_moderators([user_id!("@mod:example.org").to_owned()]);
+        assert_eq!(
+            serde_json::to_value(&rule).unwrap(),
+            serde_json::json!({"rule": "author_and_moderators", "moderators": ["@mod:example.org"]})
+        );
+        let rule: EditRule = serde_json::from_value(serde_json::json!({"rule": "anyone"})).unwrap();
+        assert!(rule.is_anyone());
+    }
+}
@@ -0,0 +1,29 @@
+use matrix_sdk_base::ruma::{events::Mentions, OwnedUserId, UserId};
+
+/// Builders of contents carrying `m.mentions`
+///
+/// Mentioning a user makes their homeserver notify them through the default
+/// `.m.rule.is_user_mention` push rule, no matter if they follow the object.
+pub trait MentionsBuilder {
+    /// the mentions of the content being built, created on first access
+    fn mentions_mut(&mut self) -> &mut Mentions;
+
+    /// Mention the given user
+    fn add_mention(&mut self, user_id: OwnedUserId) -> &mut Self {
+        self.mentions_mut().user_ids.insert(user_id);
+        self
+    }
+
+    /// Whether to mention the entire room
+    fn room_mention(&mut self, mention: bool) -> &mut Self {
+        self.mentions_mut().room = mention;
+        self
+    }
+}
+
+/// Whether `mentions` of an event sent by `sender` concern `user_id`
+///
+/// Users mentioning themselves don’t count.
+pub fn mentions_user(mentions: Option<&Mentions>, sender: &UserId, user_id: &UserId) -> bool {
+    sender != user_id && mentions.is_some_and(|m| m.room || m.user_ids.contains(user_id))
+}
@@ -6,10 +6,11 @@ use matrix_sdk_base::ruma::events::{
         AudioMessageEventContent, FileMessageEventContent, ImageMessageEventContent,
         LocationMessageEventContent, TextMessageEventContent, VideoMessageEventContent,
     },
+    Mentions,
 };
 use serde::{Deserialize, Serialize};
 
-use super::{Colorize, ObjRef, Update};
+use super::{Colorize, EditRule, MentionsBuilder, ObjRef, Update};
 use crate::{util::deserialize_some, Result};
 
 // if you change the order of these enum variables, enum value will change and parsing of old content will fail
@@ -241,6 +242,28 @@ pub struct NewsEntryEventContent {
     /// A news entry may have one or more slides of news
     /// which are scrolled through horizontally
     pub slides: Vec<NewsSlide>,
+
+    /// The users and rooms mentioned in the slides
+    #[builder(setter(into), default)]
+    #[serde(
+        rename = "m.mentions",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub mentions: Option<Mentions>,
+
+    /// Who may update this news entry
+    #[builder(setter(into), default)]
+    #[serde(default, skip_serializing_if = "EditRule::is_anyone")]
+    pub edit_rule: EditRule,
+}
+
+impl MentionsBuilder for NewsEntryBuilder {
+    fn mentions_mut(&mut self) -> &mut Mentions {
+        self.mentions
+            .get_or_insert_with(Default::default)
+            .get_or_insert_with(Mentions::new)
+    }
 }
 
 /// The payload for our news update event.
@@ -1,10 +1,12 @@
 use derive_builder::Builder;
 use derive_getters::Getters;
-use matrix_sdk_base::ruma::events::{macros::EventContent, room::message::TextMessageEventContent};
+use matrix_sdk_base::ruma::events::{
+    macros::EventContent, room::message::TextMessageEventContent, Mentions,
+};
 use serde::{Deserialize, Serialize};
 use tracing::trace;
 
-use super::{Display, Update};
+use super::{Display, EditRule, MentionsBuilder, Update};
 use crate::{util::deserialize_some, Result};
 
 /// The Pin Event
@@ -29,6 +31,28 @@ pub struct PinEventContent {
     #[builder(setter(into), default)]
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub display: Option<Display>,
+
+    /// The users and rooms mentioned in the content
+    #[builder(setter(into), default)]
+    #[serde(
+        rename = "m.mentions",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub mentions: Option<Mentions>,
+
+    /// Who may update this pin
+    #[builder(setter(into), default)]
+    #[serde(default, skip_serializing_if = "EditRule::is_anyone")]
+    pub edit_rule: EditRule,
+}
+
+impl MentionsBuilder for PinBuilder {
+    fn mentions_mut(&mut self) -> &mut Mentions {
+        self.mentions
+            .get_or_insert_with(Default::default)
+            .get_or_insert_with(Mentions::new)
+    }
 }
 
 /// The Pin Event
@@ -75,6 +99,23 @@ pub struct PinUpdateEventContent {
         deserialize_with = "deserialize_some"
     )]
     pub display: Option<Option<Display>>,
+
+    /// The users and rooms mentioned in the updated content
+    #[builder(setter(into), default)]
+    #[serde(
+        rename = "m.mentions",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub mentions: Option<Mentions>,
+}
+
+impl MentionsBuilder for PinUpdateBuilder {
+    fn mentions_mut(&mut self) -> &mut Mentions {
+        self.mentions
+            .get_or_insert_with(Default::default)
+            .get_or_insert_with(Mentions::new)
+    }
 }
 
 impl PinUpdateEventContent {
@@ -0,0 +1,279 @@
+use derive_builder::Builder;
+use derive_getters::Getters;
+use matrix_sdk_base::ruma::events::{macros::EventContent, room::message::TextMessageEventContent};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use strum::{Display, ParseError};
+use tracing::trace;
+
+use super::{BelongsTo, Display as DisplayParams, EditRule, Update, UtcDateTime};
+use crate::{util::deserialize_some, Result};
+
+/// How many answers a single vote may pick
+#[derive(Clone, Debug, Default, Serialize, Deserialize, Display, Eq, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+#[strum(serialize_all = "kebab-case")]
+pub enum PollKind {
+    #[default]
+    SingleChoice,
+    MultipleChoice,
+}
+
+impl FromStr for PollKind {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "single-choice" => Ok(PollKind::SingleChoice),
+            "multiple-choice" => Ok(PollKind::MultipleChoice),
+            _ => Err(ParseError::VariantNotFound),
+        }
+    }
+}
+
+/// Whether clients may show who voted for which answer
+///
+/// Votes are regular room events, so this is a request to clients rather than
+/// a cryptographic guarantee: for anonymous polls only the tallies are shown.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, Display, Eq, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+#[strum(serialize_all = "kebab-case")]
+pub enum PollVoting {
+    #[default]
+    Public,
+    Anonymous,
+}
+
+impl FromStr for PollVoting {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "public" => Ok(PollVoting::Public),
+            "anonymous" => Ok(PollVoting::Anonymous),
+            _ => Err(ParseError::VariantNotFound),
+        }
+    }
+}
+
+/// A possible answer to a poll
+#[derive(Clone, Debug, Deserialize, Serialize, Eq, PartialEq)]
+pub struct PollAnswer {
+    /// Identifies this answer in votes, unique within the poll
+    pub id: String,
+
+    /// The text shown for this answer
+    pub title: String,
+}
+
+impl PollAnswer {
+    pub fn new(id: String, title: String) -> Self {
+        PollAnswer { id, title }
+    }
+
+    pub fn id(&self) -> String {
+        self.id.clone()
+    }
+
+    pub fn title(&self) -> String {
+        self.title.clone()
+    }
+}
+
+/// The Poll Event
+#[derive(Clone, Debug, Deserialize, Serialize, EventContent, Builder, Getters)]
+#[ruma_event(type = "global.acter.dev.poll", kind = MessageLike)]
+#[builder(name = "PollBuilder", derive(Debug))]
+pub struct PollEventContent {
+    /// The question of this poll
+    pub title: String,
+
+    /// Optionally some further explanation
+    #[builder(setter(into), default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<TextMessageEventContent>,
+
+    /// The answers to pick from
+    #[builder(default)]
+    #[serde(default)]
+    pub answers: Vec<PollAnswer>,
+
+    /// Single or multiple choice
+    #[builder(default)]
+    #[serde(default)]
+    pub kind: PollKind,
+
+    /// Public or anonymous voting
+    #[builder(default)]
+    #[serde(default)]
+    pub voting: PollVoting,
+
+    /// Votes sent after this point in time aren’t counted
+    #[builder(setter(into), default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub utc_closes_at: Option<UtcDateTime>,
+
+    /// Optionally, a poll can be colored
+    #[builder(setter(into), default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display: Option<DisplayParams>,
+
+    /// Who may update this poll
+    #[builder(setter(into), default)]
+    #[serde(default, skip_serializing_if = "EditRule::is_anyone")]
+    pub edit_rule: EditRule,
+}
+
+impl PollEventContent {
+    pub fn answer(&self, id: &str) -> Option<&PollAnswer> {
+        self.answers.iter().find(|a| a.id == id)
+    }
+
+    pub fn is_multiple_choice(&self) -> bool {
+        self.kind == PollKind::MultipleChoice
+    }
+
+    pub fn is_anonymous(&self) -> bool {
+        self.voting == PollVoting::Anonymous
+    }
+}
+
+impl PollBuilder {
+    /// Add an answer, with the next free numeric id
+    pub fn add_answer(&mut self, title: String) -> &mut Self {
+        let answers = self.answers.get_or_insert_with(Default::default);
+        let id = (1..)
+            .map(|i: usize| i.to_string())
+            .find(|id| !answers.iter().any(|a| &a.id == id))
+            .expect("there is always a free id");
+        answers.push(PollAnswer::new(id, title));
+        self
+    }
+}
+
+/// The Poll Update Event
+#[derive(Clone, Debug, Deserialize, Serialize, EventContent, Builder, Getters)]
+#[ruma_event(type = "global.acter.dev.poll.update", kind = MessageLike)]
+#[builder(name = "PollUpdateBuilder", derive(Debug))]
+pub struct PollUpdateEventContent {
+    #[builder(setter(into))]
+    #[serde(rename = "m.relates_to")]
+    pub poll: Update,
+
+    /// If you want to update the poll question
+    #[builder(setter(into), default)]
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_some"
+    )]
+    pub title: Option<String>,
+
+    /// Update the further explanation
+    #[builder(setter(into), default)]
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_some"
+    )]
+    pub content: Option<Option<TextMessageEventContent>>,
+
+    /// Move, set or remove the closing date
+    #[builder(setter(into), default)]
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_some"
+    )]
+    pub utc_closes_at: Option<Option<UtcDateTime>>,
+
+    /// Optionally some displaying parameters
+    #[builder(setter(into), default)]
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_some"
+    )]
+    pub display: Option<Option<DisplayParams>>,
+}
+
+impl PollUpdateEventContent {
+    pub fn apply(&self, poll: &mut PollEventContent) -> Result<bool> {
+        let mut updated = false;
+        if let Some(title) = &self.title {
+            poll.title.clone_from(title);
+            updated = true;
+        }
+        if let Some(content) = &self.content {
+            poll.content.clone_from(content);
+            updated = true;
+        }
+        if let Some(utc_closes_at) = &self.utc_closes_at {
+            poll.utc_closes_at.clone_from(utc_closes_at);
+            updated = true;
+        }
+        if let Some(display) = &self.display {
+            poll.display.clone_from(display);
+            updated = true;
+        }
+
+        trace!(update = ?self, ?updated, ?poll, "Poll updated");
+
+        Ok(updated)
+    }
+}
+
+/// The Vote Event
+///
+/// The latest vote of a user replaces all previous ones, sending no answers
+/// retracts it.
+#[derive(Clone, Debug, Deserialize, Serialize, EventContent, Builder)]
+#[ruma_event(type = "global.acter.dev.poll.vote", kind = MessageLike)]
+#[builder(name = "PollVoteBuilder", derive(Debug))]
+pub struct PollVoteEventContent {
+    #[builder(setter(into))]
+    #[serde(rename = "m.relates_to")]
+    pub to: BelongsTo,
+
+    /// The ids of the picked answers
+    #[builder(default)]
+    #[serde(default)]
+    pub answers: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ensure_minimal_poll_parses() -> Result<()> {
+        let json_raw = r#"{"type":"global.acter.dev.poll",
+            "room_id":"!euhIDqDVvVXulrhWgN:ds9.acter.global","sender":"@odo:ds9.acter.global",
+            "content":{"title":"Where to go for lunch?", "answers": [
+                {"id": "1", "title": "Quark’s"}, {"id": "2", "title": "Replimat"}
+            ]},"origin_server_ts":1672407531453,
+            "unsigned":{"age":11523850},
+            "event_id":"$KwumA4L3M-duXu0I3UA886LvN-BDCKAyxR1skNfnh3c",
+            "user_id":"@odo:ds9.acter.global","age":11523850}"#;
+        let event = serde_json::from_str::<OriginalPollEvent>(json_raw)?;
+        assert_eq!(event.content.title, "Where to go for lunch?");
+        assert_eq!(event.content.kind, PollKind::SingleChoice);
+        assert_eq!(event.content.voting, PollVoting::Public);
+        assert_eq!(event.content.answer("2").unwrap().title, "Replimat");
+        Ok(())
+    }
+
+    #[test]
+    fn builder_numbers_answers() -> anyhow::Result<()> {
+        let poll = PollBuilder::default()
+            .title("Date of the next meetup".to_owned())
+            .kind(PollKind::MultipleChoice)
+            .add_answer("Monday".to_owned())
+            .add_answer("Friday".to_owned())
+            .build()?;
+        let ids: Vec<_> = poll.answers.iter().map(|a| a.id.as_str()).collect();
+        assert_eq!(ids, ["1", "2"]);
+        assert!(poll.is_multiple_choice());
+        Ok(())
+    }
+}
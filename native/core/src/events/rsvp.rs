@@ -4,7 +4,7 @@ use serde::{Deserialize, Serialize};
 use std::str::FromStr;
 use strum::{Display, ParseError};
 
-use super::BelongsTo;
+use super::{BelongsTo, UtcDateTime};
 
 /// RSVP status
 // previously accepted only PascalCase
@@ -46,4 +46,10 @@ pub struct RsvpEventContent {
 
     /// The status responded by this user
     pub status: RsvpStatus,
+
+    /// For recurring events: the occurrence this response is about, identified by its
+    /// `recurrence_id`. Responses without apply to the entire series.
+    #[builder(setter(into), default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub occurrence: Option<UtcDateTime>,
 }
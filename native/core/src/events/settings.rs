@@ -13,7 +13,7 @@ use std::borrow::Cow;
 use matrix_sdk::ruma::events::StaticEventContent;
 pub use space::{
     ActerAppSettings, ActerAppSettingsContent, ActerAppSettingsContentBuilder,
-    ActerAppSettingsContentBuilderError, EventsSettings, NewsSettings, PinsSettings,
+    ActerAppSettingsContentBuilderError, EventsSettings, NewsSettings, PinsSettings, PollsSettings,
     SimpleOnOffSetting, SimpleOnOffSettingBuilder, SimpleSettingWithTurnOff,
     SimpleSettingWithTurnOffBuilder, StoriesSettings, TasksSettings,
 };
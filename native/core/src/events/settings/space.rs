@@ -57,6 +57,7 @@ impl SimpleOnOffSetting {
 
 pub type TasksSettings = SimpleOnOffSetting;
 pub type StoriesSettings = SimpleOnOffSetting;
+pub type PollsSettings = SimpleOnOffSetting;
 pub type NewsSettings = SimpleSettingWithTurnOff;
 pub type PinsSettings = SimpleSettingWithTurnOff;
 pub type EventsSettings = SimpleSettingWithTurnOff;
@@ -76,6 +77,8 @@ pub struct ActerAppSettingsContent {
     pub(crate) events: Option<EventsSettings>,
     pub(crate) tasks: Option<TasksSettings>,
     pub(crate) stories: Option<StoriesSettings>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) polls: Option<PollsSettings>,
 }
 
 impl ActerAppSettingsContent {
@@ -94,6 +97,9 @@ impl ActerAppSettingsContent {
     pub fn stories(&self) -> StoriesSettings {
         self.stories.clone().unwrap_or_default()
     }
+    pub fn polls(&self) -> PollsSettings {
+        self.polls.clone().unwrap_or_default()
+    }
 
     pub fn off() -> ActerAppSettingsContent {
         ActerAppSettingsContent {
@@ -102,6 +108,7 @@ impl ActerAppSettingsContent {
             events: EventsSettings::off(),
             tasks: TasksSettings::off(),
             stories: StoriesSettings::off(),
+            polls: PollsSettings::off(),
         }
     }
 
@@ -112,6 +119,7 @@ impl ActerAppSettingsContent {
             events: EventsSettings::on(),
             tasks: TasksSettings::on(),
             stories: StoriesSettings::on(),
+            polls: PollsSettings::on(),
         }
     }
 
@@ -122,6 +130,7 @@ impl ActerAppSettingsContent {
             .events(self.events.clone())
             .tasks(self.tasks.clone())
             .stories(self.stories.clone())
+            .polls(self.polls.clone())
             .to_owned()
     }
 }
@@ -2,7 +2,10 @@ use chrono_tz::Tz;
 use core::result::Result as CoreResult;
 use derive_builder::Builder;
 use derive_getters::Getters;
-use matrix_sdk_base::ruma::events::{macros::EventContent, room::message::TextMessageEventContent};
+use matrix_sdk_base::ruma::{
+    events::{macros::EventContent, room::message::TextMessageEventContent, Mentions},
+    OwnedEventId, OwnedUserId,
+};
 use serde::{Deserialize, Serialize};
 use serde_repr::{Deserialize_repr, Serialize_repr};
 use tracing::trace;
@@ -11,7 +14,9 @@ use tracing::trace;
 /// modeled after [JMAP Tasks](https://jmap.io/spec-tasks.html), extensions to
 /// [ietf rfc8984](https://www.rfc-editor.org/rfc/rfc8984.html#name-task).
 ///
-use super::{BelongsTo, Date, Display, Update, UtcDateTime};
+use super::{
+    calendar::RecurrenceRule, BelongsTo, Date, Display, MentionsBuilder, Update, UtcDateTime,
+};
 use crate::{util::deserialize_some, Result as ActerResult};
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
@@ -45,6 +50,36 @@ impl Priority {
     }
 }
 
+/// A state of the workflow tasks in a TaskList move through, e.g. a kanban column
+#[derive(Clone, Debug, Deserialize, Serialize, Eq, PartialEq)]
+pub struct WorkflowState {
+    /// The stable identifier tasks refer to
+    pub key: String,
+    /// The human readable name
+    pub name: String,
+    /// Tasks in this state count as done
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub is_done: bool,
+}
+
+impl WorkflowState {
+    pub fn new(key: String, name: String, is_done: bool) -> Self {
+        WorkflowState { key, name, is_done }
+    }
+
+    pub fn key(&self) -> String {
+        self.key.clone()
+    }
+
+    pub fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.is_done
+    }
+}
+
 /// The TaskList Event
 ///
 /// modeled after [JMAP TaskList](https://jmap.io/spec-tasks.html#tasklists)
@@ -82,6 +117,22 @@ pub struct TaskListEventContent {
     #[builder(setter(into), default)]
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub categories: Vec<String>,
+
+    /// The states tasks in this list move through, in order
+    #[builder(setter(into), default)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub workflow: Vec<WorkflowState>,
+
+    /// Who may update this task list
+    #[builder(setter(into), default)]
+    #[serde(default, skip_serializing_if = "EditRule::is_anyone")]
+    pub edit_rule: EditRule,
+}
+
+impl TaskListEventContent {
+    pub fn workflow_state(&self, key: &str) -> Option<&WorkflowState> {
+        self.workflow.iter().find(|s| s.key == key)
+    }
 }
 
 /// The TaskList Event
@@ -159,6 +210,14 @@ pub struct TaskListUpdateEventContent {
         deserialize_with = "deserialize_some"
     )]
     pub categories: Option<Vec<String>>,
+
+    #[builder(default)]
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_some"
+    )]
+    pub workflow: Option<Vec<WorkflowState>>,
 }
 
 impl TaskListUpdateEventContent {
@@ -196,6 +255,10 @@ impl TaskListUpdateEventContent {
             task_list.categories.clone_from(categories);
             updated = true;
         }
+        if let Some(workflow) = &self.workflow {
+            task_list.workflow.clone_from(workflow);
+            updated = true;
+        }
 
         trace!(update = ?self, ?updated, ?task_list, "TaskList updated");
 
@@ -224,6 +287,16 @@ pub struct TaskEventContent {
     #[serde(rename = "m.relates_to")]
     pub task_list_id: BelongsTo,
 
+    /// The task this is a subtask of, within the same tasklist
+    #[builder(setter(into), default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_task: Option<BelongsTo>,
+
+    /// The tasks that need to be done before this one can be done
+    #[builder(setter(into), default)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub blocked_by: Vec<OwnedEventId>,
+
     /// Further information describing the task
     #[builder(setter(into), default)]
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -273,6 +346,43 @@ pub struct TaskEventContent {
     #[builder(setter(into), default)]
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub categories: Vec<String>,
+
+    /// The `UID` of the iCalendar todo this was imported from
+    #[builder(setter(into), default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ical_uid: Option<String>,
+
+    /// How does this task repeat? The next instance is created once this one is done
+    #[builder(setter(into), default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recurrence_rule: Option<RecurrenceRule>,
+
+    /// The done instance of the recurring task this one was created from
+    #[builder(setter(into), default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous_instance: Option<OwnedEventId>,
+
+    /// The key of the workflow state of the tasklist this task is in
+    #[builder(setter(into), default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+
+    /// The users and rooms mentioned in the description
+    #[builder(setter(into), default)]
+    #[serde(
+        rename = "m.mentions",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub mentions: Option<Mentions>,
+}
+
+impl MentionsBuilder for TaskBuilder {
+    fn mentions_mut(&mut self) -> &mut Mentions {
+        self.mentions
+            .get_or_insert_with(Default::default)
+            .get_or_insert_with(Mentions::new)
+    }
 }
 
 impl TaskBuilder {
@@ -308,6 +418,24 @@ pub struct TaskUpdateEventContent {
     )]
     pub title: Option<String>,
 
+    /// The task this is a subtask of
+    #[builder(default)]
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_some"
+    )]
+    pub parent_task: Option<Option<BelongsTo>>,
+
+    /// The tasks that need to be done before this one can be done
+    #[builder(default)]
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_some"
+    )]
+    pub blocked_by: Option<Vec<OwnedEventId>>,
+
     /// Every tasks belongs to a tasklist
     /// Further information describing the task
     #[builder(default)]
@@ -397,6 +525,41 @@ pub struct TaskUpdateEventContent {
         deserialize_with = "deserialize_some"
     )]
     pub categories: Option<Vec<String>>,
+
+    /// How does this task repeat?
+    #[builder(default)]
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_some"
+    )]
+    pub recurrence_rule: Option<Option<RecurrenceRule>>,
+
+    /// Move the task to this workflow state
+    #[builder(default)]
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_some"
+    )]
+    pub status: Option<Option<String>>,
+
+    /// The users and rooms mentioned in the updated description
+    #[builder(setter(into), default)]
+    #[serde(
+        rename = "m.mentions",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub mentions: Option<Mentions>,
+}
+
+impl MentionsBuilder for TaskUpdateBuilder {
+    fn mentions_mut(&mut self) -> &mut Mentions {
+        self.mentions
+            .get_or_insert_with(Default::default)
+            .get_or_insert_with(Mentions::new)
+    }
 }
 
 impl TaskUpdateEventContent {
@@ -406,6 +569,14 @@ impl TaskUpdateEventContent {
             task.title.clone_from(title);
             updated = true;
         }
+        if let Some(parent_task) = &self.parent_task {
+            task.parent_task.clone_from(parent_task);
+            updated = true;
+        }
+        if let Some(blocked_by) = &self.blocked_by {
+            task.blocked_by.clone_from(blocked_by);
+            updated = true;
+        }
         if let Some(description) = &self.description {
             task.description.clone_from(description);
             updated = true;
@@ -446,6 +617,14 @@ impl TaskUpdateEventContent {
             task.categories.clone_from(categories);
             updated = true;
         }
+        if let Some(recurrence_rule) = &self.recurrence_rule {
+            task.recurrence_rule.clone_from(recurrence_rule);
+            updated = true;
+        }
+        if let Some(status) = &self.status {
+            task.status.clone_from(status);
+            updated = true;
+        }
 
         trace!(update = ?self, ?updated, ?task, "Task updated");
 
@@ -472,3 +651,56 @@ pub struct TaskSelfUnassignEventContent {
     #[serde(rename = "m.relates_to")]
     pub task: BelongsTo,
 }
+
+/// TaskAssign Event, assigning someone else to the task
+///
+/// Sending it is gated by its own power level, the assignee is mentioned
+/// so they get notified.
+#[derive(Clone, Debug, Deserialize, Serialize, EventContent, Getters)]
+#[ruma_event(type = "global.acter.dev.task.assign", kind = MessageLike)]
+pub struct TaskAssignEventContent {
+    #[serde(rename = "m.relates_to")]
+    pub task: BelongsTo,
+    pub assignee: OwnedUserId,
+    #[serde(
+        rename = "m.mentions",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub mentions: Option<Mentions>,
+}
+
+impl TaskAssignEventContent {
+    pub fn new(task: OwnedEventId, assignee: OwnedUserId) -> Self {
+        TaskAssignEventContent {
+            task: task.into(),
+            mentions: Some(Mentions::with_user_ids([assignee.clone()])),
+            assignee,
+        }
+    }
+}
+
+/// TaskUnassign Event, removing someone else from the task
+#[derive(Clone, Debug, Deserialize, Serialize, EventContent, Getters)]
+#[ruma_event(type = "global.acter.dev.task.unassign", kind = MessageLike)]
+pub struct TaskUnassignEventContent {
+    #[serde(rename = "m.relates_to")]
+    pub task: BelongsTo,
+    pub assignee: OwnedUserId,
+    #[serde(
+        rename = "m.mentions",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub mentions: Option<Mentions>,
+}
+
+impl TaskUnassignEventContent {
+    pub fn new(task: OwnedEventId, assignee: OwnedUserId) -> Self {
+        TaskUnassignEventContent {
+            task: task.into(),
+            mentions: Some(Mentions::with_user_ids([assignee.clone()])),
+            assignee,
+        }
+    }
+}
@@ -1,12 +1,21 @@
 use matrix_sdk::ruma::OwnedEventId;
 use matrix_sdk_base::ruma::{
     events::{room::redaction::OriginalRoomRedactionEvent, UnsignedRoomRedactionEvent},
-    OwnedRoomId,
+    MilliSecondsSinceUnixEpoch, OwnedRoomId,
 };
 use scc::hash_map::{Entry, HashMap};
-use std::sync::Arc;
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
 use tokio::sync::broadcast::{channel, Receiver, Sender};
-use tracing::{error, info, trace, trace_span, warn};
+use tracing::{debug, error, info, trace, trace_span, warn};
+
+mod pending;
+pub use pending::PendingOnParent;
 
 use crate::{
     models::{ActerModel, AnyActerModel, EventMeta, RedactedActerModel},
@@ -15,20 +24,57 @@ use crate::{
     Error, Result,
 };
 
+static PENDING_ON_PARENT_KEY: &str = "ACTER::PENDING_ON_PARENT";
+/// How long models wait for their parent to arrive by default
+pub const DEFAULT_PENDING_EXPIRY: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
 #[derive(Clone, Debug)]
 pub struct Executor {
     store: Store,
     notifiers: Arc<HashMap<ExecuteReference, Sender<()>>>,
+    pending: Arc<Mutex<PendingOnParent>>,
+    pending_expiry_ms: Arc<AtomicU64>,
 }
 
 impl Executor {
     pub async fn new(store: Store) -> Result<Self> {
+        let pending = match store
+            .get_raw::<PendingOnParent>(PENDING_ON_PARENT_KEY)
+            .await
+        {
+            Ok(pending) => pending,
+            Err(Error::ModelNotFound(_)) => Default::default(),
+            Err(error) => {
+                warn!(
+                    ?error,
+                    "Couldn’t read models pending on their parent, dropping"
+                );
+                Default::default()
+            }
+        };
         Ok(Executor {
             store,
             notifiers: Default::default(),
+            pending: Arc::new(Mutex::new(pending)),
+            pending_expiry_ms: Arc::new(AtomicU64::new(DEFAULT_PENDING_EXPIRY.as_millis() as u64)),
         })
     }
 
+    /// How long a model waits for the model it refers to before it is dropped
+    pub fn set_pending_expiry(&self, expiry: Duration) {
+        let millis = u64::try_from(expiry.as_millis()).unwrap_or(u64::MAX);
+        self.pending_expiry_ms.store(millis, Ordering::Relaxed);
+    }
+
+    pub fn pending_expiry(&self) -> Duration {
+        Duration::from_millis(self.pending_expiry_ms.load(Ordering::Relaxed))
+    }
+
+    /// How many models are waiting for the model they refer to
+    pub fn pending_count(&self) -> Result<usize> {
+        Ok(self.pending.lock()?.len())
+    }
+
     pub fn store(&self) -> &Store {
         &self.store
     }
@@ -96,9 +142,34 @@ impl Executor {
     }
 
     pub async fn handle(&self, model: AnyActerModel) -> Result<()> {
-        let event_id = model.event_id().to_string();
+        let event_id = model.event_id().to_owned();
+        if self.execute_or_queue(model).await? {
+            self.replay_pending(event_id).await?;
+        }
+        Ok(())
+    }
+
+    /// Execute the model, queueing it if the model it refers to isn’t known
+    /// yet. Returns whether the model was stored.
+    async fn execute_or_queue(&self, model: AnyActerModel) -> Result<bool> {
+        let event_id = model.event_id().to_owned();
         trace!(?event_id, ?model, "handle");
+        // kept to queue it, should any object it refers to be missing, not
+        // just those it `belongs_to`: comments, reactions, RSVPs, … refer
+        // to their object without
+        let pending = model.clone();
         match model.execute(&self.store).await {
+            Err(Error::ModelNotFound(missing)) => match OwnedEventId::try_from(missing.as_str()) {
+                Ok(parent) if parent != event_id => {
+                    debug!(?event_id, ?parent, "parent not found, queueing");
+                    self.queue_pending(parent, pending).await?;
+                    Ok(false)
+                }
+                _ => {
+                    error!(?event_id, missing, "handling failed");
+                    Err(Error::ModelNotFound(missing))
+                }
+            },
             Err(error) => {
                 error!(?event_id, ?error, "handling failed");
                 Err(error)
@@ -107,9 +178,56 @@ impl Executor {
                 trace!(?event_id, "handling done");
                 info!("******************** executor handled: {:?}", &keys);
                 self.notify(keys);
-                Ok(())
+                Ok(true)
+            }
+        }
+    }
+
+    async fn queue_pending(&self, parent: OwnedEventId, model: AnyActerModel) -> Result<()> {
+        let snapshot = {
+            let mut pending = self.pending.lock()?;
+            let now = MilliSecondsSinceUnixEpoch::now();
+            let expired = pending.expire(now, self.pending_expiry());
+            if expired > 0 {
+                info!(expired, "dropped models whose parent never arrived");
+            }
+            pending.push(parent, model, now);
+            pending.clone()
+        };
+        self.store.set_raw(PENDING_ON_PARENT_KEY, &snapshot).await
+    }
+
+    /// Apply the models that were waiting for `parent`, and then those
+    /// waiting on them in turn
+    async fn replay_pending(&self, parent: OwnedEventId) -> Result<()> {
+        let mut parents = vec![parent];
+        while let Some(parent) = parents.pop() {
+            let (models, snapshot) = {
+                let mut pending = self.pending.lock()?;
+                let models = pending.take(&parent);
+                if models.is_empty() {
+                    continue;
+                }
+                (models, pending.clone())
+            };
+            self.store.set_raw(PENDING_ON_PARENT_KEY, &snapshot).await?;
+            debug!(
+                ?parent,
+                count = models.len(),
+                "replaying models pending on parent"
+            );
+            for model in models {
+                let event_id = model.event_id().to_owned();
+                match self.execute_or_queue(model).await {
+                    Ok(true) => parents.push(event_id),
+                    Ok(false) => {} // waiting for another parent
+                    Err(error) => {
+                        warn!(?event_id, ?error, "replaying pending model failed");
+                    }
+                }
             }
         }
+        Ok(())
     }
 
     pub async fn clear_room(&self, room_id: &OwnedRoomId) -> Result<()> {
@@ -185,14 +303,24 @@ mod tests {
 
     use super::*;
     use crate::{
-        events::{comments::CommentEventContent, BelongsTo},
-        models::{Comment, TestModelBuilder},
-        referencing::{IndexKey, ObjectListIndex},
+        activities::Activity,
+        events::{
+            comments::{CommentBuilder, CommentEventContent},
+            polls::{PollBuilder, PollVoteEventContent},
+            AnyActerEvent, BelongsTo, MentionsBuilder,
+        },
+        history::EditHistory,
+        models::{
+            ensure_no_task_cycle, next_task_instance, Comment, CommentsManager, Poll, PollVote,
+            PollVotesManager, TestModelBuilder,
+        },
+        referencing::{IndexKey, ModelParam, ObjectListIndex, SpecialListsIndex},
     };
     use matrix_sdk::Client;
     use matrix_sdk_base::{
         ruma::{
             api::MatrixVersion, event_id, events::room::message::TextMessageEventContent, user_id,
+            MilliSecondsSinceUnixEpoch, UInt, UserId,
         },
         store::{MemoryStore, StoreConfig},
     };
@@ -309,6 +437,7 @@ mod tests {
                     event_id: parent_id,
                 },
                 reply_to: None,
+                mentions: None,
             },
             meta: TestModelBuilder::fake_meta(),
         };
@@ -320,6 +449,200 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn threaded_comment_replies() -> Result<()> {
+        let _ = env_logger::try_init();
+        let executor = fresh_executor().await?;
+        let store = executor.store().clone();
+        let model = TestModelBuilder::default().simple().build().unwrap();
+        let parent_id = model.event_id().to_owned();
+        executor.handle(model.into()).await?;
+
+        let comment = |text: &str, on: &OwnedEventId, reply_to: Option<OwnedEventId>| Comment {
+            inner: CommentEventContent {
+                content: TextMessageEventContent::plain(text),
+                on: BelongsTo {
+                    event_id: on.clone(),
+                },
+                reply_to: reply_to.map(Into::into),
+                mentions: None,
+            },
+            meta: TestModelBuilder::fake_meta(),
+        };
+
+        let first = comment("First", &parent_id, None);
+        let first_id = first.event_id().to_owned();
+        executor.handle(first.into()).await?;
+        let reply = comment("Reply", &parent_id, Some(first_id.clone()));
+        let reply_id = reply.event_id().to_owned();
+        executor.handle(reply.into()).await?;
+
+        let root = CommentsManager::from_store_and_event_id(&store, &parent_id).await;
+        assert_eq!(root.stats().total_comments_count, 2);
+        let top_level = root.top_level_comments().await?;
+        assert_eq!(top_level.len(), 1);
+        assert_eq!(top_level[0].event_id().to_owned(), first_id);
+
+        let thread = CommentsManager::from_store_and_event_id(&store, &first_id).await;
+        assert_eq!(thread.stats().total_comments_count, 1);
+        let replies = thread.comments().await?;
+        assert_eq!(replies.len(), 1);
+        assert_eq!(replies[0].event_id().to_owned(), reply_id);
+        assert_eq!(replies[0].in_reply_to(), Some(first_id.clone()));
+
+        // replying across objects isn’t counted in the thread
+        let other = TestModelBuilder::default()
+            .simple()
+            .event_id(event_id!("$otherobject").to_owned())
+            .build()
+            .unwrap();
+        let other_id = other.event_id().to_owned();
+        executor.handle(other.into()).await?;
+        executor
+            .handle(comment("Elsewhere", &other_id, Some(first_id.clone())).into())
+            .await?;
+        let thread = CommentsManager::from_store_and_event_id(&store, &first_id).await;
+        assert_eq!(thread.stats().total_comments_count, 1);
+        assert_eq!(thread.comments().await?.len(), 1);
+        // but kept as a comment on the object
+        let elsewhere = CommentsManager::from_store_and_event_id(&store, &other_id).await;
+        assert_eq!(elsewhere.top_level_comments().await?.len(), 1);
+
+        // replying to an unknown comment doesn’t lose the comment either
+        executor
+            .handle(comment("Lost", &other_id, Some(event_id!("$unknown").to_owned())).into())
+            .await?;
+        let elsewhere = CommentsManager::from_store_and_event_id(&store, &other_id).await;
+        assert_eq!(elsewhere.top_level_comments().await?.len(), 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn poll_vote_tallies() -> Result<()> {
+        let _ = env_logger::try_init();
+        let executor = fresh_executor().await?;
+        let store = executor.store().clone();
+        let poll = Poll {
+            inner: PollBuilder::default()
+                .title("Where do we go for lunch?".to_owned())
+                .add_answer("Pizza".to_owned())
+                .add_answer("Sushi".to_owned())
+                .add_answer("Salad".to_owned())
+                .utc_closes_at(chrono::DateTime::from_timestamp_millis(5_000))
+                .build()
+                .unwrap(),
+            meta: TestModelBuilder::fake_meta(),
+        };
+        let poll_id = poll.event_id().to_owned();
+        executor.handle(poll.into()).await?;
+
+        let vote = |sender: &UserId, answers: &[&str], at: u32| PollVote {
+            inner: PollVoteEventContent {
+                to: BelongsTo {
+                    event_id: poll_id.clone(),
+                },
+                answers: answers.iter().map(|a| a.to_string()).collect(),
+            },
+            meta: EventMeta {
+                sender: sender.to_owned(),
+                origin_server_ts: MilliSecondsSinceUnixEpoch(UInt::from(at)),
+                ..TestModelBuilder::fake_meta()
+            },
+        };
+        let alice = user_id!("@alice:example.org");
+        let bob = user_id!("@bob:example.org");
+        let carol = user_id!("@carol:example.org");
+
+        executor.handle(vote(alice, &["1"], 1_000).into()).await?;
+        executor.handle(vote(bob, &["2"], 2_000).into()).await?;
+        // alice changed the vote
+        executor.handle(vote(alice, &["3"], 3_000).into()).await?;
+        // more than one answer on a single choice poll isn’t counted
+        executor
+            .handle(vote(carol, &["1", "2"], 4_000).into())
+            .await?;
+        // too late, the earlier vote stands
+        executor.handle(vote(bob, &["1"], 6_000).into()).await?;
+
+        let manager = PollVotesManager::from_store_and_event_id(&store, &poll_id).await;
+        assert_eq!(*manager.total_voters(), 2);
+        assert_eq!(manager.votes_for("1"), 0);
+        assert_eq!(manager.votes_for("2"), 1);
+        assert_eq!(manager.votes_for("3"), 1);
+
+        // retracting
+        executor.handle(vote(alice, &[], 4_500).into()).await?;
+        let manager = PollVotesManager::from_store_and_event_id(&store, &poll_id).await;
+        assert_eq!(*manager.total_voters(), 1);
+        assert_eq!(manager.votes_for("3"), 0);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn replays_comment_once_parent_arrives() -> Result<()> {
+        let _ = env_logger::try_init();
+        let executor = fresh_executor().await?;
+        let model = TestModelBuilder::default().simple().build().unwrap();
+        let parent_id = model.event_id().to_owned();
+        let parent_idx = Comment::index_for(parent_id.clone());
+
+        let comment = Comment {
+            inner: CommentEventContent {
+                content: TextMessageEventContent::plain("Early"),
+                on: BelongsTo {
+                    event_id: parent_id,
+                },
+                reply_to: None,
+                mentions: None,
+            },
+            meta: TestModelBuilder::fake_meta(),
+        };
+        let comment_id = comment.event_id().to_owned();
+
+        // arrives before the parent, kept rather than lost
+        executor.handle(comment.into()).await?;
+        assert!(executor.store().get(&comment_id).await.is_err());
+        assert_eq!(executor.pending_count()?, 1);
+
+        let mut sub = executor.subscribe(parent_idx.clone());
+        executor.handle(model.into()).await?;
+        assert!(executor.store().get(&comment_id).await.is_ok());
+        assert_eq!(executor.pending_count()?, 0);
+        assert!(sub.recv().await.is_ok());
+        assert_eq!(executor.store().get_list(&parent_idx).await?.count(), 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn pending_models_survive_restart() -> Result<()> {
+        let _ = env_logger::try_init();
+        let executor = fresh_executor().await?;
+        let child = TestModelBuilder::default()
+            .simple()
+            .belongs_to(vec![event_id!("$notyetthere").to_owned()])
+            .event_id(event_id!("$earlychild").to_owned())
+            .build()
+            .unwrap();
+        executor.handle(child.into()).await?;
+        assert_eq!(executor.pending_count()?, 1);
+
+        let restarted = Executor::new(executor.store().clone()).await?;
+        assert_eq!(restarted.pending_count()?, 1);
+
+        // expired by the next one queued
+        restarted.set_pending_expiry(Duration::ZERO);
+        tokio::time::sleep(Duration::from_millis(5)).await;
+        let other = TestModelBuilder::default()
+            .simple()
+            .belongs_to(vec![event_id!("$alsonotthere").to_owned()])
+            .event_id(event_id!("$otherchild").to_owned())
+            .build()
+            .unwrap();
+        restarted.handle(other.into()).await?;
+        assert_eq!(restarted.pending_count()?, 1);
+        Ok(())
+    }
+
     #[tokio::test]
     async fn wait_for_simple_model() -> Result<()> {
         let _ = env_logger::try_init();
@@ -410,4 +733,732 @@ mod tests {
 
         Ok(())
     }
+
+    static TASK_LIST: &str = "global.acter.dev.tasklist";
+    static TASK: &str = "global.acter.dev.task";
+    static TASK_UPDATE: &str = "global.acter.dev.task.update";
+
+    fn acter_model(
+        event_type: &str,
+        event_id: &str,
+        sender: &str,
+        ts: u64,
+        content: serde_json::Value,
+    ) -> Result<AnyActerModel> {
+        let event: AnyActerEvent = from_value(json!({
+            "type": event_type,
+            "room_id": "!room:example.org",
+            "sender": sender,
+            "event_id": event_id,
+            "origin_server_ts": ts,
+            "content": content,
+        }))?;
+        AnyActerModel::try_from(event).map_err(|e| Error::Custom(format!("{e:?}")))
+    }
+
+    fn task_model(
+        event_type: &str,
+        event_id: &str,
+        content: serde_json::Value,
+    ) -> Result<AnyActerModel> {
+        acter_model(event_type, event_id, "@test:example.org", 123456, content)
+    }
+
+    fn belongs_to(event_id: &str) -> serde_json::Value {
+        json!({ "rel_type": "global.acter.dev.belongs_to", "event_id": event_id })
+    }
+
+    #[tokio::test]
+    async fn subtasks_progress_and_cycles() -> Result<()> {
+        let _ = env_logger::try_init();
+        let executor = fresh_executor().await?;
+        let parent_id = event_id!("$parent").to_owned();
+        let done_id = event_id!("$done").to_owned();
+        let open_id = event_id!("$open").to_owned();
+
+        executor
+            .handle(task_model(TASK_LIST, "$list", json!({ "name": "List" }))?)
+            .await?;
+        executor
+            .handle(task_model(
+                TASK,
+                "$parent",
+                json!({ "title": "Parent", "m.relates_to": belongs_to("$list") }),
+            )?)
+            .await?;
+        executor
+            .handle(task_model(
+                TASK,
+                "$done",
+                json!({
+                    "title": "Done",
+                    "m.relates_to": belongs_to("$list"),
+                    "parent_task": belongs_to("$parent"),
+                    "progress_percent": 100,
+                }),
+            )?)
+            .await?;
+        executor
+            .handle(task_model(
+                TASK,
+                "$open",
+                json!({
+                    "title": "Open",
+                    "m.relates_to": belongs_to("$list"),
+                    "parent_task": belongs_to("$parent"),
+                    "blocked_by": ["$done"],
+                }),
+            )?)
+            .await?;
+
+        let AnyActerModel::Task(parent) = executor.store().get(&parent_id).await? else {
+            panic!("Parent is not a task");
+        };
+        assert!(parent.has_subtasks());
+        assert_eq!(parent.percent(), Some(50));
+        assert!(!parent.is_done());
+        assert_eq!(
+            executor
+                .store()
+                .get_list(&parent.subtasks_key())
+                .await?
+                .count(),
+            2
+        );
+
+        let AnyActerModel::TaskList(list) =
+            executor.store().get(&event_id!("$list").to_owned()).await?
+        else {
+            panic!("List is not a task list");
+        };
+        assert_eq!(*list.stats().tasks_count(), 3);
+
+        let AnyActerModel::Task(done) = executor.store().get(&done_id).await? else {
+            panic!("Done is not a task");
+        };
+        assert_eq!(
+            executor
+                .store()
+                .get_list(&done.blocking_key())
+                .await?
+                .count(),
+            1
+        );
+
+        let store = executor.store();
+        // the parent can’t become a subtask of its own subtask
+        assert!(ensure_no_task_cycle(store, &parent_id, Some(&open_id), &[])
+            .await
+            .is_err());
+        // and the blocking task can’t be blocked by the task it blocks
+        assert!(
+            ensure_no_task_cycle(store, &done_id, None, &[open_id.clone()])
+                .await
+                .is_err()
+        );
+        // unrelated relations are fine
+        assert!(
+            ensure_no_task_cycle(store, &open_id, Some(&parent_id), &[done_id])
+                .await
+                .is_ok()
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn moving_subtask_updates_both_parents() -> Result<()> {
+        let _ = env_logger::try_init();
+        let executor = fresh_executor().await?;
+        let store = executor.store().clone();
+        let old_id = event_id!("$old").to_owned();
+        let new_id = event_id!("$new").to_owned();
+
+        executor
+            .handle(task_model(TASK_LIST, "$list", json!({ "name": "List" }))?)
+            .await?;
+        for (id, title) in [("$old", "Old"), ("$new", "New")] {
+            executor
+                .handle(task_model(
+                    TASK,
+                    id,
+                    json!({ "title": title, "m.relates_to": belongs_to("$list") }),
+                )?)
+                .await?;
+        }
+        executor
+            .handle(task_model(
+                TASK,
+                "$sub",
+                json!({
+                    "title": "Sub",
+                    "m.relates_to": belongs_to("$list"),
+                    "parent_task": belongs_to("$old"),
+                    "progress_percent": 100,
+                }),
+            )?)
+            .await?;
+
+        let AnyActerModel::Task(old) = store.get(&old_id).await? else {
+            panic!("Old is not a task");
+        };
+        assert!(old.is_done());
+
+        executor
+            .handle(acter_model(
+                "global.acter.dev.task.update",
+                "$reparent",
+                "@test:example.org",
+                123457,
+                json!({
+                    "m.relates_to": { "rel_type": "global.acter.dev.update", "event_id": "$sub" },
+                    "parent_task": belongs_to("$new"),
+                }),
+            )?)
+            .await?;
+
+        let AnyActerModel::Task(old) = store.get(&old_id).await? else {
+            panic!("Old is not a task");
+        };
+        assert!(!old.has_subtasks());
+        assert_eq!(old.percent(), None);
+        assert!(!old.is_done());
+        assert_eq!(store.get_list(&old.subtasks_key()).await?.count(), 0);
+
+        let AnyActerModel::Task(new) = store.get(&new_id).await? else {
+            panic!("New is not a task");
+        };
+        assert_eq!(new.percent(), Some(100));
+        assert!(new.is_done());
+        assert_eq!(store.get_list(&new.subtasks_key()).await?.count(), 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn next_instance_of_completed_recurring_task() -> Result<()> {
+        let _ = env_logger::try_init();
+        let executor = fresh_executor().await?;
+        let store = executor.store().clone();
+        let today = chrono::NaiveDate::from_ymd_opt(2024, 1, 2).unwrap();
+
+        executor
+            .handle(task_model(
+                TASK_LIST,
+                "$list",
+                json!({
+                    "name": "Chores",
+                    "workflow": [
+                        { "key": "todo", "name": "Todo" },
+                        { "key": "done", "name": "Done", "is_done": true },
+                    ],
+                }),
+            )?)
+            .await?;
+        executor
+            .handle(task_model(
+                TASK,
+                "$kitchen",
+                json!({
+                    "title": "Clean the kitchen",
+                    "m.relates_to": belongs_to("$list"),
+                    "due_date": "2024-01-01",
+                    "recurrence_rule": { "frequency": "weekly" },
+                    "status": "todo",
+                }),
+            )?)
+            .await?;
+        let AnyActerModel::Task(task) = store.get(&event_id!("$kitchen").to_owned()).await? else {
+            panic!("Kitchen is not a task");
+        };
+        let complete = task
+            .updater()
+            .progress_percent(Some(Some(100)))
+            .build()
+            .unwrap();
+        let reopen = task.updater().progress_percent(Some(None)).build().unwrap();
+
+        // only completing it brings up the next instance
+        assert!(next_task_instance(&store, &task, &reopen, today)
+            .await?
+            .is_none());
+        let next = next_task_instance(&store, &task, &complete, today)
+            .await?
+            .expect("repeats")
+            .build()
+            .unwrap();
+        assert_eq!(next.due_date, chrono::NaiveDate::from_ymd_opt(2024, 1, 8));
+        assert_eq!(next.status.as_deref(), Some("todo"));
+
+        // not again, once it exists
+        executor
+            .handle(task_model(
+                TASK,
+                "$kitchen2",
+                json!({
+                    "title": "Clean the kitchen",
+                    "m.relates_to": belongs_to("$list"),
+                    "due_date": "2024-01-08",
+                    "previous_instance": "$kitchen",
+                }),
+            )?)
+            .await?;
+        assert!(next_task_instance(&store, &task, &complete, today)
+            .await?
+            .is_none());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn tasks_listed_per_workflow_state() -> Result<()> {
+        let _ = env_logger::try_init();
+        let executor = fresh_executor().await?;
+
+        executor
+            .handle(task_model(
+                TASK_LIST,
+                "$list",
+                json!({
+                    "name": "Board",
+                    "workflow": [
+                        { "key": "todo", "name": "Todo" },
+                        { "key": "done", "name": "Done", "is_done": true },
+                    ],
+                }),
+            )?)
+            .await?;
+        executor
+            .handle(task_model(
+                TASK,
+                "$task",
+                json!({
+                    "title": "Card",
+                    "m.relates_to": belongs_to("$list"),
+                    "status": "todo",
+                }),
+            )?)
+            .await?;
+
+        let AnyActerModel::TaskList(list) =
+            executor.store().get(&event_id!("$list").to_owned()).await?
+        else {
+            panic!("List is not a task list");
+        };
+        assert!(list.workflow_state("done").unwrap().is_done());
+        let store = executor.store();
+        let todo_key = list.status_key("todo".to_owned());
+        let done_key = list.status_key("done".to_owned());
+        assert_eq!(store.get_list(&todo_key).await?.count(), 1);
+        assert_eq!(store.get_list(&done_key).await?.count(), 0);
+
+        executor
+            .handle(task_model(
+                TASK_UPDATE,
+                "$move",
+                json!({
+                    "m.relates_to": { "rel_type": "global.acter.dev.update", "event_id": "$task" },
+                    "status": "done",
+                }),
+            )?)
+            .await?;
+
+        let AnyActerModel::Task(task) = store.get(&event_id!("$task").to_owned()).await? else {
+            panic!("Task is not a task");
+        };
+        assert_eq!(task.status.as_deref(), Some("done"));
+        assert_eq!(store.get_list(&todo_key).await?.count(), 0);
+        assert_eq!(store.get_list(&done_key).await?.count(), 1);
+        // the done state counts, without progress set
+        assert!(task.is_done());
+
+        // and stops counting once the workflow changes
+        executor
+            .handle(task_model(
+                "global.acter.dev.tasklist.update",
+                "$rework",
+                json!({
+                    "m.relates_to": { "rel_type": "global.acter.dev.update", "event_id": "$list" },
+                    "workflow": [
+                        { "key": "todo", "name": "Todo" },
+                        { "key": "done", "name": "Reviewed" },
+                    ],
+                }),
+            )?)
+            .await?;
+        let AnyActerModel::Task(task) = store.get(&event_id!("$task").to_owned()).await? else {
+            panic!("Task is not a task");
+        };
+        assert!(!task.is_done());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn comment_mentions_are_listed() -> Result<()> {
+        let _ = env_logger::try_init();
+        let executor = fresh_executor().await?;
+        let store = executor.store().clone();
+        let model = TestModelBuilder::default().simple().build().unwrap();
+        let parent_id = model.event_id().to_owned();
+        executor.handle(model.into()).await?;
+
+        let comment = |sender: &UserId, mention: &UserId| Comment {
+            inner: CommentBuilder::default()
+                .on(parent_id.clone())
+                .content(TextMessageEventContent::plain("Have a look"))
+                .add_mention(mention.to_owned())
+                .build()
+                .unwrap(),
+            meta: EventMeta {
+                sender: sender.to_owned(),
+                ..TestModelBuilder::fake_meta()
+            },
+        };
+        let me = user_id!("@test:example.org");
+        let mentioning = comment(user_id!("@alice:example.org"), me);
+        let mentioning_id = mentioning.event_id().to_owned();
+        executor.handle(mentioning.into()).await?;
+        // mentioning ourselves doesn’t count
+        executor.handle(comment(me, me).into()).await?;
+        executor
+            .handle(comment(user_id!("@alice:example.org"), user_id!("@bob:example.org")).into())
+            .await?;
+
+        let mentions_key = IndexKey::Special(SpecialListsIndex::MyMentions);
+        let mentions = store.get_list(&mentions_key).await?.collect::<Vec<_>>();
+        assert_eq!(mentions.len(), 1);
+        assert_eq!(mentions[0].event_id().to_owned(), mentioning_id);
+
+        let activity = Activity::for_acter_model(&store, mentions[0].clone()).await?;
+        assert!(activity.mentions_user(me));
+        assert!(!activity.mentions_user(user_id!("@bob:example.org")));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn pin_edit_history() -> Result<()> {
+        let _ = env_logger::try_init();
+        let executor = fresh_executor().await?;
+        let store = executor.store().clone();
+        let relates_to = json!({"rel_type": "global.acter.dev.update", "event_id": "$pin"});
+
+        executor
+            .handle(acter_model(
+                "global.acter.dev.pin",
+                "$pin",
+                "@alice:example.org",
+                1_000,
+                json!({"title": "Seats"}),
+            )?)
+            .await?;
+        executor
+            .handle(acter_model(
+                "global.acter.dev.pin.update",
+                "$retitle",
+                "@bob:example.org",
+                2_000,
+                json!({"m.relates_to": relates_to, "title": "Seat plan"}),
+            )?)
+            .await?;
+        executor
+            .handle(acter_model(
+                "global.acter.dev.pin.update",
+                "$link",
+                "@alice:example.org",
+                3_000,
+                json!({"m.relates_to": relates_to, "url": "https://acter.global"}),
+            )?)
+            .await?;
+
+        let history = EditHistory::load(&store, event_id!("$pin")).await?;
+        let entries = history.entries();
+        assert_eq!(entries.len(), 3);
+        assert!(entries[0].is_creation());
+
+        let retitle = &entries[1];
+        assert_eq!(retitle.sender(), user_id!("@bob:example.org"));
+        assert_eq!(retitle.changes().len(), 1);
+        assert_eq!(retitle.changes()[0].field, "title");
+        assert_eq!(retitle.changes()[0].old_value, Some(json!("Seats")));
+        assert_eq!(retitle.changes()[0].new_value, Some(json!("Seat plan")));
+
+        let link = &entries[2];
+        assert_eq!(link.changes()[0].field, "url");
+        assert_eq!(link.changes()[0].old_value, None);
+
+        let Some(AnyActerModel::Pin(as_of)) = history.state_as_of(event_id!("$retitle")) else {
+            panic!("no pin state as of the retitling");
+        };
+        assert_eq!(as_of.title(), "Seat plan");
+        assert!(!as_of.is_link());
+        assert!(history.is_complete());
+
+        // pins from before the original state was kept only start at the latest edit
+        let original_key =
+            ExecuteReference::ModelParam(event_id!("$pin").to_owned(), ModelParam::OriginalState)
+                .as_storage_key();
+        let original: AnyActerModel = store.get_raw(&original_key).await?;
+        store.delete_key(&original_key).await?;
+        let partial = EditHistory::load(&store, event_id!("$pin")).await?;
+        assert!(!partial.is_complete());
+        assert_eq!(partial.entries().len(), 1);
+        assert_eq!(partial.entries()[0].event_id(), event_id!("$link"));
+
+        // and the original state is gone with the pin
+        store.set_raw(&original_key, &original).await?;
+        let redaction: UnsignedRoomRedactionEvent = from_value(json!({
+            "event_id": "$redaction",
+            "sender": "@alice:example.org",
+            "origin_server_ts": 4_000,
+            "content": { "redacts": "$pin" },
+        }))?;
+        let pin = store.get(&event_id!("$pin").to_owned()).await?;
+        executor
+            .redact("pin".to_owned(), pin.event_meta().clone(), redaction)
+            .await?;
+        assert!(store.get_raw::<AnyActerModel>(&original_key).await.is_err());
+        assert!(EditHistory::load(&store, event_id!("$pin")).await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn older_updates_arriving_late_are_not_applied() -> Result<()> {
+        let _ = env_logger::try_init();
+        let executor = fresh_executor().await?;
+        let store = executor.store().clone();
+        let pin_id = event_id!("$pin").to_owned();
+        let relates_to = json!({"rel_type": "global.acter.dev.update", "event_id": "$pin"});
+        let retitle = |event_id: &str, ts: u64, title: &str| {
+            acter_model(
+                "global.acter.dev.pin.update",
+                event_id,
+                "@alice:example.org",
+                ts,
+                json!({"m.relates_to": relates_to, "title": title}),
+            )
+        };
+
+        // the recent history is loaded first, the update waits for its pin
+        executor
+            .handle(retitle("$recent", 3_000, "Recent")?)
+            .await?;
+        executor
+            .handle(acter_model(
+                "global.acter.dev.pin",
+                "$pin",
+                "@alice:example.org",
+                1_000,
+                json!({"title": "Seats"}),
+            )?)
+            .await?;
+        executor.handle(retitle("$older", 2_000, "Older")?).await?;
+
+        let AnyActerModel::Pin(pin) = store.get(&pin_id).await? else {
+            panic!("not a pin");
+        };
+        assert_eq!(pin.title(), "Recent");
+
+        // the older update is part of the history still, in order
+        let history = EditHistory::load(&store, &pin_id).await?;
+        let ids = history
+            .entries()
+            .iter()
+            .map(|e| e.event_id().as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, ["$pin", "$older", "$recent"]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn restricted_pin_ignores_other_editors() -> Result<()> {
+        let _ = env_logger::try_init();
+        let executor = fresh_executor().await?;
+        let relates_to = json!({"rel_type": "global.acter.dev.update", "event_id": "$pin"});
+        let retitle = |event_id: &str, sender: &str, ts: u64, title: &str| {
+            acter_model(
+                "global.acter.dev.pin.update",
+                event_id,
+                sender,
+                ts,
+                json!({"m.relates_to": relates_to, "title": title}),
+            )
+        };
+
+        executor
+            .handle(acter_model(
+                "global.acter.dev.pin",
+                "$pin",
+                "@alice:example.org",
+                1_000,
+                json!({
+                    "title": "Seats",
+                    "edit_rule": {
+                        "rule": "author_and_moderators",
+                        "moderators": ["@mod:example.org"],
+                    },
+                }),
+            )?)
+            .await?;
+        let pin_id = event_id!("$pin").to_owned();
+        async fn title(executor: &Executor, pin_id: &OwnedEventId) -> Result<String> {
+            let AnyActerModel::Pin(pin) = executor.store().get(pin_id).await? else {
+                panic!("not a pin");
+            };
+            Ok(pin.title())
+        }
+
+        executor
+            .handle(retitle("$other", "@bob:example.org", 2_000, "Bob’s seats")?)
+            .await?;
+        assert_eq!(title(&executor, &pin_id).await?, "Seats");
+        // dropped before being stored, so it isn’t in the history either
+        assert!(executor
+            .store()
+            .get(&event_id!("$other").to_owned())
+            .await
+            .is_err());
+
+        executor
+            .handle(retitle("$mod", "@mod:example.org", 3_000, "Seat plan")?)
+            .await?;
+        assert_eq!(title(&executor, &pin_id).await?, "Seat plan");
+
+        executor
+            .handle(retitle(
+                "$author",
+                "@alice:example.org",
+                4_000,
+                "Final seats",
+            )?)
+            .await?;
+        assert_eq!(title(&executor, &pin_id).await?, "Final seats");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn restricted_task_list_ignores_other_editors() -> Result<()> {
+        let _ = env_logger::try_init();
+        let executor = fresh_executor().await?;
+        let store = executor.store().clone();
+        let on_task = json!({ "rel_type": "global.acter.dev.update", "event_id": "$task" });
+        executor
+            .handle(acter_model(
+                TASK_LIST,
+                "$list",
+                "@alice:example.org",
+                1_000,
+                json!({
+                    "name": "Board only",
+                    "edit_rule": {
+                        "rule": "author_and_moderators",
+                        "moderators": ["@mod:example.org"],
+                    },
+                }),
+            )?)
+            .await?;
+
+        // only those allowed may add tasks
+        executor
+            .handle(acter_model(
+                TASK,
+                "$intruder",
+                "@bob:example.org",
+                2_000,
+                json!({ "title": "Bob’s task", "m.relates_to": belongs_to("$list") }),
+            )?)
+            .await?;
+        assert!(store.get(&event_id!("$intruder").to_owned()).await.is_err());
+        executor
+            .handle(acter_model(
+                TASK,
+                "$task",
+                "@mod:example.org",
+                3_000,
+                json!({ "title": "Agenda", "m.relates_to": belongs_to("$list") }),
+            )?)
+            .await?;
+        let AnyActerModel::TaskList(list) = store.get(&event_id!("$list").to_owned()).await? else {
+            panic!("not a task list");
+        };
+        assert_eq!(store.get_list(&list.tasks_key()).await?.count(), 1);
+
+        // and update them
+        executor
+            .handle(acter_model(
+                TASK_UPDATE,
+                "$retitle",
+                "@bob:example.org",
+                4_000,
+                json!({ "m.relates_to": on_task, "title": "Bob’s agenda" }),
+            )?)
+            .await?;
+        executor
+            .handle(acter_model(
+                "global.acter.dev.task.self_assign",
+                "$assign",
+                "@bob:example.org",
+                5_000,
+                json!({ "m.relates_to": belongs_to("$task") }),
+            )?)
+            .await?;
+        assert!(store.get(&event_id!("$retitle").to_owned()).await.is_err());
+        assert!(store.get(&event_id!("$assign").to_owned()).await.is_err());
+        let AnyActerModel::Task(task) = store.get(&event_id!("$task").to_owned()).await? else {
+            panic!("not a task");
+        };
+        assert_eq!(task.title(), "Agenda");
+        assert!(task.assignees().is_empty());
+        assert!(!task.can_be_edited_by(user_id!("@bob:example.org"), false));
+
+        executor
+            .handle(acter_model(
+                TASK_UPDATE,
+                "$author",
+                "@alice:example.org",
+                6_000,
+                json!({ "m.relates_to": on_task, "title": "Final agenda" }),
+            )?)
+            .await?;
+        let AnyActerModel::Task(task) = store.get(&event_id!("$task").to_owned()).await? else {
+            panic!("not a task");
+        };
+        assert_eq!(task.title(), "Final agenda");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn restricted_poll_ignores_other_editors() -> Result<()> {
+        let _ = env_logger::try_init();
+        let executor = fresh_executor().await?;
+        let store = executor.store().clone();
+        let on_poll = json!({"rel_type": "global.acter.dev.update", "event_id": "$poll"});
+        executor
+            .handle(acter_model(
+                "global.acter.dev.poll",
+                "$poll",
+                "@alice:example.org",
+                1_000,
+                json!({
+                    "title": "Lunch?",
+                    "edit_rule": { "rule": "author_and_moderators" },
+                }),
+            )?)
+            .await?;
+        executor
+            .handle(acter_model(
+                "global.acter.dev.poll.update",
+                "$other",
+                "@bob:example.org",
+                2_000,
+                json!({"m.relates_to": on_poll, "title": "Dinner?"}),
+            )?)
+            .await?;
+        assert!(store.get(&event_id!("$other").to_owned()).await.is_err());
+        let AnyActerModel::Poll(poll) = store.get(&event_id!("$poll").to_owned()).await? else {
+            panic!("not a poll");
+        };
+        assert_eq!(poll.title(), "Lunch?");
+        assert!(!poll.can_be_edited_by(user_id!("@bob:example.org"), false));
+        assert!(poll.can_be_edited_by(user_id!("@alice:example.org"), false));
+        Ok(())
+    }
 }
@@ -0,0 +1,106 @@
+use std::{collections::BTreeMap, time::Duration};
+
+use matrix_sdk_base::ruma::{EventId, MilliSecondsSinceUnixEpoch, OwnedEventId};
+use serde::{Deserialize, Serialize};
+
+use crate::models::{ActerModel, AnyActerModel};
+
+/// A model that couldn’t be applied as the model it refers to isn’t known yet
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct PendingModel {
+    model: AnyActerModel,
+    queued_at: MilliSecondsSinceUnixEpoch,
+}
+
+/// Models waiting for the parent they reference, by the event id of that parent
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct PendingOnParent {
+    by_parent: BTreeMap<OwnedEventId, Vec<PendingModel>>,
+}
+
+impl PendingOnParent {
+    pub fn len(&self) -> usize {
+        self.by_parent.values().map(Vec::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_parent.is_empty()
+    }
+
+    /// Queue the model until `parent` arrives, replacing a previously queued
+    /// version of it
+    pub fn push(
+        &mut self,
+        parent: OwnedEventId,
+        model: AnyActerModel,
+        now: MilliSecondsSinceUnixEpoch,
+    ) {
+        self.remove(model.event_id());
+        self.by_parent
+            .entry(parent)
+            .or_default()
+            .push(PendingModel {
+                model,
+                queued_at: now,
+            });
+    }
+
+    /// The models waiting for `parent`, in the order they were queued
+    pub fn take(&mut self, parent: &OwnedEventId) -> Vec<AnyActerModel> {
+        self.by_parent
+            .remove(parent)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|p| p.model)
+            .collect()
+    }
+
+    fn remove(&mut self, event_id: &EventId) {
+        self.by_parent.retain(|_, models| {
+            models.retain(|p| p.model.event_id() != event_id);
+            !models.is_empty()
+        });
+    }
+
+    /// Drop everything queued longer than `max_age` ago, returns how many
+    pub fn expire(&mut self, now: MilliSecondsSinceUnixEpoch, max_age: Duration) -> usize {
+        let before = self.len();
+        let max_age = u64::try_from(max_age.as_millis()).unwrap_or(u64::MAX);
+        let cutoff = u64::from(now.get()).saturating_sub(max_age);
+        self.by_parent.retain(|_, models| {
+            models.retain(|p| u64::from(p.queued_at.get()) >= cutoff);
+            !models.is_empty()
+        });
+        before - self.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::TestModelBuilder;
+    use matrix_sdk_base::ruma::{event_id, UInt};
+
+    fn at(millis: u32) -> MilliSecondsSinceUnixEpoch {
+        MilliSecondsSinceUnixEpoch(UInt::from(millis))
+    }
+
+    #[test]
+    fn requeue_replaces_and_expires() {
+        let parent = event_id!("$parent").to_owned();
+        let other_parent = event_id!("$other").to_owned();
+        let model = AnyActerModel::from(TestModelBuilder::default().simple().build().unwrap());
+        let mut pending = PendingOnParent::default();
+
+        pending.push(parent.clone(), model.clone(), at(1_000));
+        // waiting on a different parent now
+        pending.push(other_parent.clone(), model, at(5_000));
+        assert_eq!(pending.len(), 1);
+        assert!(pending.take(&parent).is_empty());
+
+        assert_eq!(pending.expire(at(8_000), Duration::from_secs(5)), 0);
+        assert_eq!(pending.expire(at(12_000), Duration::from_secs(5)), 1);
+        assert!(pending.is_empty());
+        assert!(pending.take(&other_parent).is_empty());
+    }
+}
@@ -0,0 +1,284 @@
+use matrix_sdk_base::ruma::{EventId, MilliSecondsSinceUnixEpoch, OwnedEventId, OwnedUserId};
+use serde_json::{Map, Value};
+use std::collections::BTreeSet;
+
+use crate::{
+    client::CoreClient,
+    models::{ActerModel, AnyActerModel, EventMeta},
+    referencing::{ExecuteReference, IndexKey, ModelParam},
+    store::Store,
+    Error, Result,
+};
+
+type Fields = Map<String, Value>;
+
+/// A field changed by an edit, with the values as found in the event content
+#[derive(Clone, Debug, PartialEq)]
+pub struct FieldChange {
+    pub field: String,
+    /// `None` if the field wasn’t set before
+    pub old_value: Option<Value>,
+    /// `None` if the field was removed
+    pub new_value: Option<Value>,
+}
+
+impl FieldChange {
+    pub fn field(&self) -> String {
+        self.field.clone()
+    }
+
+    pub fn old_value_json(&self) -> Option<String> {
+        self.old_value.as_ref().map(ToString::to_string)
+    }
+
+    pub fn new_value_json(&self) -> Option<String> {
+        self.new_value.as_ref().map(ToString::to_string)
+    }
+}
+
+/// The creation of an object or one of its updates
+#[derive(Clone, Debug)]
+pub struct HistoryEntry {
+    meta: EventMeta,
+    changes: Vec<FieldChange>,
+    /// the object right after this entry was applied
+    state: AnyActerModel,
+}
+
+impl HistoryEntry {
+    pub fn event_id(&self) -> &EventId {
+        &self.meta.event_id
+    }
+
+    pub fn sender(&self) -> OwnedUserId {
+        self.meta.sender.clone()
+    }
+
+    pub fn origin_server_ts(&self) -> MilliSecondsSinceUnixEpoch {
+        self.meta.origin_server_ts
+    }
+
+    /// Whether this is the creation of the object rather than an update
+    pub fn is_creation(&self) -> bool {
+        self.state.event_id() == self.event_id()
+    }
+
+    pub fn changes(&self) -> &[FieldChange] {
+        &self.changes
+    }
+
+    pub fn state(&self) -> &AnyActerModel {
+        &self.state
+    }
+}
+
+/// The ordered edits of a pin, task or calendar event
+#[derive(Clone, Debug)]
+pub struct EditHistory {
+    object_id: OwnedEventId,
+    /// oldest first, starting with the creation
+    entries: Vec<HistoryEntry>,
+    /// false if the original state isn’t known and the history starts at
+    /// the latest edit only
+    complete: bool,
+}
+
+impl EditHistory {
+    pub async fn load(store: &Store, object_id: &EventId) -> Result<EditHistory> {
+        let object_id = object_id.to_owned();
+        let current = store.get(&object_id).await?;
+        // bails for unsupported objects
+        state_fields(&current)?;
+
+        let mut history = store
+            .get_list(&IndexKey::ObjectHistory(object_id.clone()))
+            .await?
+            .filter(|m| *m.event_id() != *object_id)
+            .collect::<Vec<_>>();
+        // the index is latest first
+        history.reverse();
+
+        let original = match store.get_raw(&original_state_key(object_id.clone())).await {
+            Ok(original) => original,
+            Err(_) => {
+                // objects created before we kept their original state: unless it
+                // was never edited, all we know is how it looks after the latest edit
+                let mut latest_edit = None;
+                for model in &history {
+                    if current.clone().transition(model)? {
+                        latest_edit = Some(model.event_meta().clone());
+                    }
+                }
+                let Some(meta) = latest_edit else {
+                    return Self::from_original(object_id, current, history);
+                };
+                let fields = state_fields(&current)?;
+                return Ok(EditHistory {
+                    object_id,
+                    entries: vec![HistoryEntry {
+                        meta,
+                        changes: diff(&Fields::new(), &fields),
+                        state: current,
+                    }],
+                    complete: false,
+                });
+            }
+        };
+
+        Self::from_original(object_id, original, history)
+    }
+
+    /// Replay the updates on the object as created
+    fn from_original(
+        object_id: OwnedEventId,
+        mut state: AnyActerModel,
+        history: Vec<AnyActerModel>,
+    ) -> Result<EditHistory> {
+        let mut fields = state_fields(&state)?;
+        let mut entries = vec![HistoryEntry {
+            meta: state.event_meta().clone(),
+            changes: diff(&Fields::new(), &fields),
+            state: state.clone(),
+        }];
+        for model in history {
+            if !state.transition(&model)? {
+                // comments, reactions and the like don’t change the object
+                continue;
+            }
+            let new_fields = state_fields(&state)?;
+            entries.push(HistoryEntry {
+                meta: model.event_meta().clone(),
+                changes: diff(&fields, &new_fields),
+                state: state.clone(),
+            });
+            fields = new_fields;
+        }
+
+        Ok(EditHistory {
+            object_id,
+            entries,
+            complete: true,
+        })
+    }
+
+    pub fn object_id(&self) -> &EventId {
+        &self.object_id
+    }
+
+    pub fn entries(&self) -> &[HistoryEntry] {
+        &self.entries
+    }
+
+    /// Whether the entries go back to the creation of the object
+    pub fn is_complete(&self) -> bool {
+        self.complete
+    }
+
+    /// The object as it was right after the given creation or update
+    pub fn state_as_of(&self, event_id: &EventId) -> Option<&AnyActerModel> {
+        self.entries
+            .iter()
+            .find(|e| e.event_id() == event_id)
+            .map(|e| &e.state)
+    }
+}
+
+fn original_state_key(object_id: OwnedEventId) -> String {
+    ExecuteReference::ModelParam(object_id, ModelParam::OriginalState).as_storage_key()
+}
+
+/// Keep the object as it was created, to replay its edits on later
+pub(crate) async fn keep_original_state(store: &Store, model: &AnyActerModel) -> Result<()> {
+    store
+        .set_raw(&original_state_key(model.event_id().to_owned()), model)
+        .await
+}
+
+/// Forget the original state once the object is redacted or removed
+pub(crate) async fn drop_original_state(store: &Store, object_id: &EventId) -> Result<()> {
+    store
+        .delete_key(&original_state_key(object_id.to_owned()))
+        .await
+}
+
+/// The fields of the object as serialized, without the event metadata
+fn state_fields(model: &AnyActerModel) -> Result<Fields> {
+    let value = match model {
+        AnyActerModel::Pin(m) => serde_json::to_value(m)?,
+        AnyActerModel::Task(m) => serde_json::to_value(m)?,
+        AnyActerModel::CalendarEvent(m) => serde_json::to_value(m)?,
+        _ => {
+            return Err(Error::Custom(format!(
+                "{} has no edit history",
+                model.event_id()
+            )))
+        }
+    };
+    let Value::Object(mut fields) = value else {
+        return Err(Error::Custom(
+            "Model isn’t serialized as an object".to_owned(),
+        ));
+    };
+    fields.remove("meta");
+    if let Some(Value::Object(inner)) = fields.remove("inner") {
+        fields.extend(inner);
+    }
+    Ok(fields)
+}
+
+fn diff(before: &Fields, after: &Fields) -> Vec<FieldChange> {
+    let keys = before.keys().chain(after.keys()).collect::<BTreeSet<_>>();
+    keys.into_iter()
+        .filter_map(|field| {
+            let old_value = before.get(field);
+            let new_value = after.get(field);
+            (old_value != new_value).then(|| FieldChange {
+                field: field.clone(),
+                old_value: old_value.cloned(),
+                new_value: new_value.cloned(),
+            })
+        })
+        .collect()
+}
+
+impl CoreClient {
+    pub async fn edit_history(&self, object_id: &EventId) -> Result<EditHistory> {
+        EditHistory::load(&self.store, object_id).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn diff_reports_set_changed_and_removed_fields() {
+        let before = json!({"title": "Seats", "url": "https://acter.global"});
+        let after = json!({"title": "Seat plan", "display": {"color": 1}});
+        let (Value::Object(before), Value::Object(after)) = (before, after) else {
+            unreachable!()
+        };
+        let changes = diff(&before, &after);
+        assert_eq!(
+            changes,
+            vec![
+                FieldChange {
+                    field: "display".to_owned(),
+                    old_value: None,
+                    new_value: Some(json!({"color": 1})),
+                },
+                FieldChange {
+                    field: "title".to_owned(),
+                    old_value: Some(json!("Seats")),
+                    new_value: Some(json!("Seat plan")),
+                },
+                FieldChange {
+                    field: "url".to_owned(),
+                    old_value: Some(json!("https://acter.global")),
+                    new_value: None,
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,512 @@
+//! Mapping between [iCalendar](https://www.rfc-editor.org/rfc/rfc5545) data
+//! and our calendar events and tasks.
+//!
+//! All times are converted to UTC. Values with a `TZID` are read in that time
+//! zone, floating times are taken as UTC. Unknown time zones fail the item.
+
+use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Timelike};
+use chrono_tz::Tz;
+use icalendar::{
+    Calendar, CalendarComponent, Component, Event as iCalEvent, Property, Todo as iCalTodo,
+};
+use matrix_sdk_base::ruma::{events::room::message::TextMessageEventContent, UserId};
+use std::str::FromStr;
+
+use crate::{
+    events::{
+        calendar::{
+            parse_ical_date_time, CalendarEventBuilder, RecurrenceOverride, RecurrenceRule,
+        },
+        rsvp::RsvpStatus,
+        tasks::{Priority, TaskBuilder},
+        UtcDateTime,
+    },
+    Error, Result,
+};
+
+/// What an iCalendar component maps onto
+#[derive(Clone, Debug)]
+pub enum IcalContent {
+    CalendarEvent(CalendarEventBuilder),
+    /// the `task_list_id` still needs to be set before building
+    Task(TaskBuilder),
+}
+
+/// A single `VEVENT` or `VTODO` of the iCalendar data
+#[derive(Debug)]
+pub struct IcalItem {
+    pub uid: Option<String>,
+    pub summary: Option<String>,
+    /// the mapped content or why it couldn’t be mapped
+    pub content: Result<IcalContent>,
+}
+
+/// Parsed iCalendar data
+#[derive(Debug)]
+pub struct IcalData {
+    /// the name of the calendar as given by `X-WR-CALNAME`
+    pub name: Option<String>,
+    pub items: Vec<IcalItem>,
+}
+
+impl IcalData {
+    pub fn has_tasks(&self) -> bool {
+        self.items
+            .iter()
+            .any(|i| matches!(i.content, Ok(IcalContent::Task(_))))
+    }
+}
+
+/// Parse the iCalendar data
+///
+/// Events carrying a `RECURRENCE-ID` are folded into the recurring event with
+/// the same `UID`, all other components than `VEVENT` and `VTODO` are ignored.
+pub fn parse_ical(data: &str) -> Result<IcalData> {
+    let calendar = Calendar::from_str(data).map_err(|msg| Error::FailedToParse {
+        model_type: "iCalendar".to_owned(),
+        msg,
+    })?;
+    let name = calendar
+        .properties
+        .iter()
+        .find(|p| p.key() == "X-WR-CALNAME")
+        .map(|p| p.value().to_owned());
+
+    let mut items: Vec<IcalItem> = vec![];
+    let mut overrides = vec![];
+    for component in calendar.components.iter() {
+        match component {
+            CalendarComponent::Event(event) if event.property_value("RECURRENCE-ID").is_some() => {
+                overrides.push(event)
+            }
+            CalendarComponent::Event(event) => items.push(IcalItem {
+                uid: event.get_uid().map(ToOwned::to_owned),
+                summary: event.get_summary().map(ToOwned::to_owned),
+                content: calendar_event_from_ical(event).map(IcalContent::CalendarEvent),
+            }),
+            CalendarComponent::Todo(todo) => items.push(IcalItem {
+                uid: todo.get_uid().map(ToOwned::to_owned),
+                summary: todo.get_summary().map(ToOwned::to_owned),
+                content: task_from_ical(todo).map(IcalContent::Task),
+            }),
+            _ => {} // timezones and alike
+        }
+    }
+
+    for event in overrides {
+        let uid = event.get_uid();
+        let res = recurrence_override_from_ical(event).and_then(|overridden| {
+            let Some(Ok(IcalContent::CalendarEvent(builder))) = items
+                .iter_mut()
+                .find(|i| i.uid.is_some() && i.uid.as_deref() == uid)
+                .map(|i| i.content.as_mut())
+            else {
+                return Err(Error::Custom(
+                    "No recurring event found for this RECURRENCE-ID".to_owned(),
+                ));
+            };
+            builder.override_occurrence(overridden);
+            Ok(())
+        });
+        if let Err(error) = res {
+            items.push(IcalItem {
+                uid: uid.map(ToOwned::to_owned),
+                summary: event.get_summary().map(ToOwned::to_owned),
+                content: Err(error),
+            });
+        }
+    }
+
+    Ok(IcalData { name, items })
+}
+
+/// Build the calendar of the given events and todos to be subscribed to
+pub fn ical_feed(name: &str, components: Vec<CalendarComponent>) -> Calendar {
+    let mut calendar = Calendar::new();
+    calendar.append_property(Property::new("X-WR-CALNAME", name));
+    for component in components {
+        calendar.push(component);
+    }
+    calendar.done()
+}
+
+/// The `PARTSTAT` of an attendee having responded with `status`
+pub fn rsvp_partstat(status: &RsvpStatus) -> &'static str {
+    match status {
+        RsvpStatus::Yes => "ACCEPTED",
+        RsvpStatus::Maybe => "TENTATIVE",
+        RsvpStatus::No => "DECLINED",
+    }
+}
+
+/// Add the user as `ATTENDEE` of the event, as per their RSVP
+pub fn add_attendee(event: &mut iCalEvent, user_id: &UserId, status: &RsvpStatus) {
+    event.append_multi_property(
+        Property::new("ATTENDEE", user_id.matrix_uri(false).to_string())
+            .add_parameter("CN", user_id.as_str())
+            .add_parameter("PARTSTAT", rsvp_partstat(status))
+            .done(),
+    );
+}
+
+/// A date or date-time value
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum IcalTime {
+    Date(NaiveDate),
+    DateTime(UtcDateTime),
+}
+
+impl IcalTime {
+    /// Parse the value, as local time of `tzid` if given and not in UTC already
+    fn parse(value: &str, tzid: Option<&str>) -> Result<Self> {
+        let value = value.trim();
+        if value.len() == 8 {
+            let date =
+                NaiveDate::parse_from_str(value, "%Y%m%d").map_err(|e| Error::FailedToParse {
+                    model_type: "iCalendar date".to_owned(),
+                    msg: e.to_string(),
+                })?;
+            return Ok(IcalTime::Date(date));
+        }
+        let Some(tzid) = tzid.filter(|_| !value.ends_with('Z')) else {
+            return Ok(IcalTime::DateTime(parse_ical_date_time(value)?));
+        };
+        let tz = tzid.parse::<Tz>().map_err(|e| Error::FailedToParse {
+            model_type: "iCalendar TZID".to_owned(),
+            msg: format!("unknown time zone `{tzid}`: {e}"),
+        })?;
+        let local = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").map_err(|e| {
+            Error::FailedToParse {
+                model_type: "iCalendar date-time".to_owned(),
+                msg: format!("`{value}`: {e}"),
+            }
+        })?;
+        // times skipped by a DST change don’t exist, ambiguous ones take the first
+        let in_tz = tz
+            .from_local_datetime(&local)
+            .earliest()
+            .or_else(|| {
+                tz.from_local_datetime(&(local + Duration::hours(1)))
+                    .earliest()
+            })
+            .ok_or_else(|| Error::FailedToParse {
+                model_type: "iCalendar date-time".to_owned(),
+                msg: format!("`{value}` doesn’t exist in {tzid}"),
+            })?;
+        Ok(IcalTime::DateTime(in_tz.to_utc()))
+    }
+
+    fn from_property(property: &Property) -> Result<Self> {
+        IcalTime::parse(property.value(), tzid(property))
+    }
+
+    fn as_utc(&self) -> UtcDateTime {
+        match self {
+            IcalTime::Date(date) => date.and_time(NaiveTime::MIN).and_utc(),
+            IcalTime::DateTime(dt) => *dt,
+        }
+    }
+}
+
+fn tzid(property: &Property) -> Option<&str> {
+    property.params().get("TZID").map(|p| p.value())
+}
+
+/// All properties of that name, whether they were parsed as single or multi ones
+fn all_properties<'a>(component: &'a impl Component, key: &str) -> Vec<&'a Property> {
+    component
+        .properties()
+        .get(key)
+        .into_iter()
+        .chain(component.multi_properties().get(key).into_iter().flatten())
+        .collect()
+}
+
+fn time_value(component: &impl Component, key: &str) -> Result<Option<IcalTime>> {
+    component
+        .properties()
+        .get(key)
+        .map(IcalTime::from_property)
+        .transpose()
+}
+
+/// The comma separated values of all properties of that name
+fn list_value(component: &impl Component, key: &str) -> Vec<String> {
+    all_properties(component, key)
+        .into_iter()
+        .flat_map(|p| p.value().split(','))
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(ToOwned::to_owned)
+        .collect()
+}
+
+/// The times of all properties of that name, each may hold a list
+fn time_list_value(component: &impl Component, key: &str) -> Result<Vec<IcalTime>> {
+    let mut times = vec![];
+    for property in all_properties(component, key) {
+        for value in property.value().split(',').filter(|s| !s.trim().is_empty()) {
+            times.push(IcalTime::parse(value, tzid(property))?);
+        }
+    }
+    Ok(times)
+}
+
+/// Map a `VEVENT` onto a calendar event
+///
+/// All-day events (with date-only values) are shown without time.
+pub fn calendar_event_from_ical(event: &iCalEvent) -> Result<CalendarEventBuilder> {
+    let title = event
+        .get_summary()
+        .ok_or_else(|| Error::MissingField("SUMMARY".to_owned()))?;
+    let start =
+        time_value(event, "DTSTART")?.ok_or_else(|| Error::MissingField("DTSTART".to_owned()))?;
+    let utc_start = start.as_utc();
+    let utc_end = match time_value(event, "DTEND")? {
+        Some(end) => end.as_utc(),
+        // all-day events without end last the one day
+        None if matches!(start, IcalTime::Date(_)) => utc_start + Duration::days(1),
+        None => utc_start,
+    };
+
+    let mut builder = CalendarEventBuilder::default();
+    builder
+        .title(title.to_owned())
+        .utc_start(utc_start)
+        .utc_end(utc_end)
+        .show_without_time(matches!(start, IcalTime::Date(_)))
+        .categories(list_value(event, "CATEGORIES"))
+        .ical_uid(event.get_uid().map(ToOwned::to_owned));
+
+    if let Some(description) = event.get_description() {
+        builder.description(TextMessageEventContent::plain(description));
+    }
+    if let Some(location) = event.property_value("LOCATION") {
+        builder.add_physical_location(Some(location.to_owned()), None, None, None, None, None);
+    }
+    if let Some(rrule) = event.property_value("RRULE") {
+        builder.recurrence_rule(RecurrenceRule::from_str(rrule)?);
+        for excluded in time_list_value(event, "EXDATE")? {
+            builder.exclude_occurrence(excluded.as_utc());
+        }
+    }
+    Ok(builder)
+}
+
+/// Map an event with `RECURRENCE-ID` onto the override of that occurrence
+fn recurrence_override_from_ical(event: &iCalEvent) -> Result<RecurrenceOverride> {
+    let recurrence_id = time_value(event, "RECURRENCE-ID")?
+        .ok_or_else(|| Error::MissingField("RECURRENCE-ID".to_owned()))?
+        .as_utc();
+    Ok(RecurrenceOverride {
+        recurrence_id,
+        excluded: event.property_value("STATUS") == Some("CANCELLED"),
+        utc_start: time_value(event, "DTSTART")?.map(|t| t.as_utc()),
+        utc_end: time_value(event, "DTEND")?.map(|t| t.as_utc()),
+        title: event.get_summary().map(ToOwned::to_owned),
+    })
+}
+
+/// Map a `VTODO` onto a task
+///
+/// Completed todos are fully progressed.
+pub fn task_from_ical(todo: &iCalTodo) -> Result<TaskBuilder> {
+    let title = todo
+        .get_summary()
+        .ok_or_else(|| Error::MissingField("SUMMARY".to_owned()))?;
+
+    let mut builder = TaskBuilder::default();
+    builder
+        .title(title.to_owned())
+        .categories(list_value(todo, "CATEGORIES"))
+        .ical_uid(todo.get_uid().map(ToOwned::to_owned));
+
+    if let Some(description) = todo.get_description() {
+        builder.description(TextMessageEventContent::plain(description));
+    }
+    match time_value(todo, "DUE")? {
+        Some(IcalTime::Date(date)) => {
+            builder.due_date(date);
+        }
+        Some(IcalTime::DateTime(dt)) => {
+            builder
+                .due_date(dt.date_naive())
+                .utc_due_time_of_day(dt.num_seconds_from_midnight() as i32);
+        }
+        None => {}
+    }
+    if let Some(start) = time_value(todo, "DTSTART")? {
+        builder.utc_start(start.as_utc());
+    }
+
+    let completed = todo.property_value("STATUS") == Some("COMPLETED")
+        || todo.property_value("COMPLETED").is_some();
+    let progress_percent = if completed {
+        Some(100)
+    } else {
+        todo.property_value("PERCENT-COMPLETE")
+            .and_then(|p| p.trim().parse::<u8>().ok())
+            .map(|p| p.min(100))
+    };
+    builder.progress_percent(progress_percent);
+
+    let priority = match todo.property_value("PRIORITY").map(str::trim) {
+        Some("1") => Priority::Highest,
+        Some("2") => Priority::SecondHighest,
+        Some("3") => Priority::Three,
+        Some("4") => Priority::Four,
+        Some("5") => Priority::Five,
+        Some("6") => Priority::Six,
+        Some("7") => Priority::Seven,
+        Some("8") => Priority::SecondLowest,
+        Some("9") => Priority::Lowest,
+        _ => Priority::Undefined,
+    };
+    builder.priority(priority);
+    Ok(builder)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    use matrix_sdk_base::ruma::OwnedEventId;
+
+    const ICS: &str = "BEGIN:VCALENDAR\r
+VERSION:2.0\r
+PRODID:-//acter//test//EN\r
+X-WR-CALNAME:Team\r
+BEGIN:VEVENT\r
+UID:weekly@example.org\r
+SUMMARY:Standup\r
+DTSTART:20240108T090000Z\r
+DTEND:20240108T091500Z\r
+RRULE:FREQ=WEEKLY;BYDAY=MO\r
+EXDATE:20240115T090000Z\r
+EXDATE:20240129T090000Z,20240205T090000Z\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+UID:weekly@example.org\r
+RECURRENCE-ID:20240122T090000Z\r
+SUMMARY:Long Standup\r
+DTSTART:20240122T100000Z\r
+DTEND:20240122T110000Z\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+UID:holiday@example.org\r
+SUMMARY:Holiday\r
+DTSTART;VALUE=DATE:20240301\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+UID:berlin@example.org\r
+SUMMARY:Lunch in Berlin\r
+DTSTART;TZID=Europe/Berlin:20240701T120000\r
+DTEND;TZID=Europe/Berlin:20240701T130000\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+UID:nowhere@example.org\r
+SUMMARY:Lunch nowhere\r
+DTSTART;TZID=Middle/Nowhere:20240701T120000\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+UID:broken@example.org\r
+SUMMARY:No start\r
+END:VEVENT\r
+BEGIN:VTODO\r
+UID:todo@example.org\r
+SUMMARY:Prepare slides\r
+DUE:20240110T170000Z\r
+PRIORITY:1\r
+STATUS:COMPLETED\r
+END:VTODO\r
+END:VCALENDAR\r
+";
+
+    #[test]
+    fn parse_events_and_todos() -> Result<()> {
+        let data = parse_ical(ICS)?;
+        assert_eq!(data.name.as_deref(), Some("Team"));
+        assert_eq!(data.items.len(), 6);
+        assert!(data.has_tasks());
+
+        let Ok(IcalContent::CalendarEvent(standup)) = &data.items[0].content else {
+            panic!("standup not parsed: {:?}", data.items[0]);
+        };
+        let standup = standup.clone().build().expect("building works");
+        assert_eq!(standup.ical_uid.as_deref(), Some("weekly@example.org"));
+        assert_eq!(
+            standup.utc_start,
+            Utc.with_ymd_and_hms(2024, 1, 8, 9, 0, 0).unwrap()
+        );
+        assert!(standup.recurrence_rule.is_some());
+        // all EXDATE lines and all dates listed in them
+        assert_eq!(standup.recurrence_overrides.len(), 4);
+        assert!(standup.recurrence_overrides[..3].iter().all(|o| o.excluded));
+        assert_eq!(
+            standup.recurrence_overrides[2].recurrence_id,
+            Utc.with_ymd_and_hms(2024, 2, 5, 9, 0, 0).unwrap()
+        );
+        assert_eq!(
+            standup.recurrence_overrides[3].title.as_deref(),
+            Some("Long Standup")
+        );
+
+        let Ok(IcalContent::CalendarEvent(holiday)) = &data.items[1].content else {
+            panic!("holiday not parsed: {:?}", data.items[1]);
+        };
+        let holiday = holiday.clone().build().expect("building works");
+        assert!(holiday.show_without_time);
+        assert_eq!(holiday.utc_end - holiday.utc_start, Duration::days(1));
+
+        // summer time in Berlin is two hours ahead of UTC
+        let Ok(IcalContent::CalendarEvent(berlin)) = &data.items[2].content else {
+            panic!("berlin not parsed: {:?}", data.items[2]);
+        };
+        let berlin = berlin.clone().build().expect("building works");
+        assert_eq!(
+            berlin.utc_start,
+            Utc.with_ymd_and_hms(2024, 7, 1, 10, 0, 0).unwrap()
+        );
+        assert_eq!(berlin.utc_end - berlin.utc_start, Duration::hours(1));
+
+        // unknown time zones are reported, rather than guessed
+        assert_eq!(data.items[3].uid.as_deref(), Some("nowhere@example.org"));
+        assert!(data.items[3].content.is_err());
+
+        assert_eq!(data.items[4].uid.as_deref(), Some("broken@example.org"));
+        assert!(data.items[4].content.is_err());
+
+        let Ok(IcalContent::Task(todo)) = &data.items[5].content else {
+            panic!("todo not parsed: {:?}", data.items[5]);
+        };
+        let todo = todo
+            .clone()
+            .task_list_id("$tasklist:example.org".parse::<OwnedEventId>()?)
+            .build()
+            .expect("building works");
+        assert_eq!(todo.progress_percent, Some(100));
+        assert_eq!(todo.priority, Priority::Highest);
+        assert_eq!(todo.due_date, NaiveDate::from_ymd_opt(2024, 1, 10));
+        assert_eq!(todo.utc_due_time_of_day, Some(17 * 3600));
+        Ok(())
+    }
+
+    #[test]
+    fn feed_with_attendees() -> Result<()> {
+        let mut event = iCalEvent::new();
+        event.uid("event@example.org").summary("Meetup");
+        let alice = UserId::parse("@alice:example.org")?;
+        add_attendee(&mut event, &alice, &RsvpStatus::Maybe);
+
+        let feed = ical_feed("Team", vec![event.done().into()]).to_string();
+        assert!(feed.contains("X-WR-CALNAME:Team"));
+        assert!(feed.contains("PARTSTAT=TENTATIVE"));
+        assert!(feed.contains("matrix:u/alice:example.org"));
+
+        // and we can read it back
+        let data = parse_ical(&feed)?;
+        assert_eq!(data.name.as_deref(), Some("Team"));
+        assert_eq!(data.items[0].uid.as_deref(), Some("event@example.org"));
+        Ok(())
+    }
+}
@@ -11,7 +11,10 @@ pub mod client;
 pub mod error;
 pub mod events;
 pub mod executor;
+pub mod history;
+pub mod ical;
 pub mod models;
+pub mod outbox;
 pub mod push;
 pub mod referencing;
 pub mod share_link;
@@ -10,6 +10,7 @@ mod invites;
 mod meta;
 mod news;
 mod pins;
+mod polls;
 mod reactions;
 mod read_receipts;
 mod redaction;
@@ -23,25 +24,28 @@ mod test;
 
 pub use any::{ActerModel, AnyActerModel};
 pub use attachments::{Attachment, AttachmentUpdate, AttachmentsManager, AttachmentsStats};
-pub use calendar::{CalendarEvent, CalendarEventUpdate};
+pub use calendar::{CalendarEvent, CalendarEventOccurrence, CalendarEventUpdate, RecurrenceIter};
 pub use capabilities::Capability;
 pub use comments::{Comment, CommentUpdate, CommentsManager, CommentsStats};
 pub use common::*;
 pub use core::fmt::Debug;
-pub(crate) use execution::default_model_execute;
+pub use execution::may_edit;
+pub(crate) use execution::{default_model_execute, execute_update, is_stale_update};
 pub use invites::{InvitationsManager, InviteStats, MyInvitesManager};
-pub use meta::{can_redact, EventMeta};
+pub use meta::{can_redact, is_room_moderator, EventMeta};
 pub use news::{NewsEntry, NewsEntryUpdate};
 pub use pins::{Pin, PinUpdate};
+pub use polls::{Poll, PollUpdate, PollVote, PollVoteStats, PollVotesManager};
 pub use reactions::{Reaction, ReactionManager, ReactionStats};
 pub use read_receipts::{ReadReceipt, ReadReceiptStats, ReadReceiptsManager};
-pub use redaction::RedactedActerModel;
+pub use redaction::{RedactedActerModel, RedactionContent};
 pub use rsvp::{Rsvp, RsvpManager, RsvpStats};
 pub use status::{ActerSupportedRoomStatusEvents, RoomStatus};
 pub use stories::{Story, StoryUpdate};
 pub use tag::Tag;
 pub use tasks::{
-    Task, TaskList, TaskListUpdate, TaskSelfAssign, TaskSelfUnassign, TaskStats, TaskUpdate,
+    ensure_no_task_cycle, next_task_instance, Task, TaskAssign, TaskList, TaskListUpdate,
+    TaskSelfAssign, TaskSelfUnassign, TaskStats, TaskUnassign, TaskUpdate,
 };
 
 #[cfg(any(test, feature = "testing"))]
@@ -8,17 +8,21 @@ use super::invites::ExplicitInvite;
 use super::meta::EventMeta;
 use super::news::{NewsEntry, NewsEntryUpdate};
 use super::pins::{Pin, PinUpdate};
+use super::polls::{Poll, PollUpdate, PollVote};
 use super::reactions::Reaction;
 use super::read_receipts::ReadReceipt;
 use super::redaction::RedactedActerModel;
 use super::rsvp::Rsvp;
 use super::stories::{Story, StoryUpdate};
-use super::tasks::{Task, TaskList, TaskListUpdate, TaskSelfAssign, TaskSelfUnassign, TaskUpdate};
+use super::tasks::{
+    Task, TaskAssign, TaskList, TaskListUpdate, TaskSelfAssign, TaskSelfUnassign, TaskUnassign,
+    TaskUpdate,
+};
 use super::RoomStatus;
 use core::fmt::Debug;
 use enum_dispatch::enum_dispatch;
 use matrix_sdk_base::ruma::{
-    events::{reaction::ReactionEventContent, StaticEventContent},
+    events::{reaction::ReactionEventContent, Mentions, StaticEventContent},
     EventId, OwnedEventId, RoomId, UserId,
 };
 use serde::{Deserialize, Serialize};
@@ -35,14 +39,17 @@ use crate::{
         calendar::{CalendarEventEventContent, CalendarEventUpdateEventContent},
         comments::{CommentEventContent, CommentUpdateEventContent},
         explicit_invites::ExplicitInviteEventContent,
+        mentions_user,
         news::{NewsEntryEventContent, NewsEntryUpdateEventContent},
         pins::{PinEventContent, PinUpdateEventContent},
+        polls::{PollEventContent, PollUpdateEventContent, PollVoteEventContent},
         read_receipt::ReadReceiptEventContent,
         rsvp::RsvpEventContent,
         stories::{StoryEventContent, StoryUpdateEventContent},
         tasks::{
-            TaskEventContent, TaskListEventContent, TaskListUpdateEventContent,
-            TaskSelfAssignEventContent, TaskSelfUnassignEventContent, TaskUpdateEventContent,
+            TaskAssignEventContent, TaskEventContent, TaskListEventContent,
+            TaskListUpdateEventContent, TaskSelfAssignEventContent, TaskSelfUnassignEventContent,
+            TaskUnassignEventContent, TaskUpdateEventContent,
         },
         AnyActerEvent,
     },
@@ -72,6 +79,16 @@ pub trait ActerModel: Debug {
         None
     }
 
+    /// The users and rooms mentioned in the content of this model
+    fn mentions(&self) -> Option<&Mentions> {
+        None
+    }
+
+    /// Whether this model mentions the user, directly or by mentioning the room
+    fn mentions_user(&self, user_id: &UserId) -> bool {
+        mentions_user(self.mentions(), &self.event_meta().sender, user_id)
+    }
+
     /// activate to enable commenting support for this type of model
     fn capabilities(&self) -> &[Capability] {
         &[]
@@ -120,11 +137,18 @@ pub enum AnyActerModel {
     TaskUpdate(TaskUpdate),
     TaskSelfAssign(TaskSelfAssign),
     TaskSelfUnassign(TaskSelfUnassign),
+    TaskAssign(TaskAssign),
+    TaskUnassign(TaskUnassign),
 
     // -- Pins
     Pin(Pin),
     PinUpdate(PinUpdate),
 
+    // -- Polls
+    Poll(Poll),
+    PollUpdate(PollUpdate),
+    PollVote(PollVote),
+
     // -- News
     NewsEntry(NewsEntry),
     NewsEntryUpdate(NewsEntryUpdate),
@@ -162,8 +186,13 @@ impl AnyActerModel {
             AnyActerModel::TaskUpdate(_) => TaskUpdateEventContent::TYPE,
             AnyActerModel::TaskSelfAssign(_) => TaskSelfAssignEventContent::TYPE,
             AnyActerModel::TaskSelfUnassign(_) => TaskSelfUnassignEventContent::TYPE,
+            AnyActerModel::TaskAssign(_) => TaskAssignEventContent::TYPE,
+            AnyActerModel::TaskUnassign(_) => TaskUnassignEventContent::TYPE,
             AnyActerModel::Pin(_) => PinEventContent::TYPE,
             AnyActerModel::PinUpdate(_) => PinUpdateEventContent::TYPE,
+            AnyActerModel::Poll(_) => PollEventContent::TYPE,
+            AnyActerModel::PollUpdate(_) => PollUpdateEventContent::TYPE,
+            AnyActerModel::PollVote(_) => PollVoteEventContent::TYPE,
             AnyActerModel::NewsEntry(_) => NewsEntryEventContent::TYPE,
             AnyActerModel::NewsEntryUpdate(_) => NewsEntryUpdateEventContent::TYPE,
             AnyActerModel::Story(_) => StoryEventContent::TYPE,
@@ -1,3 +1,5 @@
 mod event;
+mod recurrence;
 
-pub use event::{CalendarEvent, CalendarEventUpdate};
+pub use event::{CalendarEvent, CalendarEventOccurrence, CalendarEventUpdate};
+pub use recurrence::RecurrenceIter;
@@ -5,20 +5,39 @@ use serde::{Deserialize, Serialize};
 use std::ops::Deref;
 
 use super::super::{
-    default_model_execute, ActerModel, AnyActerModel, Capability, EventMeta, Store,
+    default_model_execute, execute_update, ActerModel, AnyActerModel, Capability, EventMeta, Store,
     TextMessageContent,
 };
+use super::recurrence::RecurrenceIter;
 use crate::{
     events::{
         calendar::{
-            CalendarEventEventContent, CalendarEventUpdateBuilder, CalendarEventUpdateEventContent,
+            format_ical_date_time, CalendarEventEventContent, CalendarEventUpdateBuilder,
+            CalendarEventUpdateEventContent,
         },
         UtcDateTime,
     },
+    history::keep_original_state,
     referencing::{ExecuteReference, IndexKey, SectionIndex},
     Result,
 };
 
+/// A single occurrence of a calendar event
+///
+/// Non-recurring events have exactly one occurrence, whose `recurrence_id` is the
+/// start of the event itself.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CalendarEventOccurrence {
+    /// The start of this occurrence according to the recurrence rule
+    pub recurrence_id: UtcDateTime,
+    /// When this occurrence actually starts, after applying any overrides
+    pub utc_start: UtcDateTime,
+    /// When this occurrence actually ends, after applying any overrides
+    pub utc_end: UtcDateTime,
+    /// The title of this occurrence, if it differs from the one of the series
+    pub title: Option<String>,
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct CalendarEvent {
     pub(crate) inner: CalendarEventEventContent,
@@ -48,6 +67,14 @@ impl CalendarEvent {
         &self.meta.sender
     }
 
+    /// Whether the given user may update this calendar event, given whether they are a
+    /// moderator of the room
+    pub fn can_be_edited_by(&self, user_id: &UserId, is_room_moderator: bool) -> bool {
+        self.inner
+            .edit_rule
+            .allows(&self.meta.sender, user_id, is_room_moderator)
+    }
+
     pub fn updater(&self) -> CalendarEventUpdateBuilder {
         CalendarEventUpdateBuilder::default()
             .calendar_event(self.meta.event_id.clone())
@@ -66,14 +93,144 @@ impl CalendarEvent {
         self.inner.show_without_time
     }
 
+    pub fn is_recurring(&self) -> bool {
+        self.inner.recurrence_rule.is_some()
+    }
+
+    /// All occurrences of this event in chronological order of their `recurrence_id`
+    ///
+    /// Excluded occurrences are skipped. Beware, this might be infinite for recurring events.
+    pub fn occurrences(&self) -> impl Iterator<Item = CalendarEventOccurrence> + '_ {
+        let starts: Box<dyn Iterator<Item = UtcDateTime>> = match &self.inner.recurrence_rule {
+            Some(rule) => Box::new(RecurrenceIter::new(rule.clone(), self.inner.utc_start)),
+            None => Box::new(std::iter::once(self.inner.utc_start)),
+        };
+        let duration = self.inner.utc_end - self.inner.utc_start;
+        starts.filter_map(move |recurrence_id| {
+            let Some(overridden) = self
+                .inner
+                .recurrence_overrides
+                .iter()
+                .find(|o| o.recurrence_id == recurrence_id)
+            else {
+                return Some(CalendarEventOccurrence {
+                    recurrence_id,
+                    utc_start: recurrence_id,
+                    utc_end: recurrence_id + duration,
+                    title: None,
+                });
+            };
+            if overridden.excluded {
+                return None;
+            }
+            let utc_start = overridden.utc_start.unwrap_or(recurrence_id);
+            Some(CalendarEventOccurrence {
+                recurrence_id,
+                utc_start,
+                utc_end: overridden.utc_end.unwrap_or(utc_start + duration),
+                title: overridden.title.clone(),
+            })
+        })
+    }
+
+    /// The occurrence identified by `recurrence_id`, if it exists and isn’t excluded
+    pub fn occurrence(&self, recurrence_id: &UtcDateTime) -> Option<CalendarEventOccurrence> {
+        self.occurrences()
+            .take_while(|o| &o.recurrence_id <= recurrence_id)
+            .find(|o| &o.recurrence_id == recurrence_id)
+    }
+
+    /// The occurrences overlapping the time between `from` and `to`
+    pub fn occurrences_between(
+        &self,
+        from: &UtcDateTime,
+        to: &UtcDateTime,
+    ) -> Vec<CalendarEventOccurrence> {
+        let overlaps = |o: &CalendarEventOccurrence| &o.utc_end > from && &o.utc_start <= to;
+        let mut found = self
+            .occurrences()
+            .take_while(|o| &o.recurrence_id <= to)
+            .filter(overlaps)
+            .collect::<Vec<_>>();
+        found.extend(self.moved_from(to).filter(overlaps));
+        found.sort_by_key(|o| o.recurrence_id);
+        found.dedup_by_key(|o| o.recurrence_id);
+        found
+    }
+
+    /// The occurrences of the series from `from` on that were moved to another time
+    fn moved_from<'a>(
+        &'a self,
+        from: &'a UtcDateTime,
+    ) -> impl Iterator<Item = CalendarEventOccurrence> + 'a {
+        self.inner
+            .recurrence_overrides
+            .iter()
+            .filter(move |o| !o.excluded && o.utc_start.is_some() && &o.recurrence_id >= from)
+            .filter_map(move |o| self.occurrence(&o.recurrence_id))
+    }
+
+    /// The next `limit` occurrences starting after `after`
+    pub fn occurrences_after(
+        &self,
+        after: &UtcDateTime,
+        limit: usize,
+    ) -> Vec<CalendarEventOccurrence> {
+        self.occurrences()
+            .filter(|o| &o.utc_start > after)
+            .take(limit)
+            .collect()
+    }
+
+    /// The last `limit` occurrences having started before `before`, latest first
+    pub fn occurrences_before(
+        &self,
+        before: &UtcDateTime,
+        limit: usize,
+    ) -> Vec<CalendarEventOccurrence> {
+        let mut past = self
+            .occurrences()
+            .take_while(|o| &o.recurrence_id < before)
+            .filter(|o| &o.utc_start < before)
+            .collect::<Vec<_>>();
+        past.extend(self.moved_from(before).filter(|o| &o.utc_start < before));
+        past.sort_by_key(|o| o.recurrence_id);
+        past.dedup_by_key(|o| o.recurrence_id);
+        past.reverse();
+        past.truncate(limit);
+        past
+    }
+
+    /// The iCalendar `UID`, the one it was imported with or the event id
+    pub fn ical_uid(&self) -> &str {
+        self.inner
+            .ical_uid
+            .as_deref()
+            .unwrap_or(self.meta.event_id.as_str())
+    }
+
     pub fn as_ical_event(&self) -> iCalEvent {
         let mut cal_e_builder = iCalEvent::new();
 
         cal_e_builder
+            .uid(self.ical_uid())
             .summary(&self.inner.title)
             .starts(self.inner.utc_start)
             .ends(self.inner.utc_end)
             .class(icalendar::Class::Private);
+        if let Some(rule) = &self.inner.recurrence_rule {
+            cal_e_builder.add_property("RRULE", rule.to_string());
+            let excluded = self
+                .inner
+                .recurrence_overrides
+                .iter()
+                .filter(|o| o.excluded)
+                .map(|o| format_ical_date_time(&o.recurrence_id))
+                .collect::<Vec<_>>();
+            if !excluded.is_empty() {
+                cal_e_builder.add_property("EXDATE", excluded.join(","));
+            }
+        }
         if let Some(msg) = &self.inner.description {
             if let Some(formatted) = &msg.formatted {
                 return cal_e_builder.description(&formatted.body).done();
@@ -83,6 +240,36 @@ impl CalendarEvent {
         }
         cal_e_builder.done()
     }
+
+    /// The event and all its moved or renamed occurrences as iCal events
+    pub fn as_ical_events(&self) -> Vec<iCalEvent> {
+        let mut events = vec![self.as_ical_event()];
+        if !self.is_recurring() {
+            return events;
+        }
+        let duration = self.inner.utc_end - self.inner.utc_start;
+        for overridden in self
+            .inner
+            .recurrence_overrides
+            .iter()
+            .filter(|o| !o.excluded)
+        {
+            let utc_start = overridden.utc_start.unwrap_or(overridden.recurrence_id);
+            let mut cal_e_builder = iCalEvent::new();
+            cal_e_builder
+                .uid(self.ical_uid())
+                .add_property(
+                    "RECURRENCE-ID",
+                    format_ical_date_time(&overridden.recurrence_id),
+                )
+                .summary(overridden.title.as_ref().unwrap_or(&self.inner.title))
+                .starts(utc_start)
+                .ends(overridden.utc_end.unwrap_or(utc_start + duration))
+                .class(icalendar::Class::Private);
+            events.push(cal_e_builder.done());
+        }
+        events
+    }
 }
 
 impl ActerModel for CalendarEvent {
@@ -110,7 +297,9 @@ impl ActerModel for CalendarEvent {
     }
 
     async fn execute(self, store: &Store) -> Result<Vec<ExecuteReference>> {
-        default_model_execute(store, self.into()).await
+        let model = AnyActerModel::from(self);
+        keep_original_state(store, &model).await?;
+        default_model_execute(store, model).await
     }
 
     fn belongs_to(&self) -> Option<Vec<OwnedEventId>> {
@@ -172,7 +361,8 @@ impl ActerModel for CalendarEventUpdate {
     }
 
     async fn execute(self, store: &Store) -> Result<Vec<ExecuteReference>> {
-        default_model_execute(store, self.into()).await
+        let calendar_event_id = self.inner.calendar_event.event_id.clone();
+        execute_update(store, &calendar_event_id, self.into()).await
     }
 
     fn belongs_to(&self) -> Option<Vec<OwnedEventId>> {
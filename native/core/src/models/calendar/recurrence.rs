@@ -0,0 +1,456 @@
+use chrono::{Datelike, Days, Months, NaiveDate, TimeDelta};
+use std::collections::VecDeque;
+
+use crate::events::{
+    calendar::{Frequency, NDay, RecurrenceRule},
+    UtcDateTime,
+};
+
+/// Safety net against rules that never match, e.g. `FREQ=YEARLY;BYMONTH=2;BYMONTHDAY=30`
+const MAX_EMPTY_PERIODS: u32 = 1_000;
+
+/// Iterates over the start times of a recurrence rule in chronological order
+///
+/// Follows the expansion rules of [RFC 5545](https://www.rfc-editor.org/rfc/rfc5545#section-3.3.10)
+/// for the subset supported by [`RecurrenceRule`]. The first item is always `dtstart` itself, even
+/// if it doesn’t match the rule, and counts towards `COUNT`. `COUNT` and `UNTIL` are applied before
+/// any exclusions, as in the RFC.
+pub struct RecurrenceIter {
+    rule: RecurrenceRule,
+    dtstart: UtcDateTime,
+    period: u32,
+    emitted: u32,
+    buffer: VecDeque<UtcDateTime>,
+    done: bool,
+}
+
+impl RecurrenceIter {
+    pub fn new(rule: RecurrenceRule, dtstart: UtcDateTime) -> Self {
+        RecurrenceIter {
+            rule,
+            dtstart,
+            period: 0,
+            emitted: 0,
+            buffer: Default::default(),
+            done: false,
+        }
+    }
+
+    fn at(&self, date: NaiveDate) -> UtcDateTime {
+        date.and_time(self.dtstart.time()).and_utc()
+    }
+
+    fn matches_month(&self, date: &NaiveDate) -> bool {
+        self.rule.by_month.is_empty() || self.rule.by_month.contains(&date.month())
+    }
+
+    fn matches_month_day(&self, date: &NaiveDate) -> bool {
+        if self.rule.by_month_day.is_empty() {
+            return true;
+        }
+        let days_in_month = days_in_month(date.year(), date.month());
+        self.rule
+            .by_month_day
+            .iter()
+            .any(|d| resolve_month_day(*d, days_in_month) == Some(date.day()))
+    }
+
+    fn matches_week_day(&self, date: &NaiveDate) -> bool {
+        self.rule.by_day.is_empty()
+            || self
+                .rule
+                .by_day
+                .iter()
+                .any(|d| chrono::Weekday::from(d.day) == date.weekday())
+    }
+
+    /// the matching days within the given month
+    fn month_candidates(&self, year: i32, month: u32) -> Vec<NaiveDate> {
+        let days_in_month = days_in_month(year, month);
+        let month_days = self
+            .rule
+            .by_month_day
+            .iter()
+            .filter_map(|d| resolve_month_day(*d, days_in_month))
+            .filter_map(|d| NaiveDate::from_ymd_opt(year, month, d))
+            .collect::<Vec<_>>();
+
+        let Some(first) = NaiveDate::from_ymd_opt(year, month, 1) else {
+            return vec![];
+        };
+        let week_days = nth_week_days(&self.rule.by_day, first, days_in_month);
+
+        match (
+            self.rule.by_month_day.is_empty(),
+            self.rule.by_day.is_empty(),
+        ) {
+            (true, true) => NaiveDate::from_ymd_opt(year, month, self.dtstart.day())
+                .into_iter()
+                .collect(),
+            (false, true) => month_days,
+            (true, false) => week_days,
+            (false, false) => week_days
+                .into_iter()
+                .filter(|d| month_days.contains(d))
+                .collect(),
+        }
+    }
+
+    /// the matching days within the given year
+    fn year_candidates(&self, year: i32) -> Vec<NaiveDate> {
+        if self.rule.by_month.is_empty()
+            && self.rule.by_month_day.is_empty()
+            && !self.rule.by_day.is_empty()
+        {
+            // weekdays counted within the entire year
+            let Some(first) = NaiveDate::from_ymd_opt(year, 1, 1) else {
+                return vec![];
+            };
+            let days_in_year = if first.leap_year() { 366 } else { 365 };
+            return nth_week_days(&self.rule.by_day, first, days_in_year);
+        }
+
+        let months = if !self.rule.by_month.is_empty() {
+            self.rule.by_month.clone()
+        } else if !self.rule.by_month_day.is_empty() {
+            (1..=12).collect()
+        } else {
+            vec![self.dtstart.month()]
+        };
+        months
+            .into_iter()
+            .flat_map(|month| self.month_candidates(year, month))
+            .collect()
+    }
+
+    /// All candidates of the given period, `None` if we ran out of representable time
+    fn candidates(&self, period: u32) -> Option<Vec<UtcDateTime>> {
+        let step = period.checked_mul(self.rule.interval.max(1))?;
+        let start_date = self.dtstart.date_naive();
+        let dates = match self.rule.frequency {
+            Frequency::Hourly => {
+                let next = self
+                    .dtstart
+                    .checked_add_signed(TimeDelta::try_hours(step.into())?)?;
+                let date = next.date_naive();
+                return Some(
+                    if self.matches_month(&date)
+                        && self.matches_month_day(&date)
+                        && self.matches_week_day(&date)
+                    {
+                        vec![next]
+                    } else {
+                        vec![]
+                    },
+                );
+            }
+            Frequency::Daily => {
+                let date = start_date.checked_add_days(Days::new(step.into()))?;
+                if self.matches_month(&date)
+                    && self.matches_month_day(&date)
+                    && self.matches_week_day(&date)
+                {
+                    vec![date]
+                } else {
+                    vec![]
+                }
+            }
+            Frequency::Weekly => {
+                let week_start = start_date
+                    .checked_sub_days(Days::new(
+                        start_date.weekday().num_days_from_monday().into(),
+                    ))?
+                    .checked_add_days(Days::new(u64::from(step) * 7))?;
+                let mut dates = if self.rule.by_day.is_empty() {
+                    vec![week_start.checked_add_days(Days::new(
+                        start_date.weekday().num_days_from_monday().into(),
+                    ))?]
+                } else {
+                    self.rule
+                        .by_day
+                        .iter()
+                        .filter_map(|d| {
+                            week_start.checked_add_days(Days::new(
+                                chrono::Weekday::from(d.day).num_days_from_monday().into(),
+                            ))
+                        })
+                        .collect()
+                };
+                dates.retain(|d| self.matches_month(d));
+                dates
+            }
+            Frequency::Monthly => {
+                let month = start_date
+                    .with_day(1)?
+                    .checked_add_months(Months::new(step))?;
+                if self.matches_month(&month) {
+                    self.month_candidates(month.year(), month.month())
+                } else {
+                    vec![]
+                }
+            }
+            Frequency::Yearly => {
+                let year = start_date.year().checked_add(i32::try_from(step).ok()?)?;
+                self.year_candidates(year)
+            }
+        };
+        let mut candidates = dates.into_iter().map(|d| self.at(d)).collect::<Vec<_>>();
+        candidates.sort();
+        candidates.dedup();
+        Some(candidates)
+    }
+
+    fn fill_buffer(&mut self) {
+        let mut empty_periods = 0;
+        while self.buffer.is_empty() {
+            let Some(candidates) = self.candidates(self.period) else {
+                self.done = true;
+                return;
+            };
+            self.period += 1;
+            // `dtstart` was already emitted as the first instance
+            self.buffer
+                .extend(candidates.into_iter().filter(|c| c > &self.dtstart));
+            if self.buffer.is_empty() {
+                empty_periods += 1;
+                if empty_periods > MAX_EMPTY_PERIODS {
+                    self.done = true;
+                    return;
+                }
+            }
+        }
+    }
+}
+
+impl Iterator for RecurrenceIter {
+    type Item = UtcDateTime;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        if let Some(count) = self.rule.count {
+            if self.emitted >= count {
+                self.done = true;
+                return None;
+            }
+        }
+        let next = if self.emitted == 0 {
+            self.dtstart
+        } else {
+            self.fill_buffer();
+            self.buffer.pop_front()?
+        };
+        if let Some(until) = &self.rule.until {
+            if &next > until {
+                self.done = true;
+                return None;
+            }
+        }
+        self.emitted += 1;
+        Some(next)
+    }
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+    let Some(first) = NaiveDate::from_ymd_opt(year, month, 1) else {
+        return 0;
+    };
+    first
+        .checked_add_months(Months::new(1))
+        .and_then(|next| next.pred_opt())
+        .map(|last| last.day())
+        .unwrap_or(31)
+}
+
+/// resolve negative month days, counting from the end of the month
+fn resolve_month_day(day: i32, days_in_month: u32) -> Option<u32> {
+    let days_in_month = i32::try_from(days_in_month).ok()?;
+    let resolved = if day < 0 {
+        days_in_month + day + 1
+    } else {
+        day
+    };
+    if resolved < 1 || resolved > days_in_month {
+        return None;
+    }
+    u32::try_from(resolved).ok()
+}
+
+/// all days matching the given `NDay`s within the period of `length` days starting at `first`
+fn nth_week_days(by_day: &[NDay], first: NaiveDate, length: u32) -> Vec<NaiveDate> {
+    let mut dates = vec![];
+    for nday in by_day {
+        let weekday = chrono::Weekday::from(nday.day);
+        let offset =
+            (7 + weekday.num_days_from_monday() - first.weekday().num_days_from_monday()) % 7;
+        let all = (offset..length)
+            .step_by(7)
+            .filter_map(|d| first.checked_add_days(Days::new(d.into())))
+            .collect::<Vec<_>>();
+        match nday.nth_of_period {
+            None | Some(0) => dates.extend(all),
+            Some(nth) if nth > 0 => {
+                if let Some(d) = usize::try_from(nth - 1).ok().and_then(|i| all.get(i)) {
+                    dates.push(*d);
+                }
+            }
+            Some(nth) => {
+                let from_end = usize::try_from(-nth).unwrap_or(usize::MAX);
+                if let Some(d) = all.len().checked_sub(from_end).and_then(|i| all.get(i)) {
+                    dates.push(*d);
+                }
+            }
+        }
+    }
+    dates.sort();
+    dates.dedup();
+    dates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::calendar::{RecurrenceRule, WeekDay};
+    use chrono::{DateTime, Utc};
+    use std::str::FromStr;
+
+    fn dt(value: &str) -> UtcDateTime {
+        DateTime::parse_from_rfc3339(value)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn expand(rule: &str, start: &str, take: usize) -> Vec<String> {
+        let rule = RecurrenceRule::from_str(rule).unwrap();
+        RecurrenceIter::new(rule, dt(start))
+            .take(take)
+            .map(|d| d.format("%Y-%m-%d %H:%M").to_string())
+            .collect()
+    }
+
+    #[test]
+    fn rrule_roundtrip() {
+        let rule =
+            RecurrenceRule::from_str("RRULE:FREQ=MONTHLY;INTERVAL=2;COUNT=10;BYDAY=-1FR,2MO")
+                .unwrap();
+        assert_eq!(rule.frequency, Frequency::Monthly);
+        assert_eq!(rule.interval, 2);
+        assert_eq!(rule.count, Some(10));
+        assert_eq!(
+            rule.by_day,
+            vec![
+                NDay {
+                    day: WeekDay::Friday,
+                    nth_of_period: Some(-1)
+                },
+                NDay {
+                    day: WeekDay::Monday,
+                    nth_of_period: Some(2)
+                }
+            ]
+        );
+        assert_eq!(
+            rule.to_string(),
+            "FREQ=MONTHLY;INTERVAL=2;COUNT=10;BYDAY=-1FR,2MO"
+        );
+        assert!(RecurrenceRule::from_str("INTERVAL=2").is_err());
+        assert!(RecurrenceRule::from_str("FREQ=WEEKLY;BYSETPOS=1").is_err());
+    }
+
+    #[test]
+    fn weekly_with_count() {
+        assert_eq!(
+            expand("FREQ=WEEKLY;COUNT=3", "2024-01-01T10:00:00Z", 10),
+            ["2024-01-01 10:00", "2024-01-08 10:00", "2024-01-15 10:00"]
+        );
+    }
+
+    #[test]
+    fn weekly_by_day_with_interval() {
+        // 2024-01-03 is a wednesday, so monday of the first week is skipped
+        assert_eq!(
+            expand(
+                "FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,WE",
+                "2024-01-03T09:30:00Z",
+                4
+            ),
+            [
+                "2024-01-03 09:30",
+                "2024-01-15 09:30",
+                "2024-01-17 09:30",
+                "2024-01-29 09:30"
+            ]
+        );
+    }
+
+    #[test]
+    fn daily_until_is_inclusive() {
+        assert_eq!(
+            expand(
+                "FREQ=DAILY;UNTIL=20240103T080000Z",
+                "2024-01-01T08:00:00Z",
+                10
+            ),
+            ["2024-01-01 08:00", "2024-01-02 08:00", "2024-01-03 08:00"]
+        );
+    }
+
+    #[test]
+    fn monthly_skips_missing_days() {
+        assert_eq!(
+            expand("FREQ=MONTHLY;COUNT=3", "2024-01-31T12:00:00Z", 10),
+            ["2024-01-31 12:00", "2024-03-31 12:00", "2024-05-31 12:00"]
+        );
+    }
+
+    #[test]
+    fn monthly_last_friday() {
+        // dtstart is always the first instance, even if it doesn’t match
+        assert_eq!(
+            expand("FREQ=MONTHLY;BYDAY=-1FR", "2024-01-01T18:00:00Z", 4),
+            [
+                "2024-01-01 18:00",
+                "2024-01-26 18:00",
+                "2024-02-23 18:00",
+                "2024-03-29 18:00"
+            ]
+        );
+    }
+
+    #[test]
+    fn unmatched_dtstart_counts() {
+        assert_eq!(
+            expand("FREQ=WEEKLY;BYDAY=FR;COUNT=2", "2024-01-01T10:00:00Z", 10),
+            ["2024-01-01 10:00", "2024-01-05 10:00"]
+        );
+    }
+
+    #[test]
+    fn monthly_negative_month_day() {
+        assert_eq!(
+            expand("FREQ=MONTHLY;BYMONTHDAY=-1", "2024-01-15T00:00:00Z", 3),
+            ["2024-01-15 00:00", "2024-01-31 00:00", "2024-02-29 00:00"]
+        );
+    }
+
+    #[test]
+    fn yearly_leap_day() {
+        assert_eq!(
+            expand("FREQ=YEARLY", "2024-02-29T07:00:00Z", 2),
+            ["2024-02-29 07:00", "2028-02-29 07:00"]
+        );
+    }
+
+    #[test]
+    fn never_matching_rule_ends() {
+        assert_eq!(
+            expand(
+                "FREQ=YEARLY;BYMONTH=2;BYMONTHDAY=30",
+                "2024-01-01T00:00:00Z",
+                2
+            ),
+            ["2024-01-01 00:00"]
+        );
+    }
+}
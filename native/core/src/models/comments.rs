@@ -1,18 +1,21 @@
 use derive_getters::Getters;
-use matrix_sdk_base::ruma::{events::OriginalMessageLikeEvent, EventId, OwnedEventId, UserId};
+use matrix_sdk_base::ruma::{
+    events::{Mentions, OriginalMessageLikeEvent},
+    EventId, OwnedEventId, UserId,
+};
 use serde::{Deserialize, Serialize};
 use std::ops::Deref;
-use tracing::{error, info, trace};
+use tracing::{error, info, trace, warn};
 
 use super::{default_model_execute, ActerModel, AnyActerModel, Capability, EventMeta};
 use crate::{
     events::comments::{
         CommentBuilder, CommentEventContent, CommentUpdateBuilder, CommentUpdateEventContent,
     },
-    referencing::{ExecuteReference, IndexKey, ModelParam, ObjectListIndex},
+    referencing::{ExecuteReference, IndexKey, ModelParam, ObjectListIndex, SpecialListsIndex},
     store::Store,
     util::{is_false, is_zero},
-    Result,
+    Error, Result,
 };
 
 #[derive(Clone, Debug, Default, Deserialize, Serialize, Getters)]
@@ -72,6 +75,16 @@ impl CommentsManager {
         Ok(comments)
     }
 
+    /// The comments that aren’t a reply to another comment
+    pub async fn top_level_comments(&self) -> Result<Vec<Comment>> {
+        Ok(self
+            .comments()
+            .await?
+            .into_iter()
+            .filter(|c| c.reply_to.is_none())
+            .collect())
+    }
+
     pub(crate) async fn add_comment(&mut self, _comment: &Comment) -> Result<bool> {
         self.stats.has_comments = true;
         self.stats.total_comments_count += 1;
@@ -136,6 +149,19 @@ impl Comment {
             .to_owned()
     }
 
+    /// The comment this is a reply to, if any
+    pub fn in_reply_to(&self) -> Option<OwnedEventId> {
+        self.inner
+            .reply_to
+            .as_ref()
+            .and_then(|r| r.event_ids.first().cloned())
+    }
+
+    /// The index the replies to this comment are listed under
+    pub fn replies_index(&self) -> IndexKey {
+        Comment::index_for(self.meta.event_id.clone())
+    }
+
     pub fn reply_builder(&self) -> CommentBuilder {
         let event_id = self.meta.event_id.clone();
         CommentBuilder::default()
@@ -144,6 +170,32 @@ impl Comment {
             .to_owned()
     }
 
+    /// Replies to unknown comments or to comments on another object are kept
+    /// as comments on the object only, so they aren’t listed in any thread
+    async fn drop_invalid_replies(&mut self, store: &Store) -> Result<()> {
+        let Some(mut reply_to) = self.inner.reply_to.take() else {
+            return Ok(());
+        };
+        let mut valid = Vec::with_capacity(reply_to.event_ids.len());
+        for event_id in reply_to.event_ids.drain(..) {
+            match store.get(&event_id).await {
+                Ok(AnyActerModel::Comment(replied)) if replied.on.event_id != self.on.event_id => {
+                    warn!(?event_id, comment = ?self, "reply to a comment on another object");
+                }
+                Ok(_) => valid.push(event_id),
+                Err(Error::ModelNotFound(_)) => {
+                    warn!(?event_id, comment = ?self, "replied to comment unknown");
+                }
+                Err(error) => return Err(error),
+            }
+        }
+        if !valid.is_empty() {
+            reply_to.event_ids = valid;
+            self.inner.reply_to = Some(reply_to);
+        }
+        Ok(())
+    }
+
     fn belongs_to_inner(&self) -> Vec<OwnedEventId> {
         let mut references = self
             .inner
@@ -157,7 +209,7 @@ impl Comment {
 }
 
 impl ActerModel for Comment {
-    fn indizes(&self, _user_id: &UserId) -> Vec<IndexKey> {
+    fn indizes(&self, user_id: &UserId) -> Vec<IndexKey> {
         let mut indizes = self
             .belongs_to_inner()
             .into_iter()
@@ -166,6 +218,9 @@ impl ActerModel for Comment {
         indizes.push(IndexKey::ObjectHistory(self.inner.on.event_id.clone()));
         indizes.push(IndexKey::RoomHistory(self.meta.room_id.clone()));
         indizes.push(IndexKey::AllHistory);
+        if self.mentions_user(user_id) {
+            indizes.push(IndexKey::Special(SpecialListsIndex::MyMentions));
+        }
         indizes
     }
 
@@ -173,11 +228,16 @@ impl ActerModel for Comment {
         &self.meta
     }
 
+    fn mentions(&self) -> Option<&Mentions> {
+        self.inner.mentions.as_ref()
+    }
+
     fn capabilities(&self) -> &[Capability] {
         &[Capability::Commentable, Capability::Reactable]
     }
 
-    async fn execute(self, store: &Store) -> Result<Vec<ExecuteReference>> {
+    async fn execute(mut self, store: &Store) -> Result<Vec<ExecuteReference>> {
+        self.drop_invalid_replies(store).await?;
         let belongs_to = self.belongs_to_inner();
         trace!(event_id=?self.event_id(), ?belongs_to, "applying comment");
 
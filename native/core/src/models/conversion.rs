@@ -7,12 +7,14 @@ use crate::events::{
     explicit_invites::ExplicitInviteEventContent,
     news::{NewsEntryEventContent, NewsEntryUpdateEventContent},
     pins::{PinEventContent, PinUpdateEventContent},
+    polls::{PollEventContent, PollUpdateEventContent, PollVoteEventContent},
     read_receipt::ReadReceiptEventContent,
     rsvp::RsvpEventContent,
     stories::{StoryEventContent, StoryUpdateEventContent},
     tasks::{
-        TaskEventContent, TaskListEventContent, TaskListUpdateEventContent,
-        TaskSelfAssignEventContent, TaskSelfUnassignEventContent, TaskUpdateEventContent,
+        TaskAssignEventContent, TaskEventContent, TaskListEventContent, TaskListUpdateEventContent,
+        TaskSelfAssignEventContent, TaskSelfUnassignEventContent, TaskUnassignEventContent,
+        TaskUpdateEventContent,
     },
     AnyActerEvent,
 };
@@ -99,6 +101,54 @@ impl TryFrom<AnyActerEvent> for AnyActerModel {
                     })))
                 }
             },
+            AnyActerEvent::Poll(e) => match e {
+                MessageLikeEvent::Original(m) => Ok(AnyActerModel::Poll(m.into())),
+                MessageLikeEvent::Redacted(r) => {
+                    Err(Self::Error::ModelRedacted(Box::new(ModelRedactedDetails {
+                        model_type: PollEventContent::TYPE.to_owned(),
+                        meta: EventMeta {
+                            room_id: r.room_id,
+                            event_id: r.event_id,
+                            sender: r.sender,
+                            origin_server_ts: r.origin_server_ts,
+                            redacted: None,
+                        },
+                        reason: r.unsigned.redacted_because,
+                    })))
+                }
+            },
+            AnyActerEvent::PollUpdate(e) => match e {
+                MessageLikeEvent::Original(m) => Ok(AnyActerModel::PollUpdate(m.into())),
+                MessageLikeEvent::Redacted(r) => {
+                    Err(Self::Error::ModelRedacted(Box::new(ModelRedactedDetails {
+                        model_type: PollUpdateEventContent::TYPE.to_owned(),
+                        meta: EventMeta {
+                            room_id: r.room_id,
+                            event_id: r.event_id,
+                            sender: r.sender,
+                            origin_server_ts: r.origin_server_ts,
+                            redacted: None,
+                        },
+                        reason: r.unsigned.redacted_because,
+                    })))
+                }
+            },
+            AnyActerEvent::PollVote(e) => match e {
+                MessageLikeEvent::Original(m) => Ok(AnyActerModel::PollVote(m.into())),
+                MessageLikeEvent::Redacted(r) => {
+                    Err(Self::Error::ModelRedacted(Box::new(ModelRedactedDetails {
+                        model_type: PollVoteEventContent::TYPE.to_owned(),
+                        meta: EventMeta {
+                            room_id: r.room_id,
+                            event_id: r.event_id,
+                            sender: r.sender,
+                            origin_server_ts: r.origin_server_ts,
+                            redacted: None,
+                        },
+                        reason: r.unsigned.redacted_because,
+                    })))
+                }
+            },
             AnyActerEvent::NewsEntry(e) => match e {
                 MessageLikeEvent::Original(m) => Ok(AnyActerModel::NewsEntry(m.into())),
                 MessageLikeEvent::Redacted(r) => {
@@ -260,6 +310,38 @@ impl TryFrom<AnyActerEvent> for AnyActerModel {
                     })))
                 }
             },
+            AnyActerEvent::TaskAssign(e) => match e {
+                MessageLikeEvent::Original(m) => Ok(AnyActerModel::TaskAssign(m.into())),
+                MessageLikeEvent::Redacted(r) => {
+                    Err(Self::Error::ModelRedacted(Box::new(ModelRedactedDetails {
+                        model_type: TaskAssignEventContent::TYPE.to_owned(),
+                        meta: EventMeta {
+                            room_id: r.room_id,
+                            event_id: r.event_id,
+                            sender: r.sender,
+                            origin_server_ts: r.origin_server_ts,
+                            redacted: None,
+                        },
+                        reason: r.unsigned.redacted_because,
+                    })))
+                }
+            },
+            AnyActerEvent::TaskUnassign(e) => match e {
+                MessageLikeEvent::Original(m) => Ok(AnyActerModel::TaskUnassign(m.into())),
+                MessageLikeEvent::Redacted(r) => {
+                    Err(Self::Error::ModelRedacted(Box::new(ModelRedactedDetails {
+                        model_type: TaskUnassignEventContent::TYPE.to_owned(),
+                        meta: EventMeta {
+                            room_id: r.room_id,
+                            event_id: r.event_id,
+                            sender: r.sender,
+                            origin_server_ts: r.origin_server_ts,
+                            redacted: None,
+                        },
+                        reason: r.unsigned.redacted_because,
+                    })))
+                }
+            },
             AnyActerEvent::Comment(e) => match e {
                 MessageLikeEvent::Original(m) => Ok(AnyActerModel::Comment(m.into())),
                 MessageLikeEvent::Redacted(r) => {
@@ -1,32 +1,145 @@
 use async_recursion::async_recursion;
-use matrix_sdk_base::ruma::OwnedEventId;
-use tracing::trace;
+use matrix_sdk_base::ruma::{EventId, OwnedEventId, UserId};
+use tracing::{trace, warn};
 
-use crate::referencing::ExecuteReference;
 pub use crate::store::Store;
+use crate::{
+    referencing::{ExecuteReference, IndexKey},
+    Error,
+};
 
-use super::{ActerModel, AnyActerModel};
+use super::{is_room_moderator, ActerModel, AnyActerModel};
 
-#[async_recursion]
 pub async fn transition_tree(
     store: &Store,
     parents: Vec<OwnedEventId>,
     model: &AnyActerModel,
 ) -> crate::Result<Vec<AnyActerModel>> {
     let mut models = vec![];
+    transition_tree_into(store, parents, model, &mut models).await?;
+    Ok(models)
+}
+
+#[async_recursion]
+async fn transition_tree_into(
+    store: &Store,
+    parents: Vec<OwnedEventId>,
+    model: &AnyActerModel,
+    models: &mut Vec<AnyActerModel>,
+) -> crate::Result<()> {
     for p in parents {
-        let mut parent = store.get(&p).await?;
+        // reached through several paths (e.g. a subtask and its task list),
+        // continue on the already transitioned state rather than the stored one
+        let known = models.iter().position(|m| *m.event_id() == *p);
+        let mut parent = match known {
+            Some(idx) => models.remove(idx),
+            None => store.get(&p).await?,
+        };
+        let previous_grandparents = parent.belongs_to().unwrap_or_default();
         if parent.transition(model)? {
-            if let Some(grandparents) = parent.belongs_to() {
-                let mut parent_models = transition_tree(store, grandparents, &parent).await?;
-                if !parent_models.is_empty() {
-                    models.append(&mut parent_models);
+            let mut grandparents = parent.belongs_to().unwrap_or_default();
+            // the ones it left, e.g. the previous parent of a moved subtask, must
+            // learn about it as well, as long as they are still around
+            for left in previous_grandparents {
+                if grandparents.contains(&left) {
+                    continue;
+                }
+                if models.iter().any(|m| *m.event_id() == *left) || store.get(&left).await.is_ok() {
+                    grandparents.push(left);
                 }
             }
+            if !grandparents.is_empty() {
+                transition_tree_into(store, grandparents, &parent, models).await?;
+            }
+            models.push(parent);
+        } else if known.is_some() {
             models.push(parent);
         }
     }
-    Ok(models)
+    Ok(())
+}
+
+fn can_be_edited_by(object: &AnyActerModel, editor: &UserId, is_room_moderator: bool) -> bool {
+    match object {
+        AnyActerModel::Pin(pin) => pin.can_be_edited_by(editor, is_room_moderator),
+        AnyActerModel::TaskList(list) => list.can_be_edited_by(editor, is_room_moderator),
+        AnyActerModel::Task(task) => task.can_be_edited_by(editor, is_room_moderator),
+        AnyActerModel::CalendarEvent(event) => event.can_be_edited_by(editor, is_room_moderator),
+        AnyActerModel::NewsEntry(entry) => entry.can_be_edited_by(editor, is_room_moderator),
+        AnyActerModel::Poll(poll) => poll.can_be_edited_by(editor, is_room_moderator),
+        _ => true,
+    }
+}
+
+/// Whether `editor` may update the object under its edit rule. Unknown
+/// objects pass, updates of those are queued until they arrive.
+///
+/// Edit rules are only checked here, before updates are stored, as only
+/// here the power levels of the room are at hand.
+pub async fn may_edit(store: &Store, object_id: &EventId, editor: &UserId) -> crate::Result<bool> {
+    let object = match store.get(&object_id.to_owned()).await {
+        Ok(object) => object,
+        Err(Error::ModelNotFound(_)) => return Ok(true),
+        Err(error) => return Err(error),
+    };
+    if can_be_edited_by(&object, editor, false) {
+        return Ok(true);
+    }
+    // only look up the power levels if the rule doesn’t allow the editor anyway
+    let Some(room) = store.client.get_room(&object.event_meta().room_id) else {
+        return Ok(false);
+    };
+    let moderator = is_room_moderator(&room, editor).await?;
+    Ok(can_be_edited_by(&object, editor, moderator))
+}
+
+/// The updates overriding each other, assigning and unassigning a user
+/// for example
+fn update_kind(model: &AnyActerModel) -> &str {
+    match model {
+        AnyActerModel::TaskAssign(_) | AnyActerModel::TaskUnassign(_) => "task.assign",
+        AnyActerModel::TaskSelfAssign(_) | AnyActerModel::TaskSelfUnassign(_) => "task.self_assign",
+        _ => model.model_type(),
+    }
+}
+
+/// Whether an update of the same kind newer than `model` was applied to the
+/// object already, e.g. when older history is loaded after the recent one
+pub(crate) async fn is_stale_update(
+    store: &Store,
+    object_id: &EventId,
+    model: &AnyActerModel,
+) -> crate::Result<bool> {
+    let ts = model.event_meta().origin_server_ts;
+    let kind = update_kind(model);
+    Ok(store
+        .get_list(&IndexKey::ObjectHistory(object_id.to_owned()))
+        .await?
+        .any(|m| {
+            m.event_meta().origin_server_ts > ts
+                && *m.event_id() != *model.event_id()
+                && update_kind(&m) == kind
+        }))
+}
+
+/// Execute the update of `object_id`, unless its edit rule doesn’t allow the
+/// sender: those are dropped before they are stored. Updates older than the
+/// ones applied already are only kept for the history of the object.
+pub(crate) async fn execute_update(
+    store: &Store,
+    object_id: &EventId,
+    model: AnyActerModel,
+) -> crate::Result<Vec<ExecuteReference>> {
+    let editor = &model.event_meta().sender;
+    if !may_edit(store, object_id, editor).await? {
+        warn!(update = ?model.event_id(), ?editor, "dropping update not allowed by edit rule");
+        return Ok(vec![]);
+    }
+    if is_stale_update(store, object_id, &model).await? {
+        trace!(update = ?model.event_id(), ?object_id, "newer update applied already");
+        return store.save(model).await;
+    }
+    default_model_execute(store, model).await
 }
 
 pub async fn default_model_execute(
@@ -1,11 +1,15 @@
 use matrix_sdk::room::Room;
 use matrix_sdk_base::ruma::{
-    events::room::redaction::OriginalRoomRedactionEvent, MilliSecondsSinceUnixEpoch, OwnedEventId,
-    OwnedRoomId, OwnedUserId, UserId,
+    events::room::{
+        power_levels::RoomPowerLevelsEventContent, redaction::OriginalRoomRedactionEvent,
+    },
+    MilliSecondsSinceUnixEpoch, OwnedEventId, OwnedRoomId, OwnedUserId, UserId,
 };
 
 use serde::{Deserialize, Serialize};
 
+use crate::events::MODERATOR_POWER_LEVEL;
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[cfg_attr(any(test, feature = "testing"), derive(PartialEq, Eq))]
 pub struct EventMeta {
@@ -52,3 +56,15 @@ pub async fn can_redact(room: &Room, sender_id: &UserId) -> crate::error::Result
         room.can_user_redact_other(user_id).await?
     })
 }
+
+/// Whether `user_id` has at least the moderator power level in the room
+pub async fn is_room_moderator(room: &Room, user_id: &UserId) -> crate::error::Result<bool> {
+    let Some(power_levels) = room
+        .get_state_event_static::<RoomPowerLevelsEventContent>()
+        .await?
+    else {
+        return Ok(false);
+    };
+    let power_levels = power_levels.deserialize()?.power_levels();
+    Ok(i64::from(power_levels.for_user(user_id)) >= MODERATOR_POWER_LEVEL)
+}
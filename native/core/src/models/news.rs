@@ -1,12 +1,17 @@
 use matrix_sdk::ruma::OwnedEventId;
-use matrix_sdk_base::ruma::{events::OriginalMessageLikeEvent, RoomId, UserId};
+use matrix_sdk_base::ruma::{
+    events::{Mentions, OriginalMessageLikeEvent},
+    RoomId, UserId,
+};
 use serde::{Deserialize, Serialize};
 use std::ops::Deref;
 
-use super::{default_model_execute, ActerModel, AnyActerModel, Capability, EventMeta};
+use super::{
+    default_model_execute, execute_update, ActerModel, AnyActerModel, Capability, EventMeta,
+};
 use crate::{
     events::news::{NewsEntryEventContent, NewsEntryUpdateBuilder, NewsEntryUpdateEventContent},
-    referencing::{ExecuteReference, IndexKey, SectionIndex},
+    referencing::{ExecuteReference, IndexKey, SectionIndex, SpecialListsIndex},
     store::Store,
     Result,
 };
@@ -33,6 +38,14 @@ impl NewsEntry {
         &self.meta.sender
     }
 
+    /// Whether the given user may update this news entry, given whether they are a
+    /// moderator of the room
+    pub fn can_be_edited_by(&self, user_id: &UserId, is_room_moderator: bool) -> bool {
+        self.inner
+            .edit_rule
+            .allows(&self.meta.sender, user_id, is_room_moderator)
+    }
+
     pub fn updater(&self) -> NewsEntryUpdateBuilder {
         NewsEntryUpdateBuilder::default()
             .news_entry(self.meta.event_id.clone())
@@ -41,20 +54,28 @@ impl NewsEntry {
 }
 
 impl ActerModel for NewsEntry {
-    fn indizes(&self, _user_id: &UserId) -> Vec<IndexKey> {
-        vec![
+    fn indizes(&self, user_id: &UserId) -> Vec<IndexKey> {
+        let mut indizes = vec![
             IndexKey::Section(SectionIndex::Boosts),
             IndexKey::RoomSection(self.meta.room_id.clone(), SectionIndex::Boosts),
             IndexKey::ObjectHistory(self.meta.event_id.clone()),
             IndexKey::RoomHistory(self.meta.room_id.clone()),
             IndexKey::AllHistory,
-        ]
+        ];
+        if self.mentions_user(user_id) {
+            indizes.push(IndexKey::Special(SpecialListsIndex::MyMentions));
+        }
+        indizes
     }
 
     fn event_meta(&self) -> &EventMeta {
         &self.meta
     }
 
+    fn mentions(&self) -> Option<&Mentions> {
+        self.inner.mentions.as_ref()
+    }
+
     fn capabilities(&self) -> &[Capability] {
         &[
             Capability::Commentable,
@@ -123,7 +144,8 @@ impl ActerModel for NewsEntryUpdate {
     }
 
     async fn execute(self, store: &Store) -> Result<Vec<ExecuteReference>> {
-        default_model_execute(store, self.into()).await
+        let news_entry_id = self.inner.news_entry.event_id.clone();
+        execute_update(store, &news_entry_id, self.into()).await
     }
 
     fn belongs_to(&self) -> Option<Vec<OwnedEventId>> {
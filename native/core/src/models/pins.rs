@@ -1,12 +1,18 @@
 use matrix_sdk::ruma::OwnedEventId;
-use matrix_sdk_base::ruma::{events::OriginalMessageLikeEvent, RoomId, UserId};
+use matrix_sdk_base::ruma::{
+    events::{Mentions, OriginalMessageLikeEvent},
+    RoomId, UserId,
+};
 use serde::{Deserialize, Serialize};
 use std::ops::Deref;
 
-use super::{default_model_execute, ActerModel, AnyActerModel, Capability, EventMeta};
+use super::{
+    default_model_execute, execute_update, ActerModel, AnyActerModel, Capability, EventMeta,
+};
 use crate::{
     events::pins::{PinEventContent, PinUpdateBuilder, PinUpdateEventContent},
-    referencing::{ExecuteReference, IndexKey, SectionIndex},
+    history::keep_original_state,
+    referencing::{ExecuteReference, IndexKey, SectionIndex, SpecialListsIndex},
     store::Store,
     Result,
 };
@@ -39,6 +45,14 @@ impl Pin {
         self.inner.url.is_some()
     }
 
+    /// Whether the given user may update this pin, given whether they are a
+    /// moderator of the room
+    pub fn can_be_edited_by(&self, user_id: &UserId, is_room_moderator: bool) -> bool {
+        self.inner
+            .edit_rule
+            .allows(&self.meta.sender, user_id, is_room_moderator)
+    }
+
     pub fn updater(&self) -> PinUpdateBuilder {
         PinUpdateBuilder::default()
             .pin(self.meta.event_id.clone())
@@ -47,20 +61,28 @@ impl Pin {
 }
 
 impl ActerModel for Pin {
-    fn indizes(&self, _user_id: &UserId) -> Vec<IndexKey> {
-        vec![
+    fn indizes(&self, user_id: &UserId) -> Vec<IndexKey> {
+        let mut indizes = vec![
             IndexKey::RoomSection(self.meta.room_id.clone(), SectionIndex::Pins),
             IndexKey::Section(SectionIndex::Pins),
             IndexKey::ObjectHistory(self.meta.event_id.clone()),
             IndexKey::RoomHistory(self.meta.room_id.clone()),
             IndexKey::AllHistory,
-        ]
+        ];
+        if self.mentions_user(user_id) {
+            indizes.push(IndexKey::Special(SpecialListsIndex::MyMentions));
+        }
+        indizes
     }
 
     fn event_meta(&self) -> &EventMeta {
         &self.meta
     }
 
+    fn mentions(&self) -> Option<&Mentions> {
+        self.inner.mentions.as_ref()
+    }
+
     fn capabilities(&self) -> &[Capability] {
         &[
             Capability::Commentable,
@@ -70,7 +92,9 @@ impl ActerModel for Pin {
     }
 
     async fn execute(self, store: &Store) -> Result<Vec<ExecuteReference>> {
-        default_model_execute(store, self.into()).await
+        let model = AnyActerModel::from(self);
+        keep_original_state(store, &model).await?;
+        default_model_execute(store, model).await
     }
 
     fn transition(&mut self, model: &AnyActerModel) -> Result<bool> {
@@ -112,20 +136,29 @@ pub struct PinUpdate {
 }
 
 impl ActerModel for PinUpdate {
-    fn indizes(&self, _user_id: &UserId) -> Vec<IndexKey> {
-        vec![
+    fn indizes(&self, user_id: &UserId) -> Vec<IndexKey> {
+        let mut indizes = vec![
             IndexKey::ObjectHistory(self.inner.pin.event_id.clone()),
             IndexKey::RoomHistory(self.meta.room_id.clone()),
             IndexKey::AllHistory,
-        ]
+        ];
+        if self.mentions_user(user_id) {
+            indizes.push(IndexKey::Special(SpecialListsIndex::MyMentions));
+        }
+        indizes
     }
 
     fn event_meta(&self) -> &EventMeta {
         &self.meta
     }
 
+    fn mentions(&self) -> Option<&Mentions> {
+        self.inner.mentions.as_ref()
+    }
+
     async fn execute(self, store: &Store) -> Result<Vec<ExecuteReference>> {
-        default_model_execute(store, self.into()).await
+        let pin_id = self.inner.pin.event_id.clone();
+        execute_update(store, &pin_id, self.into()).await
     }
 
     fn belongs_to(&self) -> Option<Vec<OwnedEventId>> {
@@ -0,0 +1,424 @@
+use derive_getters::Getters;
+use matrix_sdk_base::ruma::{
+    events::OriginalMessageLikeEvent, EventId, OwnedEventId, OwnedUserId, RoomId, UserId,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    ops::Deref,
+};
+use tracing::{error, trace};
+
+use super::{
+    default_model_execute, execute_update, ActerModel, AnyActerModel, Capability, EventMeta,
+};
+use crate::{
+    events::{
+        polls::{
+            PollEventContent, PollUpdateBuilder, PollUpdateEventContent, PollVoteBuilder,
+            PollVoteEventContent,
+        },
+        UtcDateTime,
+    },
+    referencing::{ExecuteReference, IndexKey, ModelParam, ObjectListIndex, SectionIndex},
+    store::Store,
+    Result,
+};
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Poll {
+    pub(crate) inner: PollEventContent,
+    pub meta: EventMeta,
+}
+
+impl Deref for Poll {
+    type Target = PollEventContent;
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl Poll {
+    pub fn title(&self) -> String {
+        self.inner.title.clone()
+    }
+
+    pub fn room_id(&self) -> &RoomId {
+        &self.meta.room_id
+    }
+
+    pub fn sender(&self) -> &UserId {
+        &self.meta.sender
+    }
+
+    /// Whether the given user may update this poll, given whether they are a
+    /// moderator of the room
+    pub fn can_be_edited_by(&self, user_id: &UserId, is_room_moderator: bool) -> bool {
+        self.inner
+            .edit_rule
+            .allows(&self.meta.sender, user_id, is_room_moderator)
+    }
+
+    /// Whether the closing date has passed at `now`
+    pub fn is_closed_at(&self, now: &UtcDateTime) -> bool {
+        self.inner
+            .utc_closes_at
+            .as_ref()
+            .is_some_and(|closes_at| closes_at <= now)
+    }
+
+    /// The answers of `vote` to count for this poll
+    ///
+    /// `None` if the vote doesn’t count at all: it came in after the poll
+    /// closed, picks unknown answers or more than one on a single choice poll.
+    /// An empty list is a retracted vote.
+    pub fn counted_answers(&self, vote: &PollVote) -> Option<Vec<String>> {
+        if let Some(sent_at) = vote.meta.origin_server_ts.to_system_time() {
+            if self.is_closed_at(&UtcDateTime::from(sent_at)) {
+                return None;
+            }
+        }
+        let mut answers = vote.inner.answers.clone();
+        answers.sort();
+        answers.dedup();
+        if answers.len() > 1 && !self.inner.is_multiple_choice() {
+            return None;
+        }
+        if answers.iter().any(|a| self.inner.answer(a).is_none()) {
+            return None;
+        }
+        Some(answers)
+    }
+
+    pub fn updater(&self) -> PollUpdateBuilder {
+        PollUpdateBuilder::default()
+            .poll(self.meta.event_id.clone())
+            .to_owned()
+    }
+}
+
+impl ActerModel for Poll {
+    fn indizes(&self, _user_id: &UserId) -> Vec<IndexKey> {
+        vec![
+            IndexKey::RoomSection(self.meta.room_id.clone(), SectionIndex::Polls),
+            IndexKey::Section(SectionIndex::Polls),
+            IndexKey::ObjectHistory(self.meta.event_id.clone()),
+            IndexKey::RoomHistory(self.meta.room_id.clone()),
+            IndexKey::AllHistory,
+        ]
+    }
+
+    fn event_meta(&self) -> &EventMeta {
+        &self.meta
+    }
+
+    fn capabilities(&self) -> &[Capability] {
+        &[
+            Capability::Commentable,
+            Capability::Attachmentable,
+            Capability::Reactable,
+        ]
+    }
+
+    async fn execute(self, store: &Store) -> Result<Vec<ExecuteReference>> {
+        default_model_execute(store, self.into()).await
+    }
+
+    fn transition(&mut self, model: &AnyActerModel) -> Result<bool> {
+        let AnyActerModel::PollUpdate(update) = model else {
+            return Ok(false);
+        };
+
+        update.apply(&mut self.inner)
+    }
+}
+
+impl From<OriginalMessageLikeEvent<PollEventContent>> for Poll {
+    fn from(outer: OriginalMessageLikeEvent<PollEventContent>) -> Self {
+        let OriginalMessageLikeEvent {
+            content,
+            room_id,
+            event_id,
+            sender,
+            origin_server_ts,
+            ..
+        } = outer;
+        Poll {
+            inner: content,
+            meta: EventMeta {
+                room_id,
+                event_id,
+                sender,
+                origin_server_ts,
+                redacted: None,
+            },
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct PollUpdate {
+    pub(crate) inner: PollUpdateEventContent,
+    meta: EventMeta,
+}
+
+impl ActerModel for PollUpdate {
+    fn indizes(&self, _user_id: &UserId) -> Vec<IndexKey> {
+        vec![
+            IndexKey::ObjectHistory(self.inner.poll.event_id.clone()),
+            IndexKey::RoomHistory(self.meta.room_id.clone()),
+            IndexKey::AllHistory,
+        ]
+    }
+
+    fn event_meta(&self) -> &EventMeta {
+        &self.meta
+    }
+
+    async fn execute(self, store: &Store) -> Result<Vec<ExecuteReference>> {
+        let poll_id = self.inner.poll.event_id.clone();
+        let mut updates = execute_update(store, &poll_id, self.into()).await?;
+        // a moved closing date changes which votes count
+        if let AnyActerModel::Poll(poll) = store.get(&poll_id).await? {
+            let mut manager = PollVotesManager::from_store_and_event_id(store, &poll_id).await;
+            if manager.recalculate(&poll).await? {
+                updates.push(manager.save().await?);
+            }
+        }
+        Ok(updates)
+    }
+
+    fn belongs_to(&self) -> Option<Vec<OwnedEventId>> {
+        Some(vec![self.inner.poll.event_id.clone()])
+    }
+}
+
+impl Deref for PollUpdate {
+    type Target = PollUpdateEventContent;
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl From<OriginalMessageLikeEvent<PollUpdateEventContent>> for PollUpdate {
+    fn from(outer: OriginalMessageLikeEvent<PollUpdateEventContent>) -> Self {
+        let OriginalMessageLikeEvent {
+            content,
+            room_id,
+            event_id,
+            sender,
+            origin_server_ts,
+            ..
+        } = outer;
+        PollUpdate {
+            inner: content,
+            meta: EventMeta {
+                room_id,
+                event_id,
+                sender,
+                origin_server_ts,
+                redacted: None,
+            },
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize, Getters)]
+pub struct PollVoteStats {
+    /// how many users have a counted vote
+    total_voters: u32,
+    /// the number of votes per answer id
+    tallies: BTreeMap<String, u32>,
+}
+
+impl PollVoteStats {
+    pub fn votes_for(&self, answer_id: &str) -> u32 {
+        self.tallies.get(answer_id).copied().unwrap_or_default()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct PollVotesManager {
+    stats: PollVoteStats,
+    event_id: OwnedEventId,
+    store: Store,
+}
+
+impl PollVotesManager {
+    fn stats_field_for(parent: OwnedEventId) -> ExecuteReference {
+        ExecuteReference::ModelParam(parent, ModelParam::PollVoteStats)
+    }
+
+    pub async fn from_store_and_event_id(store: &Store, event_id: &EventId) -> PollVotesManager {
+        let store = store.clone();
+        let stats = store
+            .get_raw(&Self::stats_field_for(event_id.to_owned()).as_storage_key())
+            .await
+            .unwrap_or_default();
+        PollVotesManager {
+            store,
+            stats,
+            event_id: event_id.to_owned(),
+        }
+    }
+
+    pub fn event_id(&self) -> OwnedEventId {
+        self.event_id.clone()
+    }
+
+    /// The vote counted for each user, with the answers it counts for
+    ///
+    /// Only the latest valid vote of a user counts, retracted votes are left out.
+    pub async fn votes(
+        &self,
+        poll: &Poll,
+    ) -> Result<HashMap<OwnedUserId, (PollVote, Vec<String>)>> {
+        let mut entries = HashMap::new();
+        for mdl in self
+            .store
+            .get_list(&PollVote::index_for(self.event_id.clone()))
+            .await?
+        {
+            let AnyActerModel::PollVote(vote) = mdl else {
+                continue;
+            };
+            if entries.contains_key(&vote.meta.sender) {
+                continue; // we ignore older entries
+            }
+            if let Some(answers) = poll.counted_answers(&vote) {
+                entries.insert(vote.meta.sender.clone(), (vote, answers));
+            }
+        }
+        entries.retain(|_, (_, answers)| !answers.is_empty());
+        Ok(entries)
+    }
+
+    /// Recount the votes, returns whether the tallies changed
+    pub(crate) async fn recalculate(&mut self, poll: &Poll) -> Result<bool> {
+        let votes = self.votes(poll).await?;
+        let mut stats = PollVoteStats {
+            total_voters: votes.len() as u32,
+            ..Default::default()
+        };
+        for answer in votes.into_values().flat_map(|(_, answers)| answers) {
+            *stats.tallies.entry(answer).or_default() += 1;
+        }
+        let changed = stats != self.stats;
+        self.stats = stats;
+        Ok(changed)
+    }
+
+    pub fn stats(&self) -> &PollVoteStats {
+        &self.stats
+    }
+
+    pub fn draft_builder(&self) -> PollVoteBuilder {
+        PollVoteBuilder::default()
+            .to(self.event_id.clone())
+            .to_owned()
+    }
+
+    pub fn update_key(&self) -> ExecuteReference {
+        Self::stats_field_for(self.event_id.clone())
+    }
+
+    pub async fn save(&self) -> Result<ExecuteReference> {
+        let update_key = self.update_key();
+        self.store
+            .set_raw(&update_key.as_storage_key(), &self.stats)
+            .await?;
+        Ok(update_key)
+    }
+}
+
+impl Deref for PollVotesManager {
+    type Target = PollVoteStats;
+    fn deref(&self) -> &Self::Target {
+        &self.stats
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct PollVote {
+    pub(crate) inner: PollVoteEventContent,
+    pub meta: EventMeta,
+}
+
+impl Deref for PollVote {
+    type Target = PollVoteEventContent;
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl PollVote {
+    pub fn index_for(parent: OwnedEventId) -> IndexKey {
+        IndexKey::ObjectList(parent, ObjectListIndex::Votes)
+    }
+}
+
+impl ActerModel for PollVote {
+    fn indizes(&self, _user_id: &UserId) -> Vec<IndexKey> {
+        vec![
+            PollVote::index_for(self.inner.to.event_id.clone()),
+            IndexKey::ObjectHistory(self.inner.to.event_id.clone()),
+            IndexKey::RoomHistory(self.meta.room_id.clone()),
+            IndexKey::AllHistory,
+        ]
+    }
+
+    fn event_meta(&self) -> &EventMeta {
+        &self.meta
+    }
+
+    async fn execute(self, store: &Store) -> Result<Vec<ExecuteReference>> {
+        let belongs_to = self.inner.to.event_id.clone();
+        trace!(event_id=?self.event_id(), ?belongs_to, "applying vote");
+
+        let poll = match store.get(&belongs_to).await? {
+            AnyActerModel::Poll(poll) => Some(poll),
+            model => {
+                error!(?model, vote = ?self, "not a poll. can’t apply");
+                None
+            }
+        };
+
+        let mut updates = store.save(self.clone().into()).await?;
+        trace!(event_id=?self.event_id(), "saved vote");
+        if let Some(poll) = poll {
+            let mut manager = PollVotesManager::from_store_and_event_id(store, &belongs_to).await;
+            if manager.recalculate(&poll).await? {
+                updates.push(manager.save().await?);
+            }
+        }
+        Ok(updates)
+    }
+
+    fn belongs_to(&self) -> Option<Vec<OwnedEventId>> {
+        // the higher ups don’t need to be bothered by this
+        None
+    }
+}
+
+impl From<OriginalMessageLikeEvent<PollVoteEventContent>> for PollVote {
+    fn from(outer: OriginalMessageLikeEvent<PollVoteEventContent>) -> Self {
+        let OriginalMessageLikeEvent {
+            content,
+            room_id,
+            event_id,
+            sender,
+            origin_server_ts,
+            ..
+        } = outer;
+        PollVote {
+            inner: content,
+            meta: EventMeta {
+                room_id,
+                event_id,
+                sender,
+                origin_server_ts,
+                redacted: None,
+            },
+        }
+    }
+}
@@ -8,7 +8,10 @@ use tracing::{error, trace};
 
 use super::{ActerModel, AnyActerModel, Capability, EventMeta};
 use crate::{
-    events::rsvp::{RsvpBuilder, RsvpEventContent},
+    events::{
+        rsvp::{RsvpBuilder, RsvpEventContent},
+        UtcDateTime,
+    },
     referencing::{ExecuteReference, IndexKey, ModelParam, ObjectListIndex},
     store::Store,
     Result,
@@ -50,6 +53,17 @@ impl RsvpManager {
     }
 
     pub async fn rsvp_entries(&self) -> Result<HashMap<OwnedUserId, Rsvp>> {
+        self.rsvp_entries_for(None).await
+    }
+
+    /// The responses valid for the given occurrence of a recurring event
+    ///
+    /// Series-wide responses apply to every occurrence, unless the user
+    /// responded to that specific occurrence more recently.
+    pub async fn rsvp_entries_for(
+        &self,
+        occurrence: Option<&UtcDateTime>,
+    ) -> Result<HashMap<OwnedUserId, Rsvp>> {
         let mut entries = HashMap::new();
         for mdl in self
             .store
@@ -57,6 +71,10 @@ impl RsvpManager {
             .await?
         {
             if let AnyActerModel::Rsvp(c) = mdl {
+                if c.inner.occurrence.is_some() && c.inner.occurrence.as_ref() != occurrence {
+                    // response for a different occurrence
+                    continue;
+                }
                 let key = c.clone().meta.sender;
                 entries.entry(key).or_insert(c); // we ignore older entries
             }
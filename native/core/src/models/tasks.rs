@@ -1,5 +1,8 @@
 mod task;
 mod task_list;
 
-pub use task::{Task, TaskSelfAssign, TaskSelfUnassign, TaskUpdate};
+pub use task::{
+    ensure_no_task_cycle, next_task_instance, Task, TaskAssign, TaskSelfAssign, TaskSelfUnassign,
+    TaskUnassign, TaskUpdate,
+};
 pub use task_list::{TaskList, TaskListUpdate, TaskStats};
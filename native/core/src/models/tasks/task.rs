@@ -1,19 +1,35 @@
+use chrono::{Duration, NaiveDate, NaiveTime};
+use icalendar::{Component, Property, Todo as iCalTodo};
 use matrix_sdk::ruma::OwnedEventId;
-use matrix_sdk_base::ruma::{events::OriginalMessageLikeEvent, OwnedUserId, RoomId, UserId};
+use matrix_sdk_base::ruma::{
+    events::{Mentions, OriginalMessageLikeEvent},
+    EventId, OwnedUserId, RoomId, UserId,
+};
 use serde::{Deserialize, Serialize};
-use std::ops::Deref;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    ops::Deref,
+};
+use tracing::warn;
 
 use super::super::{
-    default_model_execute, ActerModel, AnyActerModel, Capability, EventMeta, Store,
+    default_model_execute, execute_update, may_edit, ActerModel, AnyActerModel, Capability,
+    EventMeta, Store, TaskList,
 };
 use crate::{
-    events::tasks::{
-        TaskEventContent, TaskSelfAssignEventContent, TaskSelfUnassignEventContent,
-        TaskUpdateBuilder, TaskUpdateEventContent,
+    events::{
+        calendar::format_ical_date_time,
+        tasks::{
+            Priority, TaskAssignEventContent, TaskBuilder, TaskEventContent,
+            TaskSelfAssignEventContent, TaskSelfUnassignEventContent, TaskUnassignEventContent,
+            TaskUpdateBuilder, TaskUpdateEventContent,
+        },
+        EditRule,
     },
-    models::InvitationsManager,
+    history::keep_original_state,
+    models::{InvitationsManager, RecurrenceIter},
     referencing::{ExecuteReference, IndexKey, ObjectListIndex, SpecialListsIndex},
-    Result,
+    Error, Result,
 };
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -23,6 +39,22 @@ pub struct Task {
 
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     assignees: Vec<OwnedUserId>,
+
+    /// the progress of each subtask, the own progress is derived from it
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    subtasks: BTreeMap<OwnedEventId, u8>,
+
+    /// the workflow states of the task list that count as done
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    done_states: BTreeSet<String>,
+
+    /// the edit rule of the task list, its tasks follow it
+    #[serde(default, skip_serializing_if = "EditRule::is_anyone")]
+    edit_rule: EditRule,
+
+    /// the author of the task list, as the edit rule refers to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    list_author: Option<OwnedUserId>,
 }
 
 impl Deref for Task {
@@ -49,19 +81,77 @@ impl Task {
         &self.meta.sender
     }
 
-    pub fn is_done(&self) -> bool {
+    /// The iCalendar `UID`, the one it was imported with or the event id
+    pub fn ical_uid(&self) -> &str {
         self.inner
-            .progress_percent
-            .map(|u| u >= 100)
-            .unwrap_or_default()
+            .ical_uid
+            .as_deref()
+            .unwrap_or(self.meta.event_id.as_str())
+    }
+
+    /// Fully progressed or in a workflow state that counts as done
+    pub fn is_done(&self) -> bool {
+        self.percent().map(|u| u >= 100).unwrap_or_default()
+            || self
+                .inner
+                .status
+                .as_ref()
+                .is_some_and(|s| self.done_states.contains(s))
+    }
+
+    /// Take over the done states and the edit rule of the task list, returns
+    /// whether they changed
+    pub(crate) fn follow_list(&mut self, list: &TaskList) -> bool {
+        let done_states = list.done_states();
+        let list_author = (!list.edit_rule.is_anyone()).then(|| list.sender().to_owned());
+        if self.done_states == done_states
+            && self.edit_rule == list.edit_rule
+            && self.list_author == list_author
+        {
+            return false;
+        }
+        self.done_states = done_states;
+        self.edit_rule = list.edit_rule.clone();
+        self.list_author = list_author;
+        true
+    }
+
+    /// Whether the given user may update this task, as the task list allows,
+    /// given whether they are a moderator of the room
+    pub fn can_be_edited_by(&self, user_id: &UserId, is_room_moderator: bool) -> bool {
+        self.list_author.as_deref().map_or(true, |author| {
+            self.edit_rule.allows(author, user_id, is_room_moderator)
+        })
+    }
+
+    pub fn parent_task_id(&self) -> Option<OwnedEventId> {
+        self.inner.parent_task.as_ref().map(|p| p.event_id.clone())
+    }
+
+    pub fn has_subtasks(&self) -> bool {
+        !self.subtasks.is_empty()
+    }
+
+    pub fn subtasks_key(&self) -> IndexKey {
+        IndexKey::ObjectList(self.meta.event_id.clone(), ObjectListIndex::Subtasks)
+    }
+
+    /// The tasks this task is blocking, the reverse of `blocked_by`
+    pub fn blocking_key(&self) -> IndexKey {
+        IndexKey::ObjectList(self.meta.event_id.clone(), ObjectListIndex::Blocking)
     }
 
     pub fn is_assigned(&self, user_id: &UserId) -> bool {
         self.assignees.iter().any(|o| o == user_id)
     }
 
+    /// The progress, derived from the subtasks if there are any
     pub fn percent(&self) -> Option<u8> {
-        self.inner.progress_percent
+        if self.subtasks.is_empty() {
+            return self.inner.progress_percent;
+        }
+        let total: u32 = self.subtasks.values().map(|p| *p as u32).sum();
+        Some((total / self.subtasks.len() as u32) as u8)
     }
 
     pub fn due_date(&self) -> Option<String> {
@@ -81,6 +171,100 @@ impl Task {
             .map(|d| d.to_rfc3339_opts(chrono::SecondsFormat::Secs, true))
     }
 
+    /// The task as iCal todo, only tasks with a due date are to be found in calendars
+    pub fn as_ical_todo(&self) -> Option<iCalTodo> {
+        let due_date = self.inner.due_date?;
+        let mut todo = iCalTodo::new();
+        todo.uid(self.ical_uid())
+            .summary(&self.inner.title)
+            .add_property(
+                "STATUS",
+                if self.is_done() {
+                    "COMPLETED"
+                } else {
+                    "NEEDS-ACTION"
+                },
+            );
+        match self.inner.utc_due_time_of_day {
+            Some(seconds) => {
+                let due =
+                    due_date.and_time(NaiveTime::MIN).and_utc() + Duration::seconds(seconds as i64);
+                todo.add_property("DUE", format_ical_date_time(&due));
+            }
+            None => {
+                todo.append_property(
+                    Property::new("DUE", due_date.format("%Y%m%d").to_string())
+                        .add_parameter("VALUE", "DATE")
+                        .done(),
+                );
+            }
+        }
+        if let Some(utc_start) = &self.inner.utc_start {
+            todo.add_property("DTSTART", format_ical_date_time(utc_start));
+        }
+        if let Some(percent) = self.inner.progress_percent {
+            todo.add_property("PERCENT-COMPLETE", percent.min(100).to_string());
+        }
+        if self.inner.priority != Priority::Undefined {
+            todo.add_property("PRIORITY", (self.inner.priority.clone() as u8).to_string());
+        }
+        if !self.inner.categories.is_empty() {
+            todo.add_property("CATEGORIES", self.inner.categories.join(","));
+        }
+        if let Some(rule) = &self.inner.recurrence_rule {
+            todo.add_property("RRULE", rule.to_string());
+        }
+        if let Some(msg) = &self.inner.description {
+            todo.description(&msg.body);
+        }
+        Some(todo.done())
+    }
+
+    pub fn is_recurring(&self) -> bool {
+        self.inner.recurrence_rule.is_some()
+    }
+
+    /// The next instance of this recurring task, due on the first occurrence
+    /// after this one's due date and after `after`
+    ///
+    /// `None` if the task doesn’t repeat, has no due date or the recurrence is over.
+    pub fn next_instance(&self, after: NaiveDate) -> Option<TaskBuilder> {
+        let mut rule = self.inner.recurrence_rule.clone()?;
+        let due_date = self.inner.due_date?;
+        let dtstart = due_date.and_time(NaiveTime::MIN).and_utc();
+        let (index, next) = RecurrenceIter::new(rule.clone(), dtstart)
+            .enumerate()
+            .find(|(_, o)| o.date_naive() > due_date && o.date_naive() > after)?;
+        // the next instance starts the rule anew, so only the remaining ones are left
+        if let Some(count) = rule.count {
+            rule.count = Some(count.saturating_sub(index as u32));
+        }
+
+        let mut builder = TaskBuilder::default();
+        builder
+            .title(self.inner.title.clone())
+            .task_list_id(self.inner.task_list_id.event_id.clone())
+            .parent_task(self.inner.parent_task.clone())
+            .description(self.inner.description.clone())
+            .due_date(Some(next.date_naive()))
+            .utc_due_time_of_day(self.inner.utc_due_time_of_day)
+            .sort_order(self.inner.sort_order)
+            .priority(self.inner.priority.clone())
+            .display(self.inner.display.clone())
+            .keywords(self.inner.keywords.clone())
+            .categories(self.inner.categories.clone())
+            .recurrence_rule(Some(rule))
+            .previous_instance(Some(self.meta.event_id.clone()));
+        Some(builder)
+    }
+
+    /// This task as it will be once the update is applied
+    pub fn updated(&self, update: &TaskUpdateEventContent) -> Result<Task> {
+        let mut task = self.clone();
+        update.apply(&mut task.inner)?;
+        Ok(task)
+    }
+
     pub fn updater(&self) -> TaskUpdateBuilder {
         TaskUpdateBuilder::default()
             .task(self.meta.event_id.clone())
@@ -98,6 +282,14 @@ impl Task {
             task: self.meta.event_id.clone().into(),
         }
     }
+
+    pub fn assign_event_content(&self, assignee: OwnedUserId) -> TaskAssignEventContent {
+        TaskAssignEventContent::new(self.meta.event_id.clone(), assignee)
+    }
+
+    pub fn unassign_event_content(&self, assignee: OwnedUserId) -> TaskUnassignEventContent {
+        TaskUnassignEventContent::new(self.meta.event_id.clone(), assignee)
+    }
 }
 
 impl ActerModel for Task {
@@ -112,6 +304,28 @@ impl ActerModel for Task {
             IndexKey::ObjectHistory(self.inner.task_list_id.event_id.clone()),
             IndexKey::AllHistory,
         ];
+        if let Some(status) = &self.inner.status {
+            indizes.push(IndexKey::TaskStatus(
+                self.inner.task_list_id.event_id.clone(),
+                status.clone(),
+            ));
+        }
+        if let Some(previous) = &self.inner.previous_instance {
+            // keep the history of the recurring task together
+            indizes.push(IndexKey::ObjectHistory(previous.clone()));
+        }
+        if let Some(parent) = &self.inner.parent_task {
+            indizes.push(IndexKey::ObjectList(
+                parent.event_id.clone(),
+                ObjectListIndex::Subtasks,
+            ));
+        }
+        for blocker in &self.inner.blocked_by {
+            indizes.push(IndexKey::ObjectList(
+                blocker.clone(),
+                ObjectListIndex::Blocking,
+            ));
+        }
         if self.is_assigned(user_id) {
             indizes.push(if self.is_done() {
                 IndexKey::Special(SpecialListsIndex::MyDoneTasks)
@@ -119,6 +333,9 @@ impl ActerModel for Task {
                 IndexKey::Special(SpecialListsIndex::MyOpenTasks)
             });
         }
+        if self.mentions_user(user_id) {
+            indizes.push(IndexKey::Special(SpecialListsIndex::MyMentions));
+        }
         indizes
     }
 
@@ -126,6 +343,10 @@ impl ActerModel for Task {
         &self.meta
     }
 
+    fn mentions(&self) -> Option<&Mentions> {
+        self.inner.mentions.as_ref()
+    }
+
     fn capabilities(&self) -> &[Capability] {
         &[
             Capability::Commentable,
@@ -135,19 +356,57 @@ impl ActerModel for Task {
         ]
     }
 
-    async fn execute(self, store: &Store) -> Result<Vec<ExecuteReference>> {
-        default_model_execute(store, self.into()).await
+    async fn execute(mut self, store: &Store) -> Result<Vec<ExecuteReference>> {
+        if let Ok(AnyActerModel::TaskList(list)) =
+            store.get(&self.inner.task_list_id.event_id).await
+        {
+            if !may_edit(store, list.event_id(), &self.meta.sender).await? {
+                warn!(task = ?self.meta.event_id, "dropping task not allowed by edit rule of list");
+                return Ok(vec![]);
+            }
+            self.follow_list(&list);
+        }
+        let model = AnyActerModel::from(self);
+        keep_original_state(store, &model).await?;
+        default_model_execute(store, model).await
     }
 
     fn belongs_to(&self) -> Option<Vec<OwnedEventId>> {
-        Some(vec![self.inner.task_list_id.event_id.clone()])
+        let mut parents = vec![self.inner.task_list_id.event_id.clone()];
+        if let Some(parent) = &self.inner.parent_task {
+            parents.push(parent.event_id.clone());
+        }
+        Some(parents)
     }
 
     fn transition(&mut self, model: &AnyActerModel) -> Result<bool> {
         match model {
+            AnyActerModel::Task(subtask) => {
+                if subtask.inner.parent_task.as_ref().map(|p| &p.event_id)
+                    == Some(&self.meta.event_id)
+                {
+                    let progress = if subtask.is_done() {
+                        100
+                    } else {
+                        subtask.percent().unwrap_or_default().min(100)
+                    };
+                    let previous = self
+                        .subtasks
+                        .insert(subtask.meta.event_id.clone(), progress);
+                    Ok(previous != Some(progress))
+                } else {
+                    // moved away from us
+                    Ok(self.subtasks.remove(&subtask.meta.event_id).is_some())
+                }
+            }
+            AnyActerModel::RedactedActerModel(redacted) => {
+                Ok(self.subtasks.remove(redacted.event_id()).is_some())
+            }
             AnyActerModel::TaskUpdate(update) => update.apply(&mut self.inner),
             AnyActerModel::TaskSelfAssign(update) => update.apply(self),
             AnyActerModel::TaskSelfUnassign(update) => update.apply(self),
+            AnyActerModel::TaskAssign(update) => update.apply(self),
+            AnyActerModel::TaskUnassign(update) => update.apply(self),
             _ => Ok(false),
         }
     }
@@ -166,6 +425,10 @@ impl From<OriginalMessageLikeEvent<TaskEventContent>> for Task {
         Task {
             inner: content,
             assignees: Vec::with_capacity(0),
+            subtasks: Default::default(),
+            done_states: Default::default(),
+            edit_rule: Default::default(),
+            list_author: None,
             meta: EventMeta {
                 room_id,
                 event_id,
@@ -177,6 +440,93 @@ impl From<OriginalMessageLikeEvent<TaskEventContent>> for Task {
     }
 }
 
+/// The next instance to create once `update` completes this recurring task
+///
+/// `None` if the task doesn’t repeat, the update doesn’t complete it or its
+/// next instance exists already, e.g. because it was reopened and done again.
+/// The next instance starts over in the first open state of the task list.
+/// Must be called before the update is applied, so it sees it completing.
+pub async fn next_task_instance(
+    store: &Store,
+    task: &Task,
+    update: &TaskUpdateEventContent,
+    today: NaiveDate,
+) -> Result<Option<TaskBuilder>> {
+    if task.is_done() {
+        return Ok(None);
+    }
+    let updated = task.updated(update)?;
+    if !updated.is_done() {
+        return Ok(None);
+    }
+    let Some(mut next_instance) = updated.next_instance(today) else {
+        return Ok(None);
+    };
+
+    let task_list_id = task.inner.task_list_id.event_id.clone();
+    let tasks_key = IndexKey::ObjectList(task_list_id.clone(), ObjectListIndex::Tasks);
+    let exists = store.get_list(&tasks_key).await?.any(|m| match m {
+        AnyActerModel::Task(t) => t.inner.previous_instance.as_ref() == Some(&task.meta.event_id),
+        _ => false,
+    });
+    if exists {
+        return Ok(None);
+    }
+
+    let first_open = match store.get(&task_list_id).await {
+        Ok(AnyActerModel::TaskList(list)) => list
+            .workflow
+            .iter()
+            .find(|s| !s.is_done)
+            .map(|s| s.key.clone()),
+        _ => None,
+    };
+    next_instance.status(first_open);
+    Ok(Some(next_instance))
+}
+
+/// Ensure making `parent` the parent of `task_id` and having it blocked by
+/// `blocked_by` doesn’t create a cycle with the tasks known to the store.
+pub async fn ensure_no_task_cycle(
+    store: &Store,
+    task_id: &EventId,
+    parent: Option<&OwnedEventId>,
+    blocked_by: &[OwnedEventId],
+) -> Result<()> {
+    let mut seen = BTreeSet::new();
+    let mut next = parent.cloned();
+    while let Some(current) = next.take() {
+        if &*current == task_id {
+            return Err(Error::Custom(
+                "A task can’t be a subtask of itself or its own subtasks".to_owned(),
+            ));
+        }
+        if !seen.insert(current.clone()) {
+            break;
+        }
+        if let Ok(AnyActerModel::Task(task)) = store.get(&current).await {
+            next = task.parent_task_id();
+        }
+    }
+
+    let mut seen = BTreeSet::new();
+    let mut pending = blocked_by.to_vec();
+    while let Some(current) = pending.pop() {
+        if &*current == task_id {
+            return Err(Error::Custom(
+                "A task can’t be blocked by itself or the tasks it is blocking".to_owned(),
+            ));
+        }
+        if !seen.insert(current.clone()) {
+            continue;
+        }
+        if let Ok(AnyActerModel::Task(task)) = store.get(&current).await {
+            pending.extend(task.inner.blocked_by.iter().cloned());
+        }
+    }
+    Ok(())
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct TaskUpdate {
     pub(crate) inner: TaskUpdateEventContent,
@@ -184,20 +534,29 @@ pub struct TaskUpdate {
 }
 
 impl ActerModel for TaskUpdate {
-    fn indizes(&self, _user_id: &UserId) -> Vec<IndexKey> {
-        vec![
+    fn indizes(&self, user_id: &UserId) -> Vec<IndexKey> {
+        let mut indizes = vec![
             IndexKey::ObjectHistory(self.inner.task.event_id.clone()),
             IndexKey::RoomHistory(self.meta.room_id.clone()),
             IndexKey::AllHistory,
-        ]
+        ];
+        if self.mentions_user(user_id) {
+            indizes.push(IndexKey::Special(SpecialListsIndex::MyMentions));
+        }
+        indizes
     }
 
     fn event_meta(&self) -> &EventMeta {
         &self.meta
     }
 
+    fn mentions(&self) -> Option<&Mentions> {
+        self.inner.mentions.as_ref()
+    }
+
     async fn execute(self, store: &Store) -> Result<Vec<ExecuteReference>> {
-        default_model_execute(store, self.into()).await
+        let task_id = self.inner.task.event_id.clone();
+        execute_update(store, &task_id, self.into()).await
     }
 
     fn belongs_to(&self) -> Option<Vec<OwnedEventId>> {
@@ -268,6 +627,10 @@ impl ActerModel for TaskSelfAssign {
     async fn execute(self, store: &Store) -> Result<Vec<ExecuteReference>> {
         let belongs_to = self.inner.task.event_id.clone();
         let sender = self.meta.sender.clone();
+        if !may_edit(store, &belongs_to, &sender).await? {
+            warn!(update = ?self.meta.event_id, "dropping assignment not allowed by edit rule");
+            return Ok(vec![]);
+        }
         let manager = {
             let mut manager = InvitationsManager::from_store_and_event_id(store, &belongs_to).await;
             if manager.mark_as_accepted(sender) {
@@ -386,3 +749,210 @@ impl From<OriginalMessageLikeEvent<TaskSelfUnassignEventContent>> for TaskSelfUn
         }
     }
 }
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct TaskAssign {
+    pub(crate) inner: TaskAssignEventContent,
+    meta: EventMeta,
+}
+
+impl TaskAssign {
+    fn apply(&self, task: &mut Task) -> Result<bool> {
+        let user_id = self.inner.assignee.clone();
+        // remove any existing instance of the user in the list.
+        task.assignees.retain(|u| u != &user_id);
+        // add it at the new first entry;
+        task.assignees.insert(0, user_id);
+        Ok(true)
+    }
+}
+
+impl ActerModel for TaskAssign {
+    fn indizes(&self, _user_id: &UserId) -> Vec<IndexKey> {
+        vec![
+            IndexKey::ObjectHistory(self.inner.task.event_id.clone()),
+            IndexKey::RoomHistory(self.meta.room_id.clone()),
+            IndexKey::AllHistory,
+        ]
+    }
+
+    fn event_meta(&self) -> &EventMeta {
+        &self.meta
+    }
+
+    async fn execute(self, store: &Store) -> Result<Vec<ExecuteReference>> {
+        let task_id = self.inner.task.event_id.clone();
+        execute_update(store, &task_id, self.into()).await
+    }
+
+    fn belongs_to(&self) -> Option<Vec<OwnedEventId>> {
+        Some(vec![self.inner.task.event_id.clone()])
+    }
+}
+
+impl From<OriginalMessageLikeEvent<TaskAssignEventContent>> for TaskAssign {
+    fn from(outer: OriginalMessageLikeEvent<TaskAssignEventContent>) -> Self {
+        let OriginalMessageLikeEvent {
+            content,
+            room_id,
+            event_id,
+            sender,
+            origin_server_ts,
+            ..
+        } = outer;
+        TaskAssign {
+            inner: content,
+            meta: EventMeta {
+                room_id,
+                event_id,
+                sender,
+                origin_server_ts,
+                redacted: None,
+            },
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct TaskUnassign {
+    pub(crate) inner: TaskUnassignEventContent,
+    meta: EventMeta,
+}
+
+impl TaskUnassign {
+    fn apply(&self, task: &mut Task) -> Result<bool> {
+        let user_id = self.inner.assignee.clone();
+        // remove the user from the list.
+        task.assignees.retain(|u| u != &user_id);
+        Ok(true)
+    }
+}
+
+impl ActerModel for TaskUnassign {
+    fn indizes(&self, _user_id: &UserId) -> Vec<IndexKey> {
+        vec![
+            IndexKey::ObjectHistory(self.inner.task.event_id.clone()),
+            IndexKey::RoomHistory(self.meta.room_id.clone()),
+            IndexKey::AllHistory,
+        ]
+    }
+
+    fn event_meta(&self) -> &EventMeta {
+        &self.meta
+    }
+
+    async fn execute(self, store: &Store) -> Result<Vec<ExecuteReference>> {
+        let task_id = self.inner.task.event_id.clone();
+        execute_update(store, &task_id, self.into()).await
+    }
+
+    fn belongs_to(&self) -> Option<Vec<OwnedEventId>> {
+        Some(vec![self.inner.task.event_id.clone()])
+    }
+}
+
+impl From<OriginalMessageLikeEvent<TaskUnassignEventContent>> for TaskUnassign {
+    fn from(outer: OriginalMessageLikeEvent<TaskUnassignEventContent>) -> Self {
+        let OriginalMessageLikeEvent {
+            content,
+            room_id,
+            event_id,
+            sender,
+            origin_server_ts,
+            ..
+        } = outer;
+        TaskUnassign {
+            inner: content,
+            meta: EventMeta {
+                room_id,
+                event_id,
+                sender,
+                origin_server_ts,
+                redacted: None,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::AnyActerEvent;
+
+    fn recurring_task(due_date: &str, rrule: serde_json::Value) -> Task {
+        let event: AnyActerEvent = serde_json::from_value(serde_json::json!({
+            "type": "global.acter.dev.task",
+            "room_id": "!room:example.org",
+            "sender": "@test:example.org",
+            "event_id": "$kitchen",
+            "origin_server_ts": 123456,
+            "content": {
+                "title": "Clean the kitchen",
+                "m.relates_to": {
+                    "rel_type": "global.acter.dev.belongs_to",
+                    "event_id": "$chores",
+                },
+                "due_date": due_date,
+                "priority": 2,
+                "recurrence_rule": rrule,
+            },
+        }))
+        .unwrap();
+        let AnyActerModel::Task(task) = AnyActerModel::try_from(event).unwrap() else {
+            panic!("not a task");
+        };
+        task
+    }
+
+    #[test]
+    fn next_instance_of_weekly_task() -> Result<()> {
+        // a monday
+        let task = recurring_task(
+            "2024-01-01",
+            serde_json::json!({ "frequency": "weekly", "by_day": [{ "day": "mo" }] }),
+        );
+        let next = task
+            .next_instance(NaiveDate::from_ymd_opt(2024, 1, 1).unwrap())
+            .expect("repeats")
+            .build()
+            .unwrap();
+        assert_eq!(next.due_date, NaiveDate::from_ymd_opt(2024, 1, 8));
+        assert_eq!(next.title, "Clean the kitchen");
+        assert_eq!(next.priority, Priority::SecondHighest);
+        assert_eq!(next.previous_instance.as_deref(), Some(task.event_id()));
+        assert_eq!(next.progress_percent, None);
+
+        // done late, we skip to the next one that isn't overdue
+        let next = task
+            .next_instance(NaiveDate::from_ymd_opt(2024, 1, 10).unwrap())
+            .expect("repeats")
+            .build()
+            .unwrap();
+        assert_eq!(next.due_date, NaiveDate::from_ymd_opt(2024, 1, 15));
+        Ok(())
+    }
+
+    #[test]
+    fn next_instance_keeps_count() -> Result<()> {
+        let task = recurring_task(
+            "2024-01-01",
+            serde_json::json!({ "frequency": "daily", "count": 2 }),
+        );
+        let next = task
+            .next_instance(NaiveDate::from_ymd_opt(2024, 1, 1).unwrap())
+            .expect("repeats once more")
+            .build()
+            .unwrap();
+        assert_eq!(next.due_date, NaiveDate::from_ymd_opt(2024, 1, 2));
+        assert_eq!(next.recurrence_rule.as_ref().and_then(|r| r.count), Some(1));
+
+        let last = recurring_task(
+            "2024-01-02",
+            serde_json::json!({ "frequency": "daily", "count": 1 }),
+        );
+        assert!(last
+            .next_instance(NaiveDate::from_ymd_opt(2024, 1, 2).unwrap())
+            .is_none());
+        Ok(())
+    }
+}
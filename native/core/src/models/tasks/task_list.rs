@@ -2,11 +2,12 @@ use derive_getters::Getters;
 use matrix_sdk::ruma::OwnedEventId;
 use matrix_sdk_base::ruma::{events::OriginalMessageLikeEvent, RoomId, UserId};
 use serde::{Deserialize, Serialize};
-use std::ops::Deref;
+use std::{collections::BTreeSet, ops::Deref};
 use tracing::{trace, warn};
 
 use super::super::{
-    default_model_execute, ActerModel, AnyActerModel, Capability, EventMeta, Store,
+    default_model_execute, is_stale_update, may_edit, ActerModel, AnyActerModel, Capability,
+    EventMeta, Store,
 };
 use crate::{
     events::tasks::{TaskListEventContent, TaskListUpdateBuilder, TaskListUpdateEventContent},
@@ -25,6 +26,9 @@ pub struct TaskList {
     pub(crate) inner: TaskListEventContent,
     meta: EventMeta,
     task_stats: TaskStats,
+    /// the tasks already counted in the stats
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    task_ids: BTreeSet<OwnedEventId>,
 }
 
 impl Deref for TaskList {
@@ -47,6 +51,11 @@ impl TaskList {
         IndexKey::ObjectList(self.meta.event_id.clone(), ObjectListIndex::Tasks)
     }
 
+    /// the tasks in the given workflow state
+    pub fn status_key(&self, status: String) -> IndexKey {
+        IndexKey::TaskStatus(self.meta.event_id.clone(), status)
+    }
+
     pub fn sender(&self) -> &UserId {
         &self.meta.sender
     }
@@ -55,6 +64,24 @@ impl TaskList {
         false
     }
 
+    /// The keys of the workflow states that count as done
+    pub fn done_states(&self) -> BTreeSet<String> {
+        self.inner
+            .workflow
+            .iter()
+            .filter(|s| s.is_done)
+            .map(|s| s.key.clone())
+            .collect()
+    }
+
+    /// Whether the given user may update this task list, given whether they
+    /// are a moderator of the room
+    pub fn can_be_edited_by(&self, user_id: &UserId, is_room_moderator: bool) -> bool {
+        self.inner
+            .edit_rule
+            .allows(&self.meta.sender, user_id, is_room_moderator)
+    }
+
     pub fn updater(&self) -> TaskListUpdateBuilder {
         TaskListUpdateBuilder::default()
             .task_list(self.meta.event_id.clone())
@@ -82,6 +109,7 @@ impl From<OriginalMessageLikeEvent<TaskListEventContent>> for TaskList {
                 redacted: None,
             },
             task_stats: Default::default(),
+            task_ids: Default::default(),
         }
     }
 }
@@ -119,8 +147,20 @@ impl ActerModel for TaskList {
             AnyActerModel::Task(task) => {
                 let key = self.event_id().to_owned();
                 trace!(?key, ?task, "adding task to list");
-                self.task_stats.tasks_count += 1;
-                self.task_stats.has_tasks = true;
+                // updates of tasks and their subtasks pass through here as well
+                if self.task_ids.insert(task.event_id().to_owned()) {
+                    self.task_stats.tasks_count += 1;
+                    self.task_stats.has_tasks = true;
+                }
+                Ok(true)
+            }
+            AnyActerModel::RedactedActerModel(redacted) => {
+                // a task gone doesn’t count anymore
+                if !self.task_ids.remove(redacted.event_id()) {
+                    return Ok(false);
+                }
+                self.task_stats.tasks_count = self.task_stats.tasks_count.saturating_sub(1);
+                self.task_stats.has_tasks = self.task_stats.tasks_count > 0;
                 Ok(true)
             }
             _ => {
@@ -151,7 +191,40 @@ impl ActerModel for TaskListUpdate {
     }
 
     async fn execute(self, store: &Store) -> Result<Vec<ExecuteReference>> {
-        default_model_execute(store, self.into()).await
+        let task_list_id = self.inner.task_list.event_id.clone();
+        let changes_workflow = self.inner.workflow.is_some();
+        if !may_edit(store, &task_list_id, &self.meta.sender).await? {
+            warn!(update = ?self.meta.event_id, "dropping update not allowed by edit rule");
+            return Ok(vec![]);
+        }
+        let model = AnyActerModel::from(self);
+        if is_stale_update(store, &task_list_id, &model).await? {
+            trace!(update = ?model.event_id(), "newer update applied already");
+            return store.save(model).await;
+        }
+        let mut references = default_model_execute(store, model).await?;
+        if !changes_workflow {
+            return Ok(references);
+        }
+
+        // the tasks need to know which of their states count as done now
+        let AnyActerModel::TaskList(list) = store.get(&task_list_id).await? else {
+            return Ok(references);
+        };
+        let changed = store
+            .get_list(&list.tasks_key())
+            .await?
+            .filter_map(|m| match m {
+                AnyActerModel::Task(mut task) => {
+                    task.follow_list(&list).then(|| AnyActerModel::Task(task))
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        if !changed.is_empty() {
+            references.extend(store.save_many(changed).await?);
+        }
+        Ok(references)
     }
 
     fn belongs_to(&self) -> Option<Vec<OwnedEventId>> {
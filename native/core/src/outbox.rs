@@ -0,0 +1,478 @@
+use matrix_sdk_base::ruma::{
+    events::room::redaction::RoomRedactionEventContent, EventId, MilliSecondsSinceUnixEpoch,
+    OwnedEventId, OwnedRoomId, OwnedTransactionId, TransactionId, UInt,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{sync::Arc, time::Duration};
+use tokio::sync::{Mutex, Notify};
+use tracing::{info, trace, warn};
+
+use crate::{
+    events::AnyActerEvent,
+    executor::Executor,
+    models::{ActerModel, AnyActerModel, RedactedActerModel, RedactionContent},
+    referencing::IndexKey,
+    store::Store,
+    Error, Result,
+};
+
+static OUTBOX_KEY: &str = "ACTER::OUTBOX";
+static LOCAL_ECHO_PREFIX: &str = "$acter-local-";
+
+/// The longest we wait before retrying to send
+const MAX_BACKOFF: Duration = Duration::from_secs(5 * 60);
+
+/// Whether the given id is of an object that wasn’t sent yet
+pub fn is_local_echo(event_id: &EventId) -> bool {
+    event_id.as_str().starts_with(LOCAL_ECHO_PREFIX)
+}
+
+fn local_echo_id(transaction_id: &TransactionId) -> Result<OwnedEventId> {
+    Ok(EventId::parse(format!(
+        "{LOCAL_ECHO_PREFIX}{transaction_id}"
+    ))?)
+}
+
+/// How long to wait before the next attempt after `attempts` failed ones
+pub fn backoff(attempts: u32) -> Duration {
+    Duration::from_secs(1 << attempts.min(9)).min(MAX_BACKOFF)
+}
+
+/// An acter event waiting to be sent
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct OutboxEntry {
+    pub transaction_id: OwnedTransactionId,
+    pub room_id: OwnedRoomId,
+    pub event_type: String,
+    pub content: Value,
+    /// the id the object is shown under until sent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub local_echo_id: Option<OwnedEventId>,
+    /// the objects the local echo belongs to, as they were before it
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub echoed_parents: Vec<AnyActerModel>,
+    #[serde(default)]
+    pub attempts: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+    /// not to be retried before
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_attempt_at: Option<MilliSecondsSinceUnixEpoch>,
+}
+
+impl OutboxEntry {
+    /// How long to wait until this may be sent
+    pub fn wait_time(&self) -> Duration {
+        let Some(next) = self.next_attempt_at else {
+            return Duration::ZERO;
+        };
+        let now = MilliSecondsSinceUnixEpoch::now().get();
+        Duration::from_millis(next.get().saturating_sub(now).into())
+    }
+}
+
+/// Persisted queue of acter events, sent in order once we are online
+///
+/// Queued events are shown in the store right away under a local echo id,
+/// which is replaced by the actual event id once sent. Echoes belonging to
+/// others, like tasks or updates, are redacted from their parents once sent,
+/// and their parents rolled back if they are discarded.
+#[derive(Clone, Debug)]
+pub struct Outbox {
+    store: Store,
+    executor: Executor,
+    /// guards changes of the persisted entries
+    lock: Arc<Mutex<()>>,
+    changed: Arc<Notify>,
+}
+
+impl Outbox {
+    pub fn new(executor: Executor) -> Self {
+        Outbox {
+            store: executor.store().clone(),
+            executor,
+            lock: Default::default(),
+            changed: Default::default(),
+        }
+    }
+
+    pub async fn entries(&self) -> Result<Vec<OutboxEntry>> {
+        match self.store.get_raw(OUTBOX_KEY).await {
+            Ok(entries) => Ok(entries),
+            Err(Error::ModelNotFound(_)) => Ok(vec![]),
+            Err(error) => Err(error),
+        }
+    }
+
+    async fn set_entries(&self, entries: &Vec<OutboxEntry>) -> Result<()> {
+        self.store.set_raw(OUTBOX_KEY, entries).await
+    }
+
+    /// The entry to send next, if any
+    pub async fn next(&self) -> Result<Option<OutboxEntry>> {
+        Ok(self.entries().await?.into_iter().next())
+    }
+
+    /// Resolves once entries were added or retries requested
+    pub async fn changed(&self) {
+        self.changed.notified().await
+    }
+
+    /// Queue the given event for sending, returning its entry
+    pub async fn queue(
+        &self,
+        room_id: OwnedRoomId,
+        event_type: String,
+        content: Value,
+    ) -> Result<OutboxEntry> {
+        let transaction_id = TransactionId::new();
+        let (local_echo_id, echoed_parents) = self
+            .add_local_echo(&room_id, &event_type, &content, &transaction_id)
+            .await?;
+        let entry = OutboxEntry {
+            transaction_id,
+            room_id,
+            event_type,
+            content,
+            local_echo_id,
+            echoed_parents,
+            attempts: 0,
+            last_error: None,
+            next_attempt_at: None,
+        };
+        {
+            let _guard = self.lock.lock().await;
+            let mut entries = self.entries().await?;
+            entries.push(entry.clone());
+            self.set_entries(&entries).await?;
+        }
+        trace!(txn_id = ?entry.transaction_id, "queued acter event");
+        self.changed.notify_one();
+        Ok(entry)
+    }
+
+    async fn add_local_echo(
+        &self,
+        room_id: &OwnedRoomId,
+        event_type: &str,
+        content: &Value,
+        transaction_id: &TransactionId,
+    ) -> Result<(Option<OwnedEventId>, Vec<AnyActerModel>)> {
+        let event_id = local_echo_id(transaction_id)?;
+        let event = serde_json::from_value::<AnyActerEvent>(json!({
+            "type": event_type,
+            "event_id": event_id,
+            "room_id": room_id,
+            "sender": self.store.user_id(),
+            "origin_server_ts": MilliSecondsSinceUnixEpoch::now(),
+            "content": content,
+        }))?;
+        let model = match AnyActerModel::try_from(event) {
+            Ok(model) => model,
+            Err(error) => {
+                warn!(?error, event_type, "no local echo for queued event");
+                return Ok((None, vec![]));
+            }
+        };
+        let mut parents = Vec::new();
+        for parent_id in model.belongs_to().unwrap_or_default() {
+            match self.store.get(&parent_id).await {
+                Ok(parent) => parents.push(parent),
+                Err(Error::ModelNotFound(_)) => {}
+                Err(error) => return Err(error),
+            }
+        }
+        self.executor.handle(model).await?;
+        Ok((Some(event_id), parents))
+    }
+
+    /// Take the local echo out of the store and of the objects it belongs to
+    async fn remove_local_echo(&self, local_echo_id: &OwnedEventId) -> Result<()> {
+        let model = match self.store.get(local_echo_id).await {
+            Ok(model) => model,
+            // e.g. an update dropped for its edit rule
+            Err(Error::ModelNotFound(_)) => return Ok(()),
+            Err(error) => return Err(error),
+        };
+        let mut keys = Vec::new();
+        if !matches!(model, AnyActerModel::RedactedActerModel(_)) {
+            let meta = model.event_meta().clone();
+            let redacted = RedactedActerModel::new(
+                model.model_type().to_owned(),
+                meta.clone(),
+                RedactionContent {
+                    content: RoomRedactionEventContent::new_v1(),
+                    event_id: meta.event_id,
+                    sender: meta.sender,
+                    origin_server_ts: MilliSecondsSinceUnixEpoch::now(),
+                },
+            );
+            keys.extend(model.redact(&self.store, redacted).await?);
+        }
+        keys.extend(self.store.remove(local_echo_id).await?);
+        self.executor.notify(keys);
+        Ok(())
+    }
+
+    /// Bring the objects a discarded echo belonged to back to how they were
+    /// before it, replaying what happened to them since
+    async fn roll_back_parents(&self, parents: Vec<AnyActerModel>) -> Result<()> {
+        let mut restored = Vec::new();
+        for mut parent in parents {
+            let parent_id = parent.event_id().to_owned();
+            if self.store.get(&parent_id).await.is_err() {
+                // gone in the meantime, e.g. a local echo itself
+                continue;
+            }
+            let mut history = self
+                .store
+                .get_list(&IndexKey::ObjectHistory(parent_id.clone()))
+                .await?
+                .filter(|m| *m.event_id() != *parent_id)
+                .collect::<Vec<_>>();
+            // the index is latest first, the updates apply on top of each other
+            history.reverse();
+            for model in &history {
+                parent.transition(model)?;
+            }
+            restored.push(parent);
+        }
+        let mut keys = self.store.save_many(restored).await?;
+        keys.sort();
+        keys.dedup();
+        self.executor.notify(keys);
+        Ok(())
+    }
+
+    /// Drop the sent entry and its local echo, pointing the entries queued
+    /// after it at the actual event
+    pub async fn mark_sent(
+        &self,
+        transaction_id: &TransactionId,
+        event_id: OwnedEventId,
+    ) -> Result<()> {
+        let Some(entry) = self.take(transaction_id).await? else {
+            return Ok(());
+        };
+        info!(?transaction_id, ?event_id, "queued acter event sent");
+        let Some(local_echo_id) = entry.local_echo_id else {
+            return Ok(());
+        };
+        // the sent event takes over, the parents learn about it on their own
+        self.remove_local_echo(&local_echo_id).await?;
+        {
+            let _guard = self.lock.lock().await;
+            let mut entries = self.entries().await?;
+            for later in entries.iter_mut() {
+                replace_id(
+                    &mut later.content,
+                    local_echo_id.as_str(),
+                    event_id.as_str(),
+                );
+            }
+            self.set_entries(&entries).await?;
+        }
+        Ok(())
+    }
+
+    /// Note the failed attempt, returning how long to wait before retrying
+    pub async fn mark_failed(
+        &self,
+        transaction_id: &TransactionId,
+        error: String,
+    ) -> Result<Duration> {
+        let _guard = self.lock.lock().await;
+        let mut entries = self.entries().await?;
+        let Some(entry) = entries
+            .iter_mut()
+            .find(|e| *e.transaction_id == *transaction_id)
+        else {
+            return Ok(Duration::ZERO);
+        };
+        entry.attempts += 1;
+        let wait = backoff(entry.attempts);
+        let now = MilliSecondsSinceUnixEpoch::now().get();
+        entry.next_attempt_at = Some(MilliSecondsSinceUnixEpoch(UInt::new_saturating(
+            u64::from(now) + wait.as_millis() as u64,
+        )));
+        entry.last_error = Some(error);
+        self.set_entries(&entries).await?;
+        Ok(wait)
+    }
+
+    /// Give up on sending the entry, dropping its local echo
+    pub async fn discard(&self, transaction_id: &TransactionId) -> Result<bool> {
+        let Some(entry) = self.take(transaction_id).await? else {
+            return Ok(false);
+        };
+        if let Some(local_echo_id) = entry.local_echo_id {
+            self.remove_local_echo(&local_echo_id).await?;
+            self.roll_back_parents(entry.echoed_parents).await?;
+        }
+        Ok(true)
+    }
+
+    /// Retry all entries right away, e.g. once we are online again
+    pub async fn retry_now(&self) -> Result<()> {
+        {
+            let _guard = self.lock.lock().await;
+            let mut entries = self.entries().await?;
+            for entry in entries.iter_mut() {
+                entry.next_attempt_at = None;
+            }
+            self.set_entries(&entries).await?;
+        }
+        self.changed.notify_one();
+        Ok(())
+    }
+
+    async fn take(&self, transaction_id: &TransactionId) -> Result<Option<OutboxEntry>> {
+        let _guard = self.lock.lock().await;
+        let mut entries = self.entries().await?;
+        let Some(idx) = entries
+            .iter()
+            .position(|e| *e.transaction_id == *transaction_id)
+        else {
+            return Ok(None);
+        };
+        let entry = entries.remove(idx);
+        self.set_entries(&entries).await?;
+        Ok(Some(entry))
+    }
+}
+
+/// Replace all string values `from` in `value` by `to`
+fn replace_id(value: &mut Value, from: &str, to: &str) {
+    match value {
+        Value::String(s) if s == from => *s = to.to_owned(),
+        Value::Array(items) => items.iter_mut().for_each(|v| replace_id(v, from, to)),
+        Value::Object(fields) => fields.values_mut().for_each(|v| replace_id(v, from, to)),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use matrix_sdk::Client;
+    use matrix_sdk_base::{
+        ruma::{api::MatrixVersion, owned_room_id, user_id},
+        store::{MemoryStore, StoreConfig},
+    };
+
+    async fn fresh_outbox() -> Result<Outbox> {
+        let config = StoreConfig::new("tests".to_owned()).state_store(MemoryStore::new());
+        let client = Client::builder()
+            .homeserver_url("http://localhost")
+            .server_versions([MatrixVersion::V1_5])
+            .store_config(config)
+            .build()
+            .await
+            .unwrap();
+
+        let store = Store::new_with_auth(client, user_id!("@test:example.org").to_owned()).await?;
+        Ok(Outbox::new(Executor::new(store).await?))
+    }
+
+    async fn tasks_count(store: &Store, list_id: &OwnedEventId) -> Result<u32> {
+        let AnyActerModel::TaskList(list) = store.get(list_id).await? else {
+            panic!("not a task list");
+        };
+        Ok(*list.stats().tasks_count())
+    }
+
+    #[tokio::test]
+    async fn echoes_of_tasks_and_updates_leave_their_parents() -> Result<()> {
+        let outbox = fresh_outbox().await?;
+        let store = outbox.store.clone();
+        let room_id = owned_room_id!("!room:example.org");
+
+        let list = outbox
+            .queue(
+                room_id.clone(),
+                "global.acter.dev.tasklist".to_owned(),
+                json!({ "name": "List" }),
+            )
+            .await?;
+        let list_id = list.local_echo_id.clone().expect("task lists are echoed");
+        let task = outbox
+            .queue(
+                room_id.clone(),
+                "global.acter.dev.task".to_owned(),
+                json!({
+                    "title": "Task",
+                    "m.relates_to": {
+                        "rel_type": "global.acter.dev.belongs_to",
+                        "event_id": list_id,
+                    },
+                }),
+            )
+            .await?;
+        let task_id = task.local_echo_id.clone().expect("tasks are echoed");
+        assert_eq!(task.echoed_parents.len(), 1);
+        assert_eq!(tasks_count(&store, &list_id).await?, 1);
+
+        let update = outbox
+            .queue(
+                room_id.clone(),
+                "global.acter.dev.task.update".to_owned(),
+                json!({
+                    "m.relates_to": { "rel_type": "global.acter.dev.update", "event_id": task_id },
+                    "title": "Renamed",
+                }),
+            )
+            .await?;
+        let AnyActerModel::Task(renamed) = store.get(&task_id).await? else {
+            panic!("not a task");
+        };
+        assert_eq!(renamed.title(), "Renamed");
+
+        // the discarded update is rolled back
+        assert!(outbox.discard(&update.transaction_id).await?);
+        let AnyActerModel::Task(restored) = store.get(&task_id).await? else {
+            panic!("not a task");
+        };
+        assert_eq!(restored.title(), "Task");
+
+        // the sent task replaces its echo, which leaves the list and the room
+        outbox
+            .mark_sent(&task.transaction_id, EventId::parse("$sent-task")?)
+            .await?;
+        assert!(store.get(&task_id).await.is_err());
+        assert_eq!(tasks_count(&store, &list_id).await?, 0);
+        assert!(store
+            .get_list(&IndexKey::RoomModels(room_id))
+            .await?
+            .all(|m| *m.event_id() != *task_id));
+        Ok(())
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_the_max() {
+        assert_eq!(backoff(1), Duration::from_secs(2));
+        assert_eq!(backoff(3), Duration::from_secs(8));
+        assert_eq!(backoff(30), MAX_BACKOFF);
+    }
+
+    #[test]
+    fn local_echo_ids_are_replaced_everywhere() {
+        let mut content = json!({
+            "m.relates_to": {"event_id": "$acter-local-abc"},
+            "refs": ["$acter-local-abc", "$other"],
+            "title": "acter-local-abc",
+        });
+        replace_id(&mut content, "$acter-local-abc", "$sent");
+        assert_eq!(
+            content,
+            json!({
+                "m.relates_to": {"event_id": "$sent"},
+                "refs": ["$sent", "$other"],
+                "title": "acter-local-abc",
+            })
+        );
+        assert!(is_local_echo(
+            &local_echo_id(&TransactionId::new()).unwrap()
+        ));
+    }
+}
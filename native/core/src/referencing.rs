@@ -16,6 +16,7 @@ pub enum SectionIndex {
     Pins,
     Stories,
     Tasks,
+    Polls,
 }
 
 #[derive(
@@ -31,6 +32,12 @@ pub enum ObjectListIndex {
     Rsvp,
     Tasks,
     Invites,
+    /// the subtasks of a task
+    Subtasks,
+    /// the tasks blocked by a task
+    Blocking,
+    /// the votes on a poll
+    Votes,
 }
 
 #[derive(
@@ -42,6 +49,8 @@ pub enum SpecialListsIndex {
     MyOpenTasks,
     MyDoneTasks,
     InvitedTo,
+    /// the objects and updates mentioning us
+    MyMentions,
     #[cfg(any(test, feature = "testing"))]
     Test1,
     #[cfg(any(test, feature = "testing"))]
@@ -59,6 +68,8 @@ pub enum IndexKey {
     Section(SectionIndex),
     RoomSection(OwnedRoomId, SectionIndex),
     ObjectList(OwnedEventId, ObjectListIndex),
+    /// the tasks of the tasklist in the given workflow state
+    TaskStatus(OwnedEventId, String),
     Special(SpecialListsIndex),
     Redacted,
     AllHistory,
@@ -73,10 +84,14 @@ pub enum ModelParam {
     AttachmentsStats,
     ReactionStats,
     RsvpStats,
+    PollVoteStats,
     #[strum(to_string = "read_receipts")]
     ReadReceiptsStats,
     #[strum(to_string = "invites")]
     InviteStats,
+    /// the object as it was created, before any updates
+    #[strum(to_string = "original_state")]
+    OriginalState,
 }
 
 #[derive(
@@ -12,12 +12,15 @@ use crate::events::{
     comments::CommentEventContent,
     news::NewsEntryEventContent,
     pins::PinEventContent,
+    polls::{PollEventContent, PollVoteEventContent},
     rsvp::RsvpEventContent,
     settings::{
         ActerAppSettingsContent, SimpleOnOffSettingBuilder, SimpleSettingWithTurnOffBuilder,
     },
     stories::StoryEventContent,
-    tasks::{TaskEventContent, TaskListEventContent},
+    tasks::{
+        TaskAssignEventContent, TaskEventContent, TaskListEventContent, TaskUnassignEventContent,
+    },
 };
 
 pub fn new_app_permissions_builder() -> AppPermissionsBuilder {
@@ -79,6 +82,16 @@ impl AppPermissionsBuilder {
                 .entry(<PinEventContent as StaticEventContent>::TYPE.into())
                 .or_insert_with(|| Int::from(0));
         }
+        if settings.polls().active() {
+            permissions
+                .events
+                .entry(<PollEventContent as StaticEventContent>::TYPE.into())
+                .or_insert_with(|| Int::from(0));
+            permissions
+                .events
+                .entry(<PollVoteEventContent as StaticEventContent>::TYPE.into())
+                .or_insert_with(|| Int::from(0));
+        }
         if settings.tasks().active() {
             permissions
                 .events
@@ -89,6 +102,17 @@ impl AppPermissionsBuilder {
                 .events
                 .entry(<TaskEventContent as StaticEventContent>::TYPE.into())
                 .or_insert_with(|| Int::from(0));
+
+            // assigning others is a moderating action by default
+            permissions
+                .events
+                .entry(<TaskAssignEventContent as StaticEventContent>::TYPE.into())
+                .or_insert_with(|| Int::from(50));
+
+            permissions
+                .events
+                .entry(<TaskUnassignEventContent as StaticEventContent>::TYPE.into())
+                .or_insert_with(|| Int::from(50));
         }
         (settings, permissions)
     }
@@ -130,6 +154,15 @@ impl AppPermissionsBuilder {
         );
     }
 
+    pub fn polls(&mut self, active: bool) {
+        self.settings.polls = Some(
+            SimpleOnOffSettingBuilder::default()
+                .active(active)
+                .build()
+                .unwrap(),
+        );
+    }
+
     pub fn tasks(&mut self, active: bool) {
         self.settings.tasks = Some(
             SimpleOnOffSettingBuilder::default()
@@ -175,10 +208,35 @@ impl AppPermissionsBuilder {
         self.set_for_key(<TaskEventContent as StaticEventContent>::TYPE.into(), value);
     }
 
+    pub fn task_assign_permissions(&mut self, value: u32) {
+        self.set_for_key(
+            <TaskAssignEventContent as StaticEventContent>::TYPE.into(),
+            value,
+        );
+    }
+
+    pub fn task_unassign_permissions(&mut self, value: u32) {
+        self.set_for_key(
+            <TaskUnassignEventContent as StaticEventContent>::TYPE.into(),
+            value,
+        );
+    }
+
     pub fn pins_permissions(&mut self, value: u32) {
         self.set_for_key(<PinEventContent as StaticEventContent>::TYPE.into(), value);
     }
 
+    pub fn polls_permissions(&mut self, value: u32) {
+        self.set_for_key(<PollEventContent as StaticEventContent>::TYPE.into(), value);
+    }
+
+    pub fn poll_votes_permissions(&mut self, value: u32) {
+        self.set_for_key(
+            <PollVoteEventContent as StaticEventContent>::TYPE.into(),
+            value,
+        );
+    }
+
     pub fn comments_permissions(&mut self, value: u32) {
         self.set_for_key(
             <CommentEventContent as StaticEventContent>::TYPE.into(),
@@ -1,14 +1,25 @@
+use eyeball_im::VectorDiff;
+use futures::stream::BoxStream;
 use matrix_sdk::ruma::OwnedEventId;
 use matrix_sdk::Client;
 use matrix_sdk_base::ruma::{OwnedRoomId, OwnedUserId, UserId};
 use scc::hash_map::{Entry, HashMap};
 use std::collections::HashSet;
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, RwLock};
 use tracing::{debug, error, info, instrument, trace, warn};
 
 mod index;
-pub use index::{LifoIndex, RankedIndex, StoreIndex};
+mod migrations;
+mod search;
+mod sqlite;
+pub use index::{IndexOrder, LifoIndex, RankedIndex, StoreIndex};
+pub use migrations::{MigrationProgress, MigrationStep, StoreMigrator, MIGRATIONS};
+pub use search::{
+    tokenize, SearchDocument, SearchFilters, SearchFiltersBuilder, SearchHit, SearchIndex,
+};
+pub use sqlite::{SqliteModelStore, DATABASE_NAME, DATA_PATH_KEY};
 
+use crate::history::drop_original_state;
 use crate::referencing::{ExecuteReference, IndexKey};
 use crate::{
     models::{ActerModel, AnyActerModel},
@@ -21,11 +32,18 @@ pub struct Store {
     user_id: OwnedUserId,
     models: Arc<HashMap<OwnedEventId, AnyActerModel>>,
     indizes: Arc<HashMap<IndexKey, StoreIndex>>,
+    search: Arc<RwLock<SearchIndex>>,
+    /// if set, models and indizes live in here and are only loaded on demand
+    db: Option<Arc<SqliteModelStore>>,
+    /// held while writing to the database and while loading an index from
+    /// it, so the loaded index never misses a model not yet written
+    db_lock: Arc<tokio::sync::Mutex<()>>,
     dirty: Arc<Mutex<HashSet<OwnedEventId>>>, // our key mutex;
 }
 
 static ALL_MODELS_KEY: &str = "ACTER::ALL";
 static DB_VERSION_KEY: &str = "ACTER::DB_VERSION";
+static SEARCH_INDEX_KEY: &str = "ACTER::SEARCH_INDEX";
 static CURRENT_DB_VERSION: u32 = 1;
 
 async fn get_from_store<T: serde::de::DeserializeOwned>(client: Client, key: &str) -> Result<T> {
@@ -68,49 +86,41 @@ impl Store {
 
     pub async fn new(client: Client) -> Result<Self> {
         let user_id = client.user_id().ok_or(Error::ClientNotLoggedIn)?.to_owned();
-        Self::new_inner(client, user_id).await
+        Self::new_inner(client, user_id, None).await
+    }
+
+    /// Keep the models in the given database rather than the state store,
+    /// moving over any models found in the state store
+    pub async fn new_with_db(client: Client, db: SqliteModelStore) -> Result<Self> {
+        let user_id = client.user_id().ok_or(Error::ClientNotLoggedIn)?.to_owned();
+        Self::new_inner(client, user_id, Some(db)).await
     }
 
     #[cfg(test)]
     pub(crate) async fn new_with_auth(client: Client, user_id: OwnedUserId) -> Result<Self> {
-        Self::new_inner(client, user_id).await
+        Self::new_inner(client, user_id, None).await
     }
 
-    async fn new_inner(client: Client, user_id: OwnedUserId) -> Result<Self> {
-        let ver = client
-            .state_store()
-            .get_custom_value(DB_VERSION_KEY.as_bytes())
-            .await
-            .map_err(|e| Error::Custom(format!("failed to find DB version key: {e}")))?
-            .map(|u| u32::from_le_bytes(u.as_chunks().0[0]))
-            .unwrap_or_default();
-        if ver < CURRENT_DB_VERSION {
-            // "upgrading" by resetting
-            client
-                .state_store()
-                .set_custom_value_no_read(ALL_MODELS_KEY.as_bytes(), vec![])
-                .await
-                .map_err(|e| Error::Custom(format!("setting all models to [] failed: {e}")))?;
-
-            client
-                .state_store()
-                .set_custom_value_no_read(
-                    DB_VERSION_KEY.as_bytes(),
-                    CURRENT_DB_VERSION.to_le_bytes().to_vec(),
-                )
-                .await
-                .map_err(|e| Error::Custom(format!("setting db version failed: {e}")))?;
+    #[cfg(test)]
+    pub(crate) async fn new_with_auth_and_db(
+        client: Client,
+        user_id: OwnedUserId,
+        db: SqliteModelStore,
+    ) -> Result<Self> {
+        Self::new_inner(client, user_id, Some(db)).await
+    }
 
-            return Ok(Store {
-                client,
-                user_id,
-                indizes: Default::default(),
-                models: Default::default(),
-                dirty: Default::default(),
-            });
-        }
+    async fn new_inner(
+        client: Client,
+        user_id: OwnedUserId,
+        db: Option<SqliteModelStore>,
+    ) -> Result<Self> {
+        let db = db.map(Arc::new);
+        StoreMigrator::new(client.clone(), user_id.clone(), db.clone())
+            .run()
+            .await?;
 
-        // current DB version, attempt to load models
+        // at the current version, attempt to load models
 
         let data = client
             .state_store()
@@ -143,6 +153,25 @@ impl Store {
             vec![]
         };
 
+        let mut search = Self::load_search_index(&client).await?;
+
+        if let Some(db) = db {
+            let legacy = models_vec.into_iter().flatten().collect::<Vec<_>>();
+            if !legacy.is_empty() {
+                Self::move_into_db(&client, &user_id, &db, legacy, &mut search).await?;
+            }
+            return Ok(Store {
+                client,
+                user_id,
+                indizes: Default::default(),
+                models: Default::default(),
+                search: Arc::new(RwLock::new(search)),
+                db: Some(db),
+                db_lock: Default::default(),
+                dirty: Default::default(),
+            });
+        }
+
         let indizes: HashMap<IndexKey, StoreIndex> = HashMap::new();
         let models: HashMap<OwnedEventId, AnyActerModel> = HashMap::new();
         for m in models_vec {
@@ -162,6 +191,10 @@ impl Store {
                     }
                 };
             }
+            if !search.contains(&key) {
+                // missing from the persisted search index
+                search.update(&key, &m);
+            }
             // ignore duplicates
             let _ = models.insert(key, m);
         }
@@ -171,16 +204,196 @@ impl Store {
             user_id,
             indizes: Arc::new(indizes),
             models: Arc::new(models),
+            search: Arc::new(RwLock::new(search)),
+            db: None,
+            db_lock: Default::default(),
             dirty: Default::default(),
         })
     }
 
+    /// The search index is kept per room, listed under the search index key
+    fn search_index_room_key(room_id: &OwnedRoomId) -> String {
+        format!("{SEARCH_INDEX_KEY}::{room_id}")
+    }
+
+    async fn load_search_index(client: &Client) -> Result<SearchIndex> {
+        let client_store = client.state_store();
+        let Some(raw) = client_store
+            .get_custom_value(SEARCH_INDEX_KEY.as_bytes())
+            .await?
+        else {
+            return Ok(SearchIndex::default());
+        };
+        let rooms = match serde_json::from_slice::<Vec<OwnedRoomId>>(&raw) {
+            Ok(rooms) => rooms,
+            Err(_) => {
+                // the index used to be stored as a whole
+                return Ok(match serde_json::from_slice::<Vec<SearchDocument>>(&raw) {
+                    Ok(docs) => {
+                        let mut search = SearchIndex::from_documents(docs);
+                        search.mark_all_dirty();
+                        search
+                    }
+                    Err(error) => {
+                        warn!(?error, "Couldn’t read search index, rebuilding");
+                        SearchIndex::default()
+                    }
+                });
+            }
+        };
+        let mut documents = Vec::new();
+        for room_id in rooms {
+            let key = Self::search_index_room_key(&room_id);
+            let Some(raw) = client_store.get_custom_value(key.as_bytes()).await? else {
+                continue;
+            };
+            match serde_json::from_slice::<Vec<SearchDocument>>(&raw) {
+                Ok(docs) => documents.extend(docs),
+                Err(error) => warn!(?room_id, ?error, "Couldn’t read search index of room"),
+            }
+        }
+        Ok(SearchIndex::from_documents(documents))
+    }
+
+    /// Remove the persisted search index, it is rebuilt from the models
+    pub(crate) async fn remove_search_index(client: &Client) -> Result<()> {
+        let client_store = client.state_store();
+        if let Some(raw) = client_store
+            .get_custom_value(SEARCH_INDEX_KEY.as_bytes())
+            .await?
+        {
+            for room_id in serde_json::from_slice::<Vec<OwnedRoomId>>(&raw).unwrap_or_default() {
+                client_store
+                    .remove_custom_value(Self::search_index_room_key(&room_id).as_bytes())
+                    .await?;
+            }
+        }
+        client_store
+            .remove_custom_value(SEARCH_INDEX_KEY.as_bytes())
+            .await?;
+        Ok(())
+    }
+
+    /// Move the models kept as custom values in the state store into the database
+    async fn move_into_db(
+        client: &Client,
+        user_id: &UserId,
+        db: &Arc<SqliteModelStore>,
+        models: Vec<AnyActerModel>,
+        search: &mut SearchIndex,
+    ) -> Result<()> {
+        info!(count = models.len(), "moving models into the database");
+        let keys = models
+            .iter()
+            .map(|m| m.event_id().to_owned())
+            .collect::<Vec<_>>();
+        let to_save = models
+            .into_iter()
+            .map(|m| {
+                search.update(&m.event_id().to_owned(), &m);
+                let indizes = Self::db_indizes(&m, user_id);
+                (m, indizes)
+            })
+            .collect();
+        db.blocking(move |db| db.save(to_save)).await?;
+        // only drop them once they are safe in the database
+        let client_store = client.state_store();
+        client_store
+            .set_custom_value_no_read(ALL_MODELS_KEY.as_bytes(), vec![])
+            .await?;
+        for key in keys {
+            if let Err(error) = client_store
+                .remove_custom_value(format!("acter:{key}").as_bytes())
+                .await
+            {
+                warn!(?key, ?error, "Error removing moved model");
+            }
+        }
+        Ok(())
+    }
+
+    /// all indizes the model is listed under in the database
+    fn db_indizes(mdl: &AnyActerModel, user_id: &UserId) -> Vec<IndexKey> {
+        let mut indizes = mdl.indizes(user_id);
+        indizes.push(IndexKey::RoomModels(mdl.room_id().to_owned()));
+        indizes
+    }
+
+    /// Indizes are read from the database the first time they are needed
+    async fn ensure_index_loaded(&self, key: &IndexKey) -> Result<()> {
+        let Some(db) = &self.db else {
+            return Ok(());
+        };
+        if self.indizes.contains_async(key).await {
+            return Ok(());
+        }
+        // wait for pending saves to be written, so they are part of the list
+        let _db_lock = self.db_lock.lock().await;
+        if self.indizes.contains_async(key).await {
+            // loaded while we were waiting
+            return Ok(());
+        }
+        let list_key = key.clone();
+        let models = db.blocking(move |db| db.list(&list_key, 0, None)).await?;
+        let index = StoreIndex::from_ordered(key, models.iter().map(|m| m.event_meta()).collect());
+        for m in models {
+            // the version we already have is never older
+            let _ = self.models.insert(m.event_id().to_owned(), m);
+        }
+        let _ = self.indizes.insert(key.clone(), index);
+        Ok(())
+    }
+
     #[instrument(skip(self))]
     pub async fn get_list(&self, key: &IndexKey) -> Result<impl Iterator<Item = AnyActerModel>> {
+        self.ensure_index_loaded(key).await?;
         self.get_list_inner(key)
     }
 
-    pub fn get_list_inner(&self, key: &IndexKey) -> Result<impl Iterator<Item = AnyActerModel>> {
+    /// The current ids listed at `key` and the changes to them from then on
+    pub async fn subscribe_index_diff(
+        &self,
+        key: &IndexKey,
+    ) -> Result<(
+        Vec<OwnedEventId>,
+        BoxStream<'static, VectorDiff<OwnedEventId>>,
+    )> {
+        self.ensure_index_loaded(key).await?;
+        let entry = self
+            .indizes
+            .entry(key.clone())
+            .or_insert_with(|| StoreIndex::empty_for(key));
+        let index = entry.get();
+        Ok((
+            index.values().into_iter().cloned().collect(),
+            index.update_stream(),
+        ))
+    }
+
+    /// A page of the list at `key`, without loading the full list
+    pub async fn get_list_page(
+        &self,
+        key: &IndexKey,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<AnyActerModel>> {
+        match &self.db {
+            Some(db) if !self.indizes.contains_async(key).await => {
+                // pending saves must be written first
+                let _db_lock = self.db_lock.lock().await;
+                let key = key.clone();
+                db.blocking(move |db| db.list(&key, offset, Some(limit)))
+                    .await
+            }
+            _ => {
+                self.ensure_index_loaded(key).await?;
+                Ok(self.get_list_inner(key)?.skip(offset).take(limit).collect())
+            }
+        }
+    }
+
+    /// The models of an index already loaded
+    fn get_list_inner(&self, key: &IndexKey) -> Result<impl Iterator<Item = AnyActerModel>> {
         let listing = if let Some(r) = self.indizes.get(key) {
             r.get().values().into_iter().cloned().collect()
         } else {
@@ -194,12 +407,27 @@ impl Store {
         Ok(res)
     }
 
+    /// Full-text search over the objects in the store
+    pub fn search(&self, query: &str, filters: &SearchFilters) -> Result<Vec<SearchHit>> {
+        Ok(self.search.read()?.search(query, filters))
+    }
+
     pub async fn get(&self, model_key: &OwnedEventId) -> Result<AnyActerModel> {
-        let Some(o) = self.models.get_async(model_key).await else {
-            return Err(Error::ModelNotFound(model_key.to_string()));
+        if let Some(o) = self.models.get_async(model_key).await {
+            return Ok(o.get().clone());
         };
+        if let Some(db) = &self.db {
+            let key = model_key.clone();
+            if let Some(model) = db.blocking(move |db| db.get(&key)).await? {
+                let _ = self
+                    .models
+                    .insert_async(model_key.clone(), model.clone())
+                    .await;
+                return Ok(model);
+            }
+        }
 
-        Ok(o.get().clone())
+        Err(Error::ModelNotFound(model_key.to_string()))
     }
 
     pub async fn get_many(&self, model_keys: Vec<OwnedEventId>) -> Vec<Option<AnyActerModel>> {
@@ -207,11 +435,29 @@ impl Store {
         futures::future::join_all(models).await
     }
 
+    /// With a database, the previous version of the model might not be loaded
+    /// yet, but it is needed to know which indizes the model leaves
+    async fn load_previous(&self, key: &OwnedEventId) -> Result<()> {
+        let Some(db) = &self.db else {
+            return Ok(());
+        };
+        if self.models.contains_async(key).await {
+            return Ok(());
+        }
+        let db_key = key.clone();
+        if let Some(prev) = db.blocking(move |db| db.get(&db_key)).await? {
+            // never replace a newer version
+            let _ = self.models.insert_async(key.clone(), prev).await;
+        }
+        Ok(())
+    }
+
     #[instrument(skip(self))]
     async fn save_model_inner(
         &self,
         mdl: AnyActerModel,
     ) -> Result<(Vec<OwnedEventId>, Vec<IndexKey>)> {
+        self.load_previous(&mdl.event_id().to_owned()).await?;
         let mut dirty = self.dirty.lock()?; // hold the lock
         let (key, idxs) = self.model_inner_under_lock(mdl)?;
         dirty.extend(key.clone());
@@ -230,6 +476,7 @@ impl Store {
         let mut new_indizes = mdl.indizes(user_id);
         let mut removed_indizes = Vec::new();
         let event_meta = mdl.event_meta().clone();
+        self.search.write()?.update(&key, &mdl);
         match self.models.entry(key.clone()) {
             Entry::Vacant(v) => {
                 v.insert_entry(mdl);
@@ -261,7 +508,10 @@ impl Store {
                     o.get_mut().insert(&event_meta);
                 }
                 Entry::Vacant(v) => {
-                    v.insert_entry(StoreIndex::new_for(idx, &event_meta));
+                    // with a database it isn’t loaded yet and will be read from there
+                    if self.db.is_none() {
+                        v.insert_entry(StoreIndex::new_for(idx, &event_meta));
+                    }
                 }
             }
             trace!(user = ?self.user_id, ?idx, ?key, "added to index");
@@ -274,6 +524,15 @@ impl Store {
     }
 
     pub async fn save_many(&self, models: Vec<AnyActerModel>) -> Result<Vec<ExecuteReference>> {
+        let _db_lock = self.db_lock.lock().await;
+        for mdl in models.iter() {
+            self.load_previous(&mdl.event_id().to_owned()).await?;
+        }
+        let redacted = models
+            .iter()
+            .filter(|m| matches!(m, AnyActerModel::RedactedActerModel(_)))
+            .map(|m| m.event_id().to_owned())
+            .collect::<Vec<_>>();
         let mut total_keys = Vec::new();
         let mut total_indizes = Vec::new();
         {
@@ -286,6 +545,7 @@ impl Store {
             }
         }
         self.sync().await?; // FIXME: should we really run this every time?
+        self.forget(&redacted).await;
 
         // clean out the duplicates, must be sorted as only consecutive ones are removed
         total_keys.sort();
@@ -301,8 +561,12 @@ impl Store {
     }
 
     pub async fn save(&self, mdl: AnyActerModel) -> Result<Vec<ExecuteReference>> {
+        let _db_lock = self.db_lock.lock().await;
+        let redacted =
+            matches!(mdl, AnyActerModel::RedactedActerModel(_)).then(|| mdl.event_id().to_owned());
         let (model_keys, indizes) = self.save_model_inner(mdl).await?;
         self.sync().await?; // FIXME: should we really run this every time?
+        self.forget(redacted.as_slice()).await;
 
         Ok(model_keys
             .into_iter()
@@ -314,11 +578,15 @@ impl Store {
     pub async fn clear_room(&self, room_id: &OwnedRoomId) -> Result<Vec<ExecuteReference>> {
         info!(?room_id, "clearing room");
         let idx = IndexKey::RoomModels(room_id.clone());
+        self.ensure_index_loaded(&idx).await?;
+        let _db_lock = self.db_lock.lock().await;
+        let mut removed = Vec::new();
         let mut total_changed = {
             let mut dirty = self.dirty.lock()?; // hold the lock
             let mut total_changed = Vec::new();
             for model in self.get_list_inner(&idx)? {
                 let model_id = model.event_id().to_owned();
+                removed.push(model_id.clone());
                 let indizes = model.indizes(&self.user_id);
                 // remove it from all indizes
                 for index in indizes {
@@ -330,6 +598,7 @@ impl Store {
                 }
                 // remove the model itself
                 self.models.remove(&model_id);
+                self.search.write()?.remove(&model_id);
                 dirty.insert(model_id.clone());
                 total_changed.push(ExecuteReference::Model(model_id));
             }
@@ -339,6 +608,7 @@ impl Store {
             total_changed
         };
         self.sync().await?;
+        self.forget(&removed).await;
 
         // deduplicate needs them to be sorted first
         total_changed.sort();
@@ -347,7 +617,55 @@ impl Store {
         Ok(total_changed)
     }
 
+    /// Remove a single model, e.g. a local echo replaced by the actual event
+    pub async fn remove(&self, model_id: &OwnedEventId) -> Result<Vec<ExecuteReference>> {
+        let model = match self.get(model_id).await {
+            Ok(model) => model,
+            Err(Error::ModelNotFound(_)) => return Ok(vec![]),
+            Err(error) => return Err(error),
+        };
+        info!(?model_id, "removing model");
+        let _db_lock = self.db_lock.lock().await;
+        let mut total_changed = {
+            let mut dirty = self.dirty.lock()?; // hold the lock
+            let mut total_changed = Vec::new();
+            for index in Self::db_indizes(&model, &self.user_id) {
+                let _ = self
+                    .indizes
+                    .entry(index.clone())
+                    .and_modify(|l| l.remove(model_id));
+                total_changed.push(ExecuteReference::Index(index));
+            }
+            self.models.remove(model_id);
+            self.search.write()?.remove(model_id);
+            dirty.insert(model_id.clone());
+            total_changed.push(ExecuteReference::Model(model_id.clone()));
+            total_changed
+        };
+        self.sync().await?;
+        self.forget(std::slice::from_ref(model_id)).await;
+
+        total_changed.sort();
+        total_changed.dedup();
+
+        Ok(total_changed)
+    }
+
+    /// Drop what is kept about the models beyond the models themselves, once
+    /// they are removed or redacted
+    async fn forget(&self, model_ids: &[OwnedEventId]) {
+        for model_id in model_ids {
+            if let Err(error) = drop_original_state(self, model_id).await {
+                warn!(?model_id, ?error, "Error dropping original state");
+            }
+        }
+    }
+
     async fn sync(&self) -> Result<()> {
+        if let Some(db) = &self.db {
+            self.sync_db(db).await?;
+            return self.sync_search_index().await;
+        }
         trace!("sync start");
         let (models_to_write, to_remove, all_models) = {
             trace!("preparing models");
@@ -401,6 +719,8 @@ impl Store {
             .set_custom_value_no_read(ALL_MODELS_KEY.as_bytes(), all_models)
             .await?;
 
+        self.sync_search_index().await?;
+
         trace!("removing old models");
         for key in to_remove.into_iter() {
             if let Err(error) = client_store.remove_custom_value(key.as_bytes()).await {
@@ -412,6 +732,77 @@ impl Store {
 
         Ok(())
     }
+
+    /// must be called holding the `db_lock`
+    async fn sync_db(&self, db: &Arc<SqliteModelStore>) -> Result<()> {
+        let (to_save, to_remove) = {
+            let mut dirty = self.dirty.lock()?;
+            let mut to_save = Vec::new();
+            let mut to_remove = Vec::new();
+            for key in dirty.drain() {
+                match self.models.get(&key) {
+                    Some(r) => {
+                        let mdl = r.get().clone();
+                        let indizes = Self::db_indizes(&mdl, &self.user_id);
+                        to_save.push((mdl, indizes));
+                    }
+                    None => to_remove.push(key),
+                }
+            }
+            (to_save, to_remove)
+        };
+        trace!(
+            saving = to_save.len(),
+            removing = to_remove.len(),
+            "db sync"
+        );
+        db.blocking(move |db| {
+            db.save(to_save)?;
+            db.remove(&to_remove)
+        })
+        .await
+    }
+
+    /// Persist the search documents of the rooms changed since the last sync
+    async fn sync_search_index(&self) -> Result<()> {
+        let (changed, rooms) = {
+            let mut search = self.search.write()?;
+            let dirty = search.take_dirty();
+            if dirty.is_empty() {
+                return Ok(());
+            }
+            let mut changed = Vec::with_capacity(dirty.len());
+            for room_id in dirty {
+                let docs = search.room_documents(&room_id);
+                let raw = if docs.is_empty() {
+                    None
+                } else {
+                    Some(serde_json::to_vec(&docs)?)
+                };
+                changed.push((room_id, raw));
+            }
+            (changed, serde_json::to_vec(&search.rooms())?)
+        };
+        trace!(rooms = changed.len(), "syncing search index");
+        let client_store = self.client.state_store();
+        for (room_id, raw) in changed {
+            let key = Self::search_index_room_key(&room_id);
+            match raw {
+                Some(raw) => {
+                    client_store
+                        .set_custom_value_no_read(key.as_bytes(), raw)
+                        .await?;
+                }
+                None => {
+                    client_store.remove_custom_value(key.as_bytes()).await?;
+                }
+            }
+        }
+        client_store
+            .set_custom_value_no_read(SEARCH_INDEX_KEY.as_bytes(), rooms)
+            .await?;
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -424,6 +815,7 @@ mod tests {
         referencing::{SectionIndex, SpecialListsIndex},
     };
     use anyhow::bail;
+    use futures::StreamExt;
     use matrix_sdk::ruma::MilliSecondsSinceUnixEpoch;
     use matrix_sdk_base::{
         ruma::{api::MatrixVersion, event_id, user_id, OwnedEventId, OwnedRoomId},
@@ -1093,4 +1485,210 @@ mod tests {
 
         Ok(())
     }
+
+    fn temp_db_path() -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("acter-models-{}", Uuid::new_v4().hyphenated()))
+    }
+
+    async fn test_client() -> Result<Client> {
+        let config = StoreConfig::new("tests".to_owned()).state_store(MemoryStore::new());
+        Ok(Client::builder()
+            .homeserver_url("http://localhost")
+            .server_versions([MatrixVersion::V1_5])
+            .store_config(config)
+            .build()
+            .await
+            .unwrap())
+    }
+
+    fn numbered_models(count: usize, index: &IndexKey) -> Vec<TestModel> {
+        (0..count)
+            .map(|idx| {
+                TestModelBuilder::default()
+                    .simple()
+                    .event_id(OwnedEventId::try_from(format!("$DB{idx}")).unwrap())
+                    .indizes(vec![index.clone()])
+                    .build()
+                    .unwrap()
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn db_save_and_get_one() -> anyhow::Result<()> {
+        let _ = env_logger::try_init();
+        let client = test_client().await?;
+        let db = SqliteModelStore::open_in_memory(Some("passphrase"))?;
+        let store =
+            Store::new_with_auth_and_db(client, user_id!("@test:example.org").to_owned(), db)
+                .await?;
+        let model = TestModelBuilder::default().simple().build().unwrap();
+        let key = model.event_id().to_owned();
+        store.save(AnyActerModel::TestModel(model.clone())).await?;
+        let mdl = store.get(&key).await?;
+        let AnyActerModel::TestModel(other) = mdl else {
+            bail!("Returned model isn’t test model: {mdl:?}");
+        };
+        assert_eq!(model, other);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn db_recover_index_lazily() -> anyhow::Result<()> {
+        let _ = env_logger::try_init();
+        let path = temp_db_path();
+        let index = IndexKey::Special(SpecialListsIndex::Test1);
+        let models = numbered_models(5, &index);
+        {
+            let db = SqliteModelStore::open(&path, Some("passphrase"))?;
+            let store = Store::new_with_auth_and_db(
+                test_client().await?,
+                user_id!("@test:example.org").to_owned(),
+                db,
+            )
+            .await?;
+            for model in models.iter() {
+                store.save(AnyActerModel::TestModel(model.clone())).await?;
+            }
+        }
+
+        let db = SqliteModelStore::open(&path, Some("passphrase"))?;
+        let store = Store::new_with_auth_and_db(
+            test_client().await?,
+            user_id!("@test:example.org").to_owned(),
+            db,
+        )
+        .await?;
+
+        // nothing is loaded until asked for
+        assert!(!store.indizes.contains(&index));
+        let loaded = store
+            .get_list(&index)
+            .await?
+            .map(|m| m.event_id().to_owned())
+            .collect::<Vec<_>>();
+        // latest first
+        let expected = models
+            .iter()
+            .rev()
+            .map(|m| m.event_id().to_owned())
+            .collect::<Vec<_>>();
+        assert_eq!(loaded, expected);
+
+        std::fs::remove_dir_all(path)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn db_list_pages() -> anyhow::Result<()> {
+        let _ = env_logger::try_init();
+        let path = temp_db_path();
+        let index = IndexKey::Special(SpecialListsIndex::Test2);
+        let models = numbered_models(10, &index);
+        {
+            let db = SqliteModelStore::open(&path, None)?;
+            let store = Store::new_with_auth_and_db(
+                test_client().await?,
+                user_id!("@test:example.org").to_owned(),
+                db,
+            )
+            .await?;
+            for model in models.iter() {
+                store.save(AnyActerModel::TestModel(model.clone())).await?;
+            }
+        }
+
+        let db = SqliteModelStore::open(&path, None)?;
+        assert_eq!(db.count(&index)?, 10);
+        let store = Store::new_with_auth_and_db(
+            test_client().await?,
+            user_id!("@test:example.org").to_owned(),
+            db,
+        )
+        .await?;
+        let page = store
+            .get_list_page(&index, 3, 4)
+            .await?
+            .into_iter()
+            .map(|m| m.event_id().to_owned())
+            .collect::<Vec<_>>();
+        let expected = models
+            .iter()
+            .rev()
+            .skip(3)
+            .take(4)
+            .map(|m| m.event_id().to_owned())
+            .collect::<Vec<_>>();
+        assert_eq!(page, expected);
+        // read from the database directly
+        assert!(!store.indizes.contains(&index));
+
+        std::fs::remove_dir_all(path)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn db_takes_over_legacy_models() -> anyhow::Result<()> {
+        let _ = env_logger::try_init();
+        let (store, client) = fresh_store_and_client().await?;
+        let index = IndexKey::Special(SpecialListsIndex::Test3);
+        let models = numbered_models(3, &index);
+        store
+            .save_many(
+                models
+                    .iter()
+                    .map(|m| AnyActerModel::TestModel(m.clone()))
+                    .collect(),
+            )
+            .await?;
+
+        let db = SqliteModelStore::open_in_memory(Some("passphrase"))?;
+        let store = Store::new_with_auth_and_db(
+            client.clone(),
+            user_id!("@test:example.org").to_owned(),
+            db,
+        )
+        .await?;
+        assert_eq!(store.get_list(&index).await?.count(), 3);
+        for model in models.iter() {
+            let key = model.event_id().to_owned();
+            assert!(store.get(&key).await.is_ok());
+            // gone from the state store
+            assert!(client
+                .state_store()
+                .get_custom_value(format!("acter:{key}").as_bytes())
+                .await?
+                .is_none());
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn index_diff_stream() -> anyhow::Result<()> {
+        let _ = env_logger::try_init();
+        let store = fresh_store().await?;
+        let index = IndexKey::Special(SpecialListsIndex::Test1);
+        let models = numbered_models(3, &index);
+        store
+            .save(AnyActerModel::TestModel(models[0].clone()))
+            .await?;
+
+        let (current, stream) = store.subscribe_index_diff(&index).await?;
+        assert_eq!(current, vec![models[0].event_id().to_owned()]);
+        futures::pin_mut!(stream);
+
+        store
+            .save(AnyActerModel::TestModel(models[1].clone()))
+            .await?;
+        let Some(VectorDiff::PushFront { value }) = stream.next().await else {
+            bail!("expected the new model in front");
+        };
+        assert_eq!(value, models[1].event_id().to_owned());
+
+        store.clear_room(&models[0].room_id().to_owned()).await?;
+        let Some(VectorDiff::Remove { index: 1 }) = stream.next().await else {
+            bail!("expected the first model to be removed");
+        };
+        Ok(())
+    }
 }
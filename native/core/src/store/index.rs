@@ -1,12 +1,12 @@
 use std::{fmt::Debug, ops::Deref};
 
 use eyeball_im::{ObservableVector, ObservableVectorTransactionEntry, VectorDiff};
-use futures::{Stream, StreamExt};
+use futures::{stream::BoxStream, Stream, StreamExt};
 use matrix_sdk::ruma::{MilliSecondsSinceUnixEpoch, OwnedEventId};
 
 use crate::{
     models::EventMeta,
-    referencing::{IndexKey, ObjectListIndex, SectionIndex},
+    referencing::{IndexKey, ObjectListIndex, SectionIndex, SpecialListsIndex},
 };
 
 /// Keeps an index of items sorted by the given rank, highest rank first
@@ -263,6 +263,40 @@ where
     }
 }
 
+/// How the entries of an index are ordered
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum IndexOrder {
+    /// latest added first
+    Lifo,
+    /// latest added last
+    Filo,
+    /// latest by `origin_server_ts` first
+    Ranked,
+}
+
+impl IndexOrder {
+    pub fn for_key(key: &IndexKey) -> IndexOrder {
+        match key {
+            IndexKey::AllHistory
+            | IndexKey::ObjectHistory(_)
+            | IndexKey::RoomHistory(_)
+            | IndexKey::Special(SpecialListsIndex::MyMentions) => IndexOrder::Ranked,
+            //RSVPs and votes are latest first for collection
+            IndexKey::ObjectList(_, ObjectListIndex::Rsvp | ObjectListIndex::Votes) => {
+                IndexOrder::Ranked
+            }
+            IndexKey::Section(SectionIndex::Boosts)
+            | IndexKey::Section(SectionIndex::Stories)
+            | IndexKey::RoomSection(_, SectionIndex::Boosts)
+            | IndexKey::RoomSection(_, SectionIndex::Stories) => IndexOrder::Ranked,
+            IndexKey::ObjectList(_, ObjectListIndex::Tasks) | IndexKey::TaskStatus(..) => {
+                IndexOrder::Filo
+            }
+            _ => IndexOrder::Lifo,
+        }
+    }
+}
+
 pub enum StoreIndex {
     Lifo(LifoIndex<OwnedEventId>),
     Filo(FiloIndex<OwnedEventId>),
@@ -271,28 +305,31 @@ pub enum StoreIndex {
 
 impl StoreIndex {
     pub fn new_for(key: &IndexKey, meta: &EventMeta) -> StoreIndex {
-        match key {
-            IndexKey::AllHistory | IndexKey::ObjectHistory(_) | IndexKey::RoomHistory(_) => {
-                StoreIndex::Ranked(RankedIndex::new_with(
-                    meta.origin_server_ts,
-                    meta.event_id.clone(),
-                ))
-            }
-            //RSVPs are latest first for collection
-            IndexKey::ObjectList(_, ObjectListIndex::Rsvp) => StoreIndex::Ranked(
-                RankedIndex::new_with(meta.origin_server_ts, meta.event_id.clone()),
-            ),
-            IndexKey::Section(SectionIndex::Boosts)
-            | IndexKey::Section(SectionIndex::Stories)
-            | IndexKey::RoomSection(_, SectionIndex::Boosts)
-            | IndexKey::RoomSection(_, SectionIndex::Stories) => StoreIndex::Ranked(
-                RankedIndex::new_with(meta.origin_server_ts, meta.event_id.clone()),
-            ),
-            IndexKey::ObjectList(_, ObjectListIndex::Tasks) => {
-                StoreIndex::Filo(FiloIndex::new_with(meta.event_id.clone()))
+        let mut index = StoreIndex::empty_for(key);
+        index.insert(meta);
+        index
+    }
+
+    pub fn empty_for(key: &IndexKey) -> StoreIndex {
+        match IndexOrder::for_key(key) {
+            IndexOrder::Ranked => StoreIndex::Ranked(RankedIndex::default()),
+            IndexOrder::Filo => StoreIndex::Filo(FiloIndex::default()),
+            IndexOrder::Lifo => StoreIndex::Lifo(LifoIndex::default()),
+        }
+    }
+
+    /// Build the index from its members in the order of the index
+    pub fn from_ordered(key: &IndexKey, metas: Vec<&EventMeta>) -> StoreIndex {
+        let mut index = StoreIndex::empty_for(key);
+        match IndexOrder::for_key(key) {
+            // appended at the back
+            IndexOrder::Filo => metas.into_iter().for_each(|m| index.insert(m)),
+            // the latest goes in front
+            IndexOrder::Lifo | IndexOrder::Ranked => {
+                metas.into_iter().rev().for_each(|m| index.insert(m))
             }
-            _ => StoreIndex::Lifo(LifoIndex::new_with(meta.event_id.clone())),
         }
+        index
     }
 
     pub fn insert(&mut self, meta: &EventMeta) {
@@ -321,13 +358,14 @@ impl StoreIndex {
         }
     }
 
-    // pub fn update_stream(&self) -> impl Stream<Item = VectorDiff<OwnedEventId>> {
-    //     match self {
-    //         StoreIndex::Lifo(lifo_index) => lifo_index.update_stream(),
-    //         StoreIndex::Filo(lifo_index) => lifo_index.update_stream(),
-    //         StoreIndex::Ranked(ranked_index) => ranked_index.update_stream(),
-    //     }
-    // }
+    /// The changes to this index from now on
+    pub fn update_stream(&self) -> BoxStream<'static, VectorDiff<OwnedEventId>> {
+        match self {
+            StoreIndex::Lifo(lifo_index) => lifo_index.update_stream().boxed(),
+            StoreIndex::Filo(filo_index) => filo_index.update_stream().boxed(),
+            StoreIndex::Ranked(ranked_index) => ranked_index.update_stream().boxed(),
+        }
+    }
 }
 
 impl Debug for StoreIndex {
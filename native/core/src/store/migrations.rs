@@ -0,0 +1,497 @@
+use std::sync::Arc;
+
+use futures::Stream;
+use matrix_sdk::Client;
+use matrix_sdk_base::ruma::OwnedUserId;
+use serde_json::Value;
+use tokio::sync::broadcast::{channel, error::RecvError, Sender};
+use tracing::{info, warn};
+
+use super::{SqliteModelStore, Store, ALL_MODELS_KEY, CURRENT_DB_VERSION, DB_VERSION_KEY};
+use crate::{models::AnyActerModel, Error, Result};
+
+/// The first version models were kept in the database with
+const FIRST_MODELS_DB_VERSION: u32 = 1;
+
+/// One upgrade of the stored models, from the version before to `version`
+#[derive(Clone, Copy, Debug)]
+pub struct MigrationStep {
+    /// The version the models are at after this step
+    pub version: u32,
+    /// What this step does, reported with the progress
+    pub name: &'static str,
+    /// Transform the JSON of a stored `AnyActerModel`, `None` drops the model.
+    ///
+    /// An interrupted step is run again on the next start, so this must also
+    /// accept models it has already migrated.
+    pub migrate: fn(Value) -> Result<Option<Value>>,
+}
+
+/// All migration steps, in order. Add a step here and bump
+/// `CURRENT_DB_VERSION` whenever the stored format of a model changes.
+///
+/// Anything from before versioning (version `0`) can’t be migrated and is
+/// thrown away instead, as is any version without a complete path.
+pub const MIGRATIONS: &[MigrationStep] = &[];
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum MigrationProgress {
+    /// There is no way to migrate, the stored models are dropped
+    Reset {
+        from: u32,
+    },
+    Started {
+        from: u32,
+        to: u32,
+    },
+    Step {
+        version: u32,
+        name: &'static str,
+        total: usize,
+    },
+    Model {
+        version: u32,
+        done: usize,
+        total: usize,
+    },
+    Finished {
+        version: u32,
+    },
+}
+
+/// Brings the stored models up to the current version.
+///
+/// `Store::new` runs this itself. To follow the progress, create it first,
+/// `subscribe` and `run` it before creating the store.
+#[derive(Clone)]
+pub struct StoreMigrator {
+    client: Client,
+    user_id: OwnedUserId,
+    db: Option<Arc<SqliteModelStore>>,
+    steps: Vec<MigrationStep>,
+    target: u32,
+    progress: Sender<MigrationProgress>,
+}
+
+impl StoreMigrator {
+    pub fn new(client: Client, user_id: OwnedUserId, db: Option<Arc<SqliteModelStore>>) -> Self {
+        Self::with_steps(client, user_id, db, MIGRATIONS.to_vec(), CURRENT_DB_VERSION)
+    }
+
+    pub(crate) fn with_steps(
+        client: Client,
+        user_id: OwnedUserId,
+        db: Option<Arc<SqliteModelStore>>,
+        steps: Vec<MigrationStep>,
+        target: u32,
+    ) -> Self {
+        let (progress, _) = channel(64);
+        StoreMigrator {
+            client,
+            user_id,
+            db,
+            steps,
+            target,
+            progress,
+        }
+    }
+
+    pub fn subscribe(&self) -> impl Stream<Item = MigrationProgress> {
+        let mut receiver = self.progress.subscribe();
+        async_stream::stream! {
+            loop {
+                match receiver.recv().await {
+                    Ok(progress) => yield progress,
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        }
+    }
+
+    fn report(&self, progress: MigrationProgress) {
+        // nobody listening is fine
+        let _ = self.progress.send(progress);
+    }
+
+    /// The steps leading from `from` to the target, if there is a full path
+    fn path_from(&self, from: u32) -> Option<Vec<MigrationStep>> {
+        if from == 0 {
+            return None;
+        }
+        let mut version = from;
+        let mut path = Vec::new();
+        for step in self.steps.iter().filter(|s| s.version > from) {
+            if step.version != version + 1 {
+                return None;
+            }
+            path.push(*step);
+            version = step.version;
+        }
+        (version == self.target).then_some(path)
+    }
+
+    pub async fn run(&self) -> Result<()> {
+        if let Some(db) = &self.db {
+            // the migration of the database blocks
+            let migrator = self.clone();
+            db.blocking(move |db| migrator.migrate_db(db)).await?;
+        }
+        self.migrate_state_store().await
+    }
+
+    async fn stored_version(&self) -> Result<u32> {
+        Ok(self
+            .client
+            .state_store()
+            .get_custom_value(DB_VERSION_KEY.as_bytes())
+            .await
+            .map_err(|e| Error::Custom(format!("failed to find DB version key: {e}")))?
+            .map(|u| u32::from_le_bytes(u.as_chunks().0[0]))
+            .unwrap_or_default())
+    }
+
+    async fn set_version(&self, version: u32) -> Result<()> {
+        self.client
+            .state_store()
+            .set_custom_value_no_read(DB_VERSION_KEY.as_bytes(), version.to_le_bytes().to_vec())
+            .await
+            .map_err(|e| Error::Custom(format!("setting db version failed: {e}")))?;
+        Ok(())
+    }
+
+    async fn set_all_models(&self, keys: &[String]) -> Result<()> {
+        self.client
+            .state_store()
+            .set_custom_value_no_read(ALL_MODELS_KEY.as_bytes(), serde_json::to_vec(keys)?)
+            .await
+            .map_err(|e| Error::Custom(format!("setting all models failed: {e}")))?;
+        Ok(())
+    }
+
+    async fn migrate_state_store(&self) -> Result<()> {
+        let from = self.stored_version().await?;
+        if from >= self.target {
+            return Ok(());
+        }
+        let client_store = self.client.state_store();
+        // the search documents are rebuilt from the models on load
+        Store::remove_search_index(&self.client).await?;
+
+        let Some(path) = self.path_from(from) else {
+            warn!(from, to = self.target, "no migration path, resetting");
+            self.report(MigrationProgress::Reset { from });
+            self.set_all_models(&[]).await?;
+            return self.set_version(self.target).await;
+        };
+
+        info!(from, to = self.target, "migrating models");
+        self.report(MigrationProgress::Started {
+            from,
+            to: self.target,
+        });
+        let mut keys = client_store
+            .get_custom_value(ALL_MODELS_KEY.as_bytes())
+            .await?
+            .filter(|v| !v.is_empty())
+            .map(|v| serde_json::from_slice::<Vec<String>>(&v))
+            .transpose()?
+            .unwrap_or_default();
+
+        for step in path {
+            let version = step.version;
+            let total = keys.len();
+            info!(version, name = step.name, total, "migration step");
+            self.report(MigrationProgress::Step {
+                version,
+                name: step.name,
+                total,
+            });
+            let mut kept = Vec::with_capacity(total);
+            for (done, key) in keys.into_iter().enumerate() {
+                let model_key = format!("acter:{key}");
+                if let Some(raw) = client_store.get_custom_value(model_key.as_bytes()).await? {
+                    let migrated = match serde_json::from_slice(&raw) {
+                        Ok(value) => (step.migrate)(value)?,
+                        Err(error) => {
+                            warn!(key, ?error, "unreadable model, dropping");
+                            None
+                        }
+                    };
+                    match migrated {
+                        Some(value) => {
+                            client_store
+                                .set_custom_value_no_read(
+                                    model_key.as_bytes(),
+                                    serde_json::to_vec(&value)?,
+                                )
+                                .await?;
+                            kept.push(key);
+                        }
+                        None => {
+                            client_store
+                                .remove_custom_value(model_key.as_bytes())
+                                .await?;
+                        }
+                    }
+                }
+                self.report(MigrationProgress::Model {
+                    version,
+                    done: done + 1,
+                    total,
+                });
+            }
+            self.set_all_models(&kept).await?;
+            self.set_version(version).await?;
+            keys = kept;
+        }
+
+        self.report(MigrationProgress::Finished {
+            version: self.target,
+        });
+        Ok(())
+    }
+
+    fn migrate_db(&self, db: &SqliteModelStore) -> Result<()> {
+        if db.is_empty()? {
+            return db.set_models_version(self.target);
+        }
+        let from = db.models_version()?.unwrap_or(FIRST_MODELS_DB_VERSION);
+        if from >= self.target {
+            return Ok(());
+        }
+        let Some(path) = self.path_from(from) else {
+            warn!(
+                from,
+                to = self.target,
+                "no migration path for the database, resetting"
+            );
+            self.report(MigrationProgress::Reset { from });
+            db.clear()?;
+            return db.set_models_version(self.target);
+        };
+
+        info!(from, to = self.target, "migrating models in the database");
+        self.report(MigrationProgress::Started {
+            from,
+            to: self.target,
+        });
+        db.migrate_models(
+            self.target,
+            |value| {
+                let mut migrated = Some(value);
+                for step in path.iter() {
+                    let Some(value) = migrated else {
+                        break;
+                    };
+                    migrated = (step.migrate)(value)?;
+                }
+                let Some(value) = migrated else {
+                    return Ok(None);
+                };
+                let model = serde_json::from_value::<AnyActerModel>(value)?;
+                let indizes = Store::db_indizes(&model, &self.user_id);
+                Ok(Some((model, indizes)))
+            },
+            |done, total| {
+                self.report(MigrationProgress::Model {
+                    version: self.target,
+                    done,
+                    total,
+                })
+            },
+        )?;
+        self.report(MigrationProgress::Finished {
+            version: self.target,
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+    use matrix_sdk_base::{
+        ruma::{api::MatrixVersion, user_id, OwnedEventId},
+        store::{MemoryStore, StoreConfig},
+    };
+
+    /// Pins as they were stored at version 1
+    const MODELS_V1: &str = include_str!("fixtures/models-v1.json");
+
+    async fn test_client() -> Result<Client> {
+        let config = StoreConfig::new("tests".to_owned()).state_store(MemoryStore::new());
+        Ok(Client::builder()
+            .homeserver_url("http://localhost")
+            .server_versions([MatrixVersion::V1_5])
+            .store_config(config)
+            .build()
+            .await
+            .unwrap())
+    }
+
+    fn fixture() -> Vec<(String, Value)> {
+        let models: serde_json::Map<String, Value> = serde_json::from_str(MODELS_V1).unwrap();
+        models.into_iter().collect()
+    }
+
+    async fn write_fixture(client: &Client, version: u32) -> Result<()> {
+        let client_store = client.state_store();
+        let mut keys = Vec::new();
+        for (key, value) in fixture() {
+            client_store
+                .set_custom_value_no_read(
+                    format!("acter:{key}").as_bytes(),
+                    serde_json::to_vec(&value)?,
+                )
+                .await?;
+            keys.push(key);
+        }
+        client_store
+            .set_custom_value_no_read(ALL_MODELS_KEY.as_bytes(), serde_json::to_vec(&keys)?)
+            .await?;
+        client_store
+            .set_custom_value_no_read(DB_VERSION_KEY.as_bytes(), version.to_le_bytes().to_vec())
+            .await?;
+        Ok(())
+    }
+
+    fn upper_case_titles(mut value: Value) -> Result<Option<Value>> {
+        if let Some(title) = value.pointer_mut("/Pin/inner/title") {
+            if let Some(upper) = title.as_str().map(str::to_uppercase) {
+                *title = Value::String(upper);
+            }
+        }
+        Ok(Some(value))
+    }
+
+    fn drop_linked(value: Value) -> Result<Option<Value>> {
+        if value.pointer("/Pin/inner/url").is_some() {
+            return Ok(None);
+        }
+        Ok(Some(value))
+    }
+
+    fn test_steps() -> Vec<MigrationStep> {
+        vec![
+            MigrationStep {
+                version: 2,
+                name: "upper case titles",
+                migrate: upper_case_titles,
+            },
+            MigrationStep {
+                version: 3,
+                name: "drop linked pins",
+                migrate: drop_linked,
+            },
+        ]
+    }
+
+    async fn pin_title(store: &Store, key: &str) -> Result<Option<String>> {
+        let key = OwnedEventId::try_from(key).unwrap();
+        match store.get(&key).await {
+            Ok(AnyActerModel::Pin(pin)) => Ok(Some(pin.title())),
+            Ok(other) => Err(Error::Custom(format!("not a pin: {other:?}"))),
+            Err(Error::ModelNotFound(_)) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    #[tokio::test]
+    async fn migrates_recorded_models() -> anyhow::Result<()> {
+        let _ = env_logger::try_init();
+        let client = test_client().await?;
+        let user_id = user_id!("@test:example.org").to_owned();
+        write_fixture(&client, 1).await?;
+
+        let migrator =
+            StoreMigrator::with_steps(client.clone(), user_id.clone(), None, test_steps(), 3);
+        let progress = migrator.subscribe();
+        migrator.run().await?;
+        drop(migrator);
+        let progress = progress.collect::<Vec<_>>().await;
+        assert_eq!(
+            progress.first(),
+            Some(&MigrationProgress::Started { from: 1, to: 3 })
+        );
+        assert!(progress.contains(&MigrationProgress::Model {
+            version: 3,
+            done: 3,
+            total: 3
+        }));
+        assert_eq!(
+            progress.last(),
+            Some(&MigrationProgress::Finished { version: 3 })
+        );
+
+        // newer than the code knows, loaded as is
+        let store = Store::new_with_auth(client, user_id).await?;
+        assert_eq!(
+            pin_title(&store, "$pin-one").await?.as_deref(),
+            Some("TEAM MEETING NOTES")
+        );
+        assert_eq!(
+            pin_title(&store, "$pin-two").await?.as_deref(),
+            Some("WIFI PASSWORD")
+        );
+        assert_eq!(pin_title(&store, "$pin-linked").await?, None);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn resets_without_migration_path() -> anyhow::Result<()> {
+        let _ = env_logger::try_init();
+        let client = test_client().await?;
+        let user_id = user_id!("@test:example.org").to_owned();
+        write_fixture(&client, 0).await?;
+
+        let migrator =
+            StoreMigrator::with_steps(client.clone(), user_id.clone(), None, test_steps(), 3);
+        let progress = migrator.subscribe();
+        migrator.run().await?;
+        drop(migrator);
+        let progress = progress.collect::<Vec<_>>().await;
+        assert_eq!(progress, vec![MigrationProgress::Reset { from: 0 }]);
+
+        let store = Store::new_with_auth(client, user_id).await?;
+        assert_eq!(pin_title(&store, "$pin-one").await?, None);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn migrates_models_in_database() -> anyhow::Result<()> {
+        let _ = env_logger::try_init();
+        let client = test_client().await?;
+        let user_id = user_id!("@test:example.org").to_owned();
+        let db = Arc::new(SqliteModelStore::open_in_memory(Some("passphrase"))?);
+        db.save(
+            fixture()
+                .into_iter()
+                .map(|(_, value)| {
+                    let model = serde_json::from_value::<AnyActerModel>(value).unwrap();
+                    let indizes = Store::db_indizes(&model, &user_id);
+                    (model, indizes)
+                })
+                .collect(),
+        )?;
+
+        let migrator = StoreMigrator::with_steps(
+            client.clone(),
+            user_id.clone(),
+            Some(db.clone()),
+            test_steps(),
+            3,
+        );
+        migrator.run().await?;
+        assert_eq!(db.models_version()?, Some(3));
+
+        let get = |key: &str| db.get(&OwnedEventId::try_from(key).unwrap());
+        let Some(AnyActerModel::Pin(pin)) = get("$pin-one")? else {
+            anyhow::bail!("pin missing");
+        };
+        assert_eq!(pin.title(), "TEAM MEETING NOTES");
+        assert!(get("$pin-linked")?.is_none());
+        Ok(())
+    }
+}
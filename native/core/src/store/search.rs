@@ -0,0 +1,506 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use derive_builder::Builder;
+use matrix_sdk::ruma::{MilliSecondsSinceUnixEpoch, OwnedEventId, OwnedRoomId};
+use matrix_sdk_base::ruma::events::room::message::TextMessageEventContent;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    events::calendar::EventLocation,
+    models::{ActerModel, AnyActerModel},
+};
+
+// BM25 tuning, the usual defaults
+const K1: f64 = 1.2;
+const B: f64 = 0.75;
+// matches in the title count this much more than in the body
+const TITLE_BOOST: f64 = 2.0;
+// a term only matching as prefix of a word counts this much of a full match
+const PREFIX_WEIGHT: f64 = 0.5;
+
+/// Split the text into lower-cased words
+pub fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+fn text_body(content: &TextMessageEventContent) -> &str {
+    &content.body
+}
+
+/// The searchable text of a single object
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct SearchDocument {
+    pub event_id: OwnedEventId,
+    pub room_id: OwnedRoomId,
+    /// the object type, as in `ActivityObject::type_str`
+    pub object_type: String,
+    /// the object this belongs to, e.g. the object a comment was made on
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<OwnedEventId>,
+    pub title: String,
+    pub origin_server_ts: MilliSecondsSinceUnixEpoch,
+    title_terms: Vec<String>,
+    terms: BTreeMap<String, u32>,
+    length: u32,
+}
+
+impl SearchDocument {
+    fn new(
+        model: &AnyActerModel,
+        object_type: &str,
+        parent_id: Option<OwnedEventId>,
+        title: String,
+        texts: Vec<&str>,
+    ) -> Self {
+        let meta = model.event_meta();
+        let title_terms = tokenize(&title);
+        let mut terms = BTreeMap::new();
+        let mut length = 0;
+        for term in title_terms
+            .iter()
+            .cloned()
+            .chain(texts.into_iter().flat_map(tokenize))
+        {
+            *terms.entry(term).or_default() += 1;
+            length += 1;
+        }
+        SearchDocument {
+            event_id: meta.event_id.clone(),
+            room_id: meta.room_id.clone(),
+            object_type: object_type.to_owned(),
+            parent_id,
+            title,
+            origin_server_ts: meta.origin_server_ts,
+            title_terms,
+            terms,
+            length,
+        }
+    }
+
+    /// The document for the model, if it is one of the searchable objects
+    pub fn for_model(model: &AnyActerModel) -> Option<Self> {
+        let doc = match model {
+            AnyActerModel::Pin(pin) => {
+                let mut texts = vec![];
+                if let Some(content) = &pin.content {
+                    texts.push(text_body(content));
+                }
+                if let Some(url) = &pin.url {
+                    texts.push(url.as_str());
+                }
+                SearchDocument::new(model, "pin", None, pin.title.clone(), texts)
+            }
+            AnyActerModel::Poll(poll) => {
+                let mut texts: Vec<&str> = poll.content.iter().map(text_body).collect();
+                texts.extend(poll.answers.iter().map(|a| a.title.as_str()));
+                SearchDocument::new(model, "poll", None, poll.title.clone(), texts)
+            }
+            AnyActerModel::TaskList(task_list) => SearchDocument::new(
+                model,
+                "task-list",
+                None,
+                task_list.name.clone(),
+                task_list.description.iter().map(text_body).collect(),
+            ),
+            AnyActerModel::Task(task) => SearchDocument::new(
+                model,
+                "task",
+                Some(task.task_list_id.event_id.clone()),
+                task.title.clone(),
+                task.description.iter().map(text_body).collect(),
+            ),
+            AnyActerModel::CalendarEvent(event) => {
+                let mut texts: Vec<&str> = event.description.iter().map(text_body).collect();
+                for location in &event.locations {
+                    let (EventLocation::Physical {
+                        name, description, ..
+                    }
+                    | EventLocation::Virtual {
+                        name, description, ..
+                    }) = location;
+                    texts.extend(name.as_deref());
+                    texts.extend(description.as_ref().map(text_body));
+                }
+                SearchDocument::new(model, "event", None, event.title.clone(), texts)
+            }
+            AnyActerModel::NewsEntry(news) => {
+                let texts: Vec<String> = news
+                    .slides
+                    .iter()
+                    .map(|s| match s.content.text() {
+                        Some(text) => text.body,
+                        None => s.content.text_str(),
+                    })
+                    .collect();
+                let title = texts.first().cloned().unwrap_or_default();
+                SearchDocument::new(
+                    model,
+                    "news",
+                    None,
+                    title,
+                    texts.iter().skip(1).map(String::as_str).collect(),
+                )
+            }
+            AnyActerModel::Story(story) => {
+                let texts: Vec<String> = story
+                    .slides
+                    .iter()
+                    .map(|s| match s.content.text() {
+                        Some(text) => text.body,
+                        None => s.content.text_str(),
+                    })
+                    .collect();
+                let title = texts.first().cloned().unwrap_or_default();
+                SearchDocument::new(
+                    model,
+                    "story",
+                    None,
+                    title,
+                    texts.iter().skip(1).map(String::as_str).collect(),
+                )
+            }
+            AnyActerModel::Comment(comment) => SearchDocument::new(
+                model,
+                "comment",
+                Some(comment.on.event_id.clone()),
+                String::new(),
+                vec![text_body(&comment.content)],
+            ),
+            _ => return None,
+        };
+        Some(doc)
+    }
+}
+
+/// Which objects to search through
+#[derive(Clone, Debug, Default, Builder)]
+#[builder(default)]
+pub struct SearchFilters {
+    /// only objects in these spaces, all if empty
+    #[builder(setter(into))]
+    pub room_ids: Vec<OwnedRoomId>,
+    /// only objects of these types, all if empty
+    #[builder(setter(into))]
+    pub object_types: Vec<String>,
+    /// at most this many results
+    #[builder(setter(into))]
+    pub limit: Option<usize>,
+}
+
+impl SearchFilters {
+    fn matches(&self, doc: &SearchDocument) -> bool {
+        (self.room_ids.is_empty() || self.room_ids.contains(&doc.room_id))
+            && (self.object_types.is_empty() || self.object_types.contains(&doc.object_type))
+    }
+}
+
+/// A match of the search, best first
+#[derive(Clone, Debug)]
+pub struct SearchHit {
+    pub document: SearchDocument,
+    pub score: f64,
+}
+
+/// Inverted index over the searchable objects, kept up to date by the store
+#[derive(Debug, Default)]
+pub struct SearchIndex {
+    documents: HashMap<OwnedEventId, SearchDocument>,
+    postings: BTreeMap<String, HashSet<OwnedEventId>>,
+    total_length: u64,
+    /// the rooms with changes not yet persisted
+    dirty: HashSet<OwnedRoomId>,
+}
+
+impl SearchIndex {
+    pub fn from_documents(documents: Vec<SearchDocument>) -> Self {
+        let mut index = SearchIndex::default();
+        for doc in documents {
+            index.insert(doc);
+        }
+        index.dirty.clear();
+        index
+    }
+
+    pub fn len(&self) -> usize {
+        self.documents.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.documents.is_empty()
+    }
+
+    pub fn contains(&self, event_id: &OwnedEventId) -> bool {
+        self.documents.contains_key(event_id)
+    }
+
+    /// the rooms with changes not yet persisted, resets them
+    pub(crate) fn take_dirty(&mut self) -> HashSet<OwnedRoomId> {
+        std::mem::take(&mut self.dirty)
+    }
+
+    /// mark all rooms as changed, e.g. to persist them in a new layout
+    pub(crate) fn mark_all_dirty(&mut self) {
+        self.dirty = self.rooms();
+    }
+
+    /// the rooms with any indexed documents
+    pub(crate) fn rooms(&self) -> HashSet<OwnedRoomId> {
+        self.documents
+            .values()
+            .map(|doc| doc.room_id.clone())
+            .collect()
+    }
+
+    pub(crate) fn room_documents(&self, room_id: &OwnedRoomId) -> Vec<&SearchDocument> {
+        self.documents
+            .values()
+            .filter(|doc| &doc.room_id == room_id)
+            .collect()
+    }
+
+    /// (Re)index the model, dropping it if it isn’t searchable (anymore)
+    pub fn update(&mut self, event_id: &OwnedEventId, model: &AnyActerModel) {
+        match SearchDocument::for_model(model) {
+            Some(doc) => {
+                if self.documents.get(event_id) != Some(&doc) {
+                    self.remove(event_id);
+                    self.insert(doc);
+                }
+            }
+            None => self.remove(event_id),
+        }
+    }
+
+    fn insert(&mut self, doc: SearchDocument) {
+        for term in doc.terms.keys() {
+            self.postings
+                .entry(term.clone())
+                .or_default()
+                .insert(doc.event_id.clone());
+        }
+        self.total_length += u64::from(doc.length);
+        self.dirty.insert(doc.room_id.clone());
+        self.documents.insert(doc.event_id.clone(), doc);
+    }
+
+    pub fn remove(&mut self, event_id: &OwnedEventId) {
+        let Some(doc) = self.documents.remove(event_id) else {
+            return;
+        };
+        for term in doc.terms.keys() {
+            if let Some(ids) = self.postings.get_mut(term) {
+                ids.remove(event_id);
+                if ids.is_empty() {
+                    self.postings.remove(term);
+                }
+            }
+        }
+        self.total_length -= u64::from(doc.length);
+        self.dirty.insert(doc.room_id);
+    }
+
+    /// the indexed terms matching `term`, with the weight of the match
+    fn expand(&self, term: &str, allow_prefix: bool) -> Vec<(&String, f64)> {
+        if !allow_prefix {
+            return self
+                .postings
+                .get_key_value(term)
+                .map(|(k, _)| vec![(k, 1.0)])
+                .unwrap_or_default();
+        }
+        self.postings
+            .range(term.to_owned()..)
+            .take_while(|(k, _)| k.starts_with(term))
+            .map(|(k, _)| (k, if k == term { 1.0 } else { PREFIX_WEIGHT }))
+            .collect()
+    }
+
+    /// Objects containing all words of the query, the last one may be the
+    /// beginning of a word only, ranked by BM25 with titles boosted
+    pub fn search(&self, query: &str, filters: &SearchFilters) -> Vec<SearchHit> {
+        let query_terms = tokenize(query);
+        if query_terms.is_empty() || self.documents.is_empty() {
+            return vec![];
+        }
+        let doc_count = self.documents.len() as f64;
+        let avg_length = self.total_length as f64 / doc_count;
+        let last = query_terms.len() - 1;
+
+        let mut scores: Option<HashMap<&OwnedEventId, f64>> = None;
+        for (pos, query_term) in query_terms.iter().enumerate() {
+            let mut term_scores: HashMap<&OwnedEventId, f64> = HashMap::new();
+            for (term, weight) in self.expand(query_term, pos == last) {
+                let ids = &self.postings[term];
+                let n = ids.len() as f64;
+                let idf = (1.0 + (doc_count - n + 0.5) / (n + 0.5)).ln();
+                for id in ids {
+                    let doc = &self.documents[id];
+                    let tf = f64::from(doc.terms[term]);
+                    let norm = K1 * (1.0 - B + B * f64::from(doc.length) / avg_length);
+                    let mut score = idf * tf * (K1 + 1.0) / (tf + norm);
+                    if doc.title_terms.contains(term) {
+                        score *= TITLE_BOOST;
+                    }
+                    let best = term_scores.entry(id).or_default();
+                    *best = best.max(score * weight);
+                }
+            }
+            // all words must match
+            scores = Some(match scores {
+                None => term_scores,
+                Some(prev) => prev
+                    .into_iter()
+                    .filter_map(|(id, s)| term_scores.get(id).map(|t| (id, s + t)))
+                    .collect(),
+            });
+        }
+
+        let mut hits: Vec<SearchHit> = scores
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|(id, score)| {
+                let document = &self.documents[id];
+                filters.matches(document).then(|| SearchHit {
+                    document: document.clone(),
+                    score,
+                })
+            })
+            .collect();
+        hits.sort_by(|a, b| {
+            b.score.total_cmp(&a.score).then_with(|| {
+                b.document
+                    .origin_server_ts
+                    .cmp(&a.document.origin_server_ts)
+            })
+        });
+        if let Some(limit) = filters.limit {
+            hits.truncate(limit);
+        }
+        hits
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{events::AnyActerEvent, Error, Result};
+    use serde_json::{from_value, json};
+
+    fn model(
+        event_id: &str,
+        room: &str,
+        event_type: &str,
+        content: serde_json::Value,
+    ) -> Result<AnyActerModel> {
+        let event: AnyActerEvent = from_value(json!({
+            "type": event_type,
+            "room_id": room,
+            "sender": "@test:example.org",
+            "event_id": event_id,
+            "origin_server_ts": 123456,
+            "content": content,
+        }))?;
+        AnyActerModel::try_from(event).map_err(|e| Error::Custom(format!("{e:?}")))
+    }
+
+    fn pin(event_id: &str, room: &str, title: &str, body: &str) -> Result<AnyActerModel> {
+        model(
+            event_id,
+            room,
+            "global.acter.dev.pin",
+            json!({ "title": title, "content": { "body": body } }),
+        )
+    }
+
+    fn index_of(models: &[AnyActerModel]) -> SearchIndex {
+        let mut index = SearchIndex::default();
+        for m in models {
+            index.update(&m.event_id().to_owned(), m);
+        }
+        index
+    }
+
+    #[test]
+    fn title_matches_rank_first() -> Result<()> {
+        let index = index_of(&[
+            pin(
+                "$body",
+                "!a:example.org",
+                "Handbook",
+                "where is the garden key",
+            )?,
+            pin(
+                "$title",
+                "!a:example.org",
+                "Garden key",
+                "ask at the front desk",
+            )?,
+            pin("$none", "!a:example.org", "Lunch", "pizza on friday")?,
+        ]);
+        let hits = index.search("garden KEY", &SearchFilters::default());
+        let ids: Vec<_> = hits.iter().map(|h| h.document.event_id.as_str()).collect();
+        assert_eq!(ids, ["$title", "$body"]);
+        Ok(())
+    }
+
+    #[test]
+    fn last_word_matches_as_prefix() -> Result<()> {
+        let index = index_of(&[pin("$p", "!a:example.org", "Gardening", "")?]);
+        assert_eq!(index.search("gard", &SearchFilters::default()).len(), 1);
+        // but only the last one
+        assert!(index
+            .search("gard club", &SearchFilters::default())
+            .is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn filters_and_removal() -> Result<()> {
+        let mut index = index_of(&[
+            pin("$a", "!a:example.org", "Meeting notes", "")?,
+            pin("$b", "!b:example.org", "Meeting room", "")?,
+            model(
+                "$c",
+                "!a:example.org",
+                "global.acter.dev.comment",
+                json!({
+                    "m.relates_to": { "rel_type": "global.acter.dev.belongs_to", "event_id": "$a" },
+                    "content": { "body": "meeting moved to 3pm" },
+                }),
+            )?,
+        ]);
+
+        let in_space_a = SearchFiltersBuilder::default()
+            .room_ids(vec![OwnedRoomId::try_from("!a:example.org")?])
+            .build()
+            .map_err(|e| Error::Custom(e.to_string()))?;
+        assert_eq!(index.search("meeting", &in_space_a).len(), 2);
+
+        let comments = SearchFiltersBuilder::default()
+            .object_types(vec!["comment".to_owned()])
+            .build()
+            .map_err(|e| Error::Custom(e.to_string()))?;
+        let hits = index.search("meeting", &comments);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(
+            hits[0].document.parent_id.as_deref().map(|e| e.as_str()),
+            Some("$a")
+        );
+
+        index.take_dirty();
+        index.remove(&OwnedEventId::try_from("$b")?);
+        assert_eq!(index.search("meeting", &SearchFilters::default()).len(), 2);
+        assert!(index.search("room", &SearchFilters::default()).is_empty());
+        // only the room it was removed from needs to be persisted again
+        let dirty = index.take_dirty();
+        assert_eq!(dirty.len(), 1);
+        assert!(dirty.contains(&OwnedRoomId::try_from("!b:example.org")?));
+        assert!(index
+            .room_documents(&OwnedRoomId::try_from("!b:example.org")?)
+            .is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,363 @@
+use std::{
+    collections::HashSet,
+    fmt::Debug,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use matrix_sdk::ruma::{EventId, OwnedEventId};
+use matrix_sdk_store_encryption::StoreCipher;
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use tracing::{debug, trace};
+
+use super::index::IndexOrder;
+use crate::{
+    models::{ActerModel, AnyActerModel},
+    referencing::IndexKey,
+    Error, Result,
+};
+
+/// File name of the database inside the data directory
+pub const DATABASE_NAME: &str = "acter-models.sqlite3";
+/// The custom value in the state store the data directory is found under
+pub const DATA_PATH_KEY: &str = "ACTER::MODELS_DB_PATH";
+
+const DB_VERSION: u32 = 1;
+const CIPHER_KEY: &str = "cipher";
+const MODELS_VERSION_KEY: &str = "models_version";
+const MODELS_TABLE: &str = "models";
+const INDEX_TABLE: &str = "index_members";
+
+/// Model storage in SQLite, values are encrypted and keys hashed with
+/// the store cipher if a passphrase is given.
+///
+/// Every model is a row in `models` and every `IndexKey` it is listed
+/// under a row in `index_members`, so lists can be read page by page
+/// without loading everything first.
+pub struct SqliteModelStore {
+    conn: Mutex<Connection>,
+    cipher: Option<StoreCipher>,
+}
+
+impl Debug for SqliteModelStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SqliteModelStore")
+            .field("encrypted", &self.cipher.is_some())
+            .finish()
+    }
+}
+
+impl SqliteModelStore {
+    /// Open or create the database in the directory at `path`
+    pub fn open(path: &Path, passphrase: Option<&str>) -> Result<Self> {
+        std::fs::create_dir_all(path)?;
+        let conn = Connection::open(path.join(DATABASE_NAME))?;
+        Self::init(conn, passphrase)
+    }
+
+    pub fn open_in_memory(passphrase: Option<&str>) -> Result<Self> {
+        Self::init(Connection::open_in_memory()?, passphrase)
+    }
+
+    fn init(conn: Connection, passphrase: Option<&str>) -> Result<Self> {
+        conn.execute_batch(
+            "PRAGMA journal_mode = WAL;
+            CREATE TABLE IF NOT EXISTS kv (
+                key TEXT PRIMARY KEY NOT NULL,
+                value BLOB NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS models (
+                model_key BLOB PRIMARY KEY NOT NULL,
+                data BLOB NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS index_members (
+                seq INTEGER PRIMARY KEY AUTOINCREMENT,
+                index_key BLOB NOT NULL,
+                model_key BLOB NOT NULL,
+                origin_server_ts INTEGER NOT NULL,
+                UNIQUE (index_key, model_key)
+            );
+            CREATE INDEX IF NOT EXISTS index_members_by_index
+                ON index_members (index_key, seq);
+            CREATE INDEX IF NOT EXISTS index_members_by_model
+                ON index_members (model_key);",
+        )?;
+        conn.pragma_update(None, "user_version", DB_VERSION)?;
+
+        let cipher = match passphrase {
+            None => None,
+            Some(passphrase) => {
+                let exported: Option<Vec<u8>> = conn
+                    .query_row("SELECT value FROM kv WHERE key = ?1", [CIPHER_KEY], |r| {
+                        r.get(0)
+                    })
+                    .optional()?;
+                let cipher = match exported {
+                    Some(exported) => StoreCipher::import(passphrase, &exported)?,
+                    None => {
+                        let cipher = StoreCipher::new()?;
+                        conn.execute(
+                            "INSERT INTO kv (key, value) VALUES (?1, ?2)",
+                            params![CIPHER_KEY, cipher.export(passphrase)?],
+                        )?;
+                        cipher
+                    }
+                };
+                Some(cipher)
+            }
+        };
+
+        Ok(SqliteModelStore {
+            conn: Mutex::new(conn),
+            cipher,
+        })
+    }
+
+    /// Run the given calls on the blocking thread pool, rusqlite blocks
+    pub async fn blocking<T, F>(self: &Arc<Self>, call: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&SqliteModelStore) -> Result<T> + Send + 'static,
+    {
+        let db = self.clone();
+        tokio::task::spawn_blocking(move || call(&db))
+            .await
+            .map_err(|e| Error::Custom(format!("model database task failed: {e}")))?
+    }
+
+    fn encode_key(&self, table: &str, key: &[u8]) -> Vec<u8> {
+        match &self.cipher {
+            Some(cipher) => cipher.hash_key(table, key).to_vec(),
+            None => key.to_vec(),
+        }
+    }
+
+    fn model_key(&self, event_id: &EventId) -> Vec<u8> {
+        self.encode_key(MODELS_TABLE, event_id.as_bytes())
+    }
+
+    fn index_key(&self, key: &IndexKey) -> Result<Vec<u8>> {
+        Ok(self.encode_key(INDEX_TABLE, &serde_json::to_vec(key)?))
+    }
+
+    fn encode_value(&self, model: &AnyActerModel) -> Result<Vec<u8>> {
+        Ok(match &self.cipher {
+            Some(cipher) => cipher.encrypt_value(model)?,
+            None => serde_json::to_vec(model)?,
+        })
+    }
+
+    fn decode_value<T: DeserializeOwned>(&self, data: &[u8]) -> Result<T> {
+        Ok(match &self.cipher {
+            Some(cipher) => cipher.decrypt_value(data)?,
+            None => serde_json::from_slice(data)?,
+        })
+    }
+
+    /// The version of the models in here, as set by the store migrations
+    pub fn models_version(&self) -> Result<Option<u32>> {
+        let conn = self.conn.lock()?;
+        let version: Option<Vec<u8>> = conn
+            .query_row(
+                "SELECT value FROM kv WHERE key = ?1",
+                [MODELS_VERSION_KEY],
+                |r| r.get(0),
+            )
+            .optional()?;
+        Ok(version.and_then(|v| Some(u32::from_le_bytes(v.try_into().ok()?))))
+    }
+
+    pub fn set_models_version(&self, version: u32) -> Result<()> {
+        let conn = self.conn.lock()?;
+        Self::write_models_version(&conn, version)
+    }
+
+    fn write_models_version(conn: &Connection, version: u32) -> Result<()> {
+        conn.execute(
+            "INSERT OR REPLACE INTO kv (key, value) VALUES (?1, ?2)",
+            params![MODELS_VERSION_KEY, version.to_le_bytes().to_vec()],
+        )?;
+        Ok(())
+    }
+
+    /// Drop all models and index memberships
+    pub fn clear(&self) -> Result<()> {
+        let conn = self.conn.lock()?;
+        conn.execute_batch("DELETE FROM index_members; DELETE FROM models;")?;
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> Result<bool> {
+        let conn = self.conn.lock()?;
+        let count: i64 = conn.query_row("SELECT COUNT(*) FROM models", [], |r| r.get(0))?;
+        Ok(count == 0)
+    }
+
+    pub fn get(&self, event_id: &EventId) -> Result<Option<AnyActerModel>> {
+        let key = self.model_key(event_id);
+        let data: Option<Vec<u8>> = {
+            let conn = self.conn.lock()?;
+            conn.query_row("SELECT data FROM models WHERE model_key = ?1", [key], |r| {
+                r.get(0)
+            })
+            .optional()?
+        };
+        data.map(|d| self.decode_value(&d)).transpose()
+    }
+
+    /// Write the models and make them members of exactly the given indizes,
+    /// keeping the position in the indizes they already were in
+    pub fn save(&self, models: Vec<(AnyActerModel, Vec<IndexKey>)>) -> Result<()> {
+        let mut conn = self.conn.lock()?;
+        let tx = conn.transaction()?;
+        for (model, indizes) in models {
+            self.write_model(&tx, &model, &indizes)?;
+        }
+        tx.commit()?;
+        trace!("models saved");
+        Ok(())
+    }
+
+    /// Write the model, returning the key it was written under
+    fn write_model(
+        &self,
+        tx: &Transaction<'_>,
+        model: &AnyActerModel,
+        indizes: &[IndexKey],
+    ) -> Result<Vec<u8>> {
+        let model_key = self.model_key(model.event_id());
+        let ts = i64::from(model.event_meta().origin_server_ts.get());
+        tx.execute(
+            "INSERT OR REPLACE INTO models (model_key, data) VALUES (?1, ?2)",
+            params![model_key, self.encode_value(model)?],
+        )?;
+
+        let wanted = indizes
+            .iter()
+            .map(|i| self.index_key(i))
+            .collect::<Result<HashSet<_>>>()?;
+        let existing = {
+            let mut stmt =
+                tx.prepare_cached("SELECT index_key FROM index_members WHERE model_key = ?1")?;
+            let rows = stmt.query_map([&model_key], |r| r.get::<_, Vec<u8>>(0))?;
+            rows.collect::<rusqlite::Result<HashSet<_>>>()?
+        };
+        for stale in existing.difference(&wanted) {
+            tx.execute(
+                "DELETE FROM index_members WHERE index_key = ?1 AND model_key = ?2",
+                params![stale, model_key],
+            )?;
+        }
+        for added in wanted.difference(&existing) {
+            tx.execute(
+                "INSERT INTO index_members (index_key, model_key, origin_server_ts)
+                    VALUES (?1, ?2, ?3)",
+                params![added, model_key, ts],
+            )?;
+        }
+        Ok(model_key)
+    }
+
+    fn delete_model(tx: &Transaction<'_>, model_key: &[u8]) -> Result<()> {
+        tx.execute("DELETE FROM models WHERE model_key = ?1", [model_key])?;
+        tx.execute(
+            "DELETE FROM index_members WHERE model_key = ?1",
+            [model_key],
+        )?;
+        Ok(())
+    }
+
+    /// Rewrite every model through `migrate`, which gets the raw JSON of the
+    /// stored model and returns the new model and its indizes or `None` to drop
+    /// it. All or nothing: the models are only at `version` if this succeeds.
+    pub fn migrate_models<F, P>(&self, version: u32, mut migrate: F, mut progress: P) -> Result<()>
+    where
+        F: FnMut(Value) -> Result<Option<(AnyActerModel, Vec<IndexKey>)>>,
+        P: FnMut(usize, usize),
+    {
+        let mut conn = self.conn.lock()?;
+        let tx = conn.transaction()?;
+        let rows = {
+            let mut stmt = tx.prepare("SELECT model_key, data FROM models")?;
+            let rows = stmt.query_map([], |r| {
+                Ok((r.get::<_, Vec<u8>>(0)?, r.get::<_, Vec<u8>>(1)?))
+            })?;
+            rows.collect::<rusqlite::Result<Vec<_>>>()?
+        };
+        let total = rows.len();
+        for (done, (model_key, data)) in rows.into_iter().enumerate() {
+            match migrate(self.decode_value(&data)?)? {
+                Some((model, indizes)) => {
+                    if self.write_model(&tx, &model, &indizes)? != model_key {
+                        // it moved to a different key
+                        Self::delete_model(&tx, &model_key)?;
+                    }
+                }
+                None => Self::delete_model(&tx, &model_key)?,
+            }
+            progress(done + 1, total);
+        }
+        Self::write_models_version(&tx, version)?;
+        tx.commit()?;
+        debug!(total, version, "models migrated");
+        Ok(())
+    }
+
+    pub fn remove(&self, event_ids: &[OwnedEventId]) -> Result<()> {
+        let mut conn = self.conn.lock()?;
+        let tx = conn.transaction()?;
+        for event_id in event_ids {
+            Self::delete_model(&tx, &self.model_key(event_id))?;
+        }
+        tx.commit()?;
+        debug!(count = event_ids.len(), "models removed");
+        Ok(())
+    }
+
+    /// The models listed under `index` in the order of the index, starting
+    /// at `offset`, at most `limit` of them
+    pub fn list(
+        &self,
+        index: &IndexKey,
+        offset: usize,
+        limit: Option<usize>,
+    ) -> Result<Vec<AnyActerModel>> {
+        let order = match IndexOrder::for_key(index) {
+            IndexOrder::Ranked => "i.origin_server_ts DESC, i.seq DESC",
+            IndexOrder::Lifo => "i.seq DESC",
+            IndexOrder::Filo => "i.seq ASC",
+        };
+        let index_key = self.index_key(index)?;
+        // a negative limit means no limit in sqlite
+        let limit = limit.map(|l| l as i64).unwrap_or(-1);
+        let rows = {
+            let conn = self.conn.lock()?;
+            let mut stmt = conn.prepare_cached(&format!(
+                "SELECT m.data FROM index_members i
+                    JOIN models m ON m.model_key = i.model_key
+                    WHERE i.index_key = ?1
+                    ORDER BY {order}
+                    LIMIT ?2 OFFSET ?3"
+            ))?;
+            let rows = stmt.query_map(params![index_key, limit, offset as i64], |r| {
+                r.get::<_, Vec<u8>>(0)
+            })?;
+            rows.collect::<rusqlite::Result<Vec<_>>>()?
+        };
+        rows.iter().map(|d| self.decode_value(d)).collect()
+    }
+
+    /// how many models are listed under `index`
+    pub fn count(&self, index: &IndexKey) -> Result<usize> {
+        let index_key = self.index_key(index)?;
+        let conn = self.conn.lock()?;
+        let count: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM index_members WHERE index_key = ?1",
+            [index_key],
+            |r| r.get(0),
+        )?;
+        Ok(count as usize)
+    }
+}
@@ -8,7 +8,7 @@ use indexmap::IndexMap;
 use matrix_sdk_base::{ruma::RoomId, RoomState};
 pub use minijinja::value::Value;
 use minijinja::Environment;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::{collections::BTreeMap, sync::Arc};
 use tokio_retry::{
     strategy::{jitter, FibonacciBackoff},
@@ -17,10 +17,17 @@ use tokio_retry::{
 use toml::{Table, Value as TomlValue};
 use tracing::trace;
 
+pub mod expand;
+pub mod export;
 pub mod filters;
 pub mod functions;
+pub mod inputs;
+pub mod manifest;
+pub mod validate;
 pub mod values;
 
+use expand::add_to_group;
+use manifest::{CreatedObject, SharedManifest};
 use values::{ObjRef, UserValue};
 
 use crate::{
@@ -29,6 +36,7 @@ use crate::{
         calendar::CalendarEventEventContent,
         news::NewsEntryEventContent,
         pins::PinEventContent,
+        polls::PollEventContent,
         stories::StoryEventContent,
         tasks::{TaskEventContent, TaskListEventContent},
     },
@@ -69,16 +77,23 @@ pub enum Error {
 
     #[error("{0} already found in context.")]
     ContextClash(String),
+
+    #[error("Input '{0}' is invalid: {1}")]
+    InvalidInput(String, String),
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
 #[serde(tag = "type", rename_all = "kebab-case")]
 pub enum Input {
     Text {
         #[serde(default)]
         required: bool,
 
+        #[serde(default, skip_serializing_if = "Option::is_none")]
         description: Option<String>,
+
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        default: Option<String>,
     },
     User {
         #[serde(default)]
@@ -87,6 +102,7 @@ pub enum Input {
         #[serde(default, rename = "is-default")]
         is_default: bool,
 
+        #[serde(default, skip_serializing_if = "Option::is_none")]
         description: Option<String>,
     },
     Space {
@@ -96,7 +112,80 @@ pub enum Input {
         #[serde(default, rename = "is-default")]
         is_default: bool,
 
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+    },
+    /// A day, given as `YYYY-MM-DD`
+    Date {
+        #[serde(default)]
+        required: bool,
+
+        #[serde(default, skip_serializing_if = "Option::is_none")]
         description: Option<String>,
+
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        default: Option<String>,
+    },
+    /// A point in time, given in RFC 3339
+    DateTime {
+        #[serde(default)]
+        required: bool,
+
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        default: Option<String>,
+    },
+    Number {
+        #[serde(default)]
+        required: bool,
+
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        default: Option<f64>,
+
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        min: Option<f64>,
+
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        max: Option<f64>,
+    },
+    Boolean {
+        #[serde(default)]
+        required: bool,
+
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        default: Option<bool>,
+    },
+    /// One of the given options
+    Choice {
+        #[serde(default)]
+        required: bool,
+
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+
+        options: Vec<String>,
+
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        default: Option<String>,
+    },
+    /// A list of texts, given comma separated
+    List {
+        #[serde(default)]
+        required: bool,
+
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        default: Option<Vec<String>>,
     },
 }
 
@@ -105,8 +194,27 @@ impl Input {
         match self {
             Input::Text { required, .. }
             | Input::User { required, .. }
-            | Input::Space { required, .. } => *required,
-            // _ => false,
+            | Input::Space { required, .. }
+            | Input::Date { required, .. }
+            | Input::DateTime { required, .. }
+            | Input::Number { required, .. }
+            | Input::Boolean { required, .. }
+            | Input::Choice { required, .. }
+            | Input::List { required, .. } => *required,
+        }
+    }
+
+    pub fn description(&self) -> Option<&str> {
+        match self {
+            Input::Text { description, .. }
+            | Input::User { description, .. }
+            | Input::Space { description, .. }
+            | Input::Date { description, .. }
+            | Input::DateTime { description, .. }
+            | Input::Number { description, .. }
+            | Input::Boolean { description, .. }
+            | Input::Choice { description, .. }
+            | Input::List { description, .. } => description.as_deref(),
         }
     }
 
@@ -148,6 +256,10 @@ pub enum ObjectInner {
         #[serde(flatten)]
         fields: PinEventContent,
     },
+    Poll {
+        #[serde(flatten)]
+        fields: PollEventContent,
+    },
     CalendarEvent {
         #[serde(flatten)]
         fields: CalendarEventEventContent,
@@ -174,14 +286,15 @@ pub struct Object {
     obj: ObjectInner,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
 pub struct TemplateV01 {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     name: Option<String>,
     inputs: IndexMap<String, Input>,
     objects: IndexMap<String, Table>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
 #[serde(tag = "version")]
 pub enum TemplatesRoot {
     #[serde(rename = "0.1.1", alias = "0.1.0", alias = "0.1")]
@@ -226,6 +339,18 @@ fn execute_value_template(
     }
 }
 
+fn environment<'source>() -> Environment<'source> {
+    let mut env = Environment::new();
+
+    // functions
+    env.add_function("future", functions::future);
+    env.add_function("now", functions::now);
+
+    // filters
+
+    env
+}
+
 impl ExecutionStream {
     pub fn new(
         total: u32,
@@ -267,16 +392,22 @@ pub struct Engine {
     root: TemplateV01,
     context: Context,
     users: BTreeMap<String, Arc<CoreClient>>,
+    manifest: SharedManifest,
 }
 
 impl Engine {
     pub fn with_template(source: &str) -> Result<Self, Error> {
         let TemplatesRoot::V01(root) = toml::from_str::<TemplatesRoot>(source)?;
+        // broken defaults are the template’s fault, not of whoever runs it
+        for (name, input) in &root.inputs {
+            input.default_value(name)?;
+        }
 
         Ok(Self {
             root,
             context: Default::default(),
             users: Default::default(),
+            manifest: Default::default(),
         })
     }
 
@@ -284,7 +415,12 @@ impl Engine {
         &self.root.inputs
     }
 
+    /// Add the value, coerced into the type of the input of that name
     pub fn add_context(&mut self, name: String, value: Value) -> Result<(), Error> {
+        let value = match self.root.inputs.get(&name) {
+            Some(input) => input.coerce(&name, value)?,
+            None => value,
+        };
         if self.context.insert(name.clone(), value).is_some() {
             Err(Error::ContextClash(name))
         } else {
@@ -322,22 +458,12 @@ impl Engine {
     pub fn execute(&self) -> Result<ExecutionStream, Error> {
         trace!(name = ?self.root.name, "executing");
 
-        let env = {
-            let mut env = Environment::new();
-
-            // functions
-            env.add_function("future", functions::future);
-            env.add_function("now", functions::now);
-
-            // filters
-
-            env
-        };
+        let env = environment();
 
         let users = self.users.clone();
+        let manifest = self.manifest.clone();
         let mut context = self.context.clone();
-        let objects = self.root.objects.clone();
-        let total = objects.len();
+        self.add_defaults(&mut context)?;
         let (default_user, default_user_key, mut default_space) = {
             let mut default_user = None;
             let mut default_user_name = None;
@@ -378,13 +504,21 @@ impl Engine {
             (default_user, default_user_name, default_space)
         };
 
+        // objects of a previous run only fit if it ran the same way
+        manifest.check_fingerprint(self.fingerprint(&context)?)?;
+
+        let objects = self.expand(&context)?;
+        let total = objects.len();
         trace!(?default_user_key, "starting stream");
 
         let stream = try_stream! {
             trace!(total = objects.len(), "starting execution");
-            for (count, (key, fields)) in objects.into_iter().enumerate() {
+            for (count, item) in objects.into_iter().enumerate() {
+                let key = item.key.clone();
+                let group = item.group.clone();
                 trace!(count, ?key, "executing");
-                let reformatted = execute_value_template(TomlValue::Table(fields), &env, &context)
+                let item_context = item.context(&context);
+                let reformatted = execute_value_template(TomlValue::Table(item.fields), &env, &item_context)
                     .map_err(|e| Error::RenderingObject(key.clone(), e.to_string()))?;
                 let TomlValue::Table(t) = reformatted else {
                     unreachable!("We always get back a table after sending in a table.");
@@ -400,6 +534,26 @@ impl Engine {
                     users.get(&username).ok_or_else(|| Error::UnknownReference("user".to_owned(), key.clone(), username))?.clone()
                 };
 
+                if let Some(created) = manifest.get(&key)? {
+                    if created.obj_type != obj.ref_type() {
+                        Err(Error::Remap(
+                            format!("{key} was created as {}", created.obj_type),
+                            format!("but is a {} now", obj.ref_type()),
+                        ))?;
+                    }
+                    trace!(?key, id = created.id, "already created, skipping");
+                    context.insert(
+                        key.clone(),
+                        Value::from_object(ObjRef::new(created.id, created.obj_type)),
+                    );
+                    add_to_group(&mut context, &group, &key);
+                    if let ObjectInner::Space { is_default: true, .. } = obj {
+                        default_space = Some(key.clone());
+                    }
+                    yield;
+                    continue
+                }
+
                 if let ObjectInner::Space { is_default, fields } = obj {
                     if is_default && default_space.is_some() {
                         Err(Error::TooManyDefaults("Space".to_owned()))?;
@@ -408,6 +562,11 @@ impl Engine {
                         .create_acter_space(fields)
                         .await
                         .map_err(|e| Error::Remap(format!("Creating space '{key}' failed"), e.to_string()))?;
+                    manifest.record(&key, CreatedObject {
+                        id: new_room_id.to_string(),
+                        obj_type: "space".to_owned(),
+                        room_id: None,
+                    })?;
                     context.insert(
                         key.clone(),
                         Value::from_object(ObjRef::new(new_room_id.to_string() , "space".to_owned())),
@@ -415,6 +574,7 @@ impl Engine {
                     if is_default {
                         default_space = Some(key.clone());
                     }
+                    add_to_group(&mut context, &group, &key);
                     let retry_strategy = FibonacciBackoff::from_millis(100)
                         .map(jitter)
                         .take(10);
@@ -432,6 +592,7 @@ impl Engine {
                             })
                     }).await?;
 
+                    yield;
                     continue
                 };
 
@@ -455,7 +616,7 @@ impl Engine {
                     .get_room(&room_id)
                     .ok_or_else(|| Error::UnknownReference(format!("{key}.room"), room_name.clone(), key.clone()))?;
 
-                match obj {
+                let (id, obj_type) = match obj {
                     ObjectInner::TaskList { fields } => {
                         trace!(?fields, "submitting task list");
                         let id = room
@@ -464,11 +625,7 @@ impl Engine {
                             .map_err(|e| Error::Remap(format!("{key} submission failed"), e.to_string()))?
                             .event_id;
                         trace!(?id, "task list created");
-                        context.insert(
-                            key.clone(),
-                            Value::from_object(ObjRef::new(id.to_string(), "task-list".to_owned())),
-                        );
-                        yield
+                        (id, "task-list")
                     }
                     ObjectInner::Task { fields } => {
                         trace!(?fields, "submitting task");
@@ -478,11 +635,7 @@ impl Engine {
                             .map_err(|e| Error::Remap(format!("{key} submission failed"), e.to_string()))?
                             .event_id;
                         trace!(?id, "task created");
-                        context.insert(
-                            key.clone(),
-                            Value::from_object(ObjRef::new(id.to_string(), "task".to_owned())),
-                        );
-                        yield
+                        (id, "task")
                     }
                     ObjectInner::CalendarEvent { fields } => {
                         trace!(?fields, "submitting calendar event");
@@ -492,11 +645,7 @@ impl Engine {
                             .map_err(|e| Error::Remap(format!("{key} submission failed"), e.to_string()))?
                             .event_id;
                         trace!(?id, "calendar event created");
-                        context.insert(
-                            key.clone(),
-                            Value::from_object(ObjRef::new(id.to_string(), "calendar-event".to_owned())),
-                        );
-                        yield
+                        (id, "calendar-event")
                     }
                     ObjectInner::Pin { fields } => {
                         trace!(?fields, "submitting pin");
@@ -506,11 +655,17 @@ impl Engine {
                             .map_err(|e| Error::Remap(format!("{key} submission failed"), e.to_string()))?
                             .event_id;
                         trace!(?id, "pin created");
-                        context.insert(
-                            key.clone(),
-                            Value::from_object(ObjRef::new(id.to_string(), "pin".to_owned())),
-                        );
-                        yield
+                        (id, "pin")
+                    }
+                    ObjectInner::Poll { fields } => {
+                        trace!(?fields, "submitting poll");
+                        let id = room
+                            .send(fields)
+                            .await
+                            .map_err(|e| Error::Remap(format!("{key} submission failed"), e.to_string()))?
+                            .event_id;
+                        trace!(?id, "poll created");
+                        (id, "poll")
                     }
                     ObjectInner::NewsEntry { fields } => {
                         trace!(?fields, "submitting news entry");
@@ -520,11 +675,7 @@ impl Engine {
                             .map_err(|e| Error::Remap(format!("{key} submission failed"), e.to_string()))?
                             .event_id;
                         trace!(?id, "news created");
-                        context.insert(
-                            key.clone(),
-                            Value::from_object(ObjRef::new(id.to_string(), "news-entry".to_owned())),
-                        );
-                        yield
+                        (id, "news-entry")
                     }
                     ObjectInner::Story { fields } => {
                         trace!(?fields, "submitting story");
@@ -534,16 +685,23 @@ impl Engine {
                             .map_err(|e| Error::Remap(format!("{key} submission failed"), e.to_string()))?
                             .event_id;
                         trace!(?id, "story created");
-                        context.insert(
-                            key.clone(),
-                            Value::from_object(ObjRef::new(id.to_string(), "story".to_owned())),
-                        );
-                        yield
+                        (id, "story")
                     }
                     ObjectInner::Space { .. } => {
                         unreachable!("we already handled that above");
                     }
-                }
+                };
+                manifest.record(&key, CreatedObject {
+                    id: id.to_string(),
+                    obj_type: obj_type.to_owned(),
+                    room_id: Some(room_id.to_string()),
+                })?;
+                context.insert(
+                    key.clone(),
+                    Value::from_object(ObjRef::new(id.to_string(), obj_type.to_owned())),
+                );
+                add_to_group(&mut context, &group, &key);
+                yield
             }
         };
 
@@ -598,6 +756,17 @@ type = "pin"
 title = "Acter Source Code"
 url = "https://github.com/acterglobal/a3"
 
+[objects.lunch-poll]
+type = "poll"
+title = "Where do we go for lunch?"
+kind = "multiple-choice"
+voting = "anonymous"
+utc_closes_at = '{{ future(days="1") }}'
+answers = [
+{ id = "1", title = "Pizza" },
+{ id = "2", title = "Sushi" },
+]
+
 [objects.example-news]
 type = "news-entry"
 slides = [
@@ -0,0 +1,244 @@
+use std::collections::HashSet;
+use toml::{Table, Value as TomlValue};
+
+use super::{environment, Context, Engine, Error, Value};
+
+/// Repeat the object for every item of the expression, e.g. `range(12)` or
+/// the name of a list input
+static FOR_EACH: &str = "for-each";
+/// The name the current item is known under, `item` if not given
+static FOR_EACH_AS: &str = "for-each-as";
+/// Only create the object if the expression is true
+static WHEN: &str = "when";
+
+/// A single object to create, after loops and conditions were applied
+pub(super) struct Expanded {
+    pub key: String,
+    /// the key of the loop this was generated by, referencing all of them
+    pub group: Option<String>,
+    pub fields: Table,
+    /// the loop item and index, only known while rendering this object
+    pub locals: Context,
+}
+
+impl Expanded {
+    /// The context to render this object in
+    pub fn context(&self, context: &Context) -> Context {
+        let mut context = context.clone();
+        context.extend(self.locals.clone());
+        context
+    }
+}
+
+fn eval(key: &str, expr: &TomlValue, context: &Context) -> Result<Value, Error> {
+    let TomlValue::String(expr) = expr else {
+        return Err(Error::RenderingObject(
+            key.to_owned(),
+            format!("{expr} is not an expression"),
+        ));
+    };
+    let env = environment();
+    env.compile_expression(expr)
+        .and_then(|e| e.eval(context))
+        .map_err(|e| Error::RenderingObject(key.to_owned(), e.to_string()))
+}
+
+fn is_wanted(key: &str, when: Option<&TomlValue>, context: &Context) -> Result<bool, Error> {
+    match when {
+        Some(when) => Ok(eval(key, when, context)?.is_true()),
+        None => Ok(true),
+    }
+}
+
+impl Engine {
+    /// Turn the objects of the template into the ones to create
+    ///
+    /// `for-each` and `when` are evaluated against the inputs only, as the
+    /// number of objects has to be known before creating any of them. The
+    /// objects of a loop are keyed `{key}_{index}`, while `{key}` holds the
+    /// list of all of them, with dashes in `key` turned into underscores so
+    /// they can be referenced.
+    pub(super) fn expand(&self, context: &Context) -> Result<Vec<Expanded>, Error> {
+        let mut taken = self.root.objects.keys().cloned().collect::<HashSet<_>>();
+        let mut expanded = Vec::with_capacity(self.root.objects.len());
+
+        for (key, fields) in self.root.objects.iter() {
+            let mut fields = fields.clone();
+            let for_each = fields.remove(FOR_EACH);
+            let for_each_as = fields.remove(FOR_EACH_AS);
+            let when = fields.remove(WHEN);
+
+            let Some(for_each) = for_each else {
+                if is_wanted(key, when.as_ref(), context)? {
+                    expanded.push(Expanded {
+                        key: key.clone(),
+                        group: None,
+                        fields,
+                        locals: Default::default(),
+                    });
+                }
+                continue;
+            };
+
+            let item_name = match for_each_as {
+                Some(TomlValue::String(name)) => name,
+                Some(other) => {
+                    return Err(Error::RenderingObject(
+                        key.clone(),
+                        format!("{FOR_EACH_AS} must be a name, not {other}"),
+                    ))
+                }
+                None => "item".to_owned(),
+            };
+            // the locals would hide what the objects refer to otherwise
+            for local in [item_name.as_str(), "index"] {
+                if self.root.inputs.contains_key(local)
+                    || self.root.objects.contains_key(local)
+                    || context.contains_key(local)
+                {
+                    return Err(Error::RenderingObject(
+                        key.clone(),
+                        format!("loop local `{local}` shadows an input or object"),
+                    ));
+                }
+            }
+            let group = key.replace('-', "_");
+            if group != *key && !taken.insert(group.clone()) {
+                return Err(Error::ContextClash(group));
+            }
+            let items = eval(key, &for_each, context)?
+                .try_iter()
+                .map_err(|e| Error::RenderingObject(key.clone(), e.to_string()))?;
+
+            for (index, item) in items.enumerate() {
+                let mut locals = Context::new();
+                locals.insert(item_name.clone(), item);
+                locals.insert("index".to_owned(), Value::from(index));
+
+                let mut item_context = context.clone();
+                item_context.extend(locals.clone());
+                if !is_wanted(key, when.as_ref(), &item_context)? {
+                    continue;
+                }
+
+                let item_key = format!("{group}_{index}");
+                if !taken.insert(item_key.clone()) {
+                    return Err(Error::ContextClash(item_key));
+                }
+                expanded.push(Expanded {
+                    key: item_key,
+                    group: Some(group.clone()),
+                    fields: fields.clone(),
+                    locals,
+                });
+            }
+        }
+        Ok(expanded)
+    }
+}
+
+/// Make the object just created known as part of its loop
+pub(super) fn add_to_group(context: &mut Context, group: &Option<String>, key: &str) {
+    let (Some(group), Some(obj)) = (group, context.get(key).cloned()) else {
+        return;
+    };
+    let mut items = context
+        .get(group)
+        .and_then(|v| v.try_iter().ok())
+        .map(|items| items.collect::<Vec<_>>())
+        .unwrap_or_default();
+    items.push(obj);
+    context.insert(group.clone(), Value::from(items));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static TEMPLATE: &str = r#"
+version = "0.1.1"
+
+[inputs]
+teams = { type = "list", default = ["design", "dev"] }
+sessions = { type = "number", default = 3 }
+public = { type = "boolean", default = false }
+
+[objects.list]
+type = "task-list"
+name = "Onboarding"
+
+[objects.team-task]
+type = "task"
+for-each = "teams"
+for-each-as = "team"
+title = "Welcome {{ team }}"
+
+[objects.session]
+type = "calendar-event"
+for-each = "range(sessions)"
+when = "index != 1"
+title = "Session {{ index + 1 }}"
+
+[objects.announcement]
+type = "news-entry"
+when = "public"
+"#;
+
+    #[test]
+    fn loops_and_conditions() -> anyhow::Result<()> {
+        let engine = Engine::with_template(TEMPLATE)?;
+        let mut context = Context::new();
+        engine.add_defaults(&mut context)?;
+
+        let expanded = engine.expand(&context)?;
+        let keys = expanded.iter().map(|e| e.key.as_str()).collect::<Vec<_>>();
+        assert_eq!(
+            keys,
+            [
+                "list",
+                "team_task_0",
+                "team_task_1",
+                "session_0",
+                "session_2"
+            ]
+        );
+
+        let team_task = &expanded[2];
+        assert_eq!(team_task.group.as_deref(), Some("team_task"));
+        assert!(!team_task.fields.contains_key(FOR_EACH));
+        let title = environment().render_str(
+            team_task.fields["title"].as_str().unwrap(),
+            team_task.context(&context),
+        )?;
+        assert_eq!(title, "Welcome dev");
+        Ok(())
+    }
+
+    #[test]
+    fn loop_locals_must_not_shadow() -> anyhow::Result<()> {
+        for (from, to) in [
+            (r#"for-each-as = "team""#, r#"for-each-as = "teams""#),
+            (r#"for-each-as = "team""#, r#"for-each-as = "list""#),
+            ("[objects.announcement]", "[objects.index]"),
+        ] {
+            let engine = Engine::with_template(&TEMPLATE.replace(from, to))?;
+            let mut context = Context::new();
+            engine.add_defaults(&mut context)?;
+            assert!(
+                matches!(engine.expand(&context), Err(Error::RenderingObject(..))),
+                "{to}"
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn generated_objects_are_grouped() {
+        let mut context = Context::new();
+        for key in ["task_0", "task_1"] {
+            context.insert(key.to_owned(), Value::from(key));
+            add_to_group(&mut context, &Some("task".to_owned()), key);
+        }
+        assert_eq!(context["task"], Value::from(vec!["task_0", "task_1"]));
+    }
+}
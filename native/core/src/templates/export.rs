@@ -0,0 +1,265 @@
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use indexmap::IndexMap;
+use matrix_sdk_base::ruma::RoomId;
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+use std::collections::BTreeMap;
+use toml::{Table, Value as TomlValue};
+use tracing::trace;
+
+use super::{Error, Input, TemplateV01, TemplatesRoot};
+use crate::{
+    client::CoreClient,
+    events::UtcDateTime,
+    models::{ActerModel, AnyActerModel},
+    referencing::IndexKey,
+};
+
+/// The key the exported space is known under in the template
+static SPACE_KEY: &str = "space";
+/// The key of the user executing the template
+static USER_KEY: &str = "main";
+
+/// Collects the objects of a space into a template
+///
+/// Ids of exported objects, the space and the exporting user are replaced
+/// by references into the template context, dates by `future(...)` relative
+/// to the time of the export.
+pub struct Exporter {
+    now: UtcDateTime,
+    /// template expressions to put in place of the given strings
+    replacements: BTreeMap<String, String>,
+    counters: BTreeMap<&'static str, u32>,
+    objects: IndexMap<String, Table>,
+}
+
+impl Exporter {
+    pub fn new(room_id: &RoomId, user_id: Option<&str>, now: UtcDateTime) -> Self {
+        let mut replacements = BTreeMap::new();
+        replacements.insert(room_id.to_string(), format!("{{{{ {SPACE_KEY}.id }}}}"));
+        if let Some(user_id) = user_id {
+            replacements.insert(user_id.to_owned(), format!("{{{{ {USER_KEY}.user_id }}}}"));
+        }
+        Exporter {
+            now,
+            replacements,
+            counters: Default::default(),
+            objects: Default::default(),
+        }
+    }
+
+    /// Add the space itself, to be created when executing the template
+    pub fn add_space(&mut self, name: Option<String>, topic: Option<String>) {
+        let mut table = Table::new();
+        table.insert("type".to_owned(), TomlValue::String("space".to_owned()));
+        table.insert("is-default".to_owned(), TomlValue::Boolean(true));
+        if let Some(name) = name {
+            table.insert("name".to_owned(), TomlValue::String(escape(name)));
+        }
+        if let Some(topic) = topic {
+            table.insert("topic".to_owned(), TomlValue::String(escape(topic)));
+        }
+        self.objects.insert(SPACE_KEY.to_owned(), table);
+    }
+
+    /// Add the model, if it is of a type templates can create
+    ///
+    /// Models must come in the order they were created in, so references
+    /// to earlier objects can be resolved.
+    pub fn add_model(&mut self, model: &AnyActerModel) -> Result<bool, Error> {
+        let (obj_type, content) = match model {
+            AnyActerModel::TaskList(m) => ("task-list", to_json(&**m)?),
+            AnyActerModel::Task(m) => ("task", to_json(&**m)?),
+            AnyActerModel::CalendarEvent(m) => ("calendar-event", to_json(&**m)?),
+            AnyActerModel::Pin(m) => ("pin", to_json(&**m)?),
+            AnyActerModel::Poll(m) => ("poll", to_json(&**m)?),
+            AnyActerModel::NewsEntry(m) => ("news-entry", to_json(&**m)?),
+            AnyActerModel::Story(m) => ("story", to_json(&**m)?),
+            _ => return Ok(false),
+        };
+        let key = self.next_key(obj_type);
+        trace!(key, event_id = ?model.event_id(), "exporting");
+
+        let mut table = Table::new();
+        table.insert("type".to_owned(), TomlValue::String(obj_type.to_owned()));
+        if let Some(TomlValue::Table(fields)) = self.convert(content) {
+            table.extend(fields);
+        }
+        self.replacements
+            .insert(model.event_id().to_string(), format!("{{{{ {key}.id }}}}"));
+        self.objects.insert(key, table);
+        Ok(true)
+    }
+
+    pub fn into_template(self, name: Option<String>) -> TemplatesRoot {
+        let mut inputs = IndexMap::new();
+        inputs.insert(
+            USER_KEY.to_owned(),
+            Input::User {
+                required: true,
+                is_default: true,
+                description: Some("The user creating the objects".to_owned()),
+            },
+        );
+        TemplatesRoot::V01(TemplateV01 {
+            name,
+            inputs,
+            objects: self.objects,
+        })
+    }
+
+    /// `task_list_1`, `task_list_2`, …, usable as names in expressions
+    fn next_key(&mut self, obj_type: &'static str) -> String {
+        let counter = self.counters.entry(obj_type).or_default();
+        *counter += 1;
+        format!("{}_{counter}", obj_type.replace('-', "_"))
+    }
+
+    /// Turn the JSON into TOML, dropping `null`s it can’t represent
+    fn convert(&self, value: JsonValue) -> Option<TomlValue> {
+        Some(match value {
+            JsonValue::Null => return None,
+            JsonValue::Bool(b) => TomlValue::Boolean(b),
+            JsonValue::Number(n) => match n.as_i64() {
+                Some(i) => TomlValue::Integer(i),
+                None => TomlValue::Float(n.as_f64()?),
+            },
+            JsonValue::String(s) => TomlValue::String(self.convert_str(s)),
+            JsonValue::Array(items) => {
+                TomlValue::Array(items.into_iter().filter_map(|v| self.convert(v)).collect())
+            }
+            JsonValue::Object(fields) => TomlValue::Table(
+                fields
+                    .into_iter()
+                    .filter_map(|(k, v)| Some((k, self.convert(v)?)))
+                    .collect(),
+            ),
+        })
+    }
+
+    fn convert_str(&self, value: String) -> String {
+        if let Some(replacement) = self.replacements.get(&value) {
+            return replacement.clone();
+        }
+        if let Ok(date) = DateTime::parse_from_rfc3339(&value) {
+            let future = future_expr(date.with_timezone(&Utc) - self.now);
+            return format!("{{{{ {future}.as_rfc3339 }}}}");
+        }
+        if let Ok(date) = NaiveDate::parse_from_str(&value, "%Y-%m-%d") {
+            // whole days only, the time of day doesn’t move the date then
+            let future = future_expr(Duration::days((date - self.now.date_naive()).num_days()));
+            return format!("{{{{ {future}.as_date }}}}");
+        }
+        escape(value)
+    }
+}
+
+fn to_json<T: Serialize>(content: &T) -> Result<JsonValue, Error> {
+    serde_json::to_value(content).map_err(|e| Error::Remap("serializing".to_owned(), e.to_string()))
+}
+
+/// Keep the text from being rendered as a template itself
+fn escape(value: String) -> String {
+    if value.contains("{{") || value.contains("{%") || value.contains("{#") {
+        format!("{{% raw %}}{value}{{% endraw %}}")
+    } else {
+        value
+    }
+}
+
+/// The `future(...)` call for the given distance from now
+fn future_expr(distance: Duration) -> String {
+    let secs = distance.num_seconds();
+    let parts = [
+        ("days", secs / 86_400),
+        ("hours", secs % 86_400 / 3_600),
+        ("mins", secs % 3_600 / 60),
+        ("secs", secs % 60),
+    ]
+    .into_iter()
+    .filter(|(_, amount)| *amount != 0)
+    .map(|(unit, amount)| format!("{unit}=\"{amount}\""))
+    .collect::<Vec<_>>();
+    if parts.is_empty() {
+        "now()".to_owned()
+    } else {
+        format!("future({})", parts.join(", "))
+    }
+}
+
+impl TemplatesRoot {
+    pub fn to_toml(&self) -> Result<String, Error> {
+        Ok(toml::to_string_pretty(self)?)
+    }
+}
+
+impl CoreClient {
+    /// Export the objects of the space as a template creating a copy of it
+    pub async fn export_template(&self, room_id: &RoomId) -> Result<String, Error> {
+        let room = self.client().get_room(room_id).ok_or_else(|| {
+            Error::UnknownReference("space".to_owned(), room_id.to_string(), "export".to_owned())
+        })?;
+        let user_id = self.client().user_id().map(ToString::to_string);
+        let mut exporter = Exporter::new(room_id, user_id.as_deref(), Utc::now());
+        exporter.add_space(room.name(), room.topic());
+
+        let mut models = self
+            .store()
+            .get_list(&IndexKey::RoomModels(room_id.to_owned()))
+            .await
+            .map_err(|e| Error::Remap("loading objects".to_owned(), e.to_string()))?
+            .collect::<Vec<_>>();
+        models.sort_by_key(|m| m.event_meta().origin_server_ts);
+        for model in &models {
+            exporter.add_model(model)?;
+        }
+
+        exporter.into_template(room.name()).to_toml()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn future_expressions() {
+        assert_eq!(future_expr(Duration::zero()), "now()");
+        assert_eq!(
+            future_expr(Duration::days(2) + Duration::minutes(5)),
+            r#"future(days="2", mins="5")"#
+        );
+        assert_eq!(future_expr(-Duration::hours(3)), r#"future(hours="-3")"#);
+    }
+
+    #[test]
+    fn strings_become_references() {
+        let room_id = matrix_sdk_base::ruma::room_id!("!space:example.org");
+        let now = Utc.with_ymd_and_hms(2024, 5, 1, 10, 0, 0).unwrap();
+        let mut exporter = Exporter::new(room_id, Some("@me:example.org"), now);
+        exporter
+            .replacements
+            .insert("$list".to_owned(), "{{ task_list_1.id }}".to_owned());
+
+        let converted = exporter.convert(serde_json::json!({
+            "m.relates_to": { "event_id": "$list" },
+            "assignees": ["@me:example.org", "@other:example.org"],
+            "utc_start": "2024-05-02T10:00:00Z",
+            "due_date": "2024-05-03",
+            "title": "{{ not a template }}",
+            "description": null,
+        }));
+        let expected: TomlValue = toml::from_str(
+            r#"
+            "m.relates_to" = { event_id = "{{ task_list_1.id }}" }
+            assignees = ["{{ main.user_id }}", "@other:example.org"]
+            utc_start = '{{ future(days="1").as_rfc3339 }}'
+            due_date = '{{ future(days="2").as_date }}'
+            title = "{% raw %}{{ not a template }}{% endraw %}"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(converted, Some(expected));
+    }
+}
@@ -0,0 +1,259 @@
+use chrono::{DateTime, NaiveDate, NaiveTime, TimeZone, Utc};
+use minijinja::value::{Value, ValueKind};
+
+use super::{values::UtcDateTimeValue, Context, Engine, Error, Input};
+
+fn invalid(name: &str, msg: impl Into<String>) -> Error {
+    Error::InvalidInput(name.to_owned(), msg.into())
+}
+
+fn parse_date(name: &str, value: &str) -> Result<Value, Error> {
+    let date = NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|e| invalid(name, format!("{value} is not a YYYY-MM-DD date: {e}")))?;
+    let date = Utc.from_utc_datetime(&date.and_time(NaiveTime::MIN));
+    Ok(Value::from_object(UtcDateTimeValue::new(date)))
+}
+
+fn parse_date_time(name: &str, value: &str) -> Result<Value, Error> {
+    let date = DateTime::parse_from_rfc3339(value)
+        .map_err(|e| invalid(name, format!("{value} is not an RFC 3339 date time: {e}")))?;
+    Ok(Value::from_object(UtcDateTimeValue::new(
+        date.with_timezone(&Utc),
+    )))
+}
+
+fn parse_bool(name: &str, value: &str) -> Result<bool, Error> {
+    match value.to_lowercase().as_str() {
+        "true" | "yes" | "1" => Ok(true),
+        "false" | "no" | "0" => Ok(false),
+        _ => Err(invalid(name, format!("{value} is neither true nor false"))),
+    }
+}
+
+/// Whole numbers stay integers, so they can be used in `range` and the like
+fn number_value(number: f64) -> Value {
+    if number.fract() == 0.0 && number.abs() < i64::MAX as f64 {
+        Value::from(number as i64)
+    } else {
+        Value::from(number)
+    }
+}
+
+impl Input {
+    /// The `type` this input is declared with
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Input::Text { .. } => "text",
+            Input::User { .. } => "user",
+            Input::Space { .. } => "space",
+            Input::Date { .. } => "date",
+            Input::DateTime { .. } => "date-time",
+            Input::Number { .. } => "number",
+            Input::Boolean { .. } => "boolean",
+            Input::Choice { .. } => "choice",
+            Input::List { .. } => "list",
+        }
+    }
+
+    /// The options to choose from, for choice inputs
+    pub fn options(&self) -> &[String] {
+        match self {
+            Input::Choice { options, .. } => options,
+            _ => &[],
+        }
+    }
+
+    /// Check the value and turn it into the type of this input
+    ///
+    /// Values given as text, e.g. on the command line, are parsed.
+    pub fn coerce(&self, name: &str, value: Value) -> Result<Value, Error> {
+        let text = value.as_str();
+        match self {
+            Input::Text { .. } => Ok(match text {
+                Some(_) => value,
+                None => Value::from(value.to_string()),
+            }),
+            Input::User { .. } | Input::Space { .. } => Ok(value),
+            Input::Date { .. } => match text {
+                Some(text) => parse_date(name, text),
+                None if value
+                    .get_attr("as_timestamp")
+                    .is_ok_and(|v| !v.is_undefined()) =>
+                {
+                    Ok(value)
+                }
+                None => Err(invalid(name, "expected a date")),
+            },
+            Input::DateTime { .. } => match text {
+                Some(text) => parse_date_time(name, text),
+                None if value
+                    .get_attr("as_timestamp")
+                    .is_ok_and(|v| !v.is_undefined()) =>
+                {
+                    Ok(value)
+                }
+                None => Err(invalid(name, "expected a date time")),
+            },
+            Input::Number { min, max, .. } => {
+                let number = match text {
+                    Some(text) => text
+                        .trim()
+                        .parse::<f64>()
+                        .map_err(|e| invalid(name, format!("{text} is not a number: {e}")))?,
+                    None => f64::try_from(value.clone())
+                        .map_err(|_| invalid(name, "expected a number"))?,
+                };
+                if min.is_some_and(|min| number < min) || max.is_some_and(|max| number > max) {
+                    return Err(invalid(
+                        name,
+                        format!("{number} is out of range {min:?}..{max:?}"),
+                    ));
+                }
+                Ok(number_value(number))
+            }
+            Input::Boolean { .. } => match text {
+                Some(text) => Ok(Value::from(parse_bool(name, text)?)),
+                None if value.kind() == ValueKind::Bool => Ok(value),
+                None => Err(invalid(name, "expected true or false")),
+            },
+            Input::Choice { options, .. } => match text {
+                Some(text) if options.iter().any(|o| o == text) => Ok(value),
+                _ => Err(invalid(
+                    name,
+                    format!("{value} is not one of {}", options.join(", ")),
+                )),
+            },
+            Input::List { .. } => match text {
+                Some(text) => Ok(Value::from(
+                    text.split(',')
+                        .map(str::trim)
+                        .filter(|s| !s.is_empty())
+                        .map(ToOwned::to_owned)
+                        .collect::<Vec<_>>(),
+                )),
+                None if value.kind() == ValueKind::Seq => Ok(value),
+                None => Err(invalid(name, "expected a list")),
+            },
+        }
+    }
+
+    /// The default as it would be given on the command line
+    pub fn default_text(&self) -> Option<String> {
+        match self {
+            Input::Text { default, .. }
+            | Input::Date { default, .. }
+            | Input::DateTime { default, .. }
+            | Input::Choice { default, .. } => default.clone(),
+            Input::User { .. } | Input::Space { .. } => None,
+            Input::Number { default, .. } => default.map(|d| d.to_string()),
+            Input::Boolean { default, .. } => default.map(|d| d.to_string()),
+            Input::List { default, .. } => default.as_ref().map(|d| d.join(", ")),
+        }
+    }
+
+    /// The value to use when none was given
+    pub fn default_value(&self, name: &str) -> Result<Option<Value>, Error> {
+        Ok(match self {
+            Input::Text { default, .. } => default.clone().map(Value::from),
+            Input::User { .. } | Input::Space { .. } => None,
+            Input::Date { default, .. } => default
+                .as_deref()
+                .map(|d| parse_date(name, d))
+                .transpose()?,
+            Input::DateTime { default, .. } => default
+                .as_deref()
+                .map(|d| parse_date_time(name, d))
+                .transpose()?,
+            Input::Number { default, .. } => default
+                .map(|d| self.coerce(name, number_value(d)))
+                .transpose()?,
+            Input::Boolean { default, .. } => default.map(Value::from),
+            Input::Choice { default, .. } => default
+                .clone()
+                .map(|d| self.coerce(name, Value::from(d)))
+                .transpose()?,
+            Input::List { default, .. } => default.clone().map(Value::from),
+        })
+    }
+}
+
+impl Engine {
+    /// Fill in the defaults of the inputs not given
+    pub(super) fn add_defaults(&self, context: &mut Context) -> Result<(), Error> {
+        for (name, input) in self.requested_inputs() {
+            if context.contains_key(name) {
+                continue;
+            }
+            if let Some(value) = input.default_value(name)? {
+                context.insert(name.clone(), value);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static TEMPLATE: &str = r#"
+version = "0.1.1"
+
+[inputs]
+first_meeting = { type = "date", required = true }
+sessions = { type = "number", min = 1, max = 52, default = 4 }
+public = { type = "boolean", default = false }
+level = { type = "choice", options = ["beginner", "expert"], default = "beginner" }
+topics = { type = "list" }
+
+[objects]
+"#;
+
+    #[test]
+    fn inputs_are_coerced() -> anyhow::Result<()> {
+        let mut engine = Engine::with_template(TEMPLATE)?;
+        engine.add_context("first_meeting".to_owned(), Value::from("2024-06-03"))?;
+        engine.add_context("topics".to_owned(), Value::from("intro, setup,"))?;
+        assert!(matches!(
+            engine.add_context("level".to_owned(), Value::from("wizard")),
+            Err(Error::InvalidInput(..))
+        ));
+        assert!(matches!(
+            engine.add_context("sessions".to_owned(), Value::from("100")),
+            Err(Error::InvalidInput(..))
+        ));
+
+        let mut context = engine.context.clone();
+        engine.add_defaults(&mut context)?;
+
+        let date = context["first_meeting"].get_attr("as_rfc3339")?;
+        assert_eq!(date.as_str(), Some("2024-06-03T00:00:00+00:00"));
+        assert_eq!(context["topics"], Value::from(vec!["intro", "setup"]));
+        assert_eq!(context["sessions"], Value::from(4));
+        assert_eq!(context["public"], Value::from(false));
+        assert_eq!(context["level"], Value::from("beginner"));
+        Ok(())
+    }
+
+    #[test]
+    fn defaults_out_of_range_are_rejected() {
+        let template = TEMPLATE.replace("default = 4", "default = 60");
+        assert!(matches!(
+            Engine::with_template(&template),
+            Err(Error::InvalidInput(..))
+        ));
+    }
+
+    #[test]
+    fn broken_dates_are_rejected() {
+        let input = Input::DateTime {
+            required: false,
+            description: None,
+            default: None,
+        };
+        assert!(input.coerce("start", Value::from("tomorrow")).is_err());
+        assert!(input
+            .coerce("start", Value::from("2024-06-03T10:00:00Z"))
+            .is_ok());
+    }
+}
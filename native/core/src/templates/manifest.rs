@@ -0,0 +1,252 @@
+use indexmap::IndexMap;
+use matrix_sdk_base::ruma::{EventId, RoomId};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::sync::{Arc, Mutex};
+use tracing::trace;
+
+use super::{Context, Engine, Error};
+use crate::client::CoreClient;
+
+/// An object created while executing a template
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct CreatedObject {
+    /// the event id, or the room id for spaces
+    pub id: String,
+    #[serde(rename = "type")]
+    pub obj_type: String,
+    /// the room the event was sent to, none for spaces
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub room_id: Option<String>,
+}
+
+/// The objects created by executing a template, by their key
+///
+/// Executing the template again with this manifest skips the objects
+/// already created and picks up where it failed, as long as neither the
+/// template nor its inputs changed.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Manifest {
+    /// hash of the template and the inputs it was executed with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    fingerprint: Option<String>,
+    objects: IndexMap<String, CreatedObject>,
+}
+
+impl Manifest {
+    pub fn from_json(source: &str) -> Result<Self, Error> {
+        serde_json::from_str(source)
+            .map_err(|e| Error::Remap("parsing manifest".to_owned(), e.to_string()))
+    }
+
+    pub fn to_json(&self) -> Result<String, Error> {
+        serde_json::to_string_pretty(self)
+            .map_err(|e| Error::Remap("serializing manifest".to_owned(), e.to_string()))
+    }
+
+    pub fn get(&self, key: &str) -> Option<&CreatedObject> {
+        self.objects.get(key)
+    }
+
+    /// in the order they were created in
+    pub fn objects(&self) -> &IndexMap<String, CreatedObject> {
+        &self.objects
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty()
+    }
+}
+
+/// The manifest as shared between the engine and its execution stream
+#[derive(Clone, Default)]
+pub(super) struct SharedManifest(Arc<Mutex<Manifest>>);
+
+impl SharedManifest {
+    fn lock(&self) -> Result<std::sync::MutexGuard<'_, Manifest>, Error> {
+        self.0
+            .lock()
+            .map_err(|e| Error::Remap("manifest".to_owned(), e.to_string()))
+    }
+
+    pub fn get(&self, key: &str) -> Result<Option<CreatedObject>, Error> {
+        Ok(self.lock()?.get(key).cloned())
+    }
+
+    /// Take on the fingerprint of this execution, unless the objects
+    /// recorded were created by a different one
+    pub fn check_fingerprint(&self, fingerprint: String) -> Result<(), Error> {
+        let mut manifest = self.lock()?;
+        if manifest
+            .fingerprint
+            .as_ref()
+            .is_some_and(|f| *f != fingerprint && !manifest.objects.is_empty())
+        {
+            return Err(Error::Remap(
+                "resuming template".to_owned(),
+                "the template or its inputs changed since".to_owned(),
+            ));
+        }
+        manifest.fingerprint = Some(fingerprint);
+        Ok(())
+    }
+
+    pub fn record(&self, key: &str, created: CreatedObject) -> Result<(), Error> {
+        trace!(key, id = created.id, "recording created object");
+        self.lock()?.objects.insert(key.to_owned(), created);
+        Ok(())
+    }
+}
+
+impl Engine {
+    /// Skip the objects of the manifest when executing, using the ids
+    /// they were created with instead
+    pub fn resume_from(&mut self, manifest: Manifest) -> Result<(), Error> {
+        *self.manifest.lock()? = manifest;
+        Ok(())
+    }
+
+    /// The objects created so far, to be stored for resuming or rolling back
+    pub fn manifest(&self) -> Result<Manifest, Error> {
+        Ok(self.manifest.lock()?.clone())
+    }
+
+    /// Hash of the template and the inputs given, users by their id
+    pub(super) fn fingerprint(&self, context: &Context) -> Result<String, Error> {
+        let template = serde_json::to_string(&self.root)
+            .map_err(|e| Error::Remap("hashing template".to_owned(), e.to_string()))?;
+        let mut hasher = Sha256::new();
+        hasher.update(template);
+        for (name, input) in self.requested_inputs() {
+            let value = if input.is_user() {
+                self.users
+                    .get(name)
+                    .and_then(|c| c.client().user_id().map(ToString::to_string))
+            } else {
+                context.get(name).map(ToString::to_string)
+            };
+            hasher.update(format!("\n{name}={}", value.unwrap_or_default()));
+        }
+        Ok(format!("{:x}", hasher.finalize()))
+    }
+}
+
+impl CoreClient {
+    /// Redact the objects created by executing a template, latest first
+    ///
+    /// Redacted objects are removed from the manifest, so a failed rollback
+    /// can be retried with it. Spaces can’t be redacted and are kept.
+    pub async fn rollback_template(
+        &self,
+        manifest: &mut Manifest,
+        reason: Option<&str>,
+    ) -> Result<u32, Error> {
+        let mut redacted = 0;
+        let keys = manifest.objects.keys().rev().cloned().collect::<Vec<_>>();
+        for key in keys {
+            let Some(CreatedObject {
+                id,
+                room_id: Some(room_id),
+                ..
+            }) = manifest.objects.get(&key).cloned()
+            else {
+                continue;
+            };
+            let room_id = RoomId::parse(&room_id)
+                .map_err(|e| Error::Remap(format!("{key}.room_id({room_id})"), e.to_string()))?;
+            let event_id = EventId::parse(&id)
+                .map_err(|e| Error::Remap(format!("{key}.id({id})"), e.to_string()))?;
+            let room = self.client().get_room(&room_id).ok_or_else(|| {
+                Error::UnknownReference("room".to_owned(), room_id.to_string(), key.clone())
+            })?;
+            trace!(key, ?event_id, "redacting");
+            room.redact(&event_id, reason, None)
+                .await
+                .map_err(|e| Error::Remap(format!("{key} redaction failed"), e.to_string()))?;
+            manifest.objects.shift_remove(&key);
+            redacted += 1;
+        }
+        Ok(redacted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::templates::Value;
+
+    #[test]
+    fn manifest_roundtrip() -> anyhow::Result<()> {
+        let shared = SharedManifest::default();
+        shared.record(
+            "space",
+            CreatedObject {
+                id: "!space:example.org".to_owned(),
+                obj_type: "space".to_owned(),
+                room_id: None,
+            },
+        )?;
+        shared.record(
+            "task_list",
+            CreatedObject {
+                id: "$list".to_owned(),
+                obj_type: "task-list".to_owned(),
+                room_id: Some("!space:example.org".to_owned()),
+            },
+        )?;
+
+        let json = shared.lock()?.to_json()?;
+        let manifest = Manifest::from_json(&json)?;
+        let keys = manifest.objects().keys().collect::<Vec<_>>();
+        assert_eq!(keys, ["space", "task_list"]);
+        assert_eq!(manifest.get("task_list").unwrap().id, "$list");
+        assert!(manifest.get("space").unwrap().room_id.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn resuming_needs_the_same_fingerprint() -> anyhow::Result<()> {
+        let shared = SharedManifest::default();
+        // nothing created yet, any execution may take over
+        shared.check_fingerprint("first".to_owned())?;
+        shared.check_fingerprint("second".to_owned())?;
+        shared.record(
+            "space",
+            CreatedObject {
+                id: "!space:example.org".to_owned(),
+                obj_type: "space".to_owned(),
+                room_id: None,
+            },
+        )?;
+
+        let manifest = Manifest::from_json(&shared.lock()?.to_json()?)?;
+        let resumed = SharedManifest(Arc::new(Mutex::new(manifest)));
+        assert!(resumed.check_fingerprint("first".to_owned()).is_err());
+        resumed.check_fingerprint("second".to_owned())?;
+        Ok(())
+    }
+
+    #[test]
+    fn fingerprint_covers_template_and_inputs() -> anyhow::Result<()> {
+        let template = r#"
+version = "0.1.1"
+
+[inputs]
+sessions = { type = "number", default = 4 }
+
+[objects]
+"#;
+        let engine = Engine::with_template(template)?;
+        let mut context = Context::new();
+        engine.add_defaults(&mut context)?;
+        let fingerprint = engine.fingerprint(&context)?;
+        assert_eq!(fingerprint, engine.fingerprint(&context)?);
+
+        let other_engine = Engine::with_template(&template.replace("= 4", "= 5"))?;
+        assert_ne!(fingerprint, other_engine.fingerprint(&context)?);
+
+        context.insert("sessions".to_owned(), Value::from(5));
+        assert_ne!(fingerprint, engine.fingerprint(&context)?);
+        Ok(())
+    }
+}
@@ -0,0 +1,299 @@
+use matrix_sdk_base::ruma::RoomId;
+use serde_json::Value as JsonValue;
+use toml::{Table, Value as TomlValue};
+use tracing::trace;
+
+use super::{
+    environment, execute_value_template,
+    expand::{add_to_group, Expanded},
+    values::ObjRef,
+    Engine, Error, Object, ObjectInner, Value,
+};
+
+/// The outcome of checking a single object of the template
+#[derive(Debug)]
+pub struct ObjectReport {
+    pub key: String,
+    /// the type of object it would create, once it could be parsed
+    pub obj_type: Option<&'static str>,
+    /// the object as it would be sent, once it could be rendered
+    pub rendered: Option<JsonValue>,
+    pub error: Option<Error>,
+}
+
+impl ObjectReport {
+    pub fn is_valid(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// What executing the template would run into, found without sending anything
+#[derive(Debug, Default)]
+pub struct ValidationReport {
+    pub missing_inputs: Vec<String>,
+    /// problems not tied to a single object, like too many defaults
+    pub errors: Vec<Error>,
+    pub objects: Vec<ObjectReport>,
+}
+
+impl ValidationReport {
+    pub fn is_valid(&self) -> bool {
+        self.missing_inputs.is_empty()
+            && self.errors.is_empty()
+            && self.objects.iter().all(ObjectReport::is_valid)
+    }
+}
+
+impl ObjectInner {
+    /// The type objects created from this are referenced as
+    pub fn ref_type(&self) -> &'static str {
+        match self {
+            ObjectInner::Space { .. } => "space",
+            ObjectInner::TaskList { .. } => "task-list",
+            ObjectInner::Task { .. } => "task",
+            ObjectInner::Pin { .. } => "pin",
+            ObjectInner::Poll { .. } => "poll",
+            ObjectInner::CalendarEvent { .. } => "calendar-event",
+            ObjectInner::NewsEntry { .. } => "news-entry",
+            ObjectInner::Story { .. } => "story",
+        }
+    }
+}
+
+impl Engine {
+    /// Render and parse all objects against the given context, without
+    /// creating anything
+    ///
+    /// Objects are referenced under made up ids by the ones after them.
+    pub fn validate(&self) -> ValidationReport {
+        trace!(name = ?self.root.name, "validating");
+        let env = environment();
+        let mut context = self.context.clone();
+        let mut report = ValidationReport::default();
+        if let Err(error) = self.add_defaults(&mut context) {
+            report.errors.push(error);
+        }
+
+        let mut default_user = None;
+        let mut default_space = None;
+        for (name, input) in self.requested_inputs() {
+            if input.is_required() && !context.contains_key(name) {
+                report.missing_inputs.push(name.clone());
+            }
+            if !input.is_default() {
+                continue;
+            }
+            if input.is_user() {
+                if default_user.is_some() {
+                    report
+                        .errors
+                        .push(Error::TooManyDefaults("User".to_owned()));
+                } else if self.users.contains_key(name) {
+                    default_user = Some(name.clone());
+                }
+            }
+            if input.is_space() {
+                if default_space.is_some() {
+                    report
+                        .errors
+                        .push(Error::TooManyDefaults("Space".to_owned()));
+                } else {
+                    default_space = Some(name.clone());
+                }
+            }
+        }
+
+        let objects = match self.expand(&context) {
+            Ok(objects) => objects,
+            Err(error) => {
+                report.errors.push(error);
+                return report;
+            }
+        };
+
+        for item in objects {
+            let mut object_report = ObjectReport {
+                key: item.key.clone(),
+                obj_type: None,
+                rendered: None,
+                error: None,
+            };
+            if let Err(error) = self.validate_object(
+                &item,
+                &env,
+                &mut context,
+                &default_user,
+                &mut default_space,
+                &mut object_report,
+            ) {
+                object_report.error = Some(error);
+            }
+            add_to_group(&mut context, &item.group, &item.key);
+            report.objects.push(object_report);
+        }
+
+        report
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn validate_object(
+        &self,
+        item: &Expanded,
+        env: &minijinja::Environment,
+        context: &mut super::Context,
+        default_user: &Option<String>,
+        default_space: &mut Option<String>,
+        report: &mut ObjectReport,
+    ) -> Result<(), Error> {
+        let key = item.key.as_str();
+        let reformatted = execute_value_template(
+            TomlValue::Table(item.fields.clone()),
+            env,
+            &item.context(context),
+        )
+        .map_err(|e| Error::RenderingObject(key.to_owned(), e.to_string()))?;
+        let TomlValue::Table(t) = reformatted else {
+            unreachable!("We always get back a table after sending in a table.");
+        };
+        report.rendered = serde_json::to_value(&t).ok();
+        let Object { room, user, obj } = Table::try_into::<Object>(t)?;
+        let obj_type = obj.ref_type();
+        report.obj_type = Some(obj_type);
+
+        if let ObjectInner::Space { is_default, .. } = obj {
+            if is_default && default_space.is_some() {
+                return Err(Error::TooManyDefaults("Space".to_owned()));
+            }
+            let id = format!("!{key}:dry-run.acter.global");
+            context.insert(
+                key.to_owned(),
+                Value::from_object(ObjRef::new(id, obj_type.to_owned())),
+            );
+            if is_default {
+                *default_space = Some(key.to_owned());
+            }
+            return self.check_user(key, user, default_user);
+        }
+
+        let room_name = match room {
+            Some(r) => r,
+            None => default_space
+                .clone()
+                .ok_or_else(|| Error::NoDefaultSet("room".to_owned(), key.to_owned()))?,
+        };
+        let room_id_str = context
+            .get(&room_name)
+            .ok_or_else(|| {
+                Error::UnknownReference("room".to_owned(), room_name.clone(), key.to_owned())
+            })?
+            .get_attr("id")
+            .map_err(|e| Error::Remap(format!("{key} room={room_name} attr=id"), e.to_string()))?
+            .to_string();
+        RoomId::parse(room_id_str.clone()).map_err(|e| {
+            Error::Remap(
+                format!("{key}.room({room_name}).id({room_id_str}) parse failed"),
+                e.to_string(),
+            )
+        })?;
+
+        let id = format!("$dry-run-{key}");
+        context.insert(
+            key.to_owned(),
+            Value::from_object(ObjRef::new(id, obj_type.to_owned())),
+        );
+        self.check_user(key, user, default_user)
+    }
+
+    /// Whether there is a client to send as the given user
+    fn check_user(
+        &self,
+        key: &str,
+        user: Option<String>,
+        default_user: &Option<String>,
+    ) -> Result<(), Error> {
+        match user {
+            None if default_user.is_none() => {
+                Err(Error::NoDefaultSet("user".to_owned(), key.to_owned()))
+            }
+            Some(username) if !self.users.contains_key(&username) => Err(Error::UnknownReference(
+                "user".to_owned(),
+                key.to_owned(),
+                username,
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static TEMPLATE: &str = r#"
+version = "0.1.1"
+name = "Validation"
+
+[inputs]
+main = { type = "user", is-default = true, required = true }
+space = { type = "space", is-default = true, required = true }
+
+[objects]
+start_list = { type = "task-list", name = "Onboarding" }
+
+[objects.task_1]
+type = "task"
+title = "Read the news"
+"m.relates_to" = { event_id = "{{ start_list.id }}" }
+
+[objects.task_2]
+type = "task"
+title = "Unknown list"
+"m.relates_to" = { event_id = "{{ missing_list.id }}" }
+
+[objects.broken-pin]
+type = "pin"
+"#;
+
+    #[test]
+    fn reports_every_object() -> anyhow::Result<()> {
+        let mut engine = Engine::with_template(TEMPLATE)?;
+        engine.add_ref(
+            "space".to_owned(),
+            "space".to_owned(),
+            "!space:example.org".to_owned(),
+        )?;
+        let report = engine.validate();
+
+        // no client was added for the main user
+        assert_eq!(report.missing_inputs, vec!["main".to_owned()]);
+        assert!(!report.is_valid());
+
+        let keys = report
+            .objects
+            .iter()
+            .map(|o| o.key.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(keys, ["start_list", "task_1", "task_2", "broken-pin"]);
+
+        // without a default user, the parsed objects can’t be sent
+        let start_list = &report.objects[0];
+        assert_eq!(start_list.obj_type, Some("task-list"));
+        assert!(matches!(start_list.error, Some(Error::NoDefaultSet(..))));
+
+        // references to earlier objects render to made up ids
+        let task_1 = &report.objects[1];
+        assert_eq!(
+            task_1.rendered.as_ref().unwrap()["m.relates_to"]["event_id"],
+            "$dry-run-start_list"
+        );
+
+        // undefined references don’t render
+        assert!(report.objects[2].error.is_some());
+
+        // the pin misses its title
+        let pin = &report.objects[3];
+        assert!(pin.obj_type.is_none());
+        assert!(pin.error.is_some());
+        Ok(())
+    }
+}
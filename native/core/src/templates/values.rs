@@ -93,11 +93,12 @@ impl Object for UtcDateTimeValue {
         match field.as_str() {
             Some("as_timestamp") => Some(Value::from(self.date.timestamp())),
             Some("as_rfc3339") => Some(Value::from(self.date.to_rfc3339())),
+            Some("as_date") => Some(Value::from(self.date.format("%Y-%m-%d").to_string())),
             _ => None,
         }
     }
 
     fn enumerate(self: &Arc<Self>) -> Enumerator {
-        Enumerator::Str(&["as_timestamp"])
+        Enumerator::Str(&["as_timestamp", "as_rfc3339", "as_date"])
     }
 }
@@ -693,3 +693,94 @@ async fn task_self_assign_and_unassign() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn task_assign_and_unassign_other() -> Result<()> {
+    let (users, _sync_states, space_id, _engine) =
+        random_users_with_random_space_under_template("taskAssignOther", 1, TMPL).await?;
+
+    let first = users.first().expect("exists");
+    let second_user = &users[1];
+    let second_id = second_user.user_id()?;
+
+    // wait for sync to catch up
+    let retry_strategy = FibonacciBackoff::from_millis(100).map(jitter).take(30);
+    let obj_entry = Retry::spawn(retry_strategy, || async {
+        let entries = first.task_lists().await?;
+        if entries.is_empty() {
+            bail!("entries not found");
+        }
+        let tasks = entries[0].tasks().await?;
+        let Some(task) = tasks.first() else {
+            bail!("task not found");
+        };
+        Ok(task.clone())
+    })
+    .await?;
+
+    // we want to see push for everything;
+    second_user
+        .room(obj_entry.room_id().to_string())
+        .await?
+        .set_notification_mode(Some("all".to_owned()))
+        .await?;
+
+    // the admin assigns the task to the second user
+    let notification_ev = obj_entry.assign_user(second_id.to_string()).await?;
+
+    let notification_item = second_user
+        .get_notification_item(space_id.to_string(), notification_ev.to_string())
+        .await?;
+    assert_eq!(notification_item.push_style(), "taskAssign");
+    assert!(notification_item.mentions_you());
+    assert_eq!(notification_item.whom(), vec![second_id.to_string()]);
+    assert_eq!(
+        notification_item
+            .parent_id_str()
+            .expect("parent is in change"),
+        obj_entry.event_id_str(),
+    );
+
+    // the assignee sees it among their assigned tasks
+    let task_id = obj_entry.event_id_str();
+    let retry_strategy = FibonacciBackoff::from_millis(100).map(jitter).take(30);
+    Retry::spawn(retry_strategy, || async {
+        let task_lists = second_user.task_lists().await?;
+        let tasks = task_lists[0].tasks().await?;
+        let Some(task) = tasks.iter().find(|t| t.event_id_str() == task_id) else {
+            bail!("task not found");
+        };
+        if !task.is_assigned_to_me() {
+            bail!("not yet assigned");
+        }
+        Ok(())
+    })
+    .await?;
+
+    // and unassign
+    let notification_ev = obj_entry.unassign_user(second_id.to_string()).await?;
+
+    let notification_item = second_user
+        .get_notification_item(space_id.to_string(), notification_ev.to_string())
+        .await?;
+    assert_eq!(notification_item.push_style(), "taskUnassign");
+    assert!(notification_item.mentions_you());
+
+    // a regular member can't assign others
+    let retry_strategy = FibonacciBackoff::from_millis(100).map(jitter).take(30);
+    let second_entry = Retry::spawn(retry_strategy, || async {
+        let task_lists = second_user.task_lists().await?;
+        let tasks = task_lists[0].tasks().await?;
+        let Some(task) = tasks.first() else {
+            bail!("task not found");
+        };
+        Ok(task.clone())
+    })
+    .await?;
+    assert!(second_entry
+        .assign_user(first.user_id()?.to_string())
+        .await
+        .is_err());
+
+    Ok(())
+}
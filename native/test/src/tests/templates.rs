@@ -1,4 +1,7 @@
+use acter_core::templates::{manifest::Manifest, Engine};
 use anyhow::{bail, Result};
+use futures::{pin_mut, StreamExt};
+use serde_json::Value;
 use tokio_retry::{
     strategy::{jitter, FibonacciBackoff},
     Retry,
@@ -64,3 +67,144 @@ async fn template_creates_space() -> Result<()> {
     assert_eq!(main_space.task_lists().await?.len(), 1);
     Ok(())
 }
+
+const EXPORT_TMPL: &str = r#"
+version = "0.1"
+name = "Export Template"
+
+[inputs]
+main = { type = "user", is-default = true, required = true, description = "The starting user" }
+
+[objects]
+main_space = { type = "space", is-default = true, name = "{{ main.display_name }}’s export test space" }
+start_list = { type = "task-list", name = "Exported list" }
+
+[objects.task_1]
+type = "task"
+title = "Due in a few days"
+assignees = ["{{ main.user_id }}"]
+"m.relates_to" = { event_id = "{{ start_list.id }}" }
+due_date = "{{ future(days=\"3\").as_date }}"
+
+[objects.acter-website-pin]
+type = "pin"
+title = "Acter Website"
+url = "https://acter.global"
+"#;
+
+#[tokio::test]
+async fn exported_template_validates() -> Result<()> {
+    let _ = env_logger::try_init();
+    let (user, sync_state, _engine) =
+        random_user_with_template("template_export", EXPORT_TMPL).await?;
+    sync_state.await_has_synced_history().await?;
+
+    // wait for sync to catch up
+    let retry_strategy = FibonacciBackoff::from_millis(100).map(jitter).take(10);
+    let space = Retry::spawn(retry_strategy, || async {
+        let Some(space) = user.spaces().await?.into_iter().next() else {
+            bail!("space not found");
+        };
+        let Some(task_list) = space.task_lists().await?.into_iter().next() else {
+            bail!("task list not found");
+        };
+        if space.pins().await?.len() != 1 || task_list.tasks().await?.len() != 1 {
+            bail!("not all objects found");
+        }
+        Ok(space)
+    })
+    .await?;
+
+    let exported = space.export_template().await?;
+    // the due date stays a date relative to the time of executing
+    assert!(exported.contains("as_date"), "{exported}");
+
+    let engine = user.template_engine(&exported).await?;
+    let report = engine.validate();
+    assert!(report.is_valid(), "{report:?}");
+    let keys = report
+        .objects
+        .iter()
+        .map(|o| o.key.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(keys.len(), 4, "{keys:?}");
+    Ok(())
+}
+
+/// Run the template to the end, picking up from the given manifest
+async fn resume(engine: &mut Engine, manifest: &Manifest) -> Result<()> {
+    engine.resume_from(manifest.clone())?;
+    let stream = engine.execute()?;
+    pin_mut!(stream);
+    while let Some(step) = stream.next().await {
+        step?;
+    }
+    Ok(())
+}
+
+/// The manifest with only the given objects, changed by `edit`
+fn partial_manifest(
+    manifest: &Manifest,
+    keys: &[&str],
+    edit: impl FnOnce(&mut Value),
+) -> Result<Manifest> {
+    let mut json: Value = serde_json::from_str(&manifest.to_json()?)?;
+    json["objects"]
+        .as_object_mut()
+        .expect("objects are a map")
+        .retain(|key, _| keys.contains(&key.as_str()));
+    edit(&mut json);
+    Ok(Manifest::from_json(&json.to_string())?)
+}
+
+#[tokio::test]
+async fn template_resumes_from_manifest() -> Result<()> {
+    let _ = env_logger::try_init();
+    let (user, sync_state, engine) = random_user_with_template("template_resume", TMPL).await?;
+    sync_state.await_has_synced_history().await?;
+    let first_run = engine.manifest()?;
+    assert_eq!(first_run.objects().len(), 5);
+
+    // as if it failed after creating the space and the list
+    let manifest = partial_manifest(&first_run, &["main_space", "start_list"], |_| {})?;
+    let mut engine = user.template_engine(TMPL).await?;
+    resume(&mut engine, &manifest).await?;
+
+    let second_run = engine.manifest()?;
+    assert_eq!(second_run.objects().len(), 5);
+    for key in ["main_space", "start_list"] {
+        // skipped, with their ids bound for the objects referring to them
+        assert_eq!(second_run.get(key), first_run.get(key), "{key}");
+    }
+    for key in ["task_1", "acter-website-pin", "acter-source-pin"] {
+        assert_ne!(second_run.get(key), first_run.get(key), "{key}");
+    }
+
+    // the new task went into the list created before
+    let retry_strategy = FibonacciBackoff::from_millis(100).map(jitter).take(10);
+    Retry::spawn(retry_strategy, || async {
+        let task_lists = user.task_lists().await?;
+        if task_lists.len() != 1 {
+            bail!("expected one task list, found {}", task_lists.len());
+        }
+        if task_lists[0].tasks().await?.len() != 2 {
+            bail!("not all tasks found");
+        }
+        Ok(())
+    })
+    .await?;
+    assert_eq!(user.spaces().await?.len(), 1);
+
+    // a recorded object of another type can’t be reused
+    let wrong_type = partial_manifest(&first_run, &["main_space", "start_list"], |json| {
+        json["objects"]["start_list"]["type"] = Value::from("pin");
+    })?;
+    let mut engine = user.template_engine(TMPL).await?;
+    assert!(resume(&mut engine, &wrong_type).await.is_err());
+
+    // neither can the objects of a different template
+    let changed = TMPL.replace("Smoketest Template", "Changed Template");
+    let mut engine = user.template_engine(&changed).await?;
+    assert!(resume(&mut engine, &manifest).await.is_err());
+    Ok(())
+}